@@ -0,0 +1,200 @@
+//! Character encodings of input and output files.
+//!
+//! Input encoding comes from `--input-encoding`, or else a byte order mark,
+//! or else a `<meta charset>` in the first 1024 bytes, as browsers look for
+//! it. Without any of these the input must be UTF-8.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Bytes searched for a `<meta charset>`, as in the HTML prescan.
+const PRESCAN_BYTES: usize = 1024;
+
+/// Where the input encoding came from, for error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Flag,
+    Bom,
+    Meta,
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Flag => "from --input-encoding",
+            Source::Bom => "from its byte order mark",
+            Source::Meta => "from its <meta charset>",
+            Source::Default => "the default",
+        })
+    }
+}
+
+/// Look up an encoding by a label such as `latin1` or `utf-16le`.
+pub fn for_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| format!("Unknown encoding: {}", label))
+}
+
+/// Decode input bytes, with the encoding given by `label` or detected.
+///
+/// Malformed input is an error rather than replacement characters, since
+/// it usually means the wrong encoding was used.
+pub fn decode(bytes: &[u8], label: Option<&str>) -> Result<String, String> {
+    let bom = Encoding::for_bom(bytes);
+    let (encoding, source) = match (label, bom) {
+        (Some(label), _) => (for_label(label)?, Source::Flag),
+        (None, Some((encoding, _))) => (encoding, Source::Bom),
+        (None, None) => match meta_charset(bytes) {
+            Some(encoding) => (encoding, Source::Meta),
+            None => (UTF_8, Source::Default),
+        },
+    };
+
+    // A BOM for the encoding in use isn't content
+    let body = match bom {
+        Some((bom_encoding, len)) if bom_encoding == encoding => &bytes[len..],
+        _ => bytes,
+    };
+    encoding
+        .decode_without_bom_handling_and_without_replacement(body)
+        .map(Cow::into_owned)
+        .ok_or_else(|| format!("not valid {} ({})", encoding.name(), source))
+}
+
+/// Encode output. UTF-16 gets a byte order mark; characters the encoding
+/// lacks are written as HTML character references like `&#8594;`.
+pub fn encode(text: &str, encoding: &'static Encoding) -> Vec<u8> {
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let mut bytes = Vec::with_capacity(2 + text.len() * 2);
+        for unit in std::iter::once(0xFEFF).chain(text.encode_utf16()) {
+            if encoding == UTF_16LE {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return bytes;
+    }
+    let (bytes, _, _) = encoding.encode(text);
+    bytes.into_owned()
+}
+
+/// Encoding named by a `<meta charset="...">` or
+/// `<meta http-equiv="Content-Type" content="...; charset=...">` near the
+/// start of the document.
+fn meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(PRESCAN_BYTES)];
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+
+    let mut rest = head.as_str();
+    while let Some(start) = rest.find("<meta") {
+        let tag = &rest[start..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        rest = &rest[start + tag.len()..];
+
+        let Some(at) = tag.find("charset") else {
+            continue;
+        };
+        let value = tag[at + "charset".len()..].trim_start();
+        let Some(value) = value.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start().trim_start_matches(['"', '\'']);
+        let label: String = value
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+            .collect();
+        if let Some(encoding) = Encoding::for_label(label.as_bytes()) {
+            // A UTF-16 document can't declare itself in ASCII; as in
+            // browsers, such a declaration means UTF-8
+            if encoding == UTF_16LE || encoding == UTF_16BE {
+                return Some(UTF_8);
+            }
+            return Some(encoding);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LATIN1: &[u8] = include_bytes!("../tests/fixtures/latin1.html");
+    const UTF16LE_BOM: &[u8] = include_bytes!("../tests/fixtures/utf16le_bom.html");
+
+    #[test]
+    fn test_latin1_from_meta_charset() {
+        let html = decode(LATIN1, None).unwrap();
+        assert!(html.contains("Café crème for £3"));
+        assert!(html.contains("© 2003"));
+    }
+
+    #[test]
+    fn test_latin1_without_detection_fails() {
+        let mut without_meta = LATIN1.to_vec();
+        let at = LATIN1.windows(7).position(|w| w == b"charset").unwrap();
+        without_meta[at..at + 7].copy_from_slice(b"notmeta");
+        assert_eq!(
+            decode(&without_meta, None).unwrap_err(),
+            "not valid UTF-8 (the default)"
+        );
+        assert!(decode(&without_meta, Some("latin1"))
+            .unwrap()
+            .contains("Café"));
+    }
+
+    #[test]
+    fn test_utf16le_from_bom() {
+        let html = decode(UTF16LE_BOM, None).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Grüße aus Köln — 東京"));
+    }
+
+    #[test]
+    fn test_flag_wins_over_meta() {
+        let html = b"<meta charset=\"utf-8\"><p>\xe9t\xe9</p>";
+        assert_eq!(
+            decode(html, None).unwrap_err(),
+            "not valid UTF-8 (from its <meta charset>)"
+        );
+        assert_eq!(
+            decode(html, Some("windows-1252")).unwrap(),
+            "<meta charset=\"utf-8\"><p>été</p>"
+        );
+    }
+
+    #[test]
+    fn test_meta_charset_forms() {
+        let http_equiv =
+            br#"<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=ISO-8859-1">"#;
+        assert_eq!(meta_charset(http_equiv), Some(encoding_rs::WINDOWS_1252));
+        assert_eq!(
+            meta_charset(b"<meta charset=shift_jis>"),
+            Some(encoding_rs::SHIFT_JIS)
+        );
+        assert_eq!(meta_charset(b"<meta charset='utf-16'>"), Some(UTF_8));
+        assert_eq!(meta_charset(b"<meta name=x><p>charset=koi8-r</p>"), None);
+        assert_eq!(meta_charset(b"<meta charset=\"nope\">"), None);
+    }
+
+    #[test]
+    fn test_unknown_label() {
+        assert_eq!(
+            decode(b"x", Some("klingon")).unwrap_err(),
+            "Unknown encoding: klingon"
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("été", for_label("latin1").unwrap()), b"\xe9t\xe9");
+        assert_eq!(encode("a→b", for_label("latin1").unwrap()), b"a&#8594;b");
+        assert_eq!(encode("hé", UTF_16LE), b"\xff\xfeh\x00\xe9\x00");
+        assert_eq!(encode("hé", UTF_16BE), b"\xfe\xff\x00h\x00\xe9");
+        assert_eq!(encode("hé", UTF_8), "hé".as_bytes());
+    }
+}
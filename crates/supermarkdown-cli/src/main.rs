@@ -11,14 +11,37 @@
 //!
 //! # With options
 //! supermarkdown --heading-style setext --exclude "nav,footer" input.html
+//!
+//! # Print conversion statistics to stderr
+//! supermarkdown --stats --exclude "nav" input.html > output.md
+//!
+//! # Show which rule and selectors apply to each element
+//! supermarkdown --outline --exclude "nav" input.html
+//!
+//! # Diff the output against options from a JSON file
+//! supermarkdown --compare-with tuned.json input.html
+//!
+//! # Convert a Latin-1 export (needs the `encoding` feature)
+//! supermarkdown --input-encoding latin1 old-page.html
+//!
+//! # Reconvert on every save (needs the `watch` feature)
+//! supermarkdown --watch --config options.json -o page.md page.html
 //! ```
 
+#[cfg(feature = "encoding")]
+mod encoding;
+#[cfg(feature = "watch")]
+mod watch;
+
 use std::env;
 use std::fs;
 use std::io::{self, Read, Write};
 use std::process;
 
-use supermarkdown::{convert_with_options, HeadingStyle, LinkStyle, Options};
+use supermarkdown::{
+    convert_outline, convert_with_metadata, convert_with_options, diff_conversions, HeadingStyle,
+    LinkStyle, Options, StrikethroughStyle,
+};
 
 fn print_help() {
     eprintln!(
@@ -32,11 +55,33 @@ USAGE:
 OPTIONS:
     -h, --help              Print this help message
     -v, --version           Print version information
+    -o, --output <FILE>     Write the output to FILE instead of stdout
+    --config <FILE>         Start from the JSON options in FILE; flags on
+                            the command line apply on top of them
     --heading-style <STYLE> Heading style: atx (default) or setext
     --link-style <STYLE>    Link style: inline (default) or referenced
     --code-fence <CHAR>     Code fence character: ` (default) or ~
     --bullet <CHAR>         Bullet marker: - (default), *, or +
+    --strikethrough <STYLE> Strikethrough style: double (default, ~~text~~),
+                            single (~text~) or html (<del>text</del>)
     --exclude <SELECTORS>   CSS selectors to exclude (comma-separated)
+    --compact               Write the smallest faithful markdown, for prompts
+    --no-trailing-newline   Don't end the output with a newline
+    --no-trim               Keep leading blank lines in the output
+    --stats                 Print conversion statistics to stderr as JSON
+    --outline               Print the element tree with matched rules and
+                            skipped elements instead of markdown
+    --compare-with <FILE>   Print a unified diff from the output with these
+                            options to the output with the JSON options in
+                            FILE, instead of markdown
+    --input-encoding <ENC>  Input encoding, like latin1 or utf-16le; by
+                            default taken from a byte order mark or
+                            <meta charset>, else UTF-8 (encoding feature)
+    --output-encoding <ENC> Output encoding, UTF-8 by default; characters it
+                            lacks become &#NNNN; (encoding feature)
+    --watch                 Reconvert to the -o file whenever FILE or the
+                            --config file changes, until Ctrl+C (watch
+                            feature)
 
 EXAMPLES:
     # Convert a file
@@ -47,6 +92,21 @@ EXAMPLES:
 
     # Exclude navigation and ads
     supermarkdown --exclude "nav,.ad,#sidebar" page.html
+
+    # See how much the excludes removed
+    supermarkdown --stats --exclude "nav,.ad" page.html > page.md
+
+    # Combine several pages into one document
+    for f in *.html; do supermarkdown "$f" >> combined.md; done
+
+    # Find out which exclude removed a section
+    supermarkdown --outline --exclude "nav,.ad" page.html
+
+    # See what a tuned configuration changes
+    supermarkdown --compare-with tuned.json page.html
+
+    # Preview a configuration while editing it
+    supermarkdown --watch --config options.json -o page.md page.html
 "#
     );
 }
@@ -55,10 +115,55 @@ fn print_version() {
     eprintln!("supermarkdown {}", env!("CARGO_PKG_VERSION"));
 }
 
-fn parse_args() -> Result<(Options, Option<String>), String> {
-    let args: Vec<String> = env::args().collect();
-    let mut options = Options::new();
+/// Parsed command line arguments.
+struct Args {
+    options: Options,
+    file_path: Option<String>,
+    output_path: Option<String>,
+    watch: bool,
+    stats: bool,
+    outline: bool,
+    compare_with: Option<Options>,
+    input_encoding: Option<String>,
+    output_encoding: Option<String>,
+}
+
+/// Read JSON options with snake_case field names, as `--config` and
+/// `--compare-with` take them.
+fn read_options(path: &str) -> Result<Options, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Error reading {}: {}", path, e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid options in {}: {}", path, e))
+}
+
+/// The `--config` file in `args`, if any.
+fn config_path(args: &[String]) -> Result<Option<&str>, String> {
+    match args.iter().position(|arg| arg == "--config") {
+        Some(i) => match args.get(i + 1) {
+            Some(path) => Ok(Some(path)),
+            None => Err("--config requires a value".to_string()),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Parse `args`, the full command line including the program name.
+///
+/// Options from `--config` are read first wherever it appears, so the
+/// other flags override them. Watch mode calls this again when the config
+/// file changes.
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut options = match config_path(args)? {
+        Some(path) => read_options(path)?,
+        None => Options::new(),
+    };
     let mut file_path: Option<String> = None;
+    let mut output_path: Option<String> = None;
+    let mut watch = false;
+    let mut stats = false;
+    let mut outline = false;
+    let mut compare_with: Option<Options> = None;
+    let mut input_encoding: Option<String> = None;
+    let mut output_encoding: Option<String> = None;
     let mut i = 1;
 
     while i < args.len() {
@@ -118,6 +223,18 @@ fn parse_args() -> Result<(Options, Option<String>), String> {
                 };
                 options = options.bullet_marker(bullet);
             }
+            "--strikethrough" => {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--strikethrough requires a value".to_string());
+                }
+                options = match args[i].to_lowercase().as_str() {
+                    "double" => options.strikethrough_style(StrikethroughStyle::DoubleTilde),
+                    "single" => options.strikethrough_style(StrikethroughStyle::SingleTilde),
+                    "html" => options.strikethrough_style(StrikethroughStyle::Html),
+                    other => return Err(format!("Unknown strikethrough style: {}", other)),
+                };
+            }
             "--exclude" => {
                 i += 1;
                 if i >= args.len() {
@@ -130,6 +247,55 @@ fn parse_args() -> Result<(Options, Option<String>), String> {
                     .collect();
                 options = options.exclude_selectors(selectors);
             }
+            "--compare-with" => {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--compare-with requires a value".to_string());
+                }
+                compare_with = Some(read_options(&args[i])?);
+            }
+            "-o" | "--output" => {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--output requires a value".to_string());
+                }
+                output_path = Some(args[i].clone());
+            }
+            // Already read before the other flags.
+            "--config" => i += 1,
+            "--input-encoding" | "--output-encoding" => {
+                let flag = args[i].as_str();
+                i += 1;
+                if i >= args.len() {
+                    return Err(format!("{} requires a value", flag));
+                }
+                if !cfg!(feature = "encoding") {
+                    return Err(format!(
+                        "{} needs supermarkdown built with the `encoding` feature",
+                        flag
+                    ));
+                }
+                #[cfg(feature = "encoding")]
+                encoding::for_label(&args[i])?;
+                if flag == "--input-encoding" {
+                    input_encoding = Some(args[i].clone());
+                } else {
+                    output_encoding = Some(args[i].clone());
+                }
+            }
+            "--compact" => options = options.compact(true),
+            "--no-trailing-newline" => options = options.trailing_newline(false),
+            "--no-trim" => options = options.trim_document(false),
+            "--stats" => stats = true,
+            "--outline" => outline = true,
+            "--watch" => {
+                if !cfg!(feature = "watch") {
+                    return Err(
+                        "--watch needs supermarkdown built with the `watch` feature".to_string()
+                    );
+                }
+                watch = true;
+            }
             arg if arg.starts_with('-') => {
                 return Err(format!("Unknown option: {}", arg));
             }
@@ -143,22 +309,91 @@ fn parse_args() -> Result<(Options, Option<String>), String> {
         i += 1;
     }
 
-    Ok((options, file_path))
+    if watch {
+        if output_path.is_none() {
+            return Err("--watch requires --output".to_string());
+        }
+        if file_path.as_deref().is_none_or(|path| path == "-") {
+            return Err("--watch requires an input file".to_string());
+        }
+    }
+
+    Ok(Args {
+        options,
+        file_path,
+        output_path,
+        watch,
+        stats,
+        outline,
+        compare_with,
+        input_encoding,
+        output_encoding,
+    })
 }
 
-fn read_input(file_path: Option<String>) -> io::Result<String> {
+fn read_bytes(file_path: Option<&str>) -> io::Result<Vec<u8>> {
     match file_path {
-        Some(path) if path != "-" => fs::read_to_string(&path),
+        Some(path) if path != "-" => fs::read(path),
         _ => {
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
+            let mut buffer = Vec::new();
+            io::stdin().read_to_end(&mut buffer)?;
             Ok(buffer)
         }
     }
 }
 
+/// Read the input as text, decoded per `--input-encoding` when supported.
+fn read_input(file_path: Option<&str>, _encoding: Option<&str>) -> Result<String, String> {
+    let bytes = read_bytes(file_path).map_err(|e| e.to_string())?;
+
+    #[cfg(feature = "encoding")]
+    return encoding::decode(&bytes, _encoding);
+
+    #[cfg(not(feature = "encoding"))]
+    String::from_utf8(bytes).map_err(|_| {
+        "not valid UTF-8 (build with the `encoding` feature to read other encodings)".to_string()
+    })
+}
+
+/// Encode the output per `--output-encoding` when supported.
+fn encode_output(markdown: String, _encoding: Option<&str>) -> Result<Vec<u8>, String> {
+    #[cfg(feature = "encoding")]
+    if let Some(label) = _encoding {
+        return Ok(encoding::encode(&markdown, encoding::for_label(label)?));
+    }
+    Ok(markdown.into_bytes())
+}
+
+/// Read the input, convert it per `args` and encode the result.
+fn render(args: &Args) -> Result<Vec<u8>, String> {
+    let input_name = match args.file_path.as_deref() {
+        Some(path) if path != "-" => path,
+        _ => "stdin",
+    };
+    let html = read_input(args.file_path.as_deref(), args.input_encoding.as_deref())
+        .map_err(|e| format!("Error reading {}: {}", input_name, e))?;
+
+    let markdown = if args.outline {
+        convert_outline(&html, &args.options)
+    } else if let Some(other) = &args.compare_with {
+        diff_conversions(&html, &args.options, other).diff
+    } else if args.stats {
+        let (markdown, metadata) = convert_with_metadata(&html, &args.options);
+        match serde_json::to_string(&metadata.stats) {
+            Ok(json) => eprintln!("{}", json),
+            Err(e) => eprintln!("Error serializing stats: {}", e),
+        }
+        markdown
+    } else {
+        convert_with_options(&html, &args.options)
+    };
+
+    encode_output(markdown, args.output_encoding.as_deref()).map_err(|e| format!("Error: {}", e))
+}
+
 fn main() {
-    let (options, file_path) = match parse_args() {
+    let argv: Vec<String> = env::args().collect();
+    let args = match parse_args(&argv) {
         Ok(result) => result,
         Err(e) => {
             eprintln!("Error: {}", e);
@@ -167,17 +402,28 @@ fn main() {
         }
     };
 
-    let html = match read_input(file_path) {
-        Ok(content) => content,
+    if args.watch {
+        #[cfg(feature = "watch")]
+        if let Err(e) = watch::run(&argv, args) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let output = match render(&args) {
+        Ok(bytes) => bytes,
         Err(e) => {
-            eprintln!("Error reading input: {}", e);
+            eprintln!("{}", e);
             process::exit(1);
         }
     };
 
-    let markdown = convert_with_options(&html, &options);
-
-    if let Err(e) = io::stdout().write_all(markdown.as_bytes()) {
+    let written = match &args.output_path {
+        Some(path) => fs::write(path, &output),
+        None => io::stdout().write_all(&output),
+    };
+    if let Err(e) = written {
         eprintln!("Error writing output: {}", e);
         process::exit(1);
     }
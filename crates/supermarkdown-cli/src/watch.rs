@@ -0,0 +1,143 @@
+//! Watch mode: reconvert whenever the input or `--config` file changes.
+//!
+//! The parent directories are watched rather than the files themselves,
+//! since editors often save by writing a new file and renaming it over the
+//! old one. Events arriving within [`DEBOUNCE`] of each other are handled
+//! as one change. Each conversion is written to a temporary file and
+//! renamed over the output, so stopping with Ctrl+C never leaves a partial
+//! output behind.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::{config_path, parse_args, render, Args};
+
+/// Quiet period after an event before converting.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Convert once, then again on every change until interrupted.
+pub fn run(argv: &[String], mut args: Args) -> Result<(), String> {
+    let input = watched_path(args.file_path.as_deref().unwrap_or_default())?;
+    let config = match config_path(argv)? {
+        Some(path) => Some(watched_path(path)?),
+        None => None,
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+    for path in [Some(&input), config.as_ref()].into_iter().flatten() {
+        let dir = path.parent().unwrap_or(Path::new("/"));
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Error watching {}: {}", dir.display(), e))?;
+    }
+
+    convert(&args);
+    loop {
+        let mut config_changed = false;
+        let mut changed = false;
+        let mut timeout = None;
+        loop {
+            let event = match timeout {
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                Some(timeout) => rx.recv_timeout(timeout),
+            };
+            let event = match event {
+                Ok(Ok(event)) => event,
+                Ok(Err(e)) => {
+                    status(&format!("watch error: {}", e));
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            };
+            // Reading the input is itself an access event.
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            for path in &event.paths {
+                if *path == input {
+                    changed = true;
+                } else if Some(path) == config.as_ref() {
+                    changed = true;
+                    config_changed = true;
+                }
+            }
+            if changed {
+                timeout = Some(DEBOUNCE);
+            }
+        }
+
+        if config_changed {
+            match parse_args(argv) {
+                Ok(reparsed) => args = reparsed,
+                Err(e) => {
+                    status(&e);
+                    continue;
+                }
+            }
+        }
+        convert(&args);
+    }
+}
+
+/// `path` made absolute, as watcher events report it.
+fn watched_path(path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(path);
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Not a file: {}", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let dir =
+        fs::canonicalize(dir).map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+    Ok(dir.join(name))
+}
+
+/// Convert and write the output, reporting the outcome on one line.
+fn convert(args: &Args) {
+    let start = Instant::now();
+    let output = args.output_path.as_deref().unwrap_or_default();
+    match render(args).and_then(|bytes| {
+        write_atomically(Path::new(output), &bytes)
+            .map(|()| bytes.len())
+            .map_err(|e| format!("Error writing {}: {}", output, e))
+    }) {
+        Ok(len) => status(&format!(
+            "wrote {} ({} bytes, {} ms)",
+            output,
+            len,
+            start.elapsed().as_millis()
+        )),
+        Err(e) => status(&e),
+    }
+}
+
+/// Write `bytes` to a hidden sibling of `path`, then rename it over `path`.
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.tmp", name));
+    fs::write(&temp, bytes)?;
+    fs::rename(&temp, path)
+}
+
+/// Print `message` to stderr after the UTC time of day.
+fn status(message: &str) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    eprintln!(
+        "[{:02}:{:02}:{:02}] {}",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60,
+        message
+    );
+}
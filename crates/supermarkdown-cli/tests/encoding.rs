@@ -0,0 +1,73 @@
+//! End-to-end tests of `--input-encoding` and `--output-encoding`.
+#![cfg(feature = "encoding")]
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn fixture(name: &str) -> String {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", name]
+        .iter()
+        .collect();
+    path.to_string_lossy().into_owned()
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_supermarkdown"))
+        .args(args)
+        .output()
+        .expect("failed to run supermarkdown")
+}
+
+#[test]
+fn test_latin1_detected_from_meta() {
+    let output = run(&[&fixture("latin1.html")]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "# Menu du jour\n\n\
+         Café crème for £3, croissant au beurre, crêpe flambée.\n\n\
+         Ouvert été comme hiver. © 2003 Chez Hélène\n"
+    );
+}
+
+#[test]
+fn test_utf16le_detected_from_bom() {
+    let output = run(&[&fixture("utf16le_bom.html")]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "# Grüße aus Köln — 東京\n\nSchöne Grüße, *Jürgen*\n"
+    );
+}
+
+#[test]
+fn test_wrong_encoding_names_file_and_encoding() {
+    let path = fixture("utf16le_bom.html");
+    let output = run(&["--input-encoding", "utf-8", &path]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "Error reading {}: not valid UTF-8 (from --input-encoding)\n",
+            path
+        )
+    );
+}
+
+#[test]
+fn test_output_encoding() {
+    let output = run(&["--output-encoding", "latin1", &fixture("latin1.html")]);
+    assert!(output.status.success());
+    assert!(output
+        .stdout
+        .starts_with(b"# Menu du jour\n\nCaf\xe9 cr\xe8me for \xa33"));
+}
+
+#[test]
+fn test_unknown_encoding() {
+    let output = run(&["--input-encoding", "klingon", &fixture("latin1.html")]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("Error: Unknown encoding: klingon\n"));
+}
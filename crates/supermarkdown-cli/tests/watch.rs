@@ -0,0 +1,72 @@
+//! End-to-end test of `--watch`.
+#![cfg(feature = "watch")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Poll `path` until it holds `expected`, for up to ten seconds.
+fn wait_for(path: &Path, expected: &str) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if fs::read_to_string(path).is_ok_and(|content| content == expected) {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_watch_reconverts_on_change() {
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("supermarkdown-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("page.html");
+    let output = dir.join("page.md");
+    let config = dir.join("options.json");
+    fs::write(&input, "<h1>First</h1>").unwrap();
+    fs::write(&config, "{}").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_supermarkdown"))
+        .arg("--watch")
+        .arg("--config")
+        .arg(&config)
+        .arg("-o")
+        .arg(&output)
+        .arg(&input)
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to run supermarkdown");
+
+    let first = wait_for(&output, "# First\n");
+    fs::write(&input, "<h1>Second</h1><p>Not valid <b>HTML").unwrap();
+    let second = wait_for(&output, "# Second\n\nNot valid **HTML**\n");
+    fs::write(&config, r#"{"heading_style": "setext"}"#).unwrap();
+    let third = wait_for(&output, "Second\n======\n\nNot valid **HTML**\n");
+    // A missing input is reported and watching goes on.
+    fs::remove_file(&input).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    fs::write(&input, "<h1>Third</h1>").unwrap();
+    let fourth = wait_for(&output, "Third\n=====\n");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(first, "initial conversion was not written");
+    assert!(second, "input change was not reconverted");
+    assert!(third, "config change was not applied");
+    assert!(fourth, "watching stopped after a conversion error");
+}
+
+#[test]
+fn test_watch_requires_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_supermarkdown"))
+        .args(["--watch", "page.html"])
+        .output()
+        .expect("failed to run supermarkdown");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--watch requires --output"));
+}
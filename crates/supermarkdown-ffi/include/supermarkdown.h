@@ -0,0 +1,50 @@
+#ifndef SUPERMARKDOWN_H
+#define SUPERMARKDOWN_H
+
+/* Generated by cbindgen from crates/supermarkdown-ffi/src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Convert HTML to Markdown with default options.
+//
+// Returns null if `html` is null or not valid UTF-8.
+//
+// # Safety
+//
+// `html` must be null or point to a NUL-terminated string.
+char *sm_convert(const char *html);
+
+// Convert HTML to Markdown with options given as a JSON object.
+//
+// Option names are the snake_case field names of the Rust `Options`
+// struct, e.g. `{"heading_style": "setext", "exclude_selectors": ["nav"]}`.
+// A null `options_json` uses the default options.
+//
+// Returns null if either string is not valid UTF-8 or the JSON is invalid.
+//
+// # Safety
+//
+// `html` and `options_json` must each be null or point to a NUL-terminated
+// string.
+char *sm_convert_with_options(const char *html, const char *options_json);
+
+// Free a string returned by this library. Passing null is a no-op.
+//
+// # Safety
+//
+// `s` must be null or a pointer returned by `sm_convert` or
+// `sm_convert_with_options` that has not been freed yet.
+void sm_free_string(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SUPERMARKDOWN_H */
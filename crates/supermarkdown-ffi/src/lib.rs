@@ -0,0 +1,161 @@
+//! C bindings for supermarkdown.
+//!
+//! Every function returns a heap-allocated, NUL-terminated UTF-8 string that
+//! must be released with [`sm_free_string`], or null when the input is null,
+//! not valid UTF-8, the options JSON is invalid, or conversion panicked.
+//!
+//! The C header lives in `include/supermarkdown.h`.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use supermarkdown::Options;
+
+/// Convert HTML to Markdown with default options.
+///
+/// Returns null if `html` is null or not valid UTF-8.
+///
+/// # Safety
+///
+/// `html` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sm_convert(html: *const c_char) -> *mut c_char {
+    guard(|| {
+        let html = read_str(html)?;
+        Some(supermarkdown::convert(html))
+    })
+}
+
+/// Convert HTML to Markdown with options given as a JSON object.
+///
+/// Option names are the snake_case field names of the Rust `Options`
+/// struct, e.g. `{"heading_style": "setext", "exclude_selectors": ["nav"]}`.
+/// A null `options_json` uses the default options.
+///
+/// Returns null if either string is not valid UTF-8 or the JSON is invalid.
+///
+/// # Safety
+///
+/// `html` and `options_json` must each be null or point to a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn sm_convert_with_options(
+    html: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    guard(|| {
+        let html = read_str(html)?;
+        let options = if options_json.is_null() {
+            Options::default()
+        } else {
+            serde_json::from_str(read_str(options_json)?).ok()?
+        };
+        Some(supermarkdown::convert_with_options(html, &options))
+    })
+}
+
+/// Free a string returned by this library. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `s` must be null or a pointer returned by `sm_convert` or
+/// `sm_convert_with_options` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sm_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Borrow a C string as `&str`, rejecting null and invalid UTF-8.
+unsafe fn read_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Run a conversion, turning failures and panics into a null pointer.
+fn guard(f: impl FnOnce() -> Option<String>) -> *mut c_char {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        // Markdown from valid UTF-8 input never contains NUL, but strip it
+        // rather than fail if an entity decoded to one.
+        Ok(Some(markdown)) => match CString::new(markdown.replace('\0', "")) {
+            Ok(s) => s.into_raw(),
+            Err(_) => ptr::null_mut(),
+        },
+        _ => ptr::null_mut(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(html: &[u8], options: Option<&[u8]>) -> Option<String> {
+        let html = CString::new(html).unwrap();
+        let options = options.map(|o| CString::new(o).unwrap());
+        unsafe {
+            let out = match &options {
+                Some(o) => sm_convert_with_options(html.as_ptr(), o.as_ptr()),
+                None => sm_convert(html.as_ptr()),
+            };
+            if out.is_null() {
+                return None;
+            }
+            let result = CStr::from_ptr(out).to_str().unwrap().to_string();
+            sm_free_string(out);
+            Some(result)
+        }
+    }
+
+    #[test]
+    fn test_convert() {
+        let result = call(b"<h1>Hello</h1>", None).unwrap();
+        assert_eq!(result, "# Hello\n");
+    }
+
+    #[test]
+    fn test_convert_with_options() {
+        let result = call(
+            b"<h1>Title</h1><nav>Menu</nav>",
+            Some(br#"{"heading_style": "setext", "exclude_selectors": ["nav"]}"#),
+        )
+        .unwrap();
+        assert!(result.contains("====="));
+        assert!(!result.contains("Menu"));
+    }
+
+    #[test]
+    fn test_null_inputs() {
+        unsafe {
+            assert!(sm_convert(ptr::null()).is_null());
+            assert!(sm_convert_with_options(ptr::null(), ptr::null()).is_null());
+            sm_free_string(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_null_options_use_defaults() {
+        let html = CString::new("<p>Text</p>").unwrap();
+        unsafe {
+            let out = sm_convert_with_options(html.as_ptr(), ptr::null());
+            assert!(!out.is_null());
+            assert_eq!(CStr::from_ptr(out).to_str().unwrap(), "Text\n");
+            sm_free_string(out);
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        assert!(call(b"<p>\xff\xfe</p>", None).is_none());
+        assert!(call(b"<p>ok</p>", Some(b"{\"heading_style\": \"\xff\"}")).is_none());
+    }
+
+    #[test]
+    fn test_invalid_options_json() {
+        assert!(call(b"<p>ok</p>", Some(b"{not json")).is_none());
+        assert!(call(b"<p>ok</p>", Some(br#"{"heading_style": "fancy"}"#)).is_none());
+    }
+}
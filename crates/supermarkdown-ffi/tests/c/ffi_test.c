@@ -0,0 +1,40 @@
+/* Smoke test for the supermarkdown C API. Exits non-zero on failure. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "supermarkdown.h"
+
+#define CHECK(cond)                                                   \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,    \
+                    __LINE__, #cond);                                 \
+            return 1;                                                 \
+        }                                                             \
+    } while (0)
+
+int main(void) {
+    char *md = sm_convert("<h1>Hello</h1><p>From <strong>C</strong></p>");
+    CHECK(md != NULL);
+    CHECK(strstr(md, "# Hello") != NULL);
+    CHECK(strstr(md, "From **C**") != NULL);
+    sm_free_string(md);
+
+    md = sm_convert_with_options("<h1>Title</h1><nav>Menu</nav>",
+                                 "{\"heading_style\": \"setext\", "
+                                 "\"exclude_selectors\": [\"nav\"]}");
+    CHECK(md != NULL);
+    CHECK(strstr(md, "=====") != NULL);
+    CHECK(strstr(md, "Menu") == NULL);
+    sm_free_string(md);
+
+    /* Failures return null instead of crashing */
+    CHECK(sm_convert(NULL) == NULL);
+    CHECK(sm_convert("<p>\xff</p>") == NULL);
+    CHECK(sm_convert_with_options("<p>x</p>", "{oops") == NULL);
+    sm_free_string(NULL);
+
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,53 @@
+//! Builds and runs `tests/c/ffi_test.c` against the compiled library.
+//!
+//! Skipped when no C compiler is available.
+
+#![cfg(unix)]
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Directory holding the library artifacts (`target/<profile>`).
+fn artifact_dir() -> PathBuf {
+    // The test binary lives in target/<profile>/deps
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn test_c_program() {
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    if Command::new(&cc).arg("--version").output().is_err() {
+        eprintln!("skipping: no C compiler found");
+        return;
+    }
+
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = artifact_dir();
+    let out = env::temp_dir().join(format!("supermarkdown_ffi_test_{}", std::process::id()));
+
+    let status = Command::new(&cc)
+        .arg(manifest.join("tests/c/ffi_test.c"))
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lsupermarkdown_ffi")
+        .arg("-o")
+        .arg(&out)
+        .status()
+        .expect("failed to run C compiler");
+    assert!(status.success(), "compiling ffi_test.c failed");
+
+    let output = Command::new(&out).output().expect("failed to run ffi_test");
+    let _ = std::fs::remove_file(&out);
+
+    assert!(
+        output.status.success(),
+        "ffi_test failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+}
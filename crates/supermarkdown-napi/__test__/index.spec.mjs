@@ -0,0 +1,71 @@
+import test from 'ava'
+
+import { convert, convertAsync, convertToBuffer, convertWithDiagnostics } from '../index.js'
+
+const largeDocument = '<ul>' + '<li><b>Item</b> text</li>'.repeat(50000) + '</ul>'
+
+test('convertAsync matches convert', async (t) => {
+  t.is(await convertAsync('<h1>Hello</h1>'), convert('<h1>Hello</h1>'))
+})
+
+test('convertAsync reports increasing progress', async (t) => {
+  const seen = []
+  let settled = false
+  const markdown = await convertAsync(largeDocument, null, (percent) => {
+    t.false(settled, 'progress reported after the promise resolved')
+    seen.push(percent)
+  })
+  settled = true
+
+  t.is(markdown, convert(largeDocument))
+  t.true(seen.length > 10)
+  t.true(seen.every((percent, i) => i === 0 || percent > seen[i - 1]))
+  t.is(seen[seen.length - 1], 100)
+})
+
+test('a throwing progress callback does not break conversion', async (t) => {
+  let calls = 0
+  const markdown = await convertAsync('<p>Hi</p>', undefined, () => {
+    calls += 1
+    throw new Error('boom')
+  })
+
+  t.is(markdown, 'Hi\n')
+  t.true(calls > 0)
+})
+
+const html = '<h1>Café</h1><p>Prices in <strong>€</strong>, see <a href="/menu">the menu</a>.</p>'
+
+test('convert accepts a Buffer of UTF-8 bytes', (t) => {
+  t.is(convert(Buffer.from(html)), convert(html))
+  t.is(convert(Buffer.from(html), { headingStyle: 'setext' }), convert(html, { headingStyle: 'setext' }))
+})
+
+test('invalid UTF-8 in a Buffer is replaced', (t) => {
+  const bytes = Buffer.from([...Buffer.from('<p>caf'), 0xe9, ...Buffer.from(' ok</p>')])
+  t.is(convert(bytes), 'caf\uFFFD ok\n')
+})
+
+test('convertToBuffer returns the UTF-8 bytes of convert', (t) => {
+  const markdown = convertToBuffer(html)
+  t.true(Buffer.isBuffer(markdown))
+  t.is(markdown.toString('utf8'), convert(html))
+  t.deepEqual(convertToBuffer(Buffer.from(html)), markdown)
+})
+
+test('convertAsync accepts a Buffer', async (t) => {
+  t.is(await convertAsync(Buffer.from(largeDocument)), convert(largeDocument))
+})
+
+test('convertWithDiagnostics warns once for an invalid selector', (t) => {
+  const { markdown, warnings } = convertWithDiagnostics('<p>Text</p>', { excludeSelectors: ['.ad', 'div['] })
+  t.is(markdown, 'Text\n')
+  t.is(warnings.length, 1)
+  t.is(warnings[0].code, 'INVALID_SELECTOR')
+  t.is(warnings[0].detail, 'div[')
+})
+
+test('convertWithDiagnostics has no warnings for valid options', (t) => {
+  t.deepEqual(convertWithDiagnostics('<p>Text</p>', { excludeSelectors: ['.ad'] }).warnings, [])
+  t.deepEqual(convertWithDiagnostics('<p>Text</p>').warnings, [])
+})
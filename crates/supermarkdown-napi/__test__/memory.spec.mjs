@@ -0,0 +1,21 @@
+import { mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+
+import test from 'ava'
+
+import { largeDocument, measure } from '../benchmark/memory.mjs'
+
+test('Buffer input and output lower peak memory on a 50MB document', (t) => {
+  const dir = mkdtempSync(join(tmpdir(), 'supermarkdown-memory-'))
+  t.teardown(() => rmSync(dir, { recursive: true, force: true }))
+  const input = join(dir, 'input.html')
+  writeFileSync(input, largeDocument(50))
+
+  const string = measure('string', input)
+  const buffer = measure('buffer', input)
+
+  t.is(buffer.bytes, string.bytes)
+  // Neither the input nor the output is copied across the boundary
+  t.true(buffer.peakRss < string.peakRss, `buffer ${buffer.peakRss} >= string ${string.peakRss}`)
+})
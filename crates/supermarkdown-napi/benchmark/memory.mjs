@@ -0,0 +1,85 @@
+// Peak memory of converting a large document through the string and Buffer
+// APIs. Each variant runs in its own process so peaks don't mix:
+//
+//   node benchmark/memory.mjs [megabytes]
+
+import { execFileSync } from 'node:child_process'
+import { mkdtempSync, readFileSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { fileURLToPath } from 'node:url'
+
+const script = fileURLToPath(import.meta.url)
+
+// Non-ASCII text makes V8 store strings two bytes per character, as many
+// real pages do.
+export function largeDocument(megabytes) {
+  const section =
+    '<h2>Café section</h2><p>Prices in € with <a href="/menu">a link</a> and <strong>bold</strong> text.</p>' +
+    '<ul><li>Crème brûlée</li><li>Matcha 抹茶</li></ul><pre><code>let x = 1;</code></pre>\n'
+  return section.repeat(Math.ceil((megabytes * 1024 * 1024) / Buffer.byteLength(section)))
+}
+
+// Run one variant in a child process, returning its peak RSS in bytes.
+export function measure(variant, input) {
+  const output = execFileSync(process.execPath, [script, '--child', variant, input], {
+    encoding: 'utf8',
+    maxBuffer: 1024 * 1024,
+  })
+  return JSON.parse(output)
+}
+
+async function child(variant, input) {
+  const { convert, convertToBuffer } = await import('../index.js')
+  const out = join(input, '..', `${variant}.md`)
+  let bytes = 0
+  switch (variant) {
+    case 'baseline':
+      // Loading the binding and reading the file, without converting
+      bytes = readFileSync(input).length
+      break
+    case 'string': {
+      const markdown = convert(readFileSync(input, 'utf8'))
+      writeFileSync(out, markdown)
+      bytes = Buffer.byteLength(markdown)
+      break
+    }
+    case 'buffer': {
+      const markdown = convertToBuffer(readFileSync(input))
+      writeFileSync(out, markdown)
+      bytes = markdown.length
+      break
+    }
+  }
+  process.stdout.write(JSON.stringify({ peakRss: process.resourceUsage().maxRSS * 1024, bytes }))
+}
+
+function mib(bytes) {
+  return `${(bytes / 1024 / 1024).toFixed(0)} MiB`
+}
+
+async function main() {
+  const megabytes = Number(process.argv[2] ?? 50)
+  const dir = mkdtempSync(join(tmpdir(), 'supermarkdown-memory-'))
+  try {
+    const input = join(dir, 'input.html')
+    writeFileSync(input, largeDocument(megabytes))
+
+    const baseline = measure('baseline', input).peakRss
+    console.log(`input: ${mib(readFileSync(input).length)}, baseline peak RSS: ${mib(baseline)}`)
+    for (const variant of ['string', 'buffer']) {
+      const { peakRss, bytes } = measure(variant, input)
+      console.log(`${variant.padEnd(6)}  peak RSS ${mib(peakRss)} (+${mib(peakRss - baseline)}), output ${mib(bytes)}`)
+    }
+  } finally {
+    rmSync(dir, { recursive: true, force: true })
+  }
+}
+
+if (process.argv[1] === script) {
+  if (process.argv[2] === '--child') {
+    await child(process.argv[3], process.argv[4])
+  } else {
+    await main()
+  }
+}
@@ -13,28 +13,167 @@ export interface ConvertOptions {
   codeFence?: string
   /** Bullet marker for unordered lists: "-" (default), "*", or "+" */
   bulletMarker?: string
+  /** Strikethrough style: "double" (default, `~~text~~`), "single" (`~text~`) or "html" (`<del>text</del>`) */
+  strikethroughStyle?: string
   /** Base URL for resolving relative links */
   baseUrl?: string
   /** CSS selectors for elements to exclude */
   excludeSelectors?: Array<string>
   /** CSS selectors for elements to force keep (overrides excludes) */
   includeSelectors?: Array<string>
+  /**
+   * Return `<script type="application/ld+json">` data in the metadata
+   * result instead of converting it
+   */
+  extractJsonLd?: boolean
+  /**
+   * Breadcrumb trails: "list" (default), "inline" (`Home > Docs > API`,
+   * before the first heading) or "skip"
+   */
+  breadcrumbs?: string
 }
 /**
  * Convert HTML to Markdown synchronously.
  *
- * @param html - The HTML string to convert
+ * @param html - The HTML to convert, as a string or a Buffer of UTF-8 bytes
  * @param options - Optional conversion options
  * @returns The converted Markdown string
  */
-export declare function convert(html: string, options?: ConvertOptions | undefined | null): string
+export declare function convert(html: string | Buffer, options?: ConvertOptions | undefined | null): string
+/**
+ * Convert HTML to Markdown, returning the UTF-8 bytes of the Markdown.
+ *
+ * The Buffer wraps the converted output without copying it into a
+ * JavaScript string, for writing straight to a file or socket.
+ *
+ * @param html - The HTML to convert, as a string or a Buffer of UTF-8 bytes
+ * @param options - Optional conversion options
+ * @returns The converted Markdown as a Buffer
+ */
+export declare function convertToBuffer(html: string | Buffer, options?: ConvertOptions | undefined | null): Buffer
+/** Statistics about how much of the document made it into the output. */
+export interface ConversionStats {
+  /** Elements visited in the parsed document */
+  elementsVisited: number
+  /** Elements matched by an exclude selector (subtree roots only) */
+  elementsSkipped: number
+  /** Skipped subtree roots per exclude selector */
+  skippedBySelector: Record<string, number>
+  /** Bytes of text inside skipped elements */
+  textBytesDropped: number
+  /** Images inside skipped elements or that produced no output */
+  imagesDropped: number
+  /** Links converted to markdown */
+  linksConverted: number
+  /** Tables converted to markdown */
+  tablesConverted: number
+  /**
+   * Elements no rule handles per tag name, not counting div, span and
+   * other grouping tags
+   */
+  unhandledTags: Record<string, number>
+  /** JSON-LD blocks left out as invalid JSON, with `extractJsonLd` */
+  jsonLdInvalid: number
+}
+/** Markdown together with metadata about the conversion. */
+export interface ConversionResult {
+  /** The converted Markdown string */
+  markdown: string
+  /** Content statistics */
+  stats: ConversionStats
+  /** Text of the document's `<title>`, if it has one */
+  title?: string
+  /** Parsed JSON-LD blocks, in document order, with `extractJsonLd` */
+  jsonLd: Array<any>
+  /** Steps of the document's first breadcrumb trail */
+  breadcrumbs: Array<string>
+}
+/**
+ * Convert HTML to Markdown, also returning conversion statistics.
+ *
+ * @param html - The HTML string to convert
+ * @param options - Optional conversion options
+ * @returns The Markdown string and statistics about skipped content
+ */
+export declare function convertWithMetadata(html: string, options?: ConvertOptions | undefined | null): ConversionResult
+/** Something a conversion dropped or ignored. */
+export interface ConversionWarning {
+  /**
+   * What the warning is about: "INVALID_SELECTOR", "INVALID_OPTION" or
+   * "TRUNCATED_OUTPUT"
+   */
+  code: string
+  /** Description of what happened */
+  message: string
+  /** The value at fault, like the selector */
+  detail?: string
+}
+/** Markdown together with warnings about what the conversion lost. */
+export interface DiagnosticsResult {
+  /** The converted Markdown string */
+  markdown: string
+  /**
+   * Ignored options and limits that cut the output short; empty when
+   * nothing was lost
+   */
+  warnings: Array<ConversionWarning>
+}
+/**
+ * Convert HTML to Markdown, also returning warnings for invalid selectors
+ * and options that were ignored and for truncated output.
+ *
+ * @param html - The HTML string to convert
+ * @param options - Optional conversion options
+ * @returns The Markdown string and its warnings
+ */
+export declare function convertWithDiagnostics(html: string, options?: ConvertOptions | undefined | null): DiagnosticsResult
+/** Where a block of the Markdown came from in the HTML. */
+export interface SourceMapEntry {
+  /** Start of the block in the Markdown, in UTF-16 code units */
+  mdStart: number
+  /** End of the block in the Markdown (exclusive), in UTF-16 code units */
+  mdEnd: number
+  /** Tag name of the HTML element */
+  htmlTag: string
+  /** `id` attribute of the HTML element */
+  htmlId?: string
+  /** `class` attribute of the HTML element */
+  htmlClass?: string
+}
+/** Markdown together with a map from Markdown ranges to HTML elements. */
+export interface SourceMapResult {
+  /** The converted Markdown string */
+  markdown: string
+  /** One entry per converted block element, in document order */
+  map: Array<SourceMapEntry>
+}
+/**
+ * Convert HTML to Markdown, also mapping each Markdown block back to the
+ * HTML element it came from.
+ *
+ * Offsets index the JavaScript string, so `markdown.slice(mdStart, mdEnd)`
+ * is the block's Markdown.
+ *
+ * @param html - The HTML string to convert
+ * @param options - Optional conversion options
+ * @returns The Markdown string and its source map
+ */
+export declare function convertWithSourceMap(html: string, options?: ConvertOptions | undefined | null): SourceMapResult
 /**
  * Convert HTML to Markdown asynchronously.
  *
  * This is useful for large documents to avoid blocking the main thread.
  *
- * @param html - The HTML string to convert
+ * `onProgress` is called with the percentage done (1 to 100, increasing)
+ * as the conversion passes each phase. It is never called after the
+ * promise settles, and exceptions it throws are ignored.
+ *
+ * A Buffer is read in place while the conversion runs, so it must not be
+ * modified until the promise settles.
+ *
+ * @param html - The HTML to convert, as a string or a Buffer of UTF-8 bytes
  * @param options - Optional conversion options
+ * @param onProgress - Optional progress callback
  * @returns A promise that resolves to the converted Markdown string
  */
-export declare function convertAsync(html: string, options?: ConvertOptions | undefined | null): Promise<string>
+export declare function convertAsync(html: string | Buffer, options?: ConvertOptions | undefined | null, onProgress?: ((percent: number) => void) | undefined | null): Promise<string>
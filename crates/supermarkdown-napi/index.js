@@ -310,7 +310,11 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { convert, convertAsync } = nativeBinding
+const { convert, convertToBuffer, convertWithMetadata, convertWithDiagnostics, convertWithSourceMap, convertAsync } = nativeBinding
 
 module.exports.convert = convert
+module.exports.convertToBuffer = convertToBuffer
+module.exports.convertWithMetadata = convertWithMetadata
+module.exports.convertWithDiagnostics = convertWithDiagnostics
+module.exports.convertWithSourceMap = convertWithSourceMap
 module.exports.convertAsync = convertAsync
@@ -1,8 +1,17 @@
 //! Node.js bindings for supermarkdown.
 
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy::Fatal, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsObject, JsUnknown};
 use napi_derive::napi;
-use supermarkdown::{HeadingStyle, LinkStyle, Options};
+use supermarkdown::{BreadcrumbHandling, HeadingStyle, LinkStyle, Options, StrikethroughStyle};
+use tokio::sync::oneshot;
 
 /// Options for HTML to Markdown conversion.
 #[derive(Default)]
@@ -16,12 +25,20 @@ pub struct ConvertOptions {
     pub code_fence: Option<String>,
     /// Bullet marker for unordered lists: "-" (default), "*", or "+"
     pub bullet_marker: Option<String>,
+    /// Strikethrough style: "double" (default, `~~text~~`), "single" (`~text~`) or "html" (`<del>text</del>`)
+    pub strikethrough_style: Option<String>,
     /// Base URL for resolving relative links
     pub base_url: Option<String>,
     /// CSS selectors for elements to exclude
     pub exclude_selectors: Option<Vec<String>>,
     /// CSS selectors for elements to force keep (overrides excludes)
     pub include_selectors: Option<Vec<String>>,
+    /// Return `<script type="application/ld+json">` data in the metadata
+    /// result instead of converting it
+    pub extract_json_ld: Option<bool>,
+    /// Breadcrumb trails: "list" (default), "inline" (`Home > Docs > API`,
+    /// before the first heading) or "skip"
+    pub breadcrumbs: Option<String>,
 }
 
 /// Convert ConvertOptions to internal Options.
@@ -53,6 +70,14 @@ fn to_internal_options(opts: Option<ConvertOptions>) -> Options {
         options = options.bullet_marker(marker_char);
     }
 
+    if let Some(style) = opts.strikethrough_style {
+        options = match style.to_lowercase().as_str() {
+            "single" => options.strikethrough_style(StrikethroughStyle::SingleTilde),
+            "html" => options.strikethrough_style(StrikethroughStyle::Html),
+            _ => options.strikethrough_style(StrikethroughStyle::DoubleTilde),
+        };
+    }
+
     if let Some(url) = opts.base_url {
         options = options.base_url(Some(url));
     }
@@ -65,39 +90,414 @@ fn to_internal_options(opts: Option<ConvertOptions>) -> Options {
         options = options.include_selectors(selectors);
     }
 
+    if let Some(enabled) = opts.extract_json_ld {
+        options = options.extract_json_ld(enabled);
+    }
+
+    if let Some(handling) = opts.breadcrumbs {
+        options = match handling.to_lowercase().as_str() {
+            "inline" => options.breadcrumbs(BreadcrumbHandling::Inline),
+            "skip" => options.breadcrumbs(BreadcrumbHandling::Skip),
+            _ => options.breadcrumbs(BreadcrumbHandling::List),
+        };
+    }
+
     options
 }
 
+/// The HTML text of a string or a Buffer of UTF-8 bytes.
+///
+/// Bytes are read in place; invalid UTF-8 sequences are replaced with
+/// U+FFFD, which is the only case that copies them.
+fn html_text(html: &Either<String, Buffer>) -> Cow<'_, str> {
+    match html {
+        Either::A(html) => Cow::Borrowed(html),
+        Either::B(bytes) => String::from_utf8_lossy(bytes),
+    }
+}
+
 /// Convert HTML to Markdown synchronously.
 ///
-/// @param html - The HTML string to convert
+/// @param html - The HTML to convert, as a string or a Buffer of UTF-8 bytes
 /// @param options - Optional conversion options
 /// @returns The converted Markdown string
 #[napi]
-pub fn convert(html: String, options: Option<ConvertOptions>) -> String {
+pub fn convert(html: Either<String, Buffer>, options: Option<ConvertOptions>) -> String {
+    convert_text(&html_text(&html), options)
+}
+
+fn convert_text(html: &str, options: Option<ConvertOptions>) -> String {
+    let opts = to_internal_options(options);
+    supermarkdown::convert_with_options(html, &opts)
+}
+
+/// Convert HTML to Markdown, returning the UTF-8 bytes of the Markdown.
+///
+/// The Buffer wraps the converted output without copying it into a
+/// JavaScript string, for writing straight to a file or socket.
+///
+/// @param html - The HTML to convert, as a string or a Buffer of UTF-8 bytes
+/// @param options - Optional conversion options
+/// @returns The converted Markdown as a Buffer
+#[napi]
+pub fn convert_to_buffer(html: Either<String, Buffer>, options: Option<ConvertOptions>) -> Buffer {
+    convert(html, options).into_bytes().into()
+}
+
+/// Statistics about how much of the document made it into the output.
+#[napi(object)]
+pub struct ConversionStats {
+    /// Elements visited in the parsed document
+    pub elements_visited: u32,
+    /// Elements matched by an exclude selector (subtree roots only)
+    pub elements_skipped: u32,
+    /// Skipped subtree roots per exclude selector
+    pub skipped_by_selector: HashMap<String, u32>,
+    /// Bytes of text inside skipped elements
+    pub text_bytes_dropped: u32,
+    /// Images inside skipped elements or that produced no output
+    pub images_dropped: u32,
+    /// Links converted to markdown
+    pub links_converted: u32,
+    /// Tables converted to markdown
+    pub tables_converted: u32,
+    /// Elements no rule handles per tag name, not counting div, span and
+    /// other grouping tags
+    pub unhandled_tags: HashMap<String, u32>,
+    /// JSON-LD blocks left out as invalid JSON, with `extractJsonLd`
+    pub json_ld_invalid: u32,
+}
+
+impl From<supermarkdown::ConversionStats> for ConversionStats {
+    fn from(stats: supermarkdown::ConversionStats) -> Self {
+        Self {
+            elements_visited: clamp(stats.elements_visited),
+            elements_skipped: clamp(stats.elements_skipped),
+            skipped_by_selector: stats
+                .skipped_by_selector
+                .into_iter()
+                .map(|(selector, count)| (selector, clamp(count)))
+                .collect(),
+            text_bytes_dropped: clamp(stats.text_bytes_dropped),
+            images_dropped: clamp(stats.images_dropped),
+            links_converted: clamp(stats.links_converted),
+            tables_converted: clamp(stats.tables_converted),
+            unhandled_tags: stats
+                .unhandled_tags
+                .into_iter()
+                .map(|(tag, count)| (tag, clamp(count)))
+                .collect(),
+            json_ld_invalid: clamp(stats.json_ld_invalid),
+        }
+    }
+}
+
+/// Saturate a count into the u32 range JavaScript numbers get here.
+fn clamp(count: usize) -> u32 {
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+/// A link found in the document.
+#[napi(object)]
+pub struct ExtractedLink {
+    /// The `href` attribute as written
+    pub href: String,
+    /// The `href` resolved against `baseUrl`
+    pub url: String,
+    /// Link text with whitespace collapsed
+    pub text: String,
+    /// Whether the link is relative or on the host of `baseUrl`
+    pub internal: bool,
+    /// Whether the link is missing from the output, e.g. inside an excluded element
+    pub dropped: bool,
+}
+
+impl From<supermarkdown::ExtractedLink> for ExtractedLink {
+    fn from(link: supermarkdown::ExtractedLink) -> Self {
+        Self {
+            href: link.href,
+            url: link.url,
+            text: link.text,
+            internal: link.internal,
+            dropped: link.dropped,
+        }
+    }
+}
+
+/// An image found in the document.
+#[napi(object)]
+pub struct ExtractedImage {
+    /// Image URL resolved against `baseUrl`; empty when the image has none
+    pub src: String,
+    /// The `alt` attribute
+    pub alt: String,
+    /// Whether the image is missing from the output
+    pub dropped: bool,
+}
+
+impl From<supermarkdown::ExtractedImage> for ExtractedImage {
+    fn from(image: supermarkdown::ExtractedImage) -> Self {
+        Self {
+            src: image.src,
+            alt: image.alt,
+            dropped: image.dropped,
+        }
+    }
+}
+
+/// Markdown together with metadata about the conversion.
+#[napi(object)]
+pub struct ConversionResult {
+    /// The converted Markdown string
+    pub markdown: String,
+    /// Content statistics
+    pub stats: ConversionStats,
+    /// Text of the document's `<title>`, if it has one
+    pub title: Option<String>,
+    /// Every link in the document, in document order
+    pub links: Vec<ExtractedLink>,
+    /// Every image in the document, in document order
+    pub images: Vec<ExtractedImage>,
+    /// Parsed JSON-LD blocks, in document order, with `extractJsonLd`
+    pub json_ld: Vec<serde_json::Value>,
+    /// Steps of the document's first breadcrumb trail
+    pub breadcrumbs: Vec<String>,
+}
+
+/// Convert HTML to Markdown, also returning conversion statistics.
+///
+/// @param html - The HTML string to convert
+/// @param options - Optional conversion options
+/// @returns The Markdown string and statistics about skipped content
+#[napi]
+pub fn convert_with_metadata(html: String, options: Option<ConvertOptions>) -> ConversionResult {
+    let opts = to_internal_options(options);
+    let (markdown, metadata) = supermarkdown::convert_with_metadata(&html, &opts);
+    ConversionResult {
+        markdown,
+        stats: metadata.stats.into(),
+        title: metadata.title,
+        links: metadata.links.into_iter().map(Into::into).collect(),
+        images: metadata.images.into_iter().map(Into::into).collect(),
+        json_ld: metadata.json_ld,
+        breadcrumbs: metadata.breadcrumbs,
+    }
+}
+
+/// Something a conversion dropped or ignored.
+#[napi(object)]
+pub struct ConversionWarning {
+    /// What the warning is about: "INVALID_SELECTOR", "INVALID_OPTION" or
+    /// "TRUNCATED_OUTPUT"
+    pub code: String,
+    /// Description of what happened
+    pub message: String,
+    /// The value at fault, like the selector
+    pub detail: Option<String>,
+}
+
+impl From<supermarkdown::Warning> for ConversionWarning {
+    fn from(warning: supermarkdown::Warning) -> Self {
+        Self {
+            code: warning.code.as_str().to_string(),
+            message: warning.message,
+            detail: warning.detail,
+        }
+    }
+}
+
+/// Markdown together with warnings about what the conversion lost.
+#[napi(object)]
+pub struct DiagnosticsResult {
+    /// The converted Markdown string
+    pub markdown: String,
+    /// Ignored options and limits that cut the output short; empty when
+    /// nothing was lost
+    pub warnings: Vec<ConversionWarning>,
+}
+
+/// Convert HTML to Markdown, also returning warnings for invalid selectors
+/// and options that were ignored and for truncated output.
+///
+/// @param html - The HTML string to convert
+/// @param options - Optional conversion options
+/// @returns The Markdown string and its warnings
+#[napi]
+pub fn convert_with_diagnostics(
+    html: String,
+    options: Option<ConvertOptions>,
+) -> DiagnosticsResult {
+    let opts = to_internal_options(options);
+    let (markdown, metadata) = supermarkdown::convert_with_metadata(&html, &opts);
+    DiagnosticsResult {
+        markdown,
+        warnings: metadata.warnings.into_iter().map(Into::into).collect(),
+    }
+}
+
+/// Where a block of the Markdown came from in the HTML.
+#[napi(object)]
+pub struct SourceMapEntry {
+    /// Start of the block in the Markdown, in UTF-16 code units
+    pub md_start: u32,
+    /// End of the block in the Markdown (exclusive), in UTF-16 code units
+    pub md_end: u32,
+    /// Tag name of the HTML element
+    pub html_tag: String,
+    /// `id` attribute of the HTML element
+    pub html_id: Option<String>,
+    /// `class` attribute of the HTML element
+    pub html_class: Option<String>,
+}
+
+/// Markdown together with a map from Markdown ranges to HTML elements.
+#[napi(object)]
+pub struct SourceMapResult {
+    /// The converted Markdown string
+    pub markdown: String,
+    /// One entry per converted block element, in document order
+    pub map: Vec<SourceMapEntry>,
+}
+
+/// Convert HTML to Markdown, also mapping each Markdown block back to the
+/// HTML element it came from.
+///
+/// Offsets index the JavaScript string, so `markdown.slice(mdStart, mdEnd)`
+/// is the block's Markdown.
+///
+/// @param html - The HTML string to convert
+/// @param options - Optional conversion options
+/// @returns The Markdown string and its source map
+#[napi]
+pub fn convert_with_source_map(html: String, options: Option<ConvertOptions>) -> SourceMapResult {
     let opts = to_internal_options(options);
-    supermarkdown::convert_with_options(&html, &opts)
+    let (markdown, map) = supermarkdown::convert_with_sourcemap(&html, &opts);
+
+    let mut offsets: Vec<usize> = map.iter().flat_map(|e| [e.md_start, e.md_end]).collect();
+    offsets.sort_unstable();
+    offsets.dedup();
+    let utf16 = utf16_offsets(&markdown, &offsets);
+    let to_utf16 = |byte: usize| clamp(utf16[offsets.binary_search(&byte).unwrap()]);
+
+    let map = map
+        .into_iter()
+        .map(|e| SourceMapEntry {
+            md_start: to_utf16(e.md_start),
+            md_end: to_utf16(e.md_end),
+            html_tag: e.html_tag,
+            html_id: e.html_id,
+            html_class: e.html_class,
+        })
+        .collect();
+    SourceMapResult { markdown, map }
+}
+
+/// UTF-16 positions of sorted byte offsets into `text`, in one pass.
+fn utf16_offsets(text: &str, offsets: &[usize]) -> Vec<usize> {
+    let mut result = Vec::with_capacity(offsets.len());
+    let mut units = 0;
+    let mut pending = offsets.iter().peekable();
+
+    for (byte, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        while pending.next_if(|&&offset| offset <= byte).is_some() {
+            result.push(units);
+        }
+        units += c.len_utf16();
+    }
+    result
+}
+
+/// Wraps a progress callback so that an exception it throws is dropped,
+/// rather than surfacing as an uncaught exception on the event loop.
+const GUARD_PROGRESS: &str = "(function (f) { return function (p) { try { f(p) } catch (e) {} } })";
+
+/// Make a progress callback callable from the conversion thread.
+fn progress_function(env: &Env, callback: JsFunction) -> Result<ThreadsafeFunction<u32, Fatal>> {
+    let guard: JsFunction = env.run_script(GUARD_PROGRESS)?;
+    let guarded = JsFunction::try_from(guard.call(None, &[callback])?)?;
+    guarded.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<u32>| Ok(vec![ctx.value]))
 }
 
 /// Convert HTML to Markdown asynchronously.
 ///
 /// This is useful for large documents to avoid blocking the main thread.
 ///
-/// @param html - The HTML string to convert
+/// `onProgress` is called with the percentage done (1 to 100, increasing)
+/// as the conversion passes each phase. It is never called after the
+/// promise settles, and exceptions it throws are ignored.
+///
+/// A Buffer is read in place while the conversion runs, so it must not be
+/// modified until the promise settles.
+///
+/// @param html - The HTML to convert, as a string or a Buffer of UTF-8 bytes
 /// @param options - Optional conversion options
+/// @param onProgress - Optional progress callback
 /// @returns A promise that resolves to the converted Markdown string
-#[napi]
-pub async fn convert_async(html: String, options: Option<ConvertOptions>) -> Result<String> {
+#[napi(
+    ts_args_type = "html: string | Buffer, options?: ConvertOptions | undefined | null, onProgress?: ((percent: number) => void) | undefined | null",
+    ts_return_type = "Promise<string>"
+)]
+pub fn convert_async(
+    env: Env,
+    html: Either<String, Buffer>,
+    options: Option<ConvertOptions>,
+    on_progress: Option<JsFunction>,
+) -> Result<JsObject> {
     let opts = to_internal_options(options);
+    let progress = on_progress
+        .map(|callback| progress_function(&env, callback))
+        .transpose()?;
 
-    // Use tokio's spawn_blocking to run the CPU-intensive conversion
-    // on a separate thread pool, avoiding blocking the Node.js event loop
-    let result =
-        tokio::task::spawn_blocking(move || supermarkdown::convert_with_options(&html, &opts))
+    env.execute_tokio_future(
+        async move {
+            // Use tokio's spawn_blocking to run the CPU-intensive conversion
+            // on a separate thread pool, avoiding blocking the Node.js event loop
+            let (markdown, last_call) = tokio::task::spawn_blocking(move || {
+                let html = html_text(&html);
+                match progress {
+                    Some(progress) => convert_reporting(&html, &opts, &progress),
+                    None => (supermarkdown::convert_with_options(&html, &opts), None),
+                }
+            })
             .await
             .map_err(|e| Error::from_reason(format!("Conversion failed: {}", e)))?;
 
-    Ok(result)
+            // Calls run in order, so once the last one has run there are none
+            // left to arrive after the promise resolves
+            if let Some(last_call) = last_call {
+                let _ = last_call.await;
+            }
+            Ok(markdown)
+        },
+        |_, markdown| Ok(markdown),
+    )
+}
+
+/// Convert, queueing a call to `progress` for each reported value.
+///
+/// Also returns a receiver that completes once the last queued call has run.
+fn convert_reporting(
+    html: &str,
+    options: &Options,
+    progress: &ThreadsafeFunction<u32, Fatal>,
+) -> (String, Option<oneshot::Receiver<()>>) {
+    let last_call = RefCell::new(None);
+    let markdown = supermarkdown::convert_with_progress(html, options, |percent| {
+        let (ran, receiver) = oneshot::channel();
+        progress.call_with_return_value(
+            u32::from(percent),
+            ThreadsafeFunctionCallMode::NonBlocking,
+            move |_: JsUnknown| {
+                let _ = ran.send(());
+                Ok(())
+            },
+        );
+        *last_call.borrow_mut() = Some(receiver);
+    });
+    (markdown, last_call.into_inner())
 }
 
 #[cfg(test)]
@@ -107,7 +507,7 @@ mod tests {
     #[test]
     fn test_convert_basic() {
         let html = "<h1>Hello</h1><p>World</p>";
-        let result = convert(html.to_string(), None);
+        let result = convert_text(html, None);
         assert!(result.contains("# Hello"));
         assert!(result.contains("World"));
     }
@@ -120,11 +520,14 @@ mod tests {
             link_style: None,
             code_fence: None,
             bullet_marker: None,
+            strikethrough_style: None,
             base_url: None,
             exclude_selectors: None,
             include_selectors: None,
+            extract_json_ld: None,
+            breadcrumbs: None,
         };
-        let result = convert(html.to_string(), Some(options));
+        let result = convert_text(html, Some(options));
         assert!(result.contains("====="));
     }
 
@@ -136,12 +539,130 @@ mod tests {
             link_style: None,
             code_fence: None,
             bullet_marker: None,
+            strikethrough_style: None,
             base_url: None,
             exclude_selectors: Some(vec!["nav".to_string()]),
             include_selectors: None,
+            extract_json_ld: None,
+            breadcrumbs: None,
         };
-        let result = convert(html.to_string(), Some(options));
+        let result = convert_text(html, Some(options));
         assert!(!result.contains("Skip"));
         assert!(result.contains("Keep"));
     }
+
+    #[test]
+    fn test_convert_with_strikethrough_style() {
+        let options = ConvertOptions {
+            strikethrough_style: Some("single".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(convert_text("<del>old</del>", Some(options)), "~old~\n");
+    }
+
+    #[test]
+    fn test_convert_with_metadata() {
+        let html = "<nav>Skip</nav><p><a href=\"/\">Keep</a></p>";
+        let options = ConvertOptions {
+            exclude_selectors: Some(vec!["nav".to_string()]),
+            ..Default::default()
+        };
+        let result = convert_with_metadata(html.to_string(), Some(options));
+        assert_eq!(result.markdown, "[Keep](/)\n");
+        assert_eq!(result.stats.elements_skipped, 1);
+        assert_eq!(result.stats.skipped_by_selector.get("nav"), Some(&1));
+        assert_eq!(result.stats.links_converted, 1);
+        assert_eq!(result.title, None);
+        assert_eq!(result.links.len(), 1);
+        assert_eq!(result.links[0].text, "Keep");
+        assert!(!result.links[0].dropped);
+    }
+
+    #[test]
+    fn test_convert_with_metadata_links_and_images() {
+        let html = "<nav><a href=\"/\">Home</a></nav>\
+                    <p><a href=\"https://other.org/\">Out</a><img src=\"a.png\" alt=\"A\"></p>";
+        let options = ConvertOptions {
+            exclude_selectors: Some(vec!["nav".to_string()]),
+            base_url: Some("https://example.com/".to_string()),
+            ..Default::default()
+        };
+        let result = convert_with_metadata(html.to_string(), Some(options));
+        assert_eq!(result.links.len(), 2);
+        assert!(result.links[0].dropped && result.links[0].internal);
+        assert!(!result.links[1].dropped && !result.links[1].internal);
+        assert_eq!(result.images[0].src, "https://example.com/a.png");
+        assert_eq!(result.images[0].alt, "A");
+    }
+
+    #[test]
+    fn test_convert_with_metadata_json_ld() {
+        let html = "<script type=\"application/ld+json\">{\"@type\": \"Product\"}</script>\
+                    <script type=\"application/ld+json\">{oops</script><p>Kettle</p>";
+        let options = ConvertOptions {
+            extract_json_ld: Some(true),
+            ..Default::default()
+        };
+        let result = convert_with_metadata(html.to_string(), Some(options));
+        assert_eq!(result.markdown, "Kettle\n");
+        assert_eq!(
+            result.json_ld,
+            vec![serde_json::json!({"@type": "Product"})]
+        );
+        assert_eq!(result.stats.json_ld_invalid, 1);
+    }
+
+    #[test]
+    fn test_convert_with_metadata_breadcrumbs() {
+        let html = "<h1>API</h1><nav aria-label=\"breadcrumb\"><ol>\
+                    <li><a href=\"/\">Home</a></li><li>API</li></ol></nav>";
+        let options = ConvertOptions {
+            breadcrumbs: Some("inline".to_string()),
+            ..Default::default()
+        };
+        let result = convert_with_metadata(html.to_string(), Some(options));
+        assert_eq!(result.markdown, "[Home](/) > API\n\n# API\n");
+        assert_eq!(result.breadcrumbs, vec!["Home", "API"]);
+    }
+
+    #[test]
+    fn test_convert_with_diagnostics() {
+        let options = ConvertOptions {
+            exclude_selectors: Some(vec![".ad".to_string(), "div[".to_string()]),
+            ..Default::default()
+        };
+        let result = convert_with_diagnostics("<p>Text</p>".to_string(), Some(options));
+        assert_eq!(result.markdown, "Text\n");
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].code, "INVALID_SELECTOR");
+        assert_eq!(result.warnings[0].detail.as_deref(), Some("div["));
+
+        let result = convert_with_diagnostics("<p>Text</p>".to_string(), None);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_convert_with_source_map() {
+        let html = "<h1>Caf\u{e9} \u{1F600}</h1><p id=\"intro\" class=\"lead\">Hello</p>";
+        let result = convert_with_source_map(html.to_string(), None);
+        let utf16: Vec<u16> = result.markdown.encode_utf16().collect();
+        let slice = |e: &SourceMapEntry| {
+            String::from_utf16(&utf16[e.md_start as usize..e.md_end as usize]).unwrap()
+        };
+
+        assert_eq!(result.map.len(), 2);
+        assert_eq!(result.map[0].html_tag, "h1");
+        assert_eq!(slice(&result.map[0]), "# Caf\u{e9} \u{1F600}");
+        assert_eq!(result.map[1].html_tag, "p");
+        assert_eq!(result.map[1].html_id.as_deref(), Some("intro"));
+        assert_eq!(result.map[1].html_class.as_deref(), Some("lead"));
+        assert_eq!(slice(&result.map[1]), "Hello");
+    }
+
+    #[test]
+    fn test_utf16_offsets() {
+        // "é" is 2 bytes and 1 unit, the emoji 4 bytes and 2 units
+        let text = "a\u{e9}\u{1F600}b";
+        assert_eq!(utf16_offsets(text, &[0, 1, 3, 7, 8]), vec![0, 1, 2, 4, 5]);
+    }
 }
@@ -0,0 +1,160 @@
+//! Python bindings for supermarkdown.
+//!
+//! Option keyword arguments mirror the Node.js bindings in snake_case:
+//! `heading_style`, `link_style`, `code_fence`, `bullet_marker`,
+//! `strikethrough_style`, `base_url`, `exclude_selectors`, and
+//! `include_selectors`.
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+use supermarkdown::{HeadingStyle, LinkStyle, Options, StrikethroughStyle};
+
+/// Build Options from keyword arguments, rejecting invalid values.
+fn to_internal_options(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Options> {
+    let mut options = Options::new();
+    let Some(kwargs) = kwargs else {
+        return Ok(options);
+    };
+
+    for (key, value) in kwargs.iter() {
+        let key: String = key.extract()?;
+        if value.is_none() {
+            continue;
+        }
+
+        options = match key.as_str() {
+            "heading_style" => match extract_str(&key, &value)?.to_lowercase().as_str() {
+                "atx" => options.heading_style(HeadingStyle::Atx),
+                "setext" => options.heading_style(HeadingStyle::Setext),
+                other => return Err(invalid(&key, other, "'atx' or 'setext'")),
+            },
+            "link_style" => match extract_str(&key, &value)?.to_lowercase().as_str() {
+                "inline" => options.link_style(LinkStyle::Inline),
+                "referenced" | "reference" => options.link_style(LinkStyle::Referenced),
+                other => return Err(invalid(&key, other, "'inline' or 'referenced'")),
+            },
+            "code_fence" => match extract_str(&key, &value)?.as_str() {
+                "`" => options.code_fence('`'),
+                "~" => options.code_fence('~'),
+                other => return Err(invalid(&key, other, "'`' or '~'")),
+            },
+            "bullet_marker" => match extract_str(&key, &value)?.as_str() {
+                "-" => options.bullet_marker('-'),
+                "*" => options.bullet_marker('*'),
+                "+" => options.bullet_marker('+'),
+                other => return Err(invalid(&key, other, "'-', '*', or '+'")),
+            },
+            "strikethrough_style" => match extract_str(&key, &value)?.to_lowercase().as_str() {
+                "double" => options.strikethrough_style(StrikethroughStyle::DoubleTilde),
+                "single" => options.strikethrough_style(StrikethroughStyle::SingleTilde),
+                "html" => options.strikethrough_style(StrikethroughStyle::Html),
+                other => return Err(invalid(&key, other, "'double', 'single', or 'html'")),
+            },
+            "base_url" => options.base_url(Some(extract_str(&key, &value)?)),
+            "exclude_selectors" => options.exclude_selectors(extract_list(&key, &value)?),
+            "include_selectors" => options.include_selectors(extract_list(&key, &value)?),
+            _ => return Err(PyTypeError::new_err(format!("unexpected option '{}'", key))),
+        };
+    }
+
+    Ok(options)
+}
+
+fn extract_str(key: &str, value: &Bound<'_, PyAny>) -> PyResult<String> {
+    value
+        .extract()
+        .map_err(|_| PyValueError::new_err(format!("{}: expected a string", key)))
+}
+
+fn extract_list(key: &str, value: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+    value
+        .extract()
+        .map_err(|_| PyValueError::new_err(format!("{}: expected a list of strings", key)))
+}
+
+fn invalid(key: &str, value: &str, expected: &str) -> PyErr {
+    PyValueError::new_err(format!(
+        "{}: invalid value '{}', expected {}",
+        key, value, expected
+    ))
+}
+
+/// Reusable converter holding a fixed set of options.
+#[pyclass(module = "supermarkdown", frozen)]
+struct Converter {
+    options: Options,
+}
+
+#[pymethods]
+impl Converter {
+    #[new]
+    #[pyo3(signature = (**options))]
+    fn new(options: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        Ok(Self {
+            options: to_internal_options(options)?,
+        })
+    }
+
+    /// Convert one HTML document to Markdown.
+    fn convert(&self, py: Python<'_>, html: &str) -> String {
+        py.allow_threads(|| supermarkdown::convert_with_options(html, &self.options))
+    }
+
+    /// Convert many HTML documents in parallel, releasing the GIL.
+    fn convert_many(&self, py: Python<'_>, htmls: Vec<String>) -> Vec<String> {
+        py.allow_threads(|| convert_all(&htmls, &self.options))
+    }
+}
+
+/// Convert HTML to Markdown.
+#[pyfunction]
+#[pyo3(signature = (html, **options))]
+fn convert(py: Python<'_>, html: &str, options: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+    let options = to_internal_options(options)?;
+    Ok(py.allow_threads(|| supermarkdown::convert_with_options(html, &options)))
+}
+
+/// Convert many HTML documents to Markdown in parallel, releasing the GIL.
+#[pyfunction]
+#[pyo3(signature = (htmls, **options))]
+fn convert_many(
+    py: Python<'_>,
+    htmls: Vec<String>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Vec<String>> {
+    let options = to_internal_options(options)?;
+    Ok(py.allow_threads(|| convert_all(&htmls, &options)))
+}
+
+fn convert_all(htmls: &[String], options: &Options) -> Vec<String> {
+    htmls
+        .par_iter()
+        .map(|html| supermarkdown::convert_with_options(html, options))
+        .collect()
+}
+
+#[pymodule]
+#[pyo3(name = "supermarkdown")]
+fn supermarkdown_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(convert, m)?)?;
+    m.add_function(wrap_pyfunction!(convert_many, m)?)?;
+    m.add_class::<Converter>()?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_all_preserves_order() {
+        let htmls: Vec<String> = (0..20).map(|i| format!("<h1>Doc {}</h1>", i)).collect();
+        let results = convert_all(&htmls, &Options::default());
+        for (i, md) in results.iter().enumerate() {
+            assert_eq!(md, &format!("# Doc {}\n", i));
+        }
+    }
+}
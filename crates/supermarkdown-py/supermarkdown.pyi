@@ -0,0 +1,44 @@
+from typing import List, Optional
+
+__version__: str
+
+def convert(
+    html: str,
+    *,
+    heading_style: Optional[str] = ...,
+    link_style: Optional[str] = ...,
+    code_fence: Optional[str] = ...,
+    bullet_marker: Optional[str] = ...,
+    strikethrough_style: Optional[str] = ...,
+    base_url: Optional[str] = ...,
+    exclude_selectors: Optional[List[str]] = ...,
+    include_selectors: Optional[List[str]] = ...,
+) -> str: ...
+def convert_many(
+    htmls: List[str],
+    *,
+    heading_style: Optional[str] = ...,
+    link_style: Optional[str] = ...,
+    code_fence: Optional[str] = ...,
+    bullet_marker: Optional[str] = ...,
+    strikethrough_style: Optional[str] = ...,
+    base_url: Optional[str] = ...,
+    exclude_selectors: Optional[List[str]] = ...,
+    include_selectors: Optional[List[str]] = ...,
+) -> List[str]: ...
+
+class Converter:
+    def __init__(
+        self,
+        *,
+        heading_style: Optional[str] = ...,
+        link_style: Optional[str] = ...,
+        code_fence: Optional[str] = ...,
+        bullet_marker: Optional[str] = ...,
+    strikethrough_style: Optional[str] = ...,
+        base_url: Optional[str] = ...,
+        exclude_selectors: Optional[List[str]] = ...,
+        include_selectors: Optional[List[str]] = ...,
+    ) -> None: ...
+    def convert(self, html: str) -> str: ...
+    def convert_many(self, htmls: List[str]) -> List[str]: ...
@@ -0,0 +1,77 @@
+"""Tests for the supermarkdown Python bindings.
+
+Run after `maturin develop`:
+
+    pytest crates/supermarkdown-py/tests
+"""
+
+import pytest
+
+import supermarkdown
+
+
+def test_convert_basic():
+    assert supermarkdown.convert("<h1>Hello</h1><p>World</p>") == "# Hello\n\nWorld\n"
+
+
+def test_convert_with_options():
+    md = supermarkdown.convert(
+        "<h1>Title</h1><nav>Menu</nav>",
+        heading_style="setext",
+        exclude_selectors=["nav"],
+    )
+    assert "=====" in md
+    assert "Menu" not in md
+
+
+def test_none_option_uses_default():
+    assert supermarkdown.convert("<h1>T</h1>", heading_style=None) == "# T\n"
+
+
+def test_convert_many_preserves_order():
+    docs = [f"<p>Doc {i}</p>" for i in range(50)]
+    assert supermarkdown.convert_many(docs) == [f"Doc {i}\n" for i in range(50)]
+
+
+def test_convert_many_with_options():
+    docs = ["<ul><li>a</li></ul>", "<ul><li>b</li></ul>"]
+    assert supermarkdown.convert_many(docs, bullet_marker="*") == ["* a\n", "* b\n"]
+
+
+def test_strikethrough_style():
+    assert supermarkdown.convert("<del>old</del>", strikethrough_style="single") == "~old~\n"
+    assert supermarkdown.convert("<del>old</del>", strikethrough_style="html") == "<del>old</del>\n"
+
+
+def test_converter_reuses_options():
+    converter = supermarkdown.Converter(link_style="referenced")
+    md = converter.convert('<a href="https://a.com">A</a>')
+    assert "[A][1]" in md
+    assert "[1]: https://a.com" in md
+    assert converter.convert_many(['<a href="https://b.com">B</a>']) == [
+        "[B][1]\n\n[1]: https://b.com\n"
+    ]
+
+
+@pytest.mark.parametrize(
+    "field, value",
+    [
+        ("heading_style", "fancy"),
+        ("link_style", "footnote"),
+        ("code_fence", "#"),
+        ("bullet_marker", "x"),
+        ("strikethrough_style", "wavy"),
+        ("exclude_selectors", "nav"),
+        ("base_url", 42),
+    ],
+)
+def test_invalid_option_raises_value_error(field, value):
+    with pytest.raises(ValueError, match=field):
+        supermarkdown.convert("<p>x</p>", **{field: value})
+    with pytest.raises(ValueError, match=field):
+        supermarkdown.Converter(**{field: value})
+
+
+def test_unknown_option_raises_type_error():
+    with pytest.raises(TypeError, match="heading_styles"):
+        supermarkdown.convert("<p>x</p>", heading_styles="atx")
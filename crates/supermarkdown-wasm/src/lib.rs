@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
-use supermarkdown::{HeadingStyle, LinkStyle, Options};
+use supermarkdown::{HeadingStyle, LinkStyle, Options, StrikethroughStyle};
 
 #[derive(Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -10,6 +10,7 @@ pub struct ConvertOptions {
     pub link_style: Option<String>,
     pub code_fence: Option<String>,
     pub bullet_marker: Option<String>,
+    pub strikethrough_style: Option<String>,
     pub base_url: Option<String>,
     pub exclude_selectors: Option<Vec<String>>,
     pub include_selectors: Option<Vec<String>>,
@@ -41,6 +42,14 @@ fn to_internal_options(opts: Option<ConvertOptions>) -> Options {
         options = options.bullet_marker(marker.chars().next().unwrap_or('-'));
     }
 
+    if let Some(style) = opts.strikethrough_style {
+        options = match style.to_lowercase().as_str() {
+            "single" => options.strikethrough_style(StrikethroughStyle::SingleTilde),
+            "html" => options.strikethrough_style(StrikethroughStyle::Html),
+            _ => options.strikethrough_style(StrikethroughStyle::DoubleTilde),
+        };
+    }
+
     if let Some(url) = opts.base_url {
         options = options.base_url(Some(url));
     }
@@ -62,15 +71,91 @@ pub fn convert(html: &str) -> String {
     supermarkdown::convert(html)
 }
 
-/// Convert HTML to Markdown with custom options
-#[wasm_bindgen(js_name = convertWithOptions)]
-pub fn convert_with_options(html: &str, options: JsValue) -> Result<String, JsError> {
+fn from_js_options(options: JsValue) -> Result<Options, JsError> {
     let opts: Option<ConvertOptions> = if options.is_undefined() || options.is_null() {
         None
     } else {
         Some(serde_wasm_bindgen::from_value(options)?)
     };
 
-    let internal_opts = to_internal_options(opts);
+    Ok(to_internal_options(opts))
+}
+
+/// Convert HTML to Markdown with custom options
+#[wasm_bindgen(js_name = convertWithOptions)]
+pub fn convert_with_options(html: &str, options: JsValue) -> Result<String, JsError> {
+    let internal_opts = from_js_options(options)?;
     Ok(supermarkdown::convert_with_options(html, &internal_opts))
 }
+
+/// A link found in the document
+#[derive(Serialize, Deserialize)]
+pub struct ExtractedLink {
+    pub href: String,
+    pub url: String,
+    pub text: String,
+    pub internal: bool,
+    pub dropped: bool,
+}
+
+/// An image found in the document
+#[derive(Serialize, Deserialize)]
+pub struct ExtractedImage {
+    pub src: String,
+    pub alt: String,
+    pub dropped: bool,
+}
+
+/// Markdown together with the document's title, links and images
+#[derive(Serialize, Deserialize)]
+pub struct ConversionResult {
+    pub markdown: String,
+    pub title: Option<String>,
+    pub links: Vec<ExtractedLink>,
+    pub images: Vec<ExtractedImage>,
+}
+
+/// Convert HTML to Markdown, also returning every link and image in the
+/// document, including ones left out of the output
+#[wasm_bindgen(js_name = convertWithMetadata)]
+pub fn convert_with_metadata(html: &str, options: JsValue) -> Result<JsValue, JsError> {
+    let internal_opts = from_js_options(options)?;
+    let (markdown, metadata) = supermarkdown::convert_with_metadata(html, &internal_opts);
+    let result = ConversionResult {
+        markdown,
+        title: metadata.title,
+        links: metadata
+            .links
+            .into_iter()
+            .map(|link| ExtractedLink {
+                href: link.href,
+                url: link.url,
+                text: link.text,
+                internal: link.internal,
+                dropped: link.dropped,
+            })
+            .collect(),
+        images: metadata
+            .images
+            .into_iter()
+            .map(|image| ExtractedImage {
+                src: image.src,
+                alt: image.alt,
+                dropped: image.dropped,
+            })
+            .collect(),
+    };
+    Ok(serde_wasm_bindgen::to_value(&result)?)
+}
+
+/// Convert UTF-8 encoded HTML to UTF-8 encoded Markdown.
+///
+/// Takes and returns `Uint8Array`s, skipping the UTF-16 string conversion
+/// `convertWithOptions` does in each direction. Invalid UTF-8 sequences are
+/// replaced with U+FFFD.
+#[wasm_bindgen(js_name = convertBytes)]
+pub fn convert_bytes(input: &[u8], options: JsValue) -> Result<Vec<u8>, JsError> {
+    let internal_opts = from_js_options(options)?;
+    let html = String::from_utf8_lossy(input);
+    Ok(supermarkdown::convert_with_options(&html, &internal_opts).into_bytes())
+}
@@ -0,0 +1,57 @@
+//! Browser tests; run with `wasm-pack test --headless --firefox`.
+
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+use supermarkdown_wasm::{
+    convert_bytes, convert_with_metadata, convert_with_options, ConversionResult,
+};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const HTML: &str = r#"<h1>Café menu</h1>
+<p>Prices in <strong>€</strong> — see <a href="/menu">the menu</a>.</p>
+<ul><li>Crème brûlée</li><li>Matcha 抹茶</li></ul>
+<pre><code class="language-js">console.log("☕");</code></pre>"#;
+
+fn options() -> JsValue {
+    serde_wasm_bindgen::to_value(&supermarkdown_wasm::ConvertOptions {
+        heading_style: Some("setext".to_string()),
+        base_url: Some("https://example.com".to_string()),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[wasm_bindgen_test]
+fn convert_bytes_matches_string_api() {
+    let from_bytes = convert_bytes(HTML.as_bytes(), JsValue::UNDEFINED).unwrap();
+    let from_string = convert_with_options(HTML, JsValue::UNDEFINED).unwrap();
+    assert_eq!(String::from_utf8(from_bytes).unwrap(), from_string);
+}
+
+#[wasm_bindgen_test]
+fn convert_bytes_applies_options() {
+    let from_bytes = convert_bytes(HTML.as_bytes(), options()).unwrap();
+    let from_string = convert_with_options(HTML, options()).unwrap();
+    assert_eq!(String::from_utf8(from_bytes).unwrap(), from_string);
+    assert!(from_string.contains("(https://example.com/menu)"));
+}
+
+#[wasm_bindgen_test]
+fn convert_bytes_replaces_invalid_utf8() {
+    let output = convert_bytes(b"<p>caf\xe9 ok</p>", JsValue::UNDEFINED).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "caf\u{FFFD} ok\n");
+}
+
+#[wasm_bindgen_test]
+fn convert_with_metadata_lists_links() {
+    let result = convert_with_metadata(HTML, options()).unwrap();
+    let result: ConversionResult = serde_wasm_bindgen::from_value(result).unwrap();
+    assert_eq!(result.links.len(), 1);
+    assert_eq!(result.links[0].url, "https://example.com/menu");
+    assert!(result.links[0].internal);
+    assert!(result.images.is_empty());
+}
@@ -1,7 +1,41 @@
 //! Benchmarks for HTML to Markdown conversion.
 
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "profile")]
+use std::time::Duration;
+
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use supermarkdown::{convert, convert_with_options, Options};
+use supermarkdown::{
+    convert, convert_into, convert_with_options, postprocess_markdown, LinkStyle, Options,
+};
+#[cfg(feature = "profile")]
+use supermarkdown::{convert_with_metadata, PhaseTimings};
+
+/// System allocator that counts allocated bytes, to report allocation
+/// overhead in `bench_large_text_node`.
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size.saturating_sub(layout.size()), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
 
 /// Simple document with basic formatting.
 const SIMPLE_HTML: &str = r#"
@@ -52,132 +86,7 @@ fn main() {
 "#;
 
 /// Complex document with nested structures.
-const COMPLEX_HTML: &str = r#"
-<!DOCTYPE html>
-<html>
-<head><title>Complex Document</title></head>
-<body>
-<nav><a href="/">Home</a> | <a href="/about">About</a></nav>
-<main>
-    <article>
-        <header>
-            <h1>Complex Article Title</h1>
-            <p class="meta">Published on <time>2024-01-01</time></p>
-        </header>
-
-        <section>
-            <h2>Introduction</h2>
-            <p>This is a complex document with <strong>nested <em>formatting</em></strong> and various elements.</p>
-            <p>It includes <a href="https://example.com" title="Example">links with titles</a> and <code>code</code>.</p>
-        </section>
-
-        <section>
-            <h2>Lists</h2>
-            <h3>Unordered List</h3>
-            <ul>
-                <li>Item one with <strong>bold</strong></li>
-                <li>Item two with nested list:
-                    <ul>
-                        <li>Nested item A</li>
-                        <li>Nested item B</li>
-                    </ul>
-                </li>
-                <li>Item three</li>
-            </ul>
-
-            <h3>Ordered List</h3>
-            <ol>
-                <li>First step</li>
-                <li>Second step with <a href="/step2">link</a></li>
-                <li>Third step</li>
-            </ol>
-        </section>
-
-        <section>
-            <h2>Code Examples</h2>
-            <pre><code class="language-python">
-def hello():
-    """Say hello."""
-    print("Hello, World!")
-
-if __name__ == "__main__":
-    hello()
-            </code></pre>
-
-            <pre><code class="language-javascript">
-function greet(name) {
-    console.log(`Hello, ${name}!`);
-}
-
-greet("World");
-            </code></pre>
-        </section>
-
-        <section>
-            <h2>Tables</h2>
-            <table>
-                <thead>
-                    <tr>
-                        <th>Feature</th>
-                        <th>Status</th>
-                        <th>Notes</th>
-                    </tr>
-                </thead>
-                <tbody>
-                    <tr>
-                        <td>Headings</td>
-                        <td>Complete</td>
-                        <td>ATX and Setext styles</td>
-                    </tr>
-                    <tr>
-                        <td>Lists</td>
-                        <td>Complete</td>
-                        <td>Ordered and unordered</td>
-                    </tr>
-                    <tr>
-                        <td>Links</td>
-                        <td>Complete</td>
-                        <td>Inline and referenced</td>
-                    </tr>
-                </tbody>
-            </table>
-        </section>
-
-        <section>
-            <h2>Blockquotes</h2>
-            <blockquote>
-                <p>This is a blockquote with multiple paragraphs.</p>
-                <p>Second paragraph in the quote with <em>emphasis</em>.</p>
-                <blockquote>
-                    <p>Nested blockquote for extra depth.</p>
-                </blockquote>
-            </blockquote>
-        </section>
-
-        <section>
-            <h2>Images</h2>
-            <figure>
-                <img src="photo.jpg" alt="A beautiful photo">
-                <figcaption>Caption for the image</figcaption>
-            </figure>
-        </section>
-
-        <details>
-            <summary>Click to expand</summary>
-            <p>Hidden content inside a details element.</p>
-        </details>
-
-        <footer>
-            <p>Article footer with <a href="/contact">contact</a> link.</p>
-        </footer>
-    </article>
-</main>
-<footer>
-    <p>&copy; 2024 Example Inc. All rights reserved.</p>
-</footer>
-</body>
-</html>
-"#;
+const COMPLEX_HTML: &str = include_str!("../tests/fixtures/complex.html");
 
 fn bench_conversion(c: &mut Criterion) {
     let mut group = c.benchmark_group("conversion");
@@ -245,6 +154,33 @@ fn bench_with_options(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_selectors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("selectors");
+
+    // No selectors takes the precompute fast path; a selector that never
+    // matches still pays for matching every element.
+    let no_selectors = Options::new();
+    let unmatched_selectors = Options::new().exclude_selectors(vec![".does-not-exist".to_string()]);
+    let matched_selectors =
+        Options::new().exclude_selectors(vec!["nav".to_string(), "footer".to_string()]);
+
+    group.throughput(Throughput::Bytes(COMPLEX_HTML.len() as u64));
+
+    group.bench_function("without_selectors", |b| {
+        b.iter(|| convert_with_options(black_box(COMPLEX_HTML), black_box(&no_selectors)));
+    });
+
+    group.bench_function("with_unmatched_selector", |b| {
+        b.iter(|| convert_with_options(black_box(COMPLEX_HTML), black_box(&unmatched_selectors)));
+    });
+
+    group.bench_function("with_matched_selectors", |b| {
+        b.iter(|| convert_with_options(black_box(COMPLEX_HTML), black_box(&matched_selectors)));
+    });
+
+    group.finish();
+}
+
 fn bench_repeated_conversion(c: &mut Criterion) {
     let mut group = c.benchmark_group("repeated_conversion");
 
@@ -261,13 +197,177 @@ fn bench_repeated_conversion(c: &mut Criterion) {
         });
     });
 
+    // One output buffer reused across documents
+    let options = Options::default();
+    group.bench_function("10_simple_docs_into", |b| {
+        let mut markdown = String::new();
+        b.iter(|| {
+            for doc in &documents {
+                convert_into(black_box(doc), &options, &mut markdown);
+            }
+        });
+    });
+
+    group.finish();
+}
+
+fn bench_large_text_node(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_text_node");
+    group.sample_size(10);
+
+    // A 10MB single-line text node, as in minified pages. Normalized text
+    // without entities is borrowed through entity decoding, whitespace
+    // normalization and escaping; the allocation reported here is parsing,
+    // building the output through the element tree, and postprocessing.
+    let text = "minified content ".repeat(10 * 1024 * 1024 / 17);
+    let html = format!("<p>{}</p>", text);
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let markdown = convert(&html);
+    let allocated = ALLOCATED.load(Ordering::Relaxed) - before;
+    assert_eq!(markdown.trim_end(), text.trim_end());
+    eprintln!(
+        "large_text_node: {} bytes input, {} bytes allocated ({:.1}x input)",
+        html.len(),
+        allocated,
+        allocated as f64 / html.len() as f64
+    );
+
+    let mut buffer = String::with_capacity(markdown.len());
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    convert_into(&html, &Options::default(), &mut buffer);
+    let allocated = ALLOCATED.load(Ordering::Relaxed) - before;
+    eprintln!(
+        "large_text_node: {} bytes allocated into a reused buffer ({:.1}x input)",
+        allocated,
+        allocated as f64 / html.len() as f64
+    );
+
+    group.throughput(Throughput::Bytes(html.len() as u64));
+    group.bench_function("10mb", |b| {
+        b.iter(|| convert(black_box(&html)));
+    });
+
     group.finish();
 }
 
+/// About 10MB of converted markdown dense with links: paragraphs of inline
+/// links, some with wrapped text or stray brackets, list items holding only
+/// an icon link, and code blocks.
+fn link_heavy_markdown() -> String {
+    let mut markdown = String::with_capacity(10 * 1024 * 1024 + 1024);
+    let mut i = 0;
+    while markdown.len() < 10 * 1024 * 1024 {
+        markdown.push_str(&format!(
+            "See [the guide](https://example.com/docs/{i}) and [its\nexamples](/ex/{i} \"Examples\") \
+             next to [the guide](https://example.com/docs/{i}), array [ index {i}.\n\n\
+             - [](https://twitter.com/share/{i})\n- [Item {i}](/items/{i})\n\n\
+             ```\nlet link = \"[x](y)\";\n```\n\n\n\n",
+        ));
+        i += 1;
+    }
+    markdown
+}
+
+fn bench_postprocess(c: &mut Criterion) {
+    let mut group = c.benchmark_group("postprocess");
+    group.sample_size(10);
+
+    let markdown = link_heavy_markdown();
+    group.throughput(Throughput::Bytes(markdown.len() as u64));
+
+    let option_sets = [
+        ("inline", Options::default()),
+        (
+            "referenced",
+            Options::new().link_style(LinkStyle::Referenced),
+        ),
+        ("merge_links", Options::new().merge_adjacent_links(true)),
+    ];
+    for (name, options) in &option_sets {
+        group.bench_with_input(BenchmarkId::new("10mb", name), &markdown, |b, markdown| {
+            b.iter(|| postprocess_markdown(black_box(markdown), options));
+        });
+    }
+
+    group.finish();
+}
+
+/// Reads the time of one phase.
+#[cfg(feature = "profile")]
+type PhaseTime = fn(&PhaseTimings) -> Duration;
+
+/// Time of each conversion phase, from the `profile` feature, so a
+/// regression in one phase isn't lost in the total. Run with
+/// `cargo bench -p supermarkdown --features profile -- phases`.
+///
+/// The share of each phase is printed before benchmarking. Typically
+/// parsing takes 40-55% of the time, conversion 30-35%, postprocessing
+/// 10-20% and precompute under 5%; pages with more markup than text, like
+/// the GitHub README fixture, spend more of it parsing.
+#[cfg(feature = "profile")]
+fn bench_phases(c: &mut Criterion) {
+    let mut group = c.benchmark_group("phases");
+
+    let documents = [
+        (
+            "news_article",
+            include_str!("../tests/fixtures/news_article.html"),
+        ),
+        (
+            "github_readme",
+            include_str!("../tests/fixtures/github_readme.html"),
+        ),
+        (
+            "wikipedia_section",
+            include_str!("../tests/fixtures/wikipedia_section.html"),
+        ),
+    ];
+    let phases: [(&str, PhaseTime); 4] = [
+        ("parse", |t| t.parse),
+        ("precompute", |t| t.precompute),
+        ("convert", |t| t.convert),
+        ("postprocess", |t| t.postprocess),
+    ];
+    let options = Options::default();
+    let timings = |html: &str| convert_with_metadata(black_box(html), &options).1.timings;
+
+    for (name, html) in documents {
+        let runs: Vec<PhaseTimings> = (0..100).map(|_| timings(html)).collect();
+        let total: Duration = runs.iter().map(PhaseTimings::total).sum();
+        let shares: Vec<String> = phases
+            .iter()
+            .map(|(phase, time)| {
+                let spent: Duration = runs.iter().map(time).sum();
+                let share = spent.as_secs_f64() / total.as_secs_f64() * 100.0;
+                format!("{} {:.0}%", phase, share)
+            })
+            .collect();
+        eprintln!("phases/{}: {}", name, shares.join(", "));
+
+        group.throughput(Throughput::Bytes(html.len() as u64));
+        for (phase, time) in phases {
+            group.bench_function(BenchmarkId::new(phase, name), |b| {
+                b.iter_custom(|iters| (0..iters).map(|_| time(&timings(html))).sum());
+            });
+        }
+    }
+
+    group.finish();
+}
+
+/// Phase timings need the `profile` feature.
+#[cfg(not(feature = "profile"))]
+fn bench_phases(_c: &mut Criterion) {}
+
 criterion_group!(
     benches,
     bench_conversion,
     bench_with_options,
-    bench_repeated_conversion
+    bench_selectors,
+    bench_repeated_conversion,
+    bench_large_text_node,
+    bench_postprocess,
+    bench_phases
 );
 criterion_main!(benches);
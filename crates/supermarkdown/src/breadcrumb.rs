@@ -0,0 +1,200 @@
+//! Breadcrumb trails like `<nav aria-label="breadcrumb"><ol>…</ol></nav>`.
+//!
+//! A trail converts to a list by default, taking a line per step. With
+//! `breadcrumbs` set to `Inline` it is written as one line instead,
+//! `Home > Docs > API`. Trails tell where a page sits rather than being part
+//! of its content, so the first one goes before the first heading even when
+//! a page has it below the title.
+
+use scraper::{ElementRef, Html};
+
+use crate::precompute::MetadataMap;
+use crate::whitespace::normalize_block_whitespace;
+
+/// Written between the steps of an inline trail.
+pub(crate) const SEPARATOR: &str = " > ";
+
+/// Whether `element` is a breadcrumb trail: a `nav`, `ol` or `ul` with
+/// "breadcrumb" in its `aria-label` or `class`, or typed as a schema.org
+/// `BreadcrumbList` with microdata.
+pub(crate) fn is_breadcrumb(element: ElementRef) -> bool {
+    let el = element.value();
+    if !matches!(el.name(), "nav" | "ol" | "ul") {
+        return false;
+    }
+    ["aria-label", "class", "itemtype"].iter().any(|name| {
+        el.attr(name)
+            .is_some_and(|value| value.to_ascii_lowercase().contains("breadcrumb"))
+    })
+}
+
+/// The steps of a trail: the items of its list, or the child elements of a
+/// `nav` holding bare links. Separators like `/` or `›` written as steps of
+/// their own are left out.
+pub(crate) fn steps(trail: ElementRef) -> Vec<ElementRef> {
+    let list = if trail.value().name() == "nav" {
+        trail
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|el| matches!(el.value().name(), "ol" | "ul"))
+    } else {
+        Some(trail)
+    };
+    let candidates: Vec<ElementRef> = match list {
+        Some(list) => list
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|child| child.value().name() == "li")
+            .collect(),
+        None => trail.children().filter_map(ElementRef::wrap).collect(),
+    };
+    candidates
+        .into_iter()
+        .filter(|step| step.text().any(|t| t.chars().any(char::is_alphanumeric)))
+        .collect()
+}
+
+/// Text of each step of a trail, whitespace collapsed.
+pub(crate) fn texts(trail: ElementRef) -> Vec<String> {
+    steps(trail)
+        .into_iter()
+        .map(|step| {
+            let text: String = step.text().collect();
+            normalize_block_whitespace(text.trim()).into_owned()
+        })
+        .collect()
+}
+
+/// The first trail in the document, and the first heading before it when
+/// there is one in the output.
+pub(crate) fn find<'a>(
+    dom: &'a Html,
+    metadata: &MetadataMap,
+) -> Option<(ElementRef<'a>, Option<ElementRef<'a>>)> {
+    let mut heading = None;
+    for element in dom
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+    {
+        if is_breadcrumb(element) {
+            return Some((element, heading));
+        }
+        let tag = element.value().name();
+        if heading.is_none()
+            && matches!(tag, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+            && !is_skipped(element, metadata)
+        {
+            heading = Some(element);
+        }
+    }
+    None
+}
+
+/// Whether `element` is left out of the output by an exclude selector or a
+/// handling option, and not kept by an include selector.
+pub(crate) fn is_skipped(element: ElementRef, metadata: &MetadataMap) -> bool {
+    for node in std::iter::once(*element).chain(element.ancestors()) {
+        if let Some(meta) = metadata.get(&node.id()) {
+            if meta.force_keep {
+                return false;
+            }
+            if meta.skip {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::{BreadcrumbHandling, Options};
+    use crate::{convert_with_metadata, convert_with_options};
+
+    const BOOTSTRAP: &str = r#"<nav aria-label="breadcrumb">
+        <ol class="breadcrumb">
+            <li class="breadcrumb-item"><a href="/">Home</a></li>
+            <li class="breadcrumb-item"><a href="/docs">Docs</a></li>
+            <li class="breadcrumb-item active" aria-current="page">API</li>
+        </ol>
+    </nav>
+    <h1>API reference</h1>
+    <p>Endpoints.</p>"#;
+
+    const MICRODATA: &str = r#"<h1>Chocolate cake</h1>
+    <ol itemscope itemtype="https://schema.org/BreadcrumbList">
+        <li itemprop="itemListElement" itemscope itemtype="https://schema.org/ListItem">
+            <a itemprop="item" href="/recipes"><span itemprop="name">Recipes</span></a>
+            <meta itemprop="position" content="1">
+        </li>
+        <li itemprop="itemListElement" itemscope itemtype="https://schema.org/ListItem">
+            <a itemprop="item" href="/recipes/cakes"><span itemprop="name">Cakes</span></a>
+            <meta itemprop="position" content="2">
+        </li>
+    </ol>
+    <p>Rich and dark.</p>"#;
+
+    fn inline() -> Options {
+        Options::new().breadcrumbs(BreadcrumbHandling::Inline)
+    }
+
+    #[test]
+    fn test_bootstrap_inline() {
+        assert_eq!(
+            convert_with_options(BOOTSTRAP, &inline()),
+            "[Home](/) > [Docs](/docs) > API\n\n# API reference\n\nEndpoints.\n"
+        );
+    }
+
+    #[test]
+    fn test_microdata_moved_before_heading() {
+        assert_eq!(
+            convert_with_options(MICRODATA, &inline()),
+            "[Recipes](/recipes) > [Cakes](/recipes/cakes)\n\n# Chocolate cake\n\nRich and dark.\n"
+        );
+    }
+
+    #[test]
+    fn test_bare_links_with_separators() {
+        let html = r#"<p>Intro.</p><nav class="breadcrumbs"><a href="/">Home</a> /
+            <span class="sep">›</span> <a href="/blog">Blog</a> <span>Post</span></nav>"#;
+        assert_eq!(
+            convert_with_options(html, &inline()),
+            "Intro.\n\n[Home](/) > [Blog](/blog) > Post\n"
+        );
+    }
+
+    #[test]
+    fn test_list_by_default_and_skip() {
+        let list = convert_with_options(BOOTSTRAP, &Options::new());
+        assert!(list.starts_with("1. [Home](/)\n2. [Docs](/docs)\n3. API\n"));
+
+        let options = Options::new().breadcrumbs(BreadcrumbHandling::Skip);
+        assert_eq!(
+            convert_with_options(MICRODATA, &options),
+            "# Chocolate cake\n\nRich and dark.\n"
+        );
+    }
+
+    #[test]
+    fn test_excluded_trail_not_moved() {
+        let options = inline().exclude_selectors(vec!["ol".to_string()]);
+        assert_eq!(
+            convert_with_options(MICRODATA, &options),
+            "# Chocolate cake\n\nRich and dark.\n"
+        );
+    }
+
+    #[test]
+    fn test_metadata_lists_steps() {
+        for options in [Options::new(), inline()] {
+            let (_, metadata) = convert_with_metadata(BOOTSTRAP, &options);
+            assert_eq!(metadata.breadcrumbs, vec!["Home", "Docs", "API"]);
+        }
+        let (_, metadata) = convert_with_metadata(MICRODATA, &Options::new());
+        assert_eq!(metadata.breadcrumbs, vec!["Recipes", "Cakes"]);
+        let (_, metadata) = convert_with_metadata("<p>No trail</p>", &Options::new());
+        assert!(metadata.breadcrumbs.is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+//! Splitting converted markdown into chunks under a size limit, for
+//! embedding or feeding a model piece by piece.
+//!
+//! Chunks break between blocks where they can: a block is a run of lines
+//! between blank lines, with a fenced code block kept whole. A block too
+//! large for one chunk is split between its lines, and a line too large
+//! between its words. Each chunk is a slice of the markdown, so nothing is
+//! rewritten and the chunks joined with what lay between them give the
+//! markdown back.
+
+use crate::postprocess::FenceState;
+
+/// Limit on the size of each chunk from [`chunk_markdown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChunkSize {
+    /// At most this many characters.
+    MaxChars(usize),
+    /// At most this many tokens, as counted by
+    /// [`estimate_tokens`](crate::estimate_tokens).
+    #[cfg(feature = "token-estimate")]
+    MaxTokens(usize),
+}
+
+impl ChunkSize {
+    fn limit(self) -> usize {
+        match self {
+            ChunkSize::MaxChars(max) => max,
+            #[cfg(feature = "token-estimate")]
+            ChunkSize::MaxTokens(max) => max,
+        }
+    }
+
+    fn measure(self, text: &str) -> usize {
+        match self {
+            ChunkSize::MaxChars(_) => text.chars().count(),
+            // The estimate of joined text never exceeds the sum of its parts
+            #[cfg(feature = "token-estimate")]
+            ChunkSize::MaxTokens(_) => crate::tokens::estimate_tokens(text),
+        }
+    }
+}
+
+/// Split `markdown` into chunks of at most `size`, in order.
+///
+/// Only a single word larger than the limit makes a larger chunk, of its
+/// own. Chunks don't include the blank lines between them.
+pub fn chunk_markdown(markdown: &str, size: ChunkSize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    for block in blocks(markdown) {
+        split_to_fit(markdown, block, size, &mut pieces);
+    }
+
+    let limit = size.limit();
+    let mut chunks = Vec::new();
+    // Byte range of the open chunk and its size
+    let mut open: Option<(usize, usize, usize)> = None;
+    for (start, end) in pieces {
+        let piece = size.measure(&markdown[start..end]);
+        open = match open {
+            Some((chunk_start, chunk_end, total)) => {
+                let total = total + size.measure(&markdown[chunk_end..start]) + piece;
+                if total <= limit {
+                    Some((chunk_start, end, total))
+                } else {
+                    chunks.push(&markdown[chunk_start..chunk_end]);
+                    Some((start, end, piece))
+                }
+            }
+            None => Some((start, end, piece)),
+        };
+    }
+    if let Some((start, end, _)) = open {
+        chunks.push(&markdown[start..end]);
+    }
+    chunks
+}
+
+/// Byte ranges of the blocks of `markdown`, without their trailing
+/// newlines.
+fn blocks(markdown: &str) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut fence = FenceState::default();
+    let mut open: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in markdown.split('\n') {
+        let start = offset;
+        let end = start + line.trim_end().len();
+        offset += line.len() + 1;
+
+        let in_code = fence.update(line);
+        if line.trim().is_empty() && !in_code {
+            blocks.extend(open.take());
+            continue;
+        }
+        open = Some(match open {
+            Some((block_start, _)) => (block_start, end),
+            None => (start, end),
+        });
+    }
+    blocks.extend(open);
+    blocks
+}
+
+/// Push `range` of `markdown` to `pieces`, split into lines and then words
+/// when it is larger than `size` allows.
+fn split_to_fit(
+    markdown: &str,
+    (start, end): (usize, usize),
+    size: ChunkSize,
+    pieces: &mut Vec<(usize, usize)>,
+) {
+    let text = &markdown[start..end];
+    if size.measure(text) <= size.limit() {
+        pieces.push((start, end));
+        return;
+    }
+
+    if text.contains('\n') {
+        let mut offset = start;
+        for line in text.split('\n') {
+            let line_end = offset + line.trim_end().len();
+            if line_end > offset {
+                split_to_fit(markdown, (offset, line_end), size, pieces);
+            }
+            offset += line.len() + 1;
+        }
+        return;
+    }
+
+    // One line: each run of non-whitespace is a word
+    let mut word_start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), word_start) {
+            (false, None) => word_start = Some(i),
+            (true, Some(begin)) => {
+                pieces.push((start + begin, start + i));
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = word_start {
+        pieces.push((start + begin, end));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str =
+        "# Title\n\nFirst paragraph here.\n\n```\ncode\n\nmore code\n```\n\n- one\n- two\n";
+
+    #[test]
+    fn test_small_document_is_one_chunk() {
+        assert_eq!(
+            chunk_markdown(DOC, ChunkSize::MaxChars(1000)),
+            vec![DOC.trim_end()]
+        );
+        assert!(chunk_markdown("", ChunkSize::MaxChars(10)).is_empty());
+        assert!(chunk_markdown("\n\n", ChunkSize::MaxChars(10)).is_empty());
+    }
+
+    #[test]
+    fn test_chunks_break_between_blocks() {
+        assert_eq!(
+            chunk_markdown(DOC, ChunkSize::MaxChars(30)),
+            vec![
+                "# Title\n\nFirst paragraph here.",
+                "```\ncode\n\nmore code\n```",
+                "- one\n- two",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_large_blocks_split_by_lines_then_words() {
+        let chunks = chunk_markdown(DOC, ChunkSize::MaxChars(12));
+        assert_eq!(
+            chunks,
+            vec![
+                "# Title",
+                "First",
+                "paragraph",
+                "here.\n\n```",
+                "code",
+                "more code",
+                "```",
+                "- one\n- two",
+            ]
+        );
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 12));
+    }
+
+    #[test]
+    fn test_long_word_gets_own_chunk() {
+        assert_eq!(
+            chunk_markdown("a https://example.com/long b", ChunkSize::MaxChars(5)),
+            vec!["a", "https://example.com/long", "b"]
+        );
+    }
+
+    #[cfg(feature = "token-estimate")]
+    #[test]
+    fn test_max_tokens() {
+        let paragraph = "The quick brown fox jumps over the lazy dog.";
+        let markdown = [paragraph; 10].join("\n\n");
+        let limit = 3 * crate::estimate_tokens(paragraph) + 2;
+        let chunks = chunk_markdown(&markdown, ChunkSize::MaxTokens(limit));
+
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[0], [paragraph; 3].join("\n\n"));
+        assert!(chunks
+            .iter()
+            .all(|chunk| crate::estimate_tokens(chunk) <= limit));
+    }
+}
@@ -1,12 +1,34 @@
 //! Main conversion orchestrator.
 
+use std::borrow::Cow;
+use std::cell::RefCell;
+
 use scraper::{ElementRef, Html};
 
+use crate::breadcrumb;
+use crate::diagnostics;
+use crate::email;
 use crate::entities::decode_entities;
-use crate::options::Options;
-use crate::postprocess::postprocess;
-use crate::precompute::{precompute_metadata, CompiledSelectors, MetadataMap};
-use crate::rules::{default_rules, find_rule, Rule};
+use crate::error::ConvertError;
+use crate::escape::{escape_block_start, escape_tag_start};
+use crate::head::{inject_og_image, HeadInfo};
+use crate::ids::IdIndex;
+use crate::json_ld;
+use crate::limits::{truncate_input, Limits, Truncation};
+use crate::options::{AbbrStyle, BreadcrumbHandling, EscapeMode, Options};
+use crate::outline::{element_label, render_outline};
+use crate::postprocess::postprocess_into;
+use crate::precompute::{
+    precompute_metadata, precompute_metadata_with_stats, CompiledSelectors, ExcludeMatch,
+    MetadataMap,
+};
+use crate::progress::Progress;
+use crate::rules::{append_abbreviations, default_rules, find_rule, ConversionContext, Rule};
+use crate::sourcemap::{self, BlockSource, SourceMapEntry};
+#[cfg(feature = "profile")]
+use crate::stats::PhaseTimings;
+use crate::stats::{CapturedAttributes, ConversionMetadata, ConversionStats, MetadataSink};
+use crate::tooltip::{append_tooltip, tooltip};
 use crate::whitespace::normalize_block_whitespace;
 
 /// The main HTML to Markdown converter.
@@ -14,6 +36,34 @@ pub struct Converter {
     rules: Vec<Box<dyn Rule>>,
 }
 
+/// State for one conversion, shared by the whole traversal.
+struct RunState<'a> {
+    /// Counters and extracted links and images, collected for
+    /// `convert_with_metadata`.
+    sink: Option<&'a MetadataSink>,
+    /// Marked block elements, collected for `convert_with_sourcemap`.
+    sources: Option<&'a RefCell<Vec<BlockSource>>>,
+    limits: Limits,
+    /// Element ids, built when `inline_tooltips` needs them.
+    ids: Option<IdIndex<'a>>,
+    /// Set by `convert_with_progress`.
+    progress: Option<&'a Progress<'a>>,
+    /// An inline breadcrumb trail found after the first heading, and that
+    /// heading, which the trail is written before.
+    breadcrumb: Option<(ElementRef<'a>, ElementRef<'a>)>,
+}
+
+/// Result of the shared conversion pipeline.
+struct RunOutput {
+    markdown: String,
+    truncated: Option<Truncation>,
+    title: Option<String>,
+    /// Attributes from `capture_attributes`; only collected with `stats`.
+    captured: Vec<CapturedAttributes>,
+    #[cfg(feature = "profile")]
+    timings: PhaseTimings,
+}
+
 impl Converter {
     /// Create a new converter with default rules.
     pub fn new() -> Self {
@@ -22,26 +72,313 @@ impl Converter {
         }
     }
 
+    /// Create a converter with the default rules minus those turned off by
+    /// `options.disable_rules`.
+    ///
+    /// The rule set is fixed here; the options passed to each conversion
+    /// don't change it.
+    pub fn with_options(options: &Options) -> Self {
+        let mut rules = default_rules();
+        if !options.disable_rules.is_empty() {
+            rules.retain(|rule| {
+                !rule.tags().iter().any(|tag| {
+                    options
+                        .disable_rules
+                        .iter()
+                        .any(|disabled| disabled.tags().contains(tag))
+                })
+            });
+        }
+        Self { rules }
+    }
+
     /// Convert HTML to Markdown.
     pub fn convert(&self, html: &str, options: &Options) -> String {
+        self.run(html, options, None, None, None, String::new())
+            .markdown
+    }
+
+    /// Convert HTML to Markdown into `out`, replacing its contents.
+    ///
+    /// The output is built in `out`'s existing allocation where it fits, so
+    /// reusing one `String` across many documents saves an allocation and
+    /// copy of the whole output per call.
+    pub fn convert_into(&self, html: &str, options: &Options, out: &mut String) {
+        let buffer = std::mem::take(out);
+        *out = self.run(html, options, None, None, None, buffer).markdown;
+    }
+
+    /// Convert HTML to Markdown, calling `progress` with the percentage done.
+    ///
+    /// Values run from 1 to 100, each reported once and in increasing order;
+    /// 100 is reported last, when the output is ready.
+    pub fn convert_with_progress(
+        &self,
+        html: &str,
+        options: &Options,
+        progress: &dyn Fn(u8),
+    ) -> String {
+        self.run(html, options, None, None, Some(progress), String::new())
+            .markdown
+    }
+
+    /// Convert HTML to Markdown, rejecting invalid options and input over
+    /// `max_input_bytes` instead of falling back or truncating.
+    pub fn try_convert(&self, html: &str, options: &Options) -> Result<String, ConvertError> {
+        options.validate()?;
+        if let Some(max) = options.max_input_bytes {
+            if html.len() > max {
+                return Err(ConvertError::InputTooLarge {
+                    len: html.len(),
+                    max,
+                });
+            }
+        }
+        Ok(self.convert(html, options))
+    }
+
+    /// Convert HTML to Markdown, also returning conversion metadata.
+    pub fn convert_with_metadata(
+        &self,
+        html: &str,
+        options: &Options,
+    ) -> (String, ConversionMetadata) {
+        let sink = MetadataSink::default();
+        let output = self.run(html, options, Some(&sink), None, None, String::new());
+
+        #[cfg(feature = "token-estimate")]
+        {
+            sink.stats.borrow_mut().estimated_tokens = crate::estimate_tokens(&output.markdown);
+        }
+
+        let mut warnings = diagnostics::option_warnings(options);
+        warnings.extend(output.truncated.map(diagnostics::truncation_warning));
+        #[cfg(feature = "validate")]
+        if options.validate_output {
+            warnings.extend(sink.expected.borrow().check(&output.markdown));
+        }
+
+        let metadata = ConversionMetadata {
+            stats: sink.stats.into_inner(),
+            truncated: output.truncated,
+            warnings,
+            title: output.title,
+            captured_attributes: output.captured,
+            links: sink.links.into_inner(),
+            images: sink.images.into_inner(),
+            #[cfg(feature = "serde")]
+            json_ld: sink.json_ld.into_inner(),
+            breadcrumbs: sink.breadcrumbs.into_inner(),
+            #[cfg(feature = "profile")]
+            timings: output.timings,
+        };
+        (output.markdown, metadata)
+    }
+
+    /// Convert HTML to Markdown, also returning the output range of each
+    /// block-level element.
+    pub fn convert_with_sourcemap(
+        &self,
+        html: &str,
+        options: &Options,
+    ) -> (String, Vec<SourceMapEntry>) {
+        let sources = RefCell::new(Vec::new());
+        let output = self.run(html, options, None, Some(&sources), None, String::new());
+        sourcemap::resolve(&output.markdown, sources.into_inner())
+    }
+
+    /// Describe how a document would be converted, without converting it.
+    pub fn outline(&self, html: &str, options: &Options) -> String {
+        let dom = Html::parse_document(html);
+        let selectors = CompiledSelectors::new(options);
+        let metadata = precompute_metadata(&dom, &selectors, options);
+        render_outline(&dom, &metadata, &selectors, &self.rules)
+    }
+
+    /// Shared conversion pipeline; counters are only updated when `sink` is
+    /// set, block output is only marked when `sources` is set, and progress
+    /// is only reported when `progress` is set.
+    ///
+    /// Also returns the limit that cut the conversion short, if any, and
+    /// the document title. The markdown is written into `buffer`, which is
+    /// cleared first.
+    fn run(
+        &self,
+        html: &str,
+        options: &Options,
+        sink: Option<&MetadataSink>,
+        sources: Option<&RefCell<Vec<BlockSource>>>,
+        progress: Option<&dyn Fn(u8)>,
+        mut buffer: String,
+    ) -> RunOutput {
+        let stats = sink.map(|sink| &sink.stats);
+        // The deadline covers parsing too
+        let limits = Limits::new(options);
+        let progress = progress.map(Progress::new);
+
+        let mut input_truncated = false;
+        let html = match options.max_input_bytes {
+            Some(max) if html.len() > max => {
+                input_truncated = true;
+                truncate_input(html, max)
+            }
+            _ => html,
+        };
+        let html = if options.email_mode {
+            email::strip_conditional_comments(html)
+        } else {
+            Cow::Borrowed(html)
+        };
+        let html = html.as_ref();
+
         if html.is_empty() {
-            return String::new();
+            if let Some(progress) = &progress {
+                progress.finished();
+            }
+            buffer.clear();
+            return RunOutput {
+                markdown: buffer,
+                truncated: input_truncated.then_some(Truncation::MaxInputBytes),
+                title: None,
+                captured: Vec::new(),
+                #[cfg(feature = "profile")]
+                timings: PhaseTimings::default(),
+            };
         }
 
+        #[cfg(any(feature = "logging", feature = "profile"))]
+        let mut timer = PhaseTimer::start();
+
         // 1. Parse HTML (html5ever handles malformed HTML gracefully)
-        let dom = Html::parse_document(html);
+        let mut dom = Html::parse_document(html);
+        if sources.is_some() {
+            sourcemap::escape_reserved(&mut dom);
+        }
+
+        #[cfg(any(feature = "logging", feature = "profile"))]
+        timer.lap("parse");
+
+        if let Some(progress) = &progress {
+            progress.parsed();
+        }
+
+        // An explicit base_url wins over the document's <base href>
+        let mut head = HeadInfo::read(&dom);
+        let options = match (&options.base_url, head.base_href.take()) {
+            (None, Some(href)) => Cow::Owned(options.clone().base_url(Some(href))),
+            _ => Cow::Borrowed(options),
+        };
+        let options = options.as_ref();
+
+        let mut state = RunState {
+            sink,
+            sources,
+            limits,
+            ids: options.inline_tooltips.then(|| IdIndex::new(&dom)),
+            progress: progress.as_ref(),
+            breadcrumb: None,
+        };
 
         // 2. Compile selectors once
         let selectors = CompiledSelectors::new(options);
 
         // 3. Pre-compute metadata (single O(n) traversal)
-        let metadata = precompute_metadata(&dom, &selectors, options);
+        let mut captured = Vec::new();
+        let mut counts = ConversionStats::default();
+        let metadata = match (stats, &progress) {
+            (Some(stats), _) => precompute_metadata_with_stats(
+                &dom,
+                &selectors,
+                options,
+                Some(&mut stats.borrow_mut()),
+                Some(&mut captured),
+            ),
+            // Progress is measured against the element count
+            (None, Some(_)) => {
+                precompute_metadata_with_stats(&dom, &selectors, options, Some(&mut counts), None)
+            }
+            (None, None) => precompute_metadata(&dom, &selectors, options),
+        };
+
+        let inline_breadcrumbs = options.breadcrumbs == BreadcrumbHandling::Inline;
+        if sink.is_some() || inline_breadcrumbs {
+            if let Some((trail, heading)) = breadcrumb::find(&dom, &metadata) {
+                if let Some(sink) = sink {
+                    *sink.breadcrumbs.borrow_mut() = breadcrumb::texts(trail);
+                }
+                if inline_breadcrumbs && !breadcrumb::is_skipped(trail, &metadata) {
+                    state.breadcrumb = heading.map(|heading| (trail, heading));
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        if let (Some(sink), true) = (sink, options.extract_json_ld) {
+            *sink.json_ld.borrow_mut() = json_ld::extract(&dom, &mut sink.stats.borrow_mut());
+        }
+
+        if let Some(progress) = &progress {
+            let elements = stats.map_or(counts.elements_visited, |s| s.borrow().elements_visited);
+            progress.precomputed(elements);
+        }
+
+        #[cfg(any(feature = "logging", feature = "profile"))]
+        timer.lap("precompute");
 
         // 4. Convert to markdown (single O(n) traversal)
-        let markdown = self.convert_element(dom.root_element(), &metadata, options);
+        let mut markdown = self.convert_element(
+            dom.root_element(),
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &state,
+        );
+        if options.inject_og_image {
+            markdown = inject_og_image(markdown, &head, options);
+        }
+        if options.abbr_style == AbbrStyle::DefinitionList {
+            markdown = append_abbreviations(markdown, &dom, &metadata);
+        }
+
+        #[cfg(any(feature = "logging", feature = "profile"))]
+        timer.lap("convert");
+
+        if let Some(progress) = &progress {
+            progress.converted();
+        }
 
         // 5. Post-process
-        postprocess(markdown, options)
+        postprocess_into(markdown, options, sources.is_some(), &mut buffer);
+        let markdown = buffer;
+
+        if let Some(progress) = &progress {
+            progress.finished();
+        }
+
+        #[cfg(any(feature = "logging", feature = "profile"))]
+        timer.lap("postprocess");
+        #[cfg(feature = "logging")]
+        timer.finish(html.len(), markdown.len());
+
+        let truncated = if input_truncated {
+            Some(Truncation::MaxInputBytes)
+        } else {
+            state.limits.truncation()
+        };
+
+        #[cfg(feature = "logging")]
+        if let Some(reason) = truncated {
+            log::warn!("Conversion stopped early: {:?}", reason);
+        }
+
+        RunOutput {
+            markdown,
+            truncated,
+            title: head.title,
+            captured,
+            #[cfg(feature = "profile")]
+            timings: timer.timings(),
+        }
     }
 
     /// Convert an element and its children to markdown.
@@ -50,8 +387,10 @@ impl Converter {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        context: &ConversionContext,
+        state: &RunState,
     ) -> String {
-        self.convert_node_internal(element, metadata, options)
+        self.convert_node_internal(element, metadata, options, context, state)
     }
 
     /// Internal conversion function.
@@ -60,25 +399,190 @@ impl Converter {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        context: &ConversionContext,
+        state: &RunState,
     ) -> String {
+        if !state.limits.visit() {
+            return String::new();
+        }
+        if let Some(progress) = state.progress {
+            progress.element();
+        }
+
         // Check skip/force_keep from metadata
         if let Some(meta) = metadata.get(&element.id()) {
             if meta.skip && !meta.force_keep {
-                return String::new();
+                if meta.has_kept_descendants {
+                    return self
+                        .convert_kept_descendants(element, metadata, options, context, state);
+                }
+                if let Some(sink) = state.sink {
+                    sink.record_dropped(element, options);
+                }
+                return match meta.exclude_match {
+                    Some(matched) if options.mark_exclusions => {
+                        exclusion_marker(element, matched, options)
+                    }
+                    _ => String::new(),
+                };
             }
         }
 
         let tag = element.value().name();
 
+        // The head holds no content; base and title are read up front
+        if tag == "head" {
+            return String::new();
+        }
+        if options.extract_json_ld && json_ld::is_json_ld(element) {
+            return String::new();
+        }
+        if options.breadcrumbs == BreadcrumbHandling::Inline && breadcrumb::is_breadcrumb(element) {
+            // The first trail may already be written before a heading
+            if state.breadcrumb.is_some_and(|(trail, _)| trail == element) {
+                return String::new();
+            }
+            return self.breadcrumb_line(element, metadata, options, context, state);
+        }
+
+        let extracted = state.sink.and_then(|sink| sink.begin(element, options));
+
         // Find matching rule
         if let Some(rule) = find_rule(&self.rules, tag) {
-            return rule.convert(element, metadata, options, &|e, m, o| {
-                self.convert_children(e, m, o)
+            let inner = context.enter(element, options);
+            let converted = rule.convert(element, metadata, options, context, &|e, m, o| {
+                // Rules pass their own element or one of its descendants
+                let context = descend(inner, element, e, o);
+                self.convert_children(e, m, o, &context, state)
             });
+            let converted = with_tooltip(converted, element, options, state);
+            let converted = match state.breadcrumb {
+                Some((trail, heading)) if heading == element => {
+                    self.breadcrumb_line(trail, metadata, options, context, state) + &converted
+                }
+                _ => converted,
+            };
+
+            #[cfg(feature = "logging")]
+            if converted.is_empty() && element.text().any(|t| !t.trim().is_empty()) {
+                log::debug!(
+                    "Rule for <{}> produced no output for non-empty element",
+                    tag
+                );
+            }
+
+            if let Some(sink) = state.sink {
+                record_converted(&mut sink.stats.borrow_mut(), tag, &converted);
+                #[cfg(feature = "validate")]
+                if options.validate_output {
+                    sink.expected
+                        .borrow_mut()
+                        .record(element, &converted, options);
+                }
+                if let Some(extracted) = extracted {
+                    sink.end(extracted, !converted.is_empty());
+                }
+            }
+            if let Some(sources) = state.sources {
+                if sourcemap::is_mapped_block(element) {
+                    return sourcemap::mark_block(converted, element, &mut sources.borrow_mut());
+                }
+            }
+            return converted;
         }
 
-        // Default: just convert children
-        self.convert_children(element, metadata, options)
+        // Default: just convert children, so a link or image without a
+        // rule is never in the output as one
+        if let Some(sink) = state.sink {
+            sink.stats.borrow_mut().record_unhandled(tag);
+            if let Some(extracted) = extracted {
+                sink.end(extracted, false);
+            }
+        }
+        let inner = context.enter(element, options);
+        let converted = self.convert_children(element, metadata, options, &inner, state);
+        let converted = if options.email_mode && tag == "div" {
+            email::div_line(element, converted)
+        } else {
+            converted
+        };
+        with_tooltip(converted, element, options, state)
+    }
+
+    /// A breadcrumb trail as one line of steps.
+    fn breadcrumb_line(
+        &self,
+        trail: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        context: &ConversionContext,
+        state: &RunState,
+    ) -> String {
+        let inner = context.enter(trail, options);
+        let steps: Vec<String> = breadcrumb::steps(trail)
+            .into_iter()
+            .map(|step| {
+                // An item's own rule would write a list marker
+                let converted = if step.value().name() == "li" {
+                    let context = descend(inner, trail, step, options);
+                    self.convert_children(step, metadata, options, &context, state)
+                } else {
+                    let context = step
+                        .parent()
+                        .and_then(ElementRef::wrap)
+                        .map_or(inner, |parent| descend(inner, trail, parent, options));
+                    self.convert_node_internal(step, metadata, options, &context, state)
+                };
+                normalize_block_whitespace(converted.trim()).into_owned()
+            })
+            .filter(|step| !step.is_empty())
+            .collect();
+        if steps.is_empty() {
+            return String::new();
+        }
+        format!("\n\n{}\n\n", steps.join(breadcrumb::SEPARATOR))
+    }
+
+    /// Convert only the force-kept parts of a skipped element.
+    ///
+    /// The skipped element's own rule and text are dropped; each kept
+    /// descendant is converted as a block of its own, except that kept items
+    /// of one list stay together as a list.
+    fn convert_kept_descendants(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        context: &ConversionContext,
+        state: &RunState,
+    ) -> String {
+        let inner = context.enter(element, options);
+        let mut result = String::new();
+        let mut after_tight_item = false;
+        for child in element.children().filter_map(ElementRef::wrap) {
+            let converted = self.convert_node_internal(child, metadata, options, &inner, state);
+            // Source whitespace around the kept block would keep the
+            // separators from collapsing
+            let converted = converted.trim();
+            if converted.is_empty() {
+                continue;
+            }
+
+            let item = child.value().name() == "li";
+            if item && after_tight_item {
+                // Drop the blank line after the previous item
+                result.pop();
+            } else {
+                result.push_str("\n\n");
+            }
+            result.push_str(converted);
+            result.push_str("\n\n");
+            after_tight_item = item
+                && !metadata
+                    .get(&child.id())
+                    .is_some_and(|meta| meta.loose_list);
+        }
+        result
     }
 
     /// Convert all children of an element.
@@ -87,25 +591,60 @@ impl Converter {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        context: &ConversionContext,
+        state: &RunState,
     ) -> String {
         let mut result = String::new();
+        // Source whitespace at the end of the previous element that its rule trimmed away
+        let mut pending_space = false;
 
         for child in element.children() {
+            // Past a limit, keep what was converted and skip the rest
+            if state.limits.exhausted() {
+                break;
+            }
+
             match child.value() {
                 scraper::Node::Text(text) => {
                     // Decode HTML entities and normalize whitespace in text nodes
-                    // (collapses multiple spaces/tabs/newlines to single space)
-                    let decoded = decode_entities(text);
+                    // (collapses multiple spaces/tabs/newlines to single space).
+                    // Each step borrows its input when it has nothing to change,
+                    // so large text nodes are only copied into the output.
+                    let decoded = decode_entities(text, options.max_entity_expansion_ratio);
                     let normalized = normalize_block_whitespace(&decoded);
-                    result.push_str(&normalized);
+                    let escaped = escape_tag_start(&normalized, options.text_escape_mode());
+                    let escaped = if options.text_escape_mode() != EscapeMode::None
+                        && at_line_start(&result)
+                        && starts_blocks(element.value().name())
+                    {
+                        Cow::Owned(escape_block_start(&escaped).into_owned())
+                    } else {
+                        escaped
+                    };
+                    push_text(&mut result, &escaped, pending_space);
+                    pending_space = false;
                 }
                 scraper::Node::Element(_) => {
                     if let Some(child_element) = ElementRef::wrap(child) {
-                        result.push_str(&self.convert_node_internal(
+                        let converted = self.convert_node_internal(
                             child_element,
                             metadata,
                             options,
-                        ));
+                            context,
+                            state,
+                        );
+                        if converted.is_empty() {
+                            continue;
+                        }
+
+                        // Rules like emphasis and links trim their content, so recover
+                        // whitespace the source had just inside the element
+                        let literal = LITERAL_TAGS.contains(&child_element.value().name());
+                        let leading = !literal && starts_with_whitespace(child);
+                        push_element(&mut result, &converted, pending_space || leading);
+                        pending_space = !literal
+                            && ends_with_whitespace(child)
+                            && !converted.ends_with(char::is_whitespace);
                     }
                 }
                 _ => {}
@@ -116,6 +655,274 @@ impl Converter {
     }
 }
 
+/// Measures conversion phases for the summary log line and, with the
+/// `profile` feature, the timings in the metadata.
+#[cfg(any(feature = "logging", feature = "profile"))]
+struct PhaseTimer {
+    last: std::time::Instant,
+    phases: Vec<(&'static str, std::time::Duration)>,
+}
+
+#[cfg(any(feature = "logging", feature = "profile"))]
+impl PhaseTimer {
+    fn start() -> Self {
+        Self {
+            last: std::time::Instant::now(),
+            phases: Vec::with_capacity(4),
+        }
+    }
+
+    /// Record the time since the previous lap under `phase`.
+    fn lap(&mut self, phase: &'static str) {
+        let now = std::time::Instant::now();
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+
+    /// Time recorded under `phase`, zero if it was never lapped.
+    #[cfg(feature = "profile")]
+    fn phase(&self, phase: &str) -> std::time::Duration {
+        self.phases
+            .iter()
+            .find(|(name, _)| *name == phase)
+            .map_or(std::time::Duration::ZERO, |(_, duration)| *duration)
+    }
+
+    #[cfg(feature = "profile")]
+    fn timings(&self) -> PhaseTimings {
+        PhaseTimings {
+            parse: self.phase("parse"),
+            precompute: self.phase("precompute"),
+            convert: self.phase("convert"),
+            postprocess: self.phase("postprocess"),
+        }
+    }
+
+    #[cfg(feature = "logging")]
+    fn finish(&self, input_len: usize, output_len: usize) {
+        if !log::log_enabled!(log::Level::Debug) {
+            return;
+        }
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(phase, duration)| format!("{} {:?}", phase, duration))
+            .collect();
+        log::debug!(
+            "Converted {} bytes of HTML to {} bytes of markdown in {:?} ({})",
+            input_len,
+            output_len,
+            self.phases
+                .iter()
+                .map(|(_, duration)| *duration)
+                .sum::<std::time::Duration>(),
+            phases.join(", ")
+        );
+    }
+}
+
+/// Count the output of a rule by the kind of element it converted.
+fn record_converted(stats: &mut ConversionStats, tag: &str, converted: &str) {
+    match tag {
+        "a" if !converted.is_empty() => stats.links_converted += 1,
+        "table" if !converted.is_empty() => stats.tables_converted += 1,
+        "img" if converted.is_empty() => stats.images_dropped += 1,
+        _ => {}
+    }
+}
+
+/// The context of the children of `descendant`, given `context`, that of
+/// the children of `element`: entered through `descendant` and the elements
+/// in between. Usually `descendant` is `element` itself or close below it.
+fn descend(
+    context: ConversionContext,
+    element: ElementRef,
+    descendant: ElementRef,
+    options: &Options,
+) -> ConversionContext {
+    let between: Vec<ElementRef> = std::iter::once(descendant)
+        .chain(descendant.ancestors().filter_map(ElementRef::wrap))
+        .take_while(|ancestor| *ancestor != element)
+        .collect();
+    between
+        .into_iter()
+        .rev()
+        .fold(context, |context, ancestor| {
+            context.enter(ancestor, options)
+        })
+}
+
+/// Add the element's tooltip after its converted content, when
+/// `inline_tooltips` is on and there is content to describe.
+fn with_tooltip(
+    converted: String,
+    element: ElementRef,
+    options: &Options,
+    state: &RunState,
+) -> String {
+    if converted.trim().is_empty() {
+        return converted;
+    }
+    match state
+        .ids
+        .as_ref()
+        .and_then(|ids| tooltip(ids, element, options))
+    {
+        Some(tooltip) => append_tooltip(converted, &tooltip),
+        None => converted,
+    }
+}
+
+/// Tags marked inline by `mark_exclusions`; other excluded elements get
+/// their marker on a line of its own.
+const INLINE_TAGS: &[&str] = &[
+    "a", "abbr", "b", "button", "cite", "code", "del", "em", "font", "i", "img", "input", "kbd",
+    "label", "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup", "svg", "time", "u",
+    "var",
+];
+
+/// Whether text in an element's own content can start a markdown block,
+/// so text beginning a line there is escaped. Inline elements usually sit
+/// mid-line, and headings and cells hold inline content only.
+fn starts_blocks(tag: &str) -> bool {
+    !INLINE_TAGS.contains(&tag)
+        && !matches!(
+            tag,
+            "h1" | "h2"
+                | "h3"
+                | "h4"
+                | "h5"
+                | "h6"
+                | "td"
+                | "th"
+                | "caption"
+                | "summary"
+                | "strike"
+        )
+}
+
+/// Whether text appended to `result` would start a line.
+fn at_line_start(result: &str) -> bool {
+    let result = result.trim_end_matches(' ');
+    result.is_empty() || result.ends_with('\n')
+}
+
+/// HTML comment standing in for an excluded element, like
+/// `<!-- excluded: nav.menu (selector "nav") -->`.
+fn exclusion_marker(element: ElementRef, matched: ExcludeMatch, options: &Options) -> String {
+    let kind = match matched {
+        ExcludeMatch::Selector(_) => "selector",
+        ExcludeMatch::TextPattern(_) => "text pattern",
+        ExcludeMatch::Handling(_) => "option",
+    };
+    let text = format!(
+        "excluded: {} ({} \"{}\")",
+        element_label(&element),
+        kind,
+        matched.source(options)
+    );
+    // A literal `-->` would end the comment early
+    let comment = format!("<!-- {} -->", text.replace("-->", "--&gt;"));
+    if INLINE_TAGS.contains(&element.value().name()) {
+        comment
+    } else {
+        format!("\n\n{}\n\n", comment)
+    }
+}
+
+/// Tags whose rules keep their content verbatim, so source whitespace is never lost.
+const LITERAL_TAGS: &[&str] = &["code", "pre"];
+
+/// Append a text node, keeping exactly one space at the boundary.
+fn push_text(result: &mut String, text: &str, want_space: bool) {
+    let text = if result.ends_with(' ') || result.ends_with('\n') {
+        text.trim_start_matches(' ')
+    } else {
+        text
+    };
+
+    if text.is_empty() {
+        return;
+    }
+
+    if want_space
+        && !result.is_empty()
+        && !result.ends_with(char::is_whitespace)
+        && !text.starts_with(char::is_whitespace)
+    {
+        result.push(' ');
+    }
+
+    result.push_str(text);
+}
+
+/// Append converted element output, keeping at most one space at the boundary.
+fn push_element(result: &mut String, chunk: &str, want_space: bool) {
+    if result.is_empty() {
+        result.push_str(chunk);
+        return;
+    }
+
+    if result.ends_with(char::is_whitespace) {
+        // Drop duplicate spaces, but never the trailing spaces of a hard break
+        let trimmed = chunk.trim_start_matches(' ');
+        if result.ends_with(' ') && !trimmed.starts_with('\n') {
+            result.push_str(trimmed);
+        } else {
+            result.push_str(chunk);
+        }
+        return;
+    }
+
+    if want_space && !chunk.starts_with(char::is_whitespace) {
+        result.push(' ');
+    }
+    result.push_str(chunk);
+}
+
+/// Whether the first non-empty text inside a node starts with whitespace.
+fn starts_with_whitespace(node: ego_tree::NodeRef<scraper::Node>) -> bool {
+    edge_whitespace(node, false).unwrap_or(false)
+}
+
+/// Whether the last non-empty text inside a node ends with whitespace.
+fn ends_with_whitespace(node: ego_tree::NodeRef<scraper::Node>) -> bool {
+    edge_whitespace(node, true).unwrap_or(false)
+}
+
+/// Check the whitespace at one edge of a node's text; `None` if it has no text.
+fn edge_whitespace(node: ego_tree::NodeRef<scraper::Node>, from_end: bool) -> Option<bool> {
+    match node.value() {
+        scraper::Node::Text(text) => {
+            let c = if from_end {
+                text.chars().next_back()
+            } else {
+                text.chars().next()
+            };
+            c.map(char::is_whitespace)
+        }
+        scraper::Node::Element(_) => {
+            if from_end {
+                let mut child = node.last_child();
+                while let Some(c) = child {
+                    if let Some(ws) = edge_whitespace(c, from_end) {
+                        return Some(ws);
+                    }
+                    child = c.prev_sibling();
+                }
+            } else {
+                for c in node.children() {
+                    if let Some(ws) = edge_whitespace(c, from_end) {
+                        return Some(ws);
+                    }
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
 impl Default for Converter {
     fn default() -> Self {
         Self::new()
@@ -125,7 +932,7 @@ impl Default for Converter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::options::{HeadingStyle, LinkStyle};
+    use crate::options::{DisabledRule, EscapeMode, HeadingStyle, LinkStyle};
 
     fn convert(html: &str) -> String {
         Converter::new().convert(html, &Options::default())
@@ -232,12 +1039,222 @@ mod tests {
         assert!(result.contains("---"));
     }
 
+    /// A flat body of `count` paragraphs.
+    fn many_paragraphs(count: usize) -> String {
+        let mut html = String::from("<body>");
+        for i in 0..count {
+            html.push_str(&format!("<p>p{}</p>", i));
+        }
+        html.push_str("</body>");
+        html
+    }
+
+    #[test]
+    fn test_max_elements_stops_traversal() {
+        let html = many_paragraphs(1_000_000);
+        let options = Options::new().max_elements(Some(100));
+
+        let start = std::time::Instant::now();
+        let (markdown, metadata) = Converter::new().convert_with_metadata(&html, &options);
+        let elapsed = start.elapsed();
+
+        assert_eq!(metadata.truncated, Some(Truncation::MaxElements));
+        // html, head and body count too, so 97 paragraphs make it
+        assert!(markdown.starts_with("p0\n\np1\n\n"));
+        assert!(markdown.ends_with("\n\np96\n"));
+        // Parsing dominates; converting 1M elements would take far longer
+        assert!(
+            elapsed < std::time::Duration::from_secs(60),
+            "{:?}",
+            elapsed
+        );
+    }
+
+    #[test]
+    fn test_deadline_returns_partial_output() {
+        let html = many_paragraphs(10_000);
+        let options = Options::new().deadline(Some(std::time::Duration::ZERO));
+        let (markdown, metadata) = Converter::new().convert_with_metadata(&html, &options);
+        assert_eq!(metadata.truncated, Some(Truncation::Deadline));
+        assert!(markdown.is_empty());
+
+        let options = Options::new().deadline(Some(std::time::Duration::from_secs(600)));
+        let (markdown, metadata) = Converter::new().convert_with_metadata(&html, &options);
+        assert_eq!(metadata.truncated, None);
+        assert!(markdown.ends_with("p9999\n"));
+    }
+
+    const BASE_DOC: &str = r#"<html><head>
+        <title>Guide</title>
+        <base href="https://example.com/docs/">
+    </head><body>
+        <p><a href="intro">Intro</a> and <a href="/api">API</a></p>
+        <img src="img/logo.png" alt="Logo">
+    </body></html>"#;
+
+    #[test]
+    fn test_base_href_resolves_relative_urls() {
+        assert_eq!(
+            convert(BASE_DOC),
+            "[Intro](https://example.com/docs/intro) and [API](https://example.com/api)\n\n\
+             ![Logo](https://example.com/docs/img/logo.png)\n"
+        );
+    }
+
+    #[test]
+    fn test_base_url_option_wins_over_base_href() {
+        let options = Options::new().base_url(Some("https://other.org/".to_string()));
+        let result = convert_with(BASE_DOC, &options);
+        assert!(
+            result.starts_with("[Intro](https://other.org/intro) and [API](https://other.org/api)")
+        );
+    }
+
+    #[test]
+    fn test_disabled_tables_flow_as_text() {
+        let html = "<table>
+            <tr><th>Name</th> <th>Age</th></tr>
+            <tr><td>Ann</td> <td>30</td></tr>
+        </table>";
+        let options = Options::new().disable_rules(vec![DisabledRule::Tables]);
+        let markdown = Converter::with_options(&options).convert(html, &options);
+        assert_eq!(markdown, "Name Age Ann 30\n");
+    }
+
+    #[test]
+    fn test_disabled_details_drop_blockquote() {
+        let html = "<details><summary>More</summary><p>Hidden text</p></details>";
+        assert_eq!(convert(html), "> **More**\n>\n> Hidden text\n");
+
+        let options = Options::new().disable_rules(vec![DisabledRule::Details]);
+        let markdown = Converter::with_options(&options).convert(html, &options);
+        assert_eq!(markdown, "More\n\nHidden text\n");
+    }
+
+    #[test]
+    fn test_disabled_rules_fixed_at_construction() {
+        let options = Options::new().disable_rules(vec![DisabledRule::Strikethrough]);
+        let html = "<p><del>old</del> new</p>";
+        assert_eq!(Converter::new().convert(html, &options), "~~old~~ new\n");
+        assert_eq!(
+            Converter::with_options(&options).convert(html, &Options::default()),
+            "old new\n"
+        );
+    }
+
+    #[test]
+    fn test_title_in_metadata_not_output() {
+        let (markdown, metadata) =
+            Converter::new().convert_with_metadata(BASE_DOC, &Options::default());
+        assert!(!markdown.contains("Guide"));
+        assert_eq!(metadata.title.as_deref(), Some("Guide"));
+
+        let (_, metadata) =
+            Converter::new().convert_with_metadata("<p>No head</p>", &Options::default());
+        assert_eq!(metadata.title, None);
+    }
+
+    #[test]
+    fn test_max_input_bytes() {
+        let options = Options::new().max_input_bytes(Some(12));
+        let (markdown, metadata) =
+            Converter::new().convert_with_metadata("<p>Hello</p><p>World</p>", &options);
+        assert_eq!(markdown, "Hello\n");
+        assert_eq!(metadata.truncated, Some(Truncation::MaxInputBytes));
+
+        let result = Converter::new().try_convert("<p>Hello</p><p>World</p>", &options);
+        assert_eq!(
+            result,
+            Err(ConvertError::InputTooLarge { len: 24, max: 12 })
+        );
+        assert_eq!(
+            Converter::new().try_convert("<p>Hello</p>", &options),
+            Ok("Hello\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_entity_heavy_input_stays_bounded() {
+        // Entities escaped once survive parsing and are decoded again
+        let options = Options::new().max_entity_expansion_ratio(2.0);
+        for bomb in ["&amp;#65;", "&#x1F600;"].map(|entity| entity.repeat(1 << 16)) {
+            let markdown = convert_with(&format!("<p>{}</p>", bomb), &options);
+            assert!(markdown.len() <= bomb.len() * 2);
+            assert!(markdown.len() < bomb.len() / 2);
+        }
+    }
+
+    #[test]
+    fn test_no_truncation_by_default() {
+        let (_, metadata) =
+            Converter::new().convert_with_metadata("<p>Hi</p>", &Options::default());
+        assert_eq!(metadata.truncated, None);
+    }
+
+    #[test]
+    fn test_large_single_text_node() {
+        let text = "word ".repeat(200_000);
+        let result = convert(&format!("<p>{}</p>", text));
+        assert_eq!(result, format!("{}\n", text.trim_end()));
+    }
+
     #[test]
     fn test_entity_decoding() {
         let result = convert("<p>&lt;html&gt; &amp; more</p>");
+        assert!(result.contains("&lt;html> & more"));
+
+        let options = Options::new().escape_mode(EscapeMode::None);
+        let result = convert_with("<p>&lt;html&gt; &amp; more</p>", &options);
         assert!(result.contains("<html> & more"));
     }
 
+    #[test]
+    fn test_tag_like_text_escaped_outside_code() {
+        let result = convert("<p>Use Vec&lt;T&gt; or <code>Vec&lt;T&gt;</code></p>");
+        assert_eq!(result, "Use Vec&lt;T> or `Vec<T>`\n");
+
+        let result = convert("<pre><code>let v: Vec&lt;T&gt;;</code></pre>");
+        assert!(result.contains("let v: Vec<T>;"));
+
+        let options = Options::new().escape_mode(EscapeMode::Backslash);
+        let result = convert_with(
+            "<p>Use Vec&lt;T&gt; or <code>Vec&lt;T&gt;</code></p>",
+            &options,
+        );
+        assert_eq!(result, "Use Vec\\<T> or `Vec<T>`\n");
+    }
+
+    #[test]
+    fn test_block_start_text_escaped() {
+        let html = "<p>2024. It was a good year.</p>\
+                    <ol><li>Real item</li><li>2. Second thoughts</li></ol>\
+                    <p>- not a list, <em>1. not</em> escaped mid-line</p>\
+                    <h2>1. Introduction</h2>";
+        assert_eq!(
+            convert(html),
+            "2024\\. It was a good year.\n\n\
+             1. Real item\n2. 2\\. Second thoughts\n\n\
+             \\- not a list, *1. not* escaped mid-line\n\n\
+             ## 1. Introduction\n"
+        );
+
+        // Escaping again finds the backslash and leaves the text alone
+        let once = convert("<p>2024. It was a good year.</p>");
+        assert_eq!(convert(&format!("<p>{}</p>", once.trim_end())), once);
+
+        let options = Options::new().escape_mode(EscapeMode::None);
+        assert_eq!(convert_with("<p>- dash</p>", &options), "- dash\n");
+    }
+
+    #[test]
+    fn test_tag_escaping_is_idempotent() {
+        // The entity decodes back to `<` and is escaped again the same way
+        let first = convert("<p>Returns Option&lt;String&gt;, see &lt;/div&gt; &amp; more</p>");
+        assert_eq!(first, "Returns Option&lt;String>, see &lt;/div> & more\n");
+        let second = convert(&format!("<p>{}</p>", first));
+        assert_eq!(first, second);
+    }
+
     #[test]
     fn test_nested_elements() {
         let result = convert("<p>This is <strong>bold and <em>italic</em></strong> text.</p>");
@@ -271,6 +1288,42 @@ mod tests {
         assert!(result.contains("Keep this"));
     }
 
+    #[test]
+    fn test_mark_exclusions_one_marker_per_region() {
+        let html = r#"<nav class="menu"><ul><li class="ad">Promo</li></ul></nav>
+            <p>Text with <span class="ad">ad</span> inside.</p>
+            <div><p>Advertisement</p></div>"#;
+        let options = Options::new()
+            .exclude_selectors(vec!["[".to_string(), "nav".to_string(), ".ad".to_string()])
+            .exclude_text_patterns(vec!["advertisement".to_string()])
+            .mark_exclusions(true);
+        let result = convert_with(html, &options);
+
+        assert_eq!(
+            result,
+            "<!-- excluded: nav.menu (selector \"nav\") -->\n\n\
+             Text with <!-- excluded: span.ad (selector \".ad\") --> inside.\n\n\
+             <!-- excluded: p (text pattern \"advertisement\") -->\n"
+        );
+        // The .ad item inside the nav has no marker of its own
+        assert_eq!(result.matches("<!--").count(), 3);
+
+        let result = convert_with(html, &options.clone().mark_exclusions(false));
+        assert!(!result.contains("<!--"));
+    }
+
+    #[test]
+    fn test_mark_exclusions_escapes_comment_end() {
+        let options = Options::new()
+            .exclude_selectors(vec!["[data-x='-->']".to_string()])
+            .mark_exclusions(true);
+        let result = convert_with("<div data-x='-->'>Gone</div><p>Kept</p>", &options);
+        assert_eq!(
+            result,
+            "<!-- excluded: div (selector \"[data-x='--&gt;']\") -->\n\nKept\n"
+        );
+    }
+
     #[test]
     fn test_whitespace_normalization() {
         // Multiple spaces should collapse to single space
@@ -290,6 +1343,87 @@ mod tests {
         assert!(result.contains("Hello world"));
     }
 
+    #[test]
+    fn test_inline_sibling_whitespace_matrix() {
+        let cases = [
+            ("em", "*x*"),
+            ("strong", "**x**"),
+            ("code", "`x`"),
+            ("a href=\"u\"", "[x](u)"),
+        ];
+
+        for (tag, md) in cases {
+            let close = tag.split(' ').next().unwrap();
+            let render = |inner: &str| {
+                let html = format!("<p>{}</p>", inner.replace("<T>", &format!("<{}>", tag)));
+                convert(&html.replace("</T>", &format!("</{}>", close)))
+            };
+
+            // Whitespace around the element in the parent text
+            assert_eq!(render("a <T>x</T> b"), format!("a {} b\n", md), "{}", tag);
+            assert_eq!(
+                render("a\n<T>x</T>\n  b"),
+                format!("a {} b\n", md),
+                "{}",
+                tag
+            );
+            // No whitespace anywhere
+            assert_eq!(render("a<T>x</T>b"), format!("a{}b\n", md), "{}", tag);
+            // Doubled whitespace collapses to one space
+            assert_eq!(render("a  <T>x</T>  b"), format!("a {} b\n", md), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn test_whitespace_inside_trimmed_elements() {
+        for (tag, md) in [
+            ("em", "*x*"),
+            ("strong", "**x**"),
+            ("a href=\"u\"", "[x](u)"),
+        ] {
+            let close = tag.split(' ').next().unwrap();
+            let before = convert(&format!("<p>a<{}> x</{}>b</p>", tag, close));
+            assert_eq!(before, format!("a {}b\n", md), "{}", tag);
+
+            let after = convert(&format!("<p>a<{}>x </{}>b</p>", tag, close));
+            assert_eq!(after, format!("a{} b\n", md), "{}", tag);
+
+            let both = convert(&format!("<p>a <{}> x </{}> b</p>", tag, close));
+            assert_eq!(both, format!("a {} b\n", md), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn test_whitespace_between_adjacent_inline_elements() {
+        assert_eq!(
+            convert("<p><strong>Bold</strong>and text</p>"),
+            "**Bold**and text\n"
+        );
+        assert_eq!(
+            convert("<p><strong>Bold</strong>\n        and text</p>"),
+            "**Bold** and text\n"
+        );
+        assert_eq!(convert("<p>text\n<em>em</em></p>"), "text *em*\n");
+        assert_eq!(
+            convert("<p><em>one </em><strong>two</strong></p>"),
+            "*one* **two**\n"
+        );
+    }
+
+    #[test]
+    fn test_whitespace_around_skipped_element() {
+        assert_eq!(
+            convert("<p>Press the <svg><title>gear</title></svg> button</p>"),
+            "Press the button\n"
+        );
+    }
+
+    #[test]
+    fn test_break_does_not_indent_next_line() {
+        let result = convert("<p>line one <br> line two</p>");
+        assert_eq!(result, "line one\nline two\n");
+    }
+
     #[test]
     fn test_complex_document() {
         let html = r#"
@@ -316,4 +1450,227 @@ mod tests {
         assert!(result.contains("```rust"));
         assert!(result.contains("fn main()"));
     }
+
+    #[test]
+    fn test_force_kept_grandchildren_converted() {
+        let options = Options::new()
+            .exclude_selectors(vec!["nav".to_string(), ".ad".to_string()])
+            .include_selectors(vec![".keep".to_string()]);
+        let html = r#"<nav><a href="/">Home</a>
+            <div class="keep"><p>Read <em>this</em> first</p><ul><li>Tip</li></ul>
+                <div class="ad">Buy <b class="keep">now</b></div></div>
+        </nav><p>Body</p>"#;
+
+        assert_eq!(
+            convert_with(html, &options),
+            "Read *this* first\n\n- Tip\n\n**now**\n\nBody\n"
+        );
+    }
+
+    #[test]
+    fn test_force_kept_list_items_keep_prefix() {
+        let options = Options::new()
+            .exclude_selectors(vec!["ul".to_string(), "ol".to_string()])
+            .include_selectors(vec![".keep".to_string()]);
+        let html = r#"<p>Before</p><ol><li>One</li><li class="keep">Two</li>
+            <li class="keep">Three<ul><li>Sub</li></ul></li><li>Four</li></ol><p>After</p>"#;
+        assert_eq!(
+            convert_with(html, &options),
+            "Before\n\n2. Two\n3. Three\n\nAfter\n"
+        );
+
+        // Rescued from a nested list, an item starts at the left edge
+        let html =
+            r#"<ul><li>Outer<ul><li class="keep"><p>Para</p><p>More</p></li></ul></li></ul>"#;
+        assert_eq!(convert_with(html, &options), "- Para\n\n  More\n");
+    }
+
+    #[test]
+    fn test_force_kept_table_parts() {
+        let options = Options::new()
+            .exclude_selectors(vec!["table".to_string()])
+            .include_selectors(vec!["td.important".to_string(), "tr.keep".to_string()]);
+        let html = r#"<p>Before</p><table>
+            <tr><th>Plan</th><th>Price</th></tr>
+            <tr><td>Basic</td><td class="important">Free for students</td></tr>
+            <tr class="keep"><td>Pro</td><td>$10</td></tr>
+        </table><p>After</p>"#;
+        assert_eq!(
+            convert_with(html, &options),
+            "Before\n\nFree for students\n\nPro | $10\n\nAfter\n"
+        );
+    }
+
+    #[test]
+    fn test_stats_counts_skipped_content() {
+        let html = r#"
+            <nav class="menu"><a href="/">Home</a><img src="logo.png"></nav>
+            <aside class="ad">Buy now</aside>
+            <aside class="ad">Sale</aside>
+            <p>Read <a href="/a">this</a> and <a href="/b">that</a>.</p>
+            <table><tr><td>1</td></tr></table>
+            <img alt="no source">
+        "#;
+        let options = Options::new().exclude_selectors(vec![
+            "nav".to_string(),
+            ".ad".to_string(),
+            ".menu".to_string(),
+        ]);
+        let (markdown, metadata) = Converter::new().convert_with_metadata(html, &options);
+        let stats = metadata.stats;
+
+        assert!(!markdown.contains("Home"));
+        assert_eq!(stats.elements_skipped, 3);
+        assert_eq!(stats.skipped_by_selector.get("nav"), Some(&1));
+        assert_eq!(stats.skipped_by_selector.get(".ad"), Some(&2));
+        assert_eq!(stats.skipped_by_selector.get(".menu"), None);
+        assert_eq!(
+            stats.text_bytes_dropped,
+            "Home".len() + "Buy now".len() + "Sale".len()
+        );
+        assert_eq!(stats.images_dropped, 2);
+        assert_eq!(stats.links_converted, 2);
+        assert_eq!(stats.tables_converted, 1);
+    }
+
+    #[test]
+    fn test_stats_elements_visited() {
+        let (_, metadata) =
+            Converter::new().convert_with_metadata("<p>One <em>two</em></p>", &Options::default());
+        // head, body, p, em below the root html element
+        assert_eq!(metadata.stats.elements_visited, 4);
+        assert_eq!(metadata.stats.elements_skipped, 0);
+    }
+
+    #[test]
+    fn test_stats_unhandled_tags() {
+        let html = r#"
+            <div><section><span>Drawn below</span></section></div>
+            <canvas>No canvas support</canvas>
+            <output>42</output>
+            <p><output>7</output></p>
+            <p>After</p>
+        "#;
+        let (markdown, metadata) =
+            Converter::new().convert_with_metadata(html, &Options::default());
+        let unhandled = metadata.stats.unhandled_tags;
+
+        assert!(markdown.contains("No canvas support"));
+        assert_eq!(unhandled.get("canvas"), Some(&1));
+        assert_eq!(unhandled.get("output"), Some(&2));
+        assert_eq!(unhandled.len(), 2, "{:?}", unhandled);
+    }
+
+    #[cfg(feature = "token-estimate")]
+    #[test]
+    fn test_stats_estimated_tokens() {
+        let html = "<h1>Title</h1><p>The quick brown fox jumps over the lazy dog.</p>";
+        let (markdown, metadata) =
+            Converter::new().convert_with_metadata(html, &Options::default());
+        assert_eq!(
+            metadata.stats.estimated_tokens,
+            crate::estimate_tokens(&markdown)
+        );
+        assert_eq!(metadata.stats.estimated_tokens, 14);
+    }
+
+    #[test]
+    fn test_stats_do_not_change_output() {
+        let html = "<h1>Title</h1><div class='x'>Gone</div><p><a href='/'>Link</a></p>";
+        let options = Options::new().exclude_selectors(vec![".x".to_string()]);
+        let (markdown, _) = Converter::new().convert_with_metadata(html, &options);
+        assert_eq!(markdown, convert_with(html, &options));
+    }
+
+    #[test]
+    fn test_metadata_links_and_images() {
+        let html = r##"
+            <nav><a href="/">Home</a></nav>
+            <p>See <a href="guide">the
+                guide</a>, <a href="https://www.example.com/x">ours</a> and
+                <a href="https://other.org/">theirs</a>.</p>
+            <a href="/card"><img src="card.png" alt="Card"></a>
+            <a href="#">Top</a> <a href="mailto:me@example.com">Mail</a>
+            <img alt="no source"> <img src="data:image/png;base64,AAAA" alt="inline">
+        "##;
+        let options = Options::new()
+            .base_url(Some("https://example.com/docs/".to_string()))
+            .exclude_selectors(vec!["nav".to_string()])
+            .data_uri_images(crate::options::DataUriImages::Drop);
+        let (_, metadata) = Converter::new().convert_with_metadata(html, &options);
+
+        let links: Vec<_> = metadata
+            .links
+            .iter()
+            .map(|l| (l.href.as_str(), l.text.as_str(), l.internal, l.dropped))
+            .collect();
+        assert_eq!(
+            links,
+            [
+                ("/", "Home", true, true),
+                ("guide", "the guide", true, false),
+                ("https://www.example.com/x", "ours", true, false),
+                ("https://other.org/", "theirs", false, false),
+                ("/card", "", true, false),
+                ("#", "Top", true, true),
+                ("mailto:me@example.com", "Mail", false, false),
+            ]
+        );
+        assert_eq!(metadata.links[1].url, "https://example.com/docs/guide");
+        assert_eq!(metadata.links[0].url, "https://example.com/");
+
+        let images: Vec<_> = metadata
+            .images
+            .iter()
+            .map(|i| (i.src.as_str(), i.alt.as_str(), i.dropped))
+            .collect();
+        assert_eq!(
+            images,
+            [
+                ("https://example.com/docs/card.png", "Card", false),
+                ("", "no source", true),
+                ("data:image/png;base64,AAAA", "inline", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metadata_links_without_link_rule() {
+        let html = "<p><a href='/a'>A</a></p>";
+        let options = Options::new().disable_rules(vec![crate::options::DisabledRule::Links]);
+        let (markdown, metadata) =
+            Converter::with_options(&options).convert_with_metadata(html, &options);
+        assert_eq!(markdown, "A\n");
+        assert!(metadata.links[0].dropped);
+    }
+
+    #[test]
+    fn test_context_through_elements_without_rules() {
+        // The <br> is reached through a div and span, which have no rule,
+        // and a paragraph, whose rule is converting
+        let html = "<table><tr><th>A</th></tr>\
+            <tr><td><div><p><span>one<br>two</span></p></div></td></tr></table>\
+            <p>three<br>four</p>";
+        let markdown = convert(html);
+        assert!(markdown.contains("| one<br>two |"));
+        assert!(markdown.ends_with("three\nfour\n"));
+    }
+
+    #[cfg(feature = "profile")]
+    #[test]
+    fn test_phase_timings() {
+        let converter = Converter::new();
+        let (_, metadata) = converter.convert_with_metadata("<p>Hi</p>", &Options::default());
+        let timings = metadata.timings;
+        assert!(timings.parse > std::time::Duration::ZERO);
+        assert!(timings.postprocess > std::time::Duration::ZERO);
+        assert_eq!(
+            timings.total(),
+            timings.parse + timings.precompute + timings.convert + timings.postprocess
+        );
+
+        // Empty input skips every phase
+        let (_, metadata) = converter.convert_with_metadata("", &Options::default());
+        assert_eq!(metadata.timings, PhaseTimings::default());
+    }
 }
@@ -0,0 +1,171 @@
+//! Warnings about content a conversion lost without failing.
+//!
+//! Conversions never fail on bad options or oversized input: an invalid
+//! selector is ignored and a limit cuts the output short. The warnings
+//! returned by [`convert_with_metadata`](crate::convert_with_metadata) say
+//! when that happened, so callers that can't see log output still notice.
+
+use scraper::Selector;
+
+use crate::limits::Truncation;
+use crate::options::Options;
+
+/// What a [`Warning`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
+#[non_exhaustive]
+pub enum WarningCode {
+    /// A selector in the options doesn't parse and was ignored.
+    InvalidSelector,
+    /// Another option has a value that can't be used and fell back to a
+    /// default, as [`Options::validate`] reports.
+    InvalidOption,
+    /// A limit stopped the conversion before the end of the document.
+    TruncatedOutput,
+    /// The markdown doesn't parse back to what was written, with
+    /// `validate_output`.
+    InvalidOutput,
+}
+
+impl WarningCode {
+    /// The code as written in bindings, like `INVALID_SELECTOR`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::InvalidSelector => "INVALID_SELECTOR",
+            Self::InvalidOption => "INVALID_OPTION",
+            Self::TruncatedOutput => "TRUNCATED_OUTPUT",
+            Self::InvalidOutput => "INVALID_OUTPUT",
+        }
+    }
+}
+
+/// Something a conversion dropped or ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Warning {
+    /// What the warning is about.
+    pub code: WarningCode,
+
+    /// Description of what happened.
+    pub message: String,
+
+    /// The value at fault, like the selector or the limit's option name.
+    pub detail: Option<String>,
+}
+
+/// Warnings for selectors and option values a conversion ignores.
+pub(crate) fn option_warnings(options: &Options) -> Vec<Warning> {
+    let email_quotes = if options.email_mode {
+        &options.email_quote_selectors[..]
+    } else {
+        &[]
+    };
+    let selectors = [
+        ("exclude_selectors", &options.exclude_selectors[..]),
+        ("include_selectors", &options.include_selectors[..]),
+        ("email_quote_selectors", email_quotes),
+    ]
+    .into_iter()
+    .flat_map(|(option, selectors)| selectors.iter().map(move |s| (option, s)))
+    .chain(
+        options
+            .capture_attributes
+            .iter()
+            .map(|(s, _)| ("capture_attributes", s)),
+    );
+
+    let mut warnings: Vec<Warning> = selectors
+        .filter_map(|(option, selector)| {
+            let e = Selector::parse(selector).err()?;
+            Some(Warning {
+                code: WarningCode::InvalidSelector,
+                message: format!("invalid selector in {} ignored: {:?}", option, e),
+                detail: Some(selector.clone()),
+            })
+        })
+        .collect();
+
+    if let Err(e) = options.validate() {
+        warnings.push(Warning {
+            code: WarningCode::InvalidOption,
+            message: e.to_string(),
+            detail: None,
+        });
+    }
+    warnings
+}
+
+/// Warning for a conversion a limit cut short.
+pub(crate) fn truncation_warning(truncation: Truncation) -> Warning {
+    let (option, message) = match truncation {
+        Truncation::MaxInputBytes => ("max_input_bytes", "input was cut before parsing"),
+        Truncation::MaxElements => ("max_elements", "conversion stopped at the element limit"),
+        Truncation::Deadline => ("deadline", "conversion stopped when the deadline passed"),
+    };
+    Warning {
+        code: WarningCode::TruncatedOutput,
+        message: message.to_string(),
+        detail: Some(option.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert_with_metadata;
+
+    #[test]
+    fn test_no_warnings_for_valid_options() {
+        let options = Options::new()
+            .exclude_selectors(vec![".ad".to_string(), "nav > ul".to_string()])
+            .include_selectors(vec!["#keep".to_string()]);
+        let (_, metadata) = convert_with_metadata("<p>Text</p>", &options);
+        assert!(metadata.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        let options = Options::new().exclude_selectors(vec![".ad".to_string(), "[[".to_string()]);
+        let (markdown, metadata) = convert_with_metadata("<p>Text</p>", &options);
+        assert_eq!(markdown, "Text\n");
+        assert_eq!(metadata.warnings.len(), 1);
+        let warning = &metadata.warnings[0];
+        assert_eq!(warning.code, WarningCode::InvalidSelector);
+        assert_eq!(warning.code.as_str(), "INVALID_SELECTOR");
+        assert_eq!(warning.detail.as_deref(), Some("[["));
+        assert!(warning.message.contains("exclude_selectors"));
+    }
+
+    #[test]
+    fn test_invalid_option() {
+        let options = Options::new().bullet_cycle(Some(vec!['-', '>']));
+        let (_, metadata) = convert_with_metadata("<ul><li>A</li></ul>", &options);
+        assert_eq!(metadata.warnings.len(), 1);
+        assert_eq!(metadata.warnings[0].code, WarningCode::InvalidOption);
+        assert!(metadata.warnings[0].message.contains("bullet_cycle"));
+    }
+
+    #[test]
+    fn test_truncated_output() {
+        let options = Options::new().max_input_bytes(Some(10));
+        let (_, metadata) = convert_with_metadata("<p>Some longer text</p>", &options);
+        assert_eq!(
+            metadata.warnings,
+            vec![truncation_warning(Truncation::MaxInputBytes)]
+        );
+        assert_eq!(
+            metadata.warnings[0].detail.as_deref(),
+            Some("max_input_bytes")
+        );
+    }
+
+    #[test]
+    fn test_invalid_email_quote_selector() {
+        let options = Options::new().email_quote_selectors(vec!["[[".to_string()]);
+        assert!(option_warnings(&options).is_empty());
+        let warnings = option_warnings(&options.email_mode(true));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("email_quote_selectors"));
+    }
+}
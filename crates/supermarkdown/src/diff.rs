@@ -0,0 +1,254 @@
+//! Line diffs between two conversions of the same document, for tuning
+//! options.
+//!
+//! The diff is a longest common subsequence over lines, rendered in the
+//! unified format with [`CONTEXT_LINES`] of context around each change. The
+//! table is quadratic in the line count, which is fine for the pages this is
+//! meant for but not for diffing whole books.
+
+use std::fmt::Write;
+
+use crate::options::Options;
+
+/// Unchanged lines shown before and after each change.
+const CONTEXT_LINES: usize = 3;
+
+/// The outputs of converting one document with two option sets.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConversionDiff {
+    /// Markdown produced with the first options.
+    pub left: String,
+
+    /// Markdown produced with the second options.
+    pub right: String,
+
+    /// Unified diff from `left` to `right`, empty when they are equal.
+    pub diff: String,
+}
+
+impl ConversionDiff {
+    /// Convert `html` with both option sets and diff the results.
+    pub(crate) fn new(html: &str, left: &Options, right: &Options) -> Self {
+        let left = crate::convert_with_options(html, left);
+        let right = crate::convert_with_options(html, right);
+        let diff = unified_diff(&left, &right);
+        Self { left, right, diff }
+    }
+
+    /// Whether both option sets produced the same markdown.
+    pub fn is_empty(&self) -> bool {
+        self.diff.is_empty()
+    }
+}
+
+/// One step of the edit script turning the old lines into the new ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// Line `.0` of the old text equals line `.1` of the new text.
+    Keep(usize, usize),
+    /// Line of the old text missing from the new text.
+    Delete(usize),
+    /// Line of the new text missing from the old text.
+    Insert(usize),
+}
+
+/// Shortest edit script between `old` and `new`, in order.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    // lcs[i][j] is the common subsequence length of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            edits.push(Edit::Keep(i, j));
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            edits.push(Edit::Delete(i));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(j));
+            j += 1;
+        }
+    }
+    edits.extend((i..old.len()).map(Edit::Delete));
+    edits.extend((j..new.len()).map(Edit::Insert));
+    edits
+}
+
+/// Render a unified diff from `old` to `new`, labelled `left` and `right`.
+///
+/// Returns an empty string when the texts have the same lines.
+pub(crate) fn unified_diff(old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let edits = edit_script(&old_lines, &new_lines);
+
+    let mut out = String::new();
+    let mut start = 0;
+    while let Some(offset) = edits[start..]
+        .iter()
+        .position(|edit| !matches!(edit, Edit::Keep(..)))
+    {
+        let first_change = start + offset;
+        let hunk_start = first_change.saturating_sub(CONTEXT_LINES).max(start);
+
+        // Extend the hunk while the next change is close enough that the
+        // context between them would touch.
+        let mut last_change = first_change;
+        let mut k = first_change + 1;
+        while k < edits.len() {
+            if !matches!(edits[k], Edit::Keep(..)) {
+                last_change = k;
+            } else if k - last_change > 2 * CONTEXT_LINES {
+                break;
+            }
+            k += 1;
+        }
+        let hunk_end = (last_change + 1 + CONTEXT_LINES).min(edits.len());
+
+        if out.is_empty() {
+            out.push_str("--- left\n+++ right\n");
+        }
+        write_hunk(
+            &edits[hunk_start..hunk_end],
+            &old_lines,
+            &new_lines,
+            &mut out,
+        );
+        start = hunk_end;
+    }
+    out
+}
+
+/// Write one `@@` hunk covering `edits`.
+fn write_hunk(edits: &[Edit], old: &[&str], new: &[&str], out: &mut String) {
+    // Line numbers are 1-based; an empty side points at the line before it.
+    let (mut old_start, mut new_start) = (None, None);
+    let (mut old_count, mut new_count) = (0, 0);
+    let (mut old_next, mut new_next) = (0, 0);
+    for edit in edits {
+        match *edit {
+            Edit::Keep(i, j) => {
+                old_start.get_or_insert(i);
+                new_start.get_or_insert(j);
+                old_count += 1;
+                new_count += 1;
+                old_next = i + 1;
+                new_next = j + 1;
+            }
+            Edit::Delete(i) => {
+                old_start.get_or_insert(i);
+                old_count += 1;
+                old_next = i + 1;
+            }
+            Edit::Insert(j) => {
+                new_start.get_or_insert(j);
+                new_count += 1;
+                new_next = j + 1;
+            }
+        }
+    }
+    let old_start = old_start.map_or(old_next, |i| i + 1);
+    let new_start = new_start.map_or(new_next, |j| j + 1);
+    let _ = writeln!(
+        out,
+        "@@ -{},{} +{},{} @@",
+        old_start, old_count, new_start, new_count
+    );
+
+    for edit in edits {
+        let (marker, line) = match *edit {
+            Edit::Keep(i, _) => (' ', old[i]),
+            Edit::Delete(i) => ('-', old[i]),
+            Edit::Insert(j) => ('+', new[j]),
+        };
+        out.push(marker);
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::HeadingStyle;
+
+    #[test]
+    fn test_equal_texts_have_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n"), "");
+        assert_eq!(unified_diff("", ""), "");
+    }
+
+    #[test]
+    fn test_changed_line() {
+        assert_eq!(
+            unified_diff("a\nb\nc\n", "a\nB\nc\n"),
+            "--- left\n+++ right\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_into_empty() {
+        assert_eq!(
+            unified_diff("", "x\n"),
+            "--- left\n+++ right\n@@ -0,0 +1,1 @@\n+x\n"
+        );
+        assert_eq!(
+            unified_diff("x\n", ""),
+            "--- left\n+++ right\n@@ -1,1 +0,0 @@\n-x\n"
+        );
+    }
+
+    #[test]
+    fn test_distant_changes_get_separate_hunks() {
+        let line = |n: usize| match n {
+            2 => "two".to_string(),
+            19 => "nineteen".to_string(),
+            n => n.to_string(),
+        };
+        let old: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new: String = (1..=20).map(|n| format!("{}\n", line(n))).collect();
+        let diff = unified_diff(&old, &new);
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n"));
+        assert!(diff.contains("@@ -16,5 +16,5 @@\n 16\n 17\n 18\n-19\n+nineteen\n 20\n"));
+    }
+
+    #[test]
+    fn test_nearby_changes_share_a_hunk() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n";
+        let new = "one\n2\n3\n4\n5\n6\nseven\n";
+        let diff = unified_diff(old, new);
+        assert_eq!(diff.matches("@@ -").count(), 1);
+        assert!(diff.starts_with("--- left\n+++ right\n@@ -1,7 +1,7 @@\n"));
+    }
+
+    #[test]
+    fn test_conversion_diff() {
+        let html = "<h1>Title</h1><p>Body</p>";
+        let left = Options::default();
+        let right = Options::new().heading_style(HeadingStyle::Setext);
+
+        let diff = ConversionDiff::new(html, &left, &right);
+        assert_eq!(diff.left, "# Title\n\nBody\n");
+        assert_eq!(diff.right, "Title\n=====\n\nBody\n");
+        assert_eq!(
+            diff.diff,
+            "--- left\n+++ right\n@@ -1,3 +1,4 @@\n-# Title\n+Title\n+=====\n \n Body\n"
+        );
+
+        assert!(ConversionDiff::new(html, &left, &left).is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! HTML email specifics for `email_mode`.
+//!
+//! Outlook reads content in `<!--[if mso]>...<![endif]-->`, which other
+//! clients skip as a comment. A parser skips it too, unless markup inside
+//! closes the comment early, as `<!--[if mso]><!-- x --><table>` does, or
+//! the downlevel-revealed form `<![if mso]>...<![endif]>` is used, whose
+//! content isn't in a comment at all. Either way Outlook-only content like
+//! VML buttons and spacer tables leaks into the text, so it is cut from the
+//! input. Content meant for every client but Outlook, as in
+//! `<!--[if !mso]><!-->...<!--<![endif]-->`, is kept.
+//!
+//! Mail editors write each line of a message as a `<div>` of its own and a
+//! blank line as `<div><br></div>`. A div holding only inline content is
+//! thus a line, and runs of lines make a paragraph.
+
+use std::borrow::Cow;
+
+use ego_tree::NodeRef;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use scraper::{ElementRef, Node};
+
+/// A conditional comment, its condition and its content.
+static COMMENT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<!--\[if\s([^\]]*)\]>(.*?)<!\[endif\]\s*-->").unwrap());
+
+/// A downlevel-revealed conditional, its condition and its content.
+static REVEALED_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<!\[if\s([^\]]*)\]>(.*?)<!\[endif\]>").unwrap());
+
+/// Block tags, so a div holding one is a container rather than a line.
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Remove conditional comments and Outlook-only conditional content from
+/// `html`.
+pub(crate) fn strip_conditional_comments(html: &str) -> Cow<'_, str> {
+    if !html.contains("[if") {
+        return Cow::Borrowed(html);
+    }
+    let html = COMMENT_RE.replace_all(html, |caps: &Captures| {
+        // <!--[if !mso]><!--> reveals the content to other clients
+        let content = &caps[2];
+        match content.strip_prefix("<!-->") {
+            Some(revealed) => revealed
+                .strip_suffix("<!--")
+                .unwrap_or(revealed)
+                .to_string(),
+            None => String::new(),
+        }
+    });
+    let html = REVEALED_RE.replace_all(&html, |caps: &Captures| {
+        if is_negated(&caps[1]) {
+            caps[2].to_string()
+        } else {
+            String::new()
+        }
+    });
+    Cow::Owned(html.into_owned())
+}
+
+/// Whether a condition like `!mso` or `!(IE)` excludes rather than picks
+/// out the clients that read the content.
+fn is_negated(condition: &str) -> bool {
+    condition.trim_start().starts_with('!')
+}
+
+/// A `<div>` written as a line of a message from its converted children,
+/// or a blank line for `<div><br></div>`. Divs holding blocks are
+/// containers and keep their content as it is.
+pub(crate) fn div_line(div: ElementRef, content: String) -> String {
+    if div.children().any(|child| is_block(&child)) {
+        return content;
+    }
+
+    let content = content.trim();
+    if content.is_empty() {
+        return if has_break(div) {
+            "\n\n".to_string()
+        } else {
+            String::new()
+        };
+    }
+    if follows_inline(div) {
+        format!("\n{}\n", content)
+    } else {
+        format!("{}\n", content)
+    }
+}
+
+fn is_block(node: &NodeRef<Node>) -> bool {
+    node.value()
+        .as_element()
+        .is_some_and(|el| BLOCK_TAGS.contains(&el.name()))
+}
+
+fn has_break(element: ElementRef) -> bool {
+    element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .any(|el| el.value().name() == "br")
+}
+
+/// Whether the div comes after text or an inline element other than
+/// `<br>`, which it has to start a new line after.
+fn follows_inline(element: ElementRef) -> bool {
+    let previous = element.prev_siblings().find(|node| match node.value() {
+        Node::Text(text) => !text.trim().is_empty(),
+        Node::Element(_) => true,
+        _ => false,
+    });
+    match previous.as_ref().map(NodeRef::value) {
+        Some(Node::Text(_)) => true,
+        Some(Node::Element(el)) => el.name() != "br" && !BLOCK_TAGS.contains(&el.name()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert, convert_with_options, Options};
+
+    fn convert_email(html: &str) -> String {
+        convert_with_options(html, &Options::new().email_mode(true))
+    }
+
+    #[test]
+    fn test_outlook_only_content_removed() {
+        let html =
+            "<p>A</p><!--[if mso]><table><tr><td>Outlook</td></tr></table><![endif]--><p>B</p>";
+        assert_eq!(strip_conditional_comments(html), "<p>A</p><p>B</p>");
+
+        // A comment inside closes the conditional comment early
+        let html = "<!--[if gte mso 9]><!-- spacer --><v:rect>Button</v:rect><![endif]-->Text";
+        assert_eq!(strip_conditional_comments(html), "Text");
+
+        let html = "<![if mso]><p>Outlook</p><![endif]><p>Text</p>";
+        assert_eq!(strip_conditional_comments(html), "<p>Text</p>");
+    }
+
+    #[test]
+    fn test_content_for_other_clients_kept() {
+        let html = "<!--[if !mso]><!--><p>Everyone else</p><!--<![endif]-->";
+        assert_eq!(strip_conditional_comments(html), "<p>Everyone else</p>");
+
+        let html = "<![if !mso]><p>Everyone else</p><![endif]>";
+        assert_eq!(strip_conditional_comments(html), "<p>Everyone else</p>");
+    }
+
+    #[test]
+    fn test_plain_html_borrowed() {
+        let html = "<p>No conditionals <!-- here --></p>";
+        assert!(matches!(strip_conditional_comments(html), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_divs_unchanged_without_email_mode() {
+        let html = "<div><div>one</div><div>two</div></div>";
+        assert_eq!(convert(html), "onetwo\n");
+    }
+
+    #[test]
+    fn test_div_lines_and_blank_lines() {
+        let html = "<div dir=\"ltr\"><div>Hi Sam,</div><div><br></div>\
+            <div>Thanks for the notes.</div><div>More <b>soon</b>.<br></div>\
+            <div><br></div><div>Alex</div></div>";
+        assert_eq!(
+            convert_email(html),
+            "Hi Sam,\n\nThanks for the notes.\nMore **soon**.\n\nAlex\n"
+        );
+    }
+
+    #[test]
+    fn test_div_line_after_inline_content() {
+        let html = "<div>Text <b>bold</b><div>Next line</div>After</div><p>Para</p>";
+        assert_eq!(
+            convert_email(html),
+            "Text **bold**\nNext line\nAfter\n\nPara\n"
+        );
+    }
+
+    #[test]
+    fn test_container_divs_unchanged() {
+        let html = "<div><p>One</p><div><ul><li>Item</li></ul></div></div>";
+        assert_eq!(convert_email(html), convert(html));
+    }
+}
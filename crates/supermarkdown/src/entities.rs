@@ -1,5 +1,7 @@
 //! HTML entity decoding.
 
+use std::borrow::Cow;
+
 use once_cell::sync::Lazy;
 use regex::Regex;
 use rustc_hash::FxHashMap;
@@ -53,9 +55,19 @@ static ENTITIES: Lazy<FxHashMap<&'static str, &'static str>> = Lazy::new(|| {
     m
 });
 
+/// Longest entity name or number decoded. The longest named entity in
+/// HTML has 31 characters; longer candidates are kept as text without
+/// scanning them to the end.
+const MAX_ENTITY_LEN: usize = 32;
+
 /// Regex for matching HTML entities (named, decimal, and hex).
-static ENTITY_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"&(?:#(\d+)|#x([0-9a-fA-F]+)|(\w+));").unwrap());
+static ENTITY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"&(?:#(\d{{1,{0}}})|#x([0-9a-fA-F]{{1,{0}}})|(\w{{1,{0}}}));",
+        MAX_ENTITY_LEN
+    ))
+    .unwrap()
+});
 
 /// Decode HTML entities in text.
 ///
@@ -64,97 +76,176 @@ static ENTITY_RE: Lazy<Regex> =
 /// - Decimal numeric entities: `&#123;` → `{`
 /// - Hexadecimal numeric entities: `&#x7B;` → `{`
 ///
-/// Unrecognized entities are left as-is.
-pub fn decode_entities(text: &str) -> String {
+/// Numeric entities for NUL, surrogates and code points past U+10FFFF
+/// decode to U+FFFD, as in the HTML parser. Unrecognized named entities,
+/// and candidates longer than [`MAX_ENTITY_LEN`], are left as-is. Text
+/// without entities is returned borrowed.
+///
+/// The decoded text is at most `max_expansion_ratio` times as long as
+/// `text`, or as long when the ratio is below 1: decoding stops before the
+/// first entity that would take it past that, keeping the rest verbatim.
+/// Every entity this decodes is at least as long as its replacement, so
+/// the limit is a safety net rather than something text reaches.
+pub fn decode_entities(text: &str, max_expansion_ratio: f64) -> Cow<'_, str> {
     if !text.contains('&') {
-        return text.to_string();
+        return Cow::Borrowed(text);
     }
 
-    ENTITY_RE
-        .replace_all(text, |caps: &regex::Captures| {
+    // Verbatim text always fits, so stopping never breaks the budget
+    let budget = (text.len() as f64 * max_expansion_ratio.max(1.0)) as usize;
+    let mut decoded = String::new();
+    let mut last = 0;
+    for caps in ENTITY_RE.captures_iter(text) {
+        let Some(entity) = caps.get(0) else {
+            continue;
+        };
+        let mut buf = [0u8; 4];
+        let replacement = if let Some(decimal) = caps.get(1) {
             // Numeric decimal: &#123;
-            if let Some(decimal) = caps.get(1) {
-                if let Ok(code) = decimal.as_str().parse::<u32>() {
-                    if let Some(c) = char::from_u32(code) {
-                        return c.to_string();
-                    }
-                }
-            }
+            numeric_char(decimal.as_str().parse::<u32>().ok()).encode_utf8(&mut buf)
+        } else if let Some(hex) = caps.get(2) {
             // Numeric hex: &#x7B;
-            if let Some(hex) = caps.get(2) {
-                if let Ok(code) = u32::from_str_radix(hex.as_str(), 16) {
-                    if let Some(c) = char::from_u32(code) {
-                        return c.to_string();
-                    }
-                }
-            }
-            // Named entity: &amp;
-            if let Some(name) = caps.get(3) {
-                let entity = format!("&{};", name.as_str());
-                if let Some(replacement) = ENTITIES.get(entity.as_str()) {
-                    return (*replacement).to_string();
-                }
-            }
-            // Return original if not recognized
-            caps[0].to_string()
-        })
-        .into_owned()
+            numeric_char(u32::from_str_radix(hex.as_str(), 16).ok()).encode_utf8(&mut buf)
+        } else {
+            // Named entity: &amp;, or the original if not recognized
+            ENTITIES
+                .get(entity.as_str())
+                .copied()
+                .unwrap_or(entity.as_str())
+        };
+
+        let rest = text.len() - entity.end();
+        if decoded.len() + (entity.start() - last) + replacement.len() + rest > budget {
+            break;
+        }
+        if decoded.is_empty() {
+            decoded.reserve(text.len());
+        }
+        decoded.push_str(&text[last..entity.start()]);
+        decoded.push_str(replacement);
+        last = entity.end();
+    }
+
+    if last == 0 {
+        return Cow::Borrowed(text);
+    }
+    decoded.push_str(&text[last..]);
+    Cow::Owned(decoded)
+}
+
+/// The character of a numeric entity; `None` is a code point too large for
+/// a u32.
+fn numeric_char(code: Option<u32>) -> char {
+    match code {
+        Some(0) | None => char::REPLACEMENT_CHARACTER,
+        // Surrogates and out-of-range code points have no char
+        Some(code) => char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_no_entities_borrows() {
+        assert!(matches!(
+            decode_entities("plain text", 1.0),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(decode_entities("a & b", 1.0), Cow::Borrowed(_)));
+        assert!(matches!(decode_entities("a &amp; b", 1.0), Cow::Owned(_)));
+    }
+
     #[test]
     fn test_named_entities() {
-        assert_eq!(decode_entities("&amp;"), "&");
-        assert_eq!(decode_entities("&lt;"), "<");
-        assert_eq!(decode_entities("&gt;"), ">");
-        assert_eq!(decode_entities("&quot;"), "\"");
-        assert_eq!(decode_entities("&apos;"), "'");
-        assert_eq!(decode_entities("&nbsp;"), " ");
+        assert_eq!(decode_entities("&amp;", 1.0), "&");
+        assert_eq!(decode_entities("&lt;", 1.0), "<");
+        assert_eq!(decode_entities("&gt;", 1.0), ">");
+        assert_eq!(decode_entities("&quot;", 1.0), "\"");
+        assert_eq!(decode_entities("&apos;", 1.0), "'");
+        assert_eq!(decode_entities("&nbsp;", 1.0), " ");
     }
 
     #[test]
     fn test_numeric_decimal() {
-        assert_eq!(decode_entities("&#38;"), "&");
-        assert_eq!(decode_entities("&#60;"), "<");
-        assert_eq!(decode_entities("&#62;"), ">");
-        assert_eq!(decode_entities("&#123;"), "{");
+        assert_eq!(decode_entities("&#38;", 1.0), "&");
+        assert_eq!(decode_entities("&#60;", 1.0), "<");
+        assert_eq!(decode_entities("&#62;", 1.0), ">");
+        assert_eq!(decode_entities("&#123;", 1.0), "{");
+    }
+
+    #[test]
+    fn test_entity_bomb_within_ratio() {
+        // A megabyte of entities, as one text node
+        let bomb = "&#65;&#x1F600;&amp;".repeat(1 << 16);
+        let decoded = decode_entities(&bomb, 3.0);
+        assert!(decoded.len() <= bomb.len() * 3);
+        assert_eq!(decoded.len(), (1 + 4 + 1) << 16);
+        assert!(decoded.starts_with("A\u{1F600}&A"));
+
+        // A ratio below 1 still lets text shrink
+        assert_eq!(decode_entities("&#65;&amp;", 0.5), "A&");
+    }
+
+    #[test]
+    fn test_overlong_entities_kept() {
+        let overlong = format!("&#{};&#x{};", "9".repeat(1 << 20), "f".repeat(1 << 20));
+        assert!(matches!(decode_entities(&overlong, 1.0), Cow::Borrowed(_)));
+
+        let name = "a".repeat(MAX_ENTITY_LEN + 1);
+        let text = format!("&{name}; &#{}; &amp;", "0".repeat(MAX_ENTITY_LEN + 1));
+        assert_eq!(
+            decode_entities(&text, 1.0),
+            format!("&{name}; &#{}; &", "0".repeat(MAX_ENTITY_LEN + 1))
+        );
     }
 
     #[test]
     fn test_numeric_hex() {
-        assert_eq!(decode_entities("&#x26;"), "&");
-        assert_eq!(decode_entities("&#x3C;"), "<");
-        assert_eq!(decode_entities("&#x3E;"), ">");
-        assert_eq!(decode_entities("&#x7B;"), "{");
+        assert_eq!(decode_entities("&#x26;", 1.0), "&");
+        assert_eq!(decode_entities("&#x3C;", 1.0), "<");
+        assert_eq!(decode_entities("&#x3E;", 1.0), ">");
+        assert_eq!(decode_entities("&#x7B;", 1.0), "{");
+    }
+
+    #[test]
+    fn test_invalid_code_points() {
+        // Never NUL, and the same replacement for every invalid code point
+        assert_eq!(decode_entities("&#0;", 1.0), "\u{FFFD}");
+        assert_eq!(decode_entities("&#x0;", 1.0), "\u{FFFD}");
+        assert_eq!(decode_entities("&#x110000;", 1.0), "\u{FFFD}");
+        assert_eq!(decode_entities("&#1114112;", 1.0), "\u{FFFD}");
+        assert_eq!(decode_entities("&#xD800;", 1.0), "\u{FFFD}");
+        assert_eq!(decode_entities("&#99999999999;", 1.0), "\u{FFFD}");
+        assert_eq!(decode_entities("&#xFFFFFFFFFF;", 1.0), "\u{FFFD}");
+        assert_eq!(decode_entities("&#x10FFFF;", 1.0), "\u{10FFFF}");
     }
 
     #[test]
     fn test_mixed() {
         assert_eq!(
-            decode_entities("Hello &amp; World &lt;test&gt;"),
+            decode_entities("Hello &amp; World &lt;test&gt;", 1.0),
             "Hello & World <test>"
         );
     }
 
     #[test]
     fn test_unknown_entity() {
-        assert_eq!(decode_entities("&unknown;"), "&unknown;");
+        assert_eq!(decode_entities("&unknown;", 1.0), "&unknown;");
     }
 
     #[test]
     fn test_no_entities() {
-        assert_eq!(decode_entities("Hello World"), "Hello World");
+        assert_eq!(decode_entities("Hello World", 1.0), "Hello World");
     }
 
     #[test]
     fn test_special_chars() {
-        assert_eq!(decode_entities("&mdash;"), "—");
-        assert_eq!(decode_entities("&hellip;"), "…");
+        assert_eq!(decode_entities("&mdash;", 1.0), "—");
+        assert_eq!(decode_entities("&hellip;", 1.0), "…");
         assert_eq!(
-            decode_entities("&ldquo;test&rdquo;"),
+            decode_entities("&ldquo;test&rdquo;", 1.0),
             "\u{201C}test\u{201D}"
         );
     }
@@ -0,0 +1,41 @@
+//! Errors returned by the checked conversion functions.
+
+use std::fmt;
+
+/// Error from a checked conversion such as
+/// [`try_convert_with_options`](crate::try_convert_with_options).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConvertError {
+    /// The input is larger than `max_input_bytes`.
+    InputTooLarge {
+        /// Input size in bytes.
+        len: usize,
+        /// The configured limit.
+        max: usize,
+    },
+    /// An option has a value that can't be used.
+    InvalidOption {
+        /// Option field name.
+        option: &'static str,
+        /// What is wrong with the value.
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InputTooLarge { len, max } => write!(
+                f,
+                "input is {} bytes, larger than the {} byte limit",
+                len, max
+            ),
+            Self::InvalidOption { option, reason } => {
+                write!(f, "invalid {}: {}", option, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
@@ -2,6 +2,10 @@
 
 #![allow(dead_code)] // Utility functions available for extensibility
 
+use std::borrow::Cow;
+
+use crate::options::EscapeMode;
+
 /// Escape special markdown characters in text.
 ///
 /// Characters escaped: \ ` * _ { } [ ] ( ) # + - . ! |
@@ -37,6 +41,24 @@ pub fn escape_title(text: &str) -> String {
     result
 }
 
+/// Generated text for the brackets of a link or image: on one line, with
+/// brackets and backslashes escaped so it can't end the text early.
+pub fn escape_link_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for word in text.split_whitespace() {
+        if !result.is_empty() {
+            result.push(' ');
+        }
+        for c in word.chars() {
+            if matches!(c, '\\' | '[' | ']') {
+                result.push('\\');
+            }
+            result.push(c);
+        }
+    }
+    result
+}
+
 /// Escape characters in link URLs.
 ///
 /// Escapes parentheses and spaces.
@@ -54,8 +76,93 @@ pub fn escape_url(url: &str) -> String {
 }
 
 /// Escape pipe characters for table cells.
+///
+/// GFM splits cells on pipes before parsing inline content, so pipes inside
+/// code spans need escaping too (`` `a \| b` `` renders as `a | b`). Pipes
+/// that are already escaped are left alone.
 pub fn escape_table_cell(text: &str) -> String {
-    text.replace('|', "\\|")
+    let mut result = String::with_capacity(text.len());
+    let mut backslashes = 0;
+
+    for c in text.chars() {
+        if c == '|' && backslashes % 2 == 0 {
+            result.push('\\');
+        }
+        backslashes = if c == '\\' { backslashes + 1 } else { 0 };
+        result.push(c);
+    }
+    result
+}
+
+/// Escape the start of text that would begin a list item, heading, quote,
+/// code fence or thematic break at the start of a line: `2024. It was`
+/// becomes `2024\. It was` and `- dash` becomes `\- dash`.
+///
+/// Already escaped text starts with a backslash, so it is left alone.
+pub fn escape_block_start(text: &str) -> Cow<'_, str> {
+    let trimmed = text.trim_start();
+    let bytes = trimmed.as_bytes();
+    // A marker must be followed by a space, a tab or the end of the text
+    let ends_marker = |i: usize| bytes.get(i).is_none_or(|&b| b == b' ' || b == b'\t');
+
+    let at = match bytes.first() {
+        Some(b'>') => Some(0),
+        Some(b'#') => {
+            let hashes = bytes.iter().take_while(|&&b| b == b'#').count();
+            (hashes <= 6 && ends_marker(hashes)).then_some(0)
+        }
+        Some(b'-' | b'+' | b'*') if ends_marker(1) => Some(0),
+        // Thematic breaks and setext underlines
+        Some(&first @ (b'-' | b'*' | b'_' | b'=')) if bytes.iter().all(|&b| b == first) => {
+            (first == b'=' || bytes.len() >= 3).then_some(0)
+        }
+        Some(b'`' | b'~') if trimmed.starts_with("```") || trimmed.starts_with("~~~") => Some(0),
+        Some(b'0'..=b'9') => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            (digits <= 9
+                && matches!(bytes.get(digits), Some(b'.' | b')'))
+                && ends_marker(digits + 1))
+            .then_some(digits)
+        }
+        _ => None,
+    };
+
+    match at {
+        Some(at) => {
+            let at = text.len() - trimmed.len() + at;
+            Cow::Owned(format!("{}\\{}", &text[..at], &text[at..]))
+        }
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Escape `<` that would open an HTML tag, comment or closing tag.
+///
+/// A `<` already preceded by an odd number of backslashes is left alone in
+/// `Backslash` mode, so escaping converted output again is stable.
+pub fn escape_tag_start(text: &str, mode: EscapeMode) -> Cow<'_, str> {
+    if mode == EscapeMode::None || !text.contains('<') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len() + 8);
+    let mut chars = text.chars().peekable();
+    let mut backslashes = 0;
+
+    while let Some(c) = chars.next() {
+        let tag_start = c == '<'
+            && chars
+                .peek()
+                .is_some_and(|&next| next.is_ascii_alphabetic() || next == '/' || next == '!');
+
+        match mode {
+            EscapeMode::Entities if tag_start => result.push_str("&lt;"),
+            EscapeMode::Backslash if tag_start && backslashes % 2 == 0 => result.push_str("\\<"),
+            _ => result.push(c),
+        }
+        backslashes = if c == '\\' { backslashes + 1 } else { 0 };
+    }
+    Cow::Owned(result)
 }
 
 /// Escape backticks in inline code.
@@ -82,11 +189,14 @@ pub fn calculate_code_backticks(code: &str) -> usize {
 }
 
 /// Resolve a relative URL against a base URL.
+///
+/// Protocol-relative URLs (`//cdn.example.com/x`) take the base's scheme.
+/// A base without a scheme (`example.com/dir/`) is taken as https, and the
+/// base's userinfo and port stay in the origin.
 pub fn resolve_url(base: &str, relative: &str) -> String {
     // If the URL is already absolute, return as-is
     if relative.starts_with("http://")
         || relative.starts_with("https://")
-        || relative.starts_with("//")
         || relative.starts_with("mailto:")
         || relative.starts_with("tel:")
         || relative.starts_with("data:")
@@ -94,34 +204,79 @@ pub fn resolve_url(base: &str, relative: &str) -> String {
         return relative.to_string();
     }
 
-    if relative.starts_with('/') {
+    let base = BaseUrl::parse(base);
+
+    if relative.starts_with("//") {
+        format!("{}:{}", base.scheme, relative)
+    } else if relative.starts_with('/') {
         // Absolute path - combine with base origin
-        if let Some(protocol_end) = base.find("://") {
-            let after_protocol = &base[protocol_end + 3..];
-            let origin_end = after_protocol
-                .find('/')
-                .map(|j| protocol_end + 3 + j)
-                .unwrap_or(base.len());
-            format!("{}{}", &base[..origin_end], relative)
-        } else {
-            format!("{}{}", base, relative)
-        }
+        format!("{}{}", base.origin(), relative)
     } else if relative.starts_with('#') || relative.starts_with('?') {
         // Fragment or query - append to base (without trailing slash)
-        let base = base.trim_end_matches('/');
-        format!("{}{}", base, relative)
-    } else {
-        // Relative path - combine with base directory
-        // If base ends with /, it's a directory - append directly
-        // Otherwise, find the last / and append to that directory
-        if base.ends_with('/') {
-            format!("{}{}", base, relative)
-        } else if let Some(last_slash) = base.rfind('/') {
-            format!("{}/{}", &base[..last_slash], relative)
+        let path = if relative.starts_with('#') {
+            base.rest.split('#').next().unwrap_or("")
         } else {
-            format!("{}/{}", base, relative)
+            base.path()
+        };
+        format!(
+            "{}{}{}",
+            base.origin(),
+            path.trim_end_matches('/'),
+            relative
+        )
+    } else {
+        // Relative path - combine with base directory, the path up to its
+        // last /; a bare origin has the root as its directory
+        let path = base.path();
+        let directory = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+        let directory = if directory.is_empty() { "/" } else { directory };
+        format!("{}{}{}", base.origin(), directory, relative)
+    }
+}
+
+/// A base URL split at the end of its origin.
+struct BaseUrl<'a> {
+    /// Scheme without `:`, `https` when the base has none.
+    scheme: &'a str,
+    /// `userinfo@host:port`, kept as written.
+    authority: &'a str,
+    /// Path, query and fragment.
+    rest: &'a str,
+}
+
+impl<'a> BaseUrl<'a> {
+    fn parse(base: &'a str) -> Self {
+        let base = base.trim();
+        let (scheme, after_scheme) = match base.split_once("://") {
+            Some((scheme, after)) if is_scheme(scheme) => (scheme, after),
+            _ => ("https", base.strip_prefix("//").unwrap_or(base)),
+        };
+        // Userinfo can't hold these, so the first one ends the authority
+        let authority_end = after_scheme
+            .find(['/', '?', '#'])
+            .unwrap_or(after_scheme.len());
+        BaseUrl {
+            scheme,
+            authority: &after_scheme[..authority_end],
+            rest: &after_scheme[authority_end..],
         }
     }
+
+    fn origin(&self) -> String {
+        format!("{}://{}", self.scheme, self.authority)
+    }
+
+    /// The path without query or fragment.
+    fn path(&self) -> &'a str {
+        self.rest.split(['?', '#']).next().unwrap_or("")
+    }
+}
+
+/// Whether `s` is a URL scheme like `https` or `svn+ssh`.
+fn is_scheme(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
 }
 
 #[cfg(test)]
@@ -150,6 +305,64 @@ mod tests {
     #[test]
     fn test_escape_table_cell() {
         assert_eq!(escape_table_cell("a | b"), "a \\| b");
+        assert_eq!(escape_table_cell("`x|y`"), "`x\\|y`");
+        // Already escaped pipes are not escaped twice
+        assert_eq!(escape_table_cell(r"a \| b"), r"a \| b");
+        assert_eq!(escape_table_cell(r"a \\| b"), r"a \\\| b");
+    }
+
+    #[test]
+    fn test_escape_block_start() {
+        assert_eq!(escape_block_start("2024. It was"), r"2024\. It was");
+        assert_eq!(escape_block_start("1) First"), r"1\) First");
+        assert_eq!(escape_block_start("- dash"), r"\- dash");
+        assert_eq!(escape_block_start(" + plus"), r" \+ plus");
+        assert_eq!(escape_block_start("* star"), r"\* star");
+        assert_eq!(escape_block_start("## Not a heading"), r"\## Not a heading");
+        assert_eq!(escape_block_start("> quoted"), r"\> quoted");
+        assert_eq!(escape_block_start("```rust"), r"\```rust");
+        assert_eq!(escape_block_start("---"), r"\---");
+        assert_eq!(escape_block_start("1."), r"1\.");
+
+        for text in [
+            "-dash",
+            "#hashtag",
+            "####### seven",
+            "1.5 litres",
+            "1234567890. x",
+            "*emphasis*",
+            "--",
+            r"\- dash",
+            "plain",
+        ] {
+            assert_eq!(escape_block_start(text), text);
+        }
+    }
+
+    #[test]
+    fn test_escape_tag_start() {
+        assert_eq!(
+            escape_tag_start("Vec<T> and </div>", EscapeMode::Entities),
+            "Vec&lt;T> and &lt;/div>"
+        );
+        assert_eq!(
+            escape_tag_start("<!-- x -->", EscapeMode::Entities),
+            "&lt;!-- x -->"
+        );
+        assert_eq!(
+            escape_tag_start("Vec<T>", EscapeMode::Backslash),
+            r"Vec\<T>"
+        );
+        assert_eq!(
+            escape_tag_start(r"Vec\<T>", EscapeMode::Backslash),
+            r"Vec\<T>"
+        );
+        assert_eq!(escape_tag_start("Vec<T>", EscapeMode::None), "Vec<T>");
+        // `<` not followed by a tag name is not HTML
+        assert_eq!(
+            escape_tag_start("a < b, x<3", EscapeMode::Entities),
+            "a < b, x<3"
+        );
     }
 
     #[test]
@@ -185,5 +398,74 @@ mod tests {
             resolve_url("https://example.com/page", "#section"),
             "https://example.com/page#section"
         );
+
+        // Relative paths against a bare origin
+        assert_eq!(
+            resolve_url("https://example.com", "other"),
+            "https://example.com/other"
+        );
+
+        // Protocol-relative URLs take the base scheme
+        assert_eq!(
+            resolve_url("http://example.com/page", "//cdn.example.com/a.js"),
+            "http://cdn.example.com/a.js"
+        );
+    }
+
+    #[test]
+    fn test_resolve_url_origins() {
+        let cases = [
+            // Ports and userinfo stay in the origin
+            (
+                "https://example.com:8443/app/page",
+                "/x",
+                "https://example.com:8443/x",
+            ),
+            (
+                "https://user@host:8443/path",
+                "/x",
+                "https://user@host:8443/x",
+            ),
+            (
+                "https://user:pw@host:8443",
+                "/x",
+                "https://user:pw@host:8443/x",
+            ),
+            (
+                "https://user@host:8443",
+                "x.png",
+                "https://user@host:8443/x.png",
+            ),
+            (
+                "https://user@host:8443",
+                "#top",
+                "https://user@host:8443#top",
+            ),
+            ("http://host:8080/a/b", "c", "http://host:8080/a/c"),
+            // Scheme-less bases are taken as https
+            ("example.com/dir/", "page", "https://example.com/dir/page"),
+            ("example.com/dir/", "/root", "https://example.com/root"),
+            ("example.com", "page", "https://example.com/page"),
+            ("example.com:81/a/b", "//cdn.net/x", "https://cdn.net/x"),
+            ("//example.com/a/", "b", "https://example.com/a/b"),
+            // The base's query and fragment aren't part of its directory
+            (
+                "https://host/dir/page?next=/a/b",
+                "img.png",
+                "https://host/dir/img.png",
+            ),
+            ("https://host?q=1", "/x", "https://host/x"),
+            ("https://host/page#old", "#new", "https://host/page#new"),
+            ("https://host/page?a=1#f", "?b=2", "https://host/page?b=2"),
+        ];
+        for (base, relative, expected) in cases {
+            assert_eq!(
+                resolve_url(base, relative),
+                expected,
+                "{} + {}",
+                base,
+                relative
+            );
+        }
     }
 }
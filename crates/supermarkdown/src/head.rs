@@ -0,0 +1,316 @@
+//! Document `<head>` handling.
+//!
+//! The head never contributes content to the markdown, but some of its
+//! elements matter: `<base href>` changes how relative URLs resolve,
+//! `<title>` is reported in the conversion metadata, and the `og:image`
+//! can be added as a hero image with `Options::inject_og_image`.
+
+use scraper::{ElementRef, Html};
+
+use crate::escape::resolve_url;
+use crate::options::Options;
+use crate::rules::format_image;
+use crate::sourcemap::split_leading_markers;
+use crate::whitespace::normalize_block_whitespace;
+
+/// What the converter reads from the document head.
+#[derive(Debug, Default)]
+pub(crate) struct HeadInfo {
+    /// `href` of the first `<base>` with one, if it is an absolute URL.
+    pub base_href: Option<String>,
+    /// Whitespace-normalized text of the `<title>`, if not empty.
+    pub title: Option<String>,
+    /// `content` of the first `og:image` (or `og:image:url`) meta tag.
+    pub og_image: Option<String>,
+    /// `content` of the first `og:url` meta tag, if an absolute URL.
+    pub og_url: Option<String>,
+    /// `href` of the first `<link rel="canonical">`, if an absolute URL.
+    pub canonical: Option<String>,
+}
+
+impl HeadInfo {
+    /// Read the head of a parsed document.
+    pub(crate) fn read(dom: &Html) -> Self {
+        let mut info = HeadInfo::default();
+        let Some(head) = dom
+            .root_element()
+            .children()
+            .filter_map(ElementRef::wrap)
+            .find(|el| el.value().name() == "head")
+        else {
+            return info;
+        };
+
+        for child in head.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                // Only the first base with an href counts, as in browsers
+                "base" if info.base_href.is_none() => {
+                    if let Some(href) = child.value().attr("href").map(str::trim) {
+                        // A relative base would need the page's own URL to resolve
+                        if href.contains("://") {
+                            info.base_href = Some(href.to_string());
+                        }
+                    }
+                }
+                "title" if info.title.is_none() => {
+                    let text: String = child.text().collect();
+                    let text = normalize_block_whitespace(&text);
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        info.title = Some(text.to_string());
+                    }
+                }
+                "meta" => {
+                    let el = child.value();
+                    let Some(property) = el.attr("property").or_else(|| el.attr("name")) else {
+                        continue;
+                    };
+                    let Some(content) = el.attr("content").map(str::trim) else {
+                        continue;
+                    };
+                    if content.is_empty() {
+                        continue;
+                    }
+                    let property = property.trim().to_ascii_lowercase();
+                    match property.as_str() {
+                        "og:image" | "og:image:url" if info.og_image.is_none() => {
+                            info.og_image = Some(content.to_string());
+                        }
+                        "og:url" if info.og_url.is_none() && content.contains("://") => {
+                            info.og_url = Some(content.to_string());
+                        }
+                        _ => {}
+                    }
+                }
+                "link" if info.canonical.is_none() => {
+                    let el = child.value();
+                    let canonical = el.attr("rel").is_some_and(|rel| {
+                        rel.split_ascii_whitespace()
+                            .any(|r| r.eq_ignore_ascii_case("canonical"))
+                    });
+                    if let Some(href) = el.attr("href").map(str::trim) {
+                        if canonical && href.contains("://") {
+                            info.canonical = Some(href.to_string());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        info
+    }
+
+    /// The `og:image` URL, resolved against `base_url`, or else against the
+    /// page's own `og:url` or canonical URL.
+    fn og_image_url(&self, options: &Options) -> Option<String> {
+        let image = self.og_image.as_deref()?;
+        let base = options
+            .base_url
+            .as_deref()
+            .or(self.og_url.as_deref())
+            .or(self.canonical.as_deref());
+        Some(match base {
+            Some(base) => resolve_url(base, image),
+            None => image.to_string(),
+        })
+    }
+}
+
+/// Add the `og:image` as an image after the document's leading level-1
+/// heading (its title), or at the very start without one. Nothing is added
+/// when the markdown already links or shows the same URL.
+pub(crate) fn inject_og_image(markdown: String, head: &HeadInfo, options: &Options) -> String {
+    let Some(src) = head.og_image_url(options) else {
+        return markdown;
+    };
+    // Dropped like any other data: URI image when so configured
+    let image = format_image("", &src, None, options);
+    let Some(destination) = image
+        .strip_prefix("![](")
+        .and_then(|rest| rest.strip_suffix(')'))
+    else {
+        return markdown;
+    };
+    if has_destination(&markdown, destination) {
+        return markdown;
+    }
+
+    let at = title_end(&markdown);
+    let mut out = String::with_capacity(markdown.len() + image.len() + 4);
+    out.push_str(&markdown[..at]);
+    if at > 0 {
+        out.push_str("\n\n");
+    }
+    out.push_str(&image);
+    out.push_str("\n\n");
+    out.push_str(&markdown[at..]);
+    out
+}
+
+/// Check whether a link or image in `markdown` points at `destination`.
+fn has_destination(markdown: &str, destination: &str) -> bool {
+    let needle = format!("]({}", destination);
+    markdown.match_indices(&needle).any(|(start, _)| {
+        matches!(
+            markdown.as_bytes().get(start + needle.len()),
+            Some(b')' | b' ')
+        )
+    })
+}
+
+/// Byte offset just past a level-1 heading opening the markdown, ATX or
+/// Setext, or 0 when it opens with something else.
+fn title_end(markdown: &str) -> usize {
+    let mut lines = markdown
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line.trim_end_matches('\n')))
+        })
+        .skip_while(|(_, line)| line.trim().is_empty());
+
+    let Some((start, first)) = lines.next() else {
+        return 0;
+    };
+    let (_, text) = split_leading_markers(first);
+    if text.starts_with("# ") {
+        return start + first.len();
+    }
+    match lines.next() {
+        Some((start, second)) => {
+            let (_, underline) = split_leading_markers(second);
+            let underline = underline.trim_end();
+            if !text.trim().is_empty()
+                && !underline.is_empty()
+                && underline.chars().all(|c| c == '=')
+            {
+                start + second.len()
+            } else {
+                0
+            }
+        }
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(html: &str) -> HeadInfo {
+        HeadInfo::read(&Html::parse_document(html))
+    }
+
+    #[test]
+    fn test_base_and_title() {
+        let info = read(
+            r#"<head><title> Docs
+                - Guide </title><base href="https://example.com/docs/"></head>"#,
+        );
+        assert_eq!(info.base_href.as_deref(), Some("https://example.com/docs/"));
+        assert_eq!(info.title.as_deref(), Some("Docs - Guide"));
+    }
+
+    #[test]
+    fn test_first_base_with_href_wins() {
+        let info = read(
+            r#"<base target="_blank"><base href="https://a.com/"><base href="https://b.com/">"#,
+        );
+        assert_eq!(info.base_href.as_deref(), Some("https://a.com/"));
+    }
+
+    #[test]
+    fn test_relative_base_ignored() {
+        assert_eq!(read(r#"<base href="/docs/">"#).base_href, None);
+    }
+
+    #[test]
+    fn test_open_graph_and_canonical() {
+        let info = read(
+            r#"<head>
+                <meta property="og:title" content="Ignored">
+                <meta name="og:image" content=" /hero.png ">
+                <meta property="og:image" content="/second.png">
+                <meta property="og:url" content="/relative">
+                <meta property="OG:URL" content="https://example.com/post">
+                <link rel="stylesheet canonical" href="https://example.com/p">
+            </head>"#,
+        );
+        assert_eq!(info.og_image.as_deref(), Some("/hero.png"));
+        assert_eq!(info.og_url.as_deref(), Some("https://example.com/post"));
+        assert_eq!(info.canonical.as_deref(), Some("https://example.com/p"));
+        assert_eq!(
+            read(r#"<meta property="og:image" content="">"#).og_image,
+            None
+        );
+    }
+
+    fn inject(html: &str, options: &Options) -> String {
+        crate::convert_with_options(html, &options.clone().inject_og_image(true))
+    }
+
+    #[test]
+    fn test_og_image_url_resolution() {
+        let head = r#"<meta property="og:image" content="img/a.png">
+            <link rel="canonical" href="https://c.com/posts/1">"#;
+        let options = Options::default();
+        assert_eq!(
+            inject(head, &options),
+            "![](https://c.com/posts/img/a.png)\n"
+        );
+
+        let options = options.base_url(Some("https://b.com/".to_string()));
+        assert_eq!(inject(head, &options), "![](https://b.com/img/a.png)\n");
+
+        let bare = r#"<meta property="og:image" content="img/a.png"><p>Text</p>"#;
+        assert_eq!(
+            inject(bare, &Options::default()),
+            "![](img/a.png)\n\nText\n"
+        );
+    }
+
+    #[test]
+    fn test_og_image_after_setext_title() {
+        let html = r#"<meta property="og:image" content="https://x.com/a.png">
+            <h1>Title</h1><p>Text</p>"#;
+        let options = Options::new().heading_style(crate::HeadingStyle::Setext);
+        assert_eq!(
+            inject(html, &options),
+            "Title\n=====\n\n![](https://x.com/a.png)\n\nText\n"
+        );
+    }
+
+    #[test]
+    fn test_og_image_before_other_headings() {
+        let html = r#"<meta property="og:image" content="https://x.com/a.png"><h2>Part</h2>"#;
+        assert_eq!(
+            inject(html, &Options::default()),
+            "![](https://x.com/a.png)\n\n## Part\n"
+        );
+    }
+
+    #[test]
+    fn test_og_image_already_linked() {
+        let html = r#"<meta property="og:image" content="https://x.com/a.png">
+            <p><a href="https://x.com/a.png" title="Full size">Hero</a></p>"#;
+        assert_eq!(
+            inject(html, &Options::default()),
+            "[Hero](https://x.com/a.png \"Full size\")\n"
+        );
+
+        // A longer URL with the same start is a different image
+        let html = r#"<meta property="og:image" content="https://x.com/a.png">
+            <img src="https://x.com/a.png?w=200">"#;
+        assert_eq!(
+            inject(html, &Options::default()),
+            "![](https://x.com/a.png)\n\n![](https://x.com/a.png?w=200)\n"
+        );
+    }
+
+    #[test]
+    fn test_body_title_ignored() {
+        let info = read("<p>Text</p><svg><title>Icon</title></svg>");
+        assert_eq!(info.title, None);
+    }
+}
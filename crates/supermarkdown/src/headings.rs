@@ -0,0 +1,560 @@
+//! Heading post-processing on the markdown output.
+//!
+//! Headings are found line by line, ignoring anything inside fenced code
+//! blocks so comment lines like `# install` are never mistaken for headings.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rustc_hash::FxHashMap;
+
+use crate::options::{HeadingDedupe, NumberingOptions};
+use crate::postprocess::FenceState;
+use crate::sourcemap::{is_marker, split_leading_markers, split_trailing_markers, strip_markers};
+
+/// Heading text that is already numbered: `2 `, `2. `, `1.4 `, `1.4. `.
+static NUMBERED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d+(\.\d+)*\.?\s").unwrap());
+
+/// A top-level heading in the markdown.
+struct Heading {
+    /// Index of the heading's first line.
+    line: usize,
+    /// Number of lines it occupies (2 for setext headings).
+    span: usize,
+    level: usize,
+    text: String,
+}
+
+/// Deduplicate headings with identical text at the same level.
+pub fn dedupe_headings(markdown: &str, mode: HeadingDedupe) -> String {
+    let mut lines: Vec<String> = markdown.lines().map(str::to_string).collect();
+
+    match mode {
+        HeadingDedupe::Suffix => suffix_duplicates(&mut lines),
+        HeadingDedupe::Merge => merge_duplicates(&mut lines),
+    }
+
+    lines.join("\n")
+}
+
+/// Append ` (n)` to the n-th occurrence of a heading so slugs differ.
+fn suffix_duplicates(lines: &mut [String]) {
+    let mut seen: FxHashMap<(usize, String), usize> = FxHashMap::default();
+
+    for heading in find_headings(lines) {
+        let count = seen
+            .entry((heading.level, heading.text.clone()))
+            .or_default();
+        *count += 1;
+        if *count == 1 {
+            continue;
+        }
+
+        // Keep source map markers around the rewritten text
+        let suffix = format!(" ({})", count);
+        lines[heading.line] = append_before_markers(&lines[heading.line], &suffix);
+        if heading.span == 2 {
+            let underline = if heading.level == 1 { "=" } else { "-" };
+            let (_, markers) = split_trailing_markers(&lines[heading.line + 1]);
+            let width = heading.text.chars().count() + suffix.chars().count();
+            lines[heading.line + 1] = format!("{}{}", underline.repeat(width), markers);
+        }
+    }
+}
+
+/// Prefix headings with hierarchical numbers: `## 1 Intro`, `### 1.1 Setup`.
+///
+/// Only top-level headings are found, so headings inside blockquotes and
+/// lists keep their text. See [`NumberingOptions`] for the rules.
+pub fn number_headings(markdown: &str, numbering: &NumberingOptions) -> String {
+    let mut lines: Vec<String> = markdown.lines().map(str::to_string).collect();
+    let start = usize::from(numbering.start_level.max(1));
+    // (level, count) of the open headings, shallowest first
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+
+    for heading in find_headings(&lines) {
+        if heading.level < start {
+            stack.clear();
+            continue;
+        }
+        if NUMBERED_RE.is_match(&heading.text) {
+            continue;
+        }
+
+        // A heading closes deeper ones; it continues the count of the last
+        // one closed at its own depth, so h2 > h4 > h3 gives 1, 1.1, 1.2
+        let mut closed = None;
+        while stack
+            .last()
+            .is_some_and(|&(level, _)| level > heading.level)
+        {
+            closed = stack.pop();
+        }
+        match stack.last_mut() {
+            Some((level, count)) if *level == heading.level => *count += 1,
+            _ => {
+                let count = closed.map_or(1, |(_, count)| count + 1);
+                stack.push((heading.level, count));
+            }
+        }
+
+        let number = stack
+            .iter()
+            .map(|(_, count)| count.to_string())
+            .collect::<Vec<_>>()
+            .join(&numbering.separator);
+        let prefix = format!("{} ", number);
+        lines[heading.line] = insert_heading_prefix(&lines[heading.line], heading.span, &prefix);
+        if heading.span == 2 {
+            let underline = if heading.level == 1 { "=" } else { "-" };
+            let (_, markers) = split_trailing_markers(&lines[heading.line + 1]);
+            let width = heading.text.chars().count() + prefix.chars().count();
+            lines[heading.line + 1] = format!("{}{}", underline.repeat(width), markers);
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Insert `prefix` before a heading's text, after any leading source map
+/// markers and, for ATX headings (`span` 1), the `#` marks.
+fn insert_heading_prefix(line: &str, span: usize, prefix: &str) -> String {
+    let (markers, rest) = split_leading_markers(line);
+    let marks = if span == 1 {
+        rest.len() - rest.trim_start_matches('#').len() + 1
+    } else {
+        0
+    };
+    format!("{}{}{}{}", markers, &rest[..marks], prefix, &rest[marks..])
+}
+
+/// Append `suffix` to a line, in front of any trailing source map markers.
+fn append_before_markers(line: &str, suffix: &str) -> String {
+    let (text, markers) = split_trailing_markers(line.trim_end());
+    format!("{}{}{}", text, suffix, markers)
+}
+
+/// Drop headings that repeat their previous sibling.
+///
+/// When the repeated section's content is identical to the previous one,
+/// the whole section goes; otherwise only the heading is removed and its
+/// content joins the previous section.
+fn merge_duplicates(lines: &mut Vec<String>) {
+    // Each removal shifts line indices, so rescan until nothing changes
+    while let Some((start, end)) = find_duplicate(lines) {
+        lines.drain(start..end);
+    }
+}
+
+/// Find the next line range to remove for `merge_duplicates`.
+fn find_duplicate(lines: &[String]) -> Option<(usize, usize)> {
+    let headings = find_headings(lines);
+    // Line where the section of heading `i` ends
+    let section_end = |i: usize| {
+        headings[i + 1..]
+            .iter()
+            .find(|next| next.level <= headings[i].level)
+            .map(|next| next.line)
+            .unwrap_or(lines.len())
+    };
+
+    for (i, heading) in headings.iter().enumerate() {
+        let Some(j) = (i + 1..headings.len()).find(|&j| headings[j].level <= heading.level) else {
+            continue;
+        };
+        let next = &headings[j];
+        if next.level != heading.level || next.text != heading.text {
+            continue;
+        }
+
+        let body = section_body(lines, heading.line + heading.span, next.line);
+        let next_body = section_body(lines, next.line + next.span, section_end(j));
+        if body == next_body {
+            return Some((next.line, section_end(j)));
+        }
+        return Some((next.line, next.line + next.span));
+    }
+
+    None
+}
+
+/// Section content between two line indices, ignoring surrounding blank
+/// lines and trailing whitespace.
+fn section_body(lines: &[String], start: usize, end: usize) -> Vec<String> {
+    let body: Vec<String> = lines[start..end]
+        .iter()
+        .map(|l| strip_markers(l.trim_end()).into_owned())
+        .collect();
+    let first = body
+        .iter()
+        .position(|l| !l.is_empty())
+        .unwrap_or(body.len());
+    let last = body
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(first, |i| i + 1);
+    body[first..last].to_vec()
+}
+
+/// Put ATX headings on lines of their own, apart from surrounding blocks.
+///
+/// A heading glued to a list marker (`- ## Title`) moves above its item, at
+/// the item's indentation, and the marker moves on to the item's next line;
+/// an item holding only the heading loses its marker. Headings at column 0
+/// get a blank line before and after. Headings inside blockquotes, table
+/// cells and code fences are left alone.
+pub fn normalize_heading_lines(markdown: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut fence = FenceState::default();
+    // Marker of the item whose heading was hoisted, as (content column,
+    // indentation, marker with its spaces), waiting for the item's next line
+    let mut pending: Option<(usize, usize, String)> = None;
+    let mut blank_after = false;
+
+    for line in markdown.lines() {
+        let in_fence = fence.update(line);
+        let blank = is_blank(line);
+        let mut line = line.to_string();
+
+        if !blank {
+            if let Some((column, indent, marker)) = pending.take() {
+                if let Some(attached) = attach_marker(&line, column, indent, &marker) {
+                    line = attached;
+                }
+            }
+        }
+        if blank_after && !blank {
+            out.push(String::new());
+        }
+        blank_after = false;
+
+        if !in_fence {
+            if let Some(item) = ListHeading::parse(&line) {
+                if out.last().is_some_and(|last| !is_blank(last)) {
+                    out.push(String::new());
+                }
+                out.push(format!(
+                    "{}{}{}",
+                    item.markers,
+                    " ".repeat(item.indent),
+                    item.heading
+                ));
+                pending = Some((item.column, item.indent, item.marker.to_string()));
+                blank_after = true;
+                continue;
+            }
+
+            let (_, rest) = split_leading_markers(&line);
+            if parse_atx(strip_markers(rest.trim_end()).as_ref()).is_some() {
+                if out.last().is_some_and(|last| !is_blank(last)) {
+                    out.push(String::new());
+                }
+                blank_after = true;
+            }
+        }
+        out.push(line);
+    }
+
+    out.join("\n")
+}
+
+/// A list item line whose content starts with an ATX heading.
+struct ListHeading<'a> {
+    /// Source map markers in front of the item.
+    markers: &'a str,
+    indent: usize,
+    /// List marker and the spaces after it.
+    marker: &'a str,
+    /// Column of the item's content.
+    column: usize,
+    heading: &'a str,
+}
+
+impl<'a> ListHeading<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let (markers, rest) = split_leading_markers(line);
+        let indent = rest.len() - rest.trim_start_matches(' ').len();
+        let item = &rest[indent..];
+
+        let marker_len = if item.starts_with(['-', '*', '+']) {
+            1
+        } else {
+            let digits = item.bytes().take_while(u8::is_ascii_digit).count();
+            let delimiter = item[digits..].starts_with(['.', ')']);
+            if !(1..=9).contains(&digits) || !delimiter {
+                return None;
+            }
+            digits + 1
+        };
+        let after = &item[marker_len..];
+        let spaces = after.len() - after.trim_start_matches(' ').len();
+        if spaces == 0 {
+            return None;
+        }
+
+        let content = &after[spaces..];
+        let (_, heading) = split_leading_markers(content);
+        parse_atx(strip_markers(heading.trim_end()).as_ref())?;
+
+        Some(ListHeading {
+            markers,
+            indent,
+            marker: &item[..marker_len + spaces],
+            column: indent + marker_len + spaces,
+            heading: content,
+        })
+    }
+}
+
+/// `line` with the item `marker` in place of its indentation, if it
+/// continues the item whose content starts at `column`.
+fn attach_marker(line: &str, column: usize, indent: usize, marker: &str) -> Option<String> {
+    let (markers, rest) = split_leading_markers(line);
+    let spaces = rest.len() - rest.trim_start_matches(' ').len();
+    if spaces < column {
+        return None;
+    }
+    Some(format!(
+        "{}{}{}{}",
+        markers,
+        " ".repeat(indent),
+        marker,
+        &rest[column..]
+    ))
+}
+
+/// Whether `line` holds nothing but whitespace and source map markers.
+fn is_blank(line: &str) -> bool {
+    line.chars().all(|c| c.is_whitespace() || is_marker(c))
+}
+
+/// Find all ATX and setext headings outside code fences.
+fn find_headings(lines: &[String]) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut fence = FenceState::default();
+    let mut i = 0;
+
+    while i < lines.len() {
+        if fence.update(&lines[i]) {
+            i += 1;
+            continue;
+        }
+
+        // Source map markers aren't part of the heading text
+        let line = strip_markers(lines[i].trim_end());
+        let line = line.as_ref();
+        if let Some((level, text)) = parse_atx(line) {
+            headings.push(Heading {
+                line: i,
+                span: 1,
+                level,
+                text: text.to_string(),
+            });
+        } else if let Some(level) = lines
+            .get(i + 1)
+            .and_then(|next| setext_level(line, &strip_markers(next)))
+        {
+            headings.push(Heading {
+                line: i,
+                span: 2,
+                level,
+                text: line.to_string(),
+            });
+            i += 1;
+        }
+        i += 1;
+    }
+
+    headings
+}
+
+/// Parse `## Text` into (level, text).
+pub(crate) fn parse_atx(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = line[level..].strip_prefix(' ')?.trim();
+    (!text.is_empty()).then_some((level, text))
+}
+
+/// Level of a setext heading whose text is `line` and underline is `next`.
+pub(crate) fn setext_level(line: &str, next: &str) -> Option<usize> {
+    if line.is_empty() || line.starts_with(char::is_whitespace) || line.starts_with('|') {
+        return None;
+    }
+
+    let next = next.trim_end();
+    if !next.is_empty() && next.chars().all(|c| c == '=') {
+        Some(1)
+    } else if !next.is_empty() && next.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suffix_duplicates() {
+        let input = "### Example\n\nA\n\n### Example\n\nB\n\n### Example\n\nC";
+        let result = dedupe_headings(input, HeadingDedupe::Suffix);
+        assert_eq!(
+            result,
+            "### Example\n\nA\n\n### Example (2)\n\nB\n\n### Example (3)\n\nC"
+        );
+    }
+
+    #[test]
+    fn test_suffix_only_same_level() {
+        let input = "## Usage\n\n### Usage\n\n## Usage";
+        let result = dedupe_headings(input, HeadingDedupe::Suffix);
+        assert_eq!(result, "## Usage\n\n### Usage\n\n## Usage (2)");
+    }
+
+    #[test]
+    fn test_suffix_setext() {
+        let input = "Intro\n-----\n\ntext\n\nIntro\n-----";
+        let result = dedupe_headings(input, HeadingDedupe::Suffix);
+        assert_eq!(result, "Intro\n-----\n\ntext\n\nIntro (2)\n---------");
+    }
+
+    #[test]
+    fn test_headings_in_code_fences_ignored() {
+        let input = "# Setup\n\n```bash\n# Setup\n```\n\n# Setup";
+        let result = dedupe_headings(input, HeadingDedupe::Suffix);
+        assert_eq!(result, "# Setup\n\n```bash\n# Setup\n```\n\n# Setup (2)");
+    }
+
+    fn number(input: &str) -> String {
+        number_headings(input, &NumberingOptions::default())
+    }
+
+    #[test]
+    fn test_normalize_list_headings() {
+        assert_eq!(
+            normalize_heading_lines("- ## Title\n\n  Body\n- Two"),
+            "## Title\n\n- Body\n- Two"
+        );
+        assert_eq!(
+            normalize_heading_lines("1. ### Only\n2. Next"),
+            "### Only\n\n2. Next"
+        );
+        assert_eq!(
+            normalize_heading_lines("- x\n\n  - #### Nested\n\n    y"),
+            "- x\n\n  #### Nested\n\n  - y"
+        );
+    }
+
+    #[test]
+    fn test_normalize_column_zero_headings() {
+        assert_eq!(
+            normalize_heading_lines("| a |\n| - |\n## After\nText"),
+            "| a |\n| - |\n\n## After\n\nText"
+        );
+        assert_eq!(normalize_heading_lines("## A\n\nB"), "## A\n\nB");
+    }
+
+    #[test]
+    fn test_normalize_leaves_other_headings() {
+        let input = "> ## Quoted\n> Text\n| ## Cell |\n```\n- # comment\n# comment\n```\n  ## Indented\n#tag";
+        assert_eq!(normalize_heading_lines(input), input);
+    }
+
+    #[test]
+    fn test_number_headings() {
+        let input = "# Spec\n\n## Intro\n\n### Goals\n\n### Scope\n\n## Configuration\n\n### Files";
+        assert_eq!(
+            number(input),
+            "# Spec\n\n## 1 Intro\n\n### 1.1 Goals\n\n### 1.2 Scope\n\n\
+             ## 2 Configuration\n\n### 2.1 Files"
+        );
+    }
+
+    #[test]
+    fn test_number_skipped_levels() {
+        // A skipped level adds no part, and a shallower heading closing the
+        // deeper one continues its count
+        let input = "## A\n\n#### A deep\n\n#### A deep too\n\n### A mid\n\n## B\n\n#### B deep";
+        assert_eq!(
+            number(input),
+            "## 1 A\n\n#### 1.1 A deep\n\n#### 1.2 A deep too\n\n### 1.3 A mid\n\n\
+             ## 2 B\n\n#### 2.1 B deep"
+        );
+    }
+
+    #[test]
+    fn test_number_restarts_above_start_level() {
+        let input = "# One\n\n## A\n\n## B\n\n# Two\n\n## C";
+        assert_eq!(
+            number(input),
+            "# One\n\n## 1 A\n\n## 2 B\n\n# Two\n\n## 1 C"
+        );
+    }
+
+    #[test]
+    fn test_number_skips_numbered_and_quoted_headings() {
+        let input =
+            "## 1. Preface\n\n## Intro\n\n> ## Quoted\n\n```\n## not a heading\n```\n\n## 2.1 Kept";
+        assert_eq!(
+            number(input),
+            "## 1. Preface\n\n## 1 Intro\n\n> ## Quoted\n\n```\n## not a heading\n```\n\n## 2.1 Kept"
+        );
+    }
+
+    #[test]
+    fn test_number_setext_and_separator() {
+        let numbering = NumberingOptions {
+            start_level: 1,
+            separator: "-".to_string(),
+        };
+        let input = "Guide\n=====\n\nSetup\n-----\n\n### Linux";
+        assert_eq!(
+            number_headings(input, &numbering),
+            "1 Guide\n=======\n\n1-1 Setup\n---------\n\n### 1-1-1 Linux"
+        );
+    }
+
+    #[test]
+    fn test_number_through_converter() {
+        let options = crate::Options::new().number_headings(Some(NumberingOptions::default()));
+        let html =
+            "<h1>Title</h1><h2>Install</h2><blockquote><h2>Note</h2></blockquote><h3>Linux</h3>";
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "# Title\n\n## 1 Install\n\n> ## Note\n\n### 1.1 Linux\n"
+        );
+        let (markdown, map) = crate::convert_with_sourcemap(html, &options);
+        assert_eq!(&markdown[map[1].md_start..map[1].md_end], "## 1 Install");
+    }
+
+    #[test]
+    fn test_merge_identical_sections() {
+        let input = "## Note\n\nSame text.\n\n## Note\n\nSame text.\n\n## Next\n\nMore";
+        let result = dedupe_headings(input, HeadingDedupe::Merge);
+        assert_eq!(result, "## Note\n\nSame text.\n\n## Next\n\nMore");
+    }
+
+    #[test]
+    fn test_merge_different_sections_keeps_content() {
+        let input = "### Example\n\nFirst\n\n### Example\n\nSecond";
+        let result = dedupe_headings(input, HeadingDedupe::Merge);
+        assert_eq!(result, "### Example\n\nFirst\n\n\nSecond");
+    }
+
+    #[test]
+    fn test_merge_skips_non_consecutive() {
+        let input = "## A\n\nx\n\n## B\n\ny\n\n## A\n\nx";
+        let result = dedupe_headings(input, HeadingDedupe::Merge);
+        assert_eq!(result, input);
+    }
+
+    #[test]
+    fn test_merge_compares_code_content() {
+        let input = "## Run\n\n```\n# one\n```\n\n## Run\n\n```\n# two\n```";
+        let result = dedupe_headings(input, HeadingDedupe::Merge);
+        assert_eq!(result, "## Run\n\n```\n# one\n```\n\n\n```\n# two\n```");
+    }
+}
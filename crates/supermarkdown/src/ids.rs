@@ -0,0 +1,164 @@
+//! Elements by `id`, for features that follow references to other elements,
+//! like `aria-describedby="ttl-def"` or `usemap="#m"`.
+//!
+//! Malformed pages point ids at the element naming them or at each other,
+//! so chains of references are followed with [`IdIndex::follow`], which
+//! never visits an element twice and gives up after [`MAX_DEPTH`] steps.
+
+use ego_tree::NodeId;
+use rustc_hash::FxHashMap;
+use scraper::{ElementRef, Html};
+
+/// Longest chain of references followed from one element.
+pub(crate) const MAX_DEPTH: usize = 8;
+
+/// Element ids of a parsed document.
+pub(crate) struct IdIndex<'a> {
+    dom: &'a Html,
+    ids: FxHashMap<&'a str, NodeId>,
+}
+
+impl<'a> IdIndex<'a> {
+    /// Index the elements of a parsed document; the first of duplicate ids
+    /// wins, as in browsers.
+    pub(crate) fn new(dom: &'a Html) -> Self {
+        let mut ids = FxHashMap::default();
+        for element in dom
+            .root_element()
+            .descendants()
+            .filter_map(ElementRef::wrap)
+        {
+            if let Some(id) = element.value().id() {
+                ids.entry(id).or_insert(element.id());
+            }
+        }
+        Self { dom, ids }
+    }
+
+    /// The element with `id`.
+    pub(crate) fn get(&self, id: &str) -> Option<ElementRef<'a>> {
+        let node = *self.ids.get(id)?;
+        self.dom.tree.get(node).and_then(ElementRef::wrap)
+    }
+
+    /// Elements reached from `start` through the whitespace-separated ids
+    /// `refs` reads from an element, in order. The references of a target
+    /// are followed in turn when `descend` says so. Elements already
+    /// reached, `start` included, are skipped, and chains stop after
+    /// [`MAX_DEPTH`] steps.
+    pub(crate) fn follow(
+        &self,
+        start: ElementRef<'a>,
+        refs: impl Fn(ElementRef<'a>) -> Option<&'a str>,
+        descend: impl Fn(ElementRef<'a>) -> bool,
+    ) -> Vec<ElementRef<'a>> {
+        let mut seen = vec![start.id()];
+        let mut reached = Vec::new();
+        // Each entry is an element whose references are next, and its depth
+        let mut pending = vec![(start, 0)];
+        while let Some((element, depth)) = pending.pop() {
+            if depth == MAX_DEPTH {
+                continue;
+            }
+            let Some(ids) = refs(element) else {
+                continue;
+            };
+            // Reversed onto the stack so targets come out in order
+            let mut next = Vec::new();
+            for target in ids.split_ascii_whitespace().filter_map(|id| self.get(id)) {
+                if seen.contains(&target.id()) {
+                    continue;
+                }
+                seen.push(target.id());
+                reached.push(target);
+                if descend(target) {
+                    next.push((target, depth + 1));
+                }
+            }
+            pending.extend(next.into_iter().rev());
+        }
+        reached
+    }
+}
+
+/// The id a fragment reference like `#intro` names.
+pub(crate) fn fragment(reference: &str) -> Option<&str> {
+    reference
+        .trim()
+        .strip_prefix('#')
+        .filter(|id| !id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids_of(elements: &[ElementRef]) -> Vec<String> {
+        elements
+            .iter()
+            .map(|el| el.value().id().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    /// The fragment of the first link inside an element.
+    fn link_target(element: ElementRef<'_>) -> Option<&str> {
+        element
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find_map(|el| el.value().attr("href"))
+            .and_then(fragment)
+    }
+
+    #[test]
+    fn test_first_duplicate_wins() {
+        let dom = Html::parse_fragment(r#"<p id="a">First</p><p id="a">Second</p>"#);
+        let ids = IdIndex::new(&dom);
+        assert_eq!(ids.get("a").unwrap().text().collect::<String>(), "First");
+        assert!(ids.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_follow_self_reference() {
+        let dom =
+            Html::parse_fragment(r#"<span id="s" aria-describedby="s t">X</span><b id="t">T</b>"#);
+        let ids = IdIndex::new(&dom);
+        let start = ids.get("s").unwrap();
+        let reached = ids.follow(start, |el| el.value().attr("aria-describedby"), |_| true);
+        assert_eq!(ids_of(&reached), ["t"]);
+    }
+
+    #[test]
+    fn test_footnote_cycle() {
+        let dom = Html::parse_fragment(
+            r##"<p>Claim<sup id="ref"><a href="#fn1">1</a></sup></p>
+            <ol><li id="fn1">See <a href="#fn2">note 2</a></li>
+            <li id="fn2">Back to <a href="#fn1">note 1</a></li></ol>"##,
+        );
+        let ids = IdIndex::new(&dom);
+        let reached = ids.follow(ids.get("ref").unwrap(), link_target, |_| true);
+        assert_eq!(ids_of(&reached), ["fn1", "fn2"]);
+    }
+
+    #[test]
+    fn test_follow_depth_limited() {
+        let mut html = String::new();
+        for i in 0..20 {
+            html.push_str(&format!(r#"<p id="n{}" data-next="n{}"></p>"#, i, i + 1));
+        }
+        let dom = Html::parse_fragment(&html);
+        let ids = IdIndex::new(&dom);
+        let reached = ids.follow(
+            ids.get("n0").unwrap(),
+            |el| el.value().attr("data-next"),
+            |_| true,
+        );
+        assert_eq!(reached.len(), MAX_DEPTH);
+    }
+
+    #[test]
+    fn test_fragment() {
+        assert_eq!(fragment(" #intro "), Some("intro"));
+        assert_eq!(fragment("#"), None);
+        assert_eq!(fragment("intro"), None);
+    }
+}
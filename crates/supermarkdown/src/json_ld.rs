@@ -0,0 +1,121 @@
+//! JSON-LD structured data from `<script type="application/ld+json">`.
+//!
+//! Pages often hold slightly broken JSON-LD, so a block that isn't valid
+//! JSON is parsed again after the repairs lenient consumers commonly make:
+//! HTML comment and CDATA wrappers and a trailing semicolon are removed,
+//! trailing commas dropped and raw line breaks in strings escaped.
+
+use scraper::ElementRef;
+#[cfg(feature = "serde")]
+use scraper::{Html, Selector};
+
+#[cfg(feature = "serde")]
+use crate::stats::ConversionStats;
+
+/// Whether `element` is a JSON-LD script.
+pub(crate) fn is_json_ld(element: ElementRef) -> bool {
+    element.value().name() == "script"
+        && element
+            .value()
+            .attr("type")
+            .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/ld+json"))
+}
+
+/// Parse every JSON-LD block in the document, counting invalid ones.
+#[cfg(feature = "serde")]
+pub(crate) fn extract(dom: &Html, stats: &mut ConversionStats) -> Vec<serde_json::Value> {
+    let scripts = Selector::parse("script").expect("valid selector");
+    let mut values = Vec::new();
+    for script in dom.select(&scripts).filter(|e| is_json_ld(*e)) {
+        let text: String = script.text().collect();
+        let text = unwrap(&text);
+        if text.is_empty() {
+            continue;
+        }
+        match serde_json::from_str(text).or_else(|_| serde_json::from_str(&repair(text))) {
+            Ok(value) => values.push(value),
+            Err(_) => stats.json_ld_invalid += 1,
+        }
+    }
+    values
+}
+
+/// Strip the comment or CDATA wrappers and trailing semicolon old pages put
+/// around script content.
+#[cfg(feature = "serde")]
+fn unwrap(text: &str) -> &str {
+    let mut text = text.trim();
+    for (open, close) in [
+        ("<!--", "-->"),
+        ("//<![CDATA[", "//]]>"),
+        ("<![CDATA[", "]]>"),
+    ] {
+        if let Some(inner) = text.strip_prefix(open) {
+            text = inner.strip_suffix(close).unwrap_or(inner).trim();
+        }
+    }
+    text.strip_suffix(';').unwrap_or(text).trim_end()
+}
+
+/// Drop trailing commas and escape raw control characters in strings.
+#[cfg(feature = "serde")]
+fn repair(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                '\n' => {
+                    result.push_str("\\n");
+                    continue;
+                }
+                '\r' => {
+                    result.push_str("\\r");
+                    continue;
+                }
+                '\t' => {
+                    result.push_str("\\t");
+                    continue;
+                }
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let rest = chars.clone().find(|c| !c.is_whitespace());
+            if matches!(rest, Some('}' | ']')) {
+                continue;
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unwrap() {
+        assert_eq!(unwrap("\n  <!-- {\"a\": 1} -->\n"), "{\"a\": 1}");
+        assert_eq!(unwrap("//<![CDATA[\n{}\n//]]>"), "{}");
+        assert_eq!(unwrap("{\"a\": 1};"), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn test_repair() {
+        let text = "{\"name\": \"Line one\nline two, done\", \"tags\": [\"a\", \"b\",\n],}";
+        let value: serde_json::Value = serde_json::from_str(&repair(text)).unwrap();
+        assert_eq!(
+            value,
+            json!({"name": "Line one\nline two, done", "tags": ["a", "b"]})
+        );
+    }
+}
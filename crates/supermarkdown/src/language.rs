@@ -0,0 +1,311 @@
+//! Code block languages from file names and first lines.
+//!
+//! Class names are the main source of a code block's language; this module
+//! covers blocks labelled with a file name (`class="language-src/main.rs"`,
+//! `data-filename="main.rs"`) and unlabelled scripts and shell transcripts,
+//! recognized by a `#!` line or a `$ ` prompt. It also turns the aliases
+//! and MIME types pages label code with into the names highlighters know.
+
+/// How a language writes a one-line comment: text before and after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CommentSyntax(pub &'static str, pub &'static str);
+
+const SLASHES: CommentSyntax = CommentSyntax("// ", "");
+const HASH: CommentSyntax = CommentSyntax("# ", "");
+const DASHES: CommentSyntax = CommentSyntax("-- ", "");
+const MARKUP: CommentSyntax = CommentSyntax("<!-- ", " -->");
+const BLOCK: CommentSyntax = CommentSyntax("/* ", " */");
+
+/// File extensions (lowercase), with their language and comment syntax.
+/// Formats without comments, like JSON, have no syntax.
+static EXTENSIONS: &[(&str, &str, Option<CommentSyntax>)] = &[
+    ("bash", "bash", Some(HASH)),
+    ("c", "c", Some(SLASHES)),
+    ("cc", "cpp", Some(SLASHES)),
+    ("cjs", "javascript", Some(SLASHES)),
+    ("cpp", "cpp", Some(SLASHES)),
+    ("cs", "csharp", Some(SLASHES)),
+    ("css", "css", Some(BLOCK)),
+    ("dart", "dart", Some(SLASHES)),
+    ("ex", "elixir", Some(HASH)),
+    ("exs", "elixir", Some(HASH)),
+    ("go", "go", Some(SLASHES)),
+    ("h", "c", Some(SLASHES)),
+    ("hpp", "cpp", Some(SLASHES)),
+    ("hs", "haskell", Some(DASHES)),
+    ("htm", "html", Some(MARKUP)),
+    ("html", "html", Some(MARKUP)),
+    ("java", "java", Some(SLASHES)),
+    ("js", "javascript", Some(SLASHES)),
+    ("json", "json", None),
+    ("jsx", "jsx", Some(SLASHES)),
+    ("kt", "kotlin", Some(SLASHES)),
+    ("kts", "kotlin", Some(SLASHES)),
+    ("lua", "lua", Some(DASHES)),
+    ("md", "markdown", Some(MARKUP)),
+    ("mjs", "javascript", Some(SLASHES)),
+    ("php", "php", Some(SLASHES)),
+    ("pl", "perl", Some(HASH)),
+    ("ps1", "powershell", Some(HASH)),
+    ("py", "python", Some(HASH)),
+    ("r", "r", Some(HASH)),
+    ("rb", "ruby", Some(HASH)),
+    ("rs", "rust", Some(SLASHES)),
+    ("scala", "scala", Some(SLASHES)),
+    ("scss", "scss", Some(SLASHES)),
+    ("sh", "bash", Some(HASH)),
+    ("sql", "sql", Some(DASHES)),
+    ("svg", "xml", Some(MARKUP)),
+    ("swift", "swift", Some(SLASHES)),
+    ("toml", "toml", Some(HASH)),
+    ("ts", "typescript", Some(SLASHES)),
+    ("tsx", "tsx", Some(SLASHES)),
+    ("xml", "xml", Some(MARKUP)),
+    ("yaml", "yaml", Some(HASH)),
+    ("yml", "yaml", Some(HASH)),
+    ("zsh", "zsh", Some(HASH)),
+];
+
+/// File names without a telling extension.
+static FILE_NAMES: &[(&str, &str, Option<CommentSyntax>)] = &[
+    ("dockerfile", "dockerfile", Some(HASH)),
+    ("gemfile", "ruby", Some(HASH)),
+    ("makefile", "makefile", Some(HASH)),
+];
+
+/// `#!` interpreters and the language of their scripts.
+static INTERPRETERS: &[(&str, &str)] = &[
+    ("bash", "bash"),
+    ("deno", "typescript"),
+    ("node", "javascript"),
+    ("nodejs", "javascript"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("python", "python"),
+    ("ruby", "ruby"),
+    ("sh", "bash"),
+    ("ts-node", "typescript"),
+    ("zsh", "zsh"),
+];
+
+/// Language names written in classes (lowercase), and the name
+/// highlighters know the language by.
+static ALIASES: &[(&str, &str)] = &[
+    ("c#", "csharp"),
+    ("c++", "cpp"),
+    ("c-sharp", "csharp"),
+    ("cs", "csharp"),
+    ("cxx", "cpp"),
+    ("docker", "dockerfile"),
+    ("ecmascript", "javascript"),
+    ("f#", "fsharp"),
+    ("golang", "go"),
+    ("htm", "html"),
+    ("js", "javascript"),
+    ("kt", "kotlin"),
+    ("make", "makefile"),
+    ("md", "markdown"),
+    ("obj-c", "objectivec"),
+    ("objc", "objectivec"),
+    ("objective-c", "objectivec"),
+    ("patch", "diff"),
+    ("posh", "powershell"),
+    ("ps1", "powershell"),
+    ("pwsh", "powershell"),
+    ("py", "python"),
+    ("py3", "python"),
+    ("python3", "python"),
+    ("rb", "ruby"),
+    ("rs", "rust"),
+    ("sh", "bash"),
+    ("sh-session", "console"),
+    ("shell-session", "console"),
+    ("shellsession", "console"),
+    ("ts", "typescript"),
+    ("xhtml", "html"),
+    ("yml", "yaml"),
+];
+
+/// Name highlighters know a language by, from how a class writes it:
+/// `c++` → `cpp`, `text/x-java` → `java`. Trailing `;`, `,` and `:` are
+/// dropped; values neither an alias nor a MIME type pass through.
+pub(crate) fn normalize_language(value: &str) -> String {
+    let value = value.trim().trim_end_matches([';', ',', ':']);
+    let lower = value.to_ascii_lowercase();
+    if let Some((_, name)) = ALIASES.iter().find(|(alias, _)| *alias == lower) {
+        return name.to_string();
+    }
+    if is_mime_type(&lower) {
+        // `text/x-java`, `application/json`, CodeMirror's `text/x-c++src`
+        let (_, subtype) = lower.split_once('/').unwrap_or_default();
+        let subtype = subtype.strip_prefix("x-").unwrap_or(subtype);
+        let subtype = match subtype.strip_suffix("src") {
+            Some(language) if !language.is_empty() => language,
+            _ => subtype,
+        };
+        return match ALIASES.iter().find(|(alias, _)| *alias == subtype) {
+            Some((_, name)) => name.to_string(),
+            None => subtype.to_string(),
+        };
+    }
+    value.to_string()
+}
+
+/// Whether a class value is a MIME type like `text/x-java` rather than a
+/// file path.
+pub(crate) fn is_mime_type(value: &str) -> bool {
+    let lower = value.to_ascii_lowercase();
+    ["text/", "application/"]
+        .iter()
+        .any(|prefix| lower.starts_with(prefix) && lower.len() > prefix.len())
+}
+
+/// Language of a file, from its name or extension: `src/main.rs` → `rust`.
+pub(crate) fn language_for_filename(path: &str) -> Option<&'static str> {
+    file_entry(path).map(|(_, lang, _)| *lang)
+}
+
+/// Comment syntax of a language known to the extension table.
+pub(crate) fn comment_syntax(language: &str) -> Option<CommentSyntax> {
+    EXTENSIONS
+        .iter()
+        .chain(FILE_NAMES)
+        .find(|(_, lang, _)| *lang == language)
+        .and_then(|(_, _, comment)| *comment)
+}
+
+fn file_entry(path: &str) -> Option<&'static (&'static str, &'static str, Option<CommentSyntax>)> {
+    let name = path.trim().rsplit(['/', '\\']).next()?.to_ascii_lowercase();
+    if let Some(entry) = FILE_NAMES.iter().find(|(file, _, _)| *file == name) {
+        return Some(entry);
+    }
+    let (_, extension) = name.rsplit_once('.')?;
+    EXTENSIONS.iter().find(|(ext, _, _)| *ext == extension)
+}
+
+/// Language of code with no label, from its first line: a `#!` line names
+/// the interpreter, and a `$ ` prompt marks a shell session (`console`).
+pub(crate) fn sniff_language(code: &str) -> Option<&'static str> {
+    let first = code.lines().find(|line| !line.trim().is_empty())?.trim();
+
+    if let Some(command) = first.strip_prefix("#!") {
+        let mut words = command.split_whitespace();
+        let mut program = words.next()?.rsplit('/').next()?;
+        // `#!/usr/bin/env python3` and `#!/usr/bin/env -S node --flag`
+        if program == "env" {
+            program = words.find(|word| !word.starts_with('-'))?;
+        }
+        // `python3`, `python3.12`
+        let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        return INTERPRETERS
+            .iter()
+            .find(|(name, _)| *name == program)
+            .map(|(_, lang)| *lang);
+    }
+
+    first.starts_with("$ ").then_some("console")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_for_filename() {
+        assert_eq!(language_for_filename("main.rs"), Some("rust"));
+        assert_eq!(language_for_filename("src/index.ts"), Some("typescript"));
+        assert_eq!(language_for_filename(".github/ci.yml"), Some("yaml"));
+        assert_eq!(language_for_filename("C:\\app\\Program.CS"), Some("csharp"));
+        assert_eq!(language_for_filename("Dockerfile"), Some("dockerfile"));
+        assert_eq!(language_for_filename("docker/Makefile"), Some("makefile"));
+    }
+
+    #[test]
+    fn test_language_for_unknown_filename() {
+        assert_eq!(language_for_filename("notes.txt"), None);
+        assert_eq!(language_for_filename("rust"), None);
+        assert_eq!(language_for_filename(""), None);
+        assert_eq!(language_for_filename("src/"), None);
+    }
+
+    #[test]
+    fn test_comment_syntax() {
+        assert_eq!(comment_syntax("rust"), Some(CommentSyntax("// ", "")));
+        assert_eq!(comment_syntax("yaml"), Some(CommentSyntax("# ", "")));
+        assert_eq!(comment_syntax("html"), Some(CommentSyntax("<!-- ", " -->")));
+        assert_eq!(comment_syntax("json"), None);
+        assert_eq!(comment_syntax("brainfuck"), None);
+    }
+
+    #[test]
+    fn test_normalize_language() {
+        let cases = [
+            ("c++", "cpp"),
+            ("C++", "cpp"),
+            ("cxx", "cpp"),
+            ("objective-c", "objectivec"),
+            ("objc", "objectivec"),
+            ("sh", "bash"),
+            ("js", "javascript"),
+            ("JS", "javascript"),
+            ("js;", "javascript"),
+            ("ts,", "typescript"),
+            ("py3", "python"),
+            ("c#", "csharp"),
+            ("f#", "fsharp"),
+            ("golang", "go"),
+            ("yml", "yaml"),
+            ("shell-session", "console"),
+            ("text/x-java", "java"),
+            ("text/x-c++src", "cpp"),
+            ("text/x-csrc", "c"),
+            ("text/x-rustsrc", "rust"),
+            ("text/x-python", "python"),
+            ("text/x-sh", "bash"),
+            ("text/javascript", "javascript"),
+            ("application/json", "json"),
+            ("application/x-yaml", "yaml"),
+            ("rust", "rust"),
+            ("bash", "bash"),
+            ("Haskell", "Haskell"),
+            ("elixir", "elixir"),
+            ("text", "text"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(normalize_language(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_is_mime_type() {
+        assert!(is_mime_type("text/x-java"));
+        assert!(is_mime_type("Application/JSON"));
+        assert!(!is_mime_type("text/"));
+        assert!(!is_mime_type("src/main.rs"));
+    }
+
+    #[test]
+    fn test_sniff_shebang() {
+        assert_eq!(sniff_language("#!/bin/bash\necho hi"), Some("bash"));
+        assert_eq!(sniff_language("#!/bin/sh\n"), Some("bash"));
+        assert_eq!(sniff_language("\n#!/usr/bin/env python3\n"), Some("python"));
+        assert_eq!(sniff_language("#!/usr/bin/python3.12"), Some("python"));
+        assert_eq!(
+            sniff_language("#!/usr/bin/env -S node --no-warnings"),
+            Some("javascript")
+        );
+        assert_eq!(sniff_language("#!/usr/bin/awk -f"), None);
+    }
+
+    #[test]
+    fn test_sniff_prompt() {
+        assert_eq!(
+            sniff_language("$ cargo build\n   Compiling"),
+            Some("console")
+        );
+        assert_eq!(sniff_language("$"), None);
+        assert_eq!(sniff_language("$HOME is set"), None);
+        assert_eq!(sniff_language("fn main() {}"), None);
+        assert_eq!(sniff_language(""), None);
+    }
+}
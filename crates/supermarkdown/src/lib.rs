@@ -25,18 +25,63 @@
 //! let markdown = convert_with_options(html, &options);
 //! ```
 
+mod breadcrumb;
+mod chunk;
 mod converter;
+mod diagnostics;
+mod diff;
+mod email;
 mod entities;
+mod error;
 mod escape;
+mod head;
+mod headings;
+mod ids;
+mod json_ld;
+mod language;
+mod limits;
 mod options;
+mod outline;
 mod postprocess;
 mod precompute;
+mod progress;
+#[cfg(test)]
+mod properties;
+mod sourcemap;
+mod stats;
+#[cfg(feature = "token-estimate")]
+mod tokens;
+mod tooltip;
+mod url;
+#[cfg(feature = "validate")]
+mod validate;
 mod whitespace;
 
 pub mod rules;
 
+pub use chunk::{chunk_markdown, ChunkSize};
 pub use converter::Converter;
-pub use options::{HeadingStyle, LinkStyle, Options};
+pub use diagnostics::{Warning, WarningCode};
+pub use diff::ConversionDiff;
+pub use error::ConvertError;
+pub use limits::Truncation;
+pub use options::{
+    AbbrStyle, AltTextFn, AltTextProvider, AsideHandling, BlockLinkStyle, BlockquoteOverflow,
+    BreadcrumbHandling, CodeBlockInfo, CodeFilename, DataUriImages, DetailsStyle, DisabledRule,
+    EscapeMode, FenceInfo, FenceInfoFn, FenceInfoFormatter, GaugeStyle, HeadingDedupe,
+    HeadingStyle, ImageStyle, LinkStyle, ListSpacing, NumberingOptions, OlTypeHandling, Options,
+    ReferenceSort, RowHeaderStyle, SelectHandling, SingleRowTables, StrikethroughStyle,
+    SvgHandling, TableCaption, TableImageCells, TableStyle, TextAlign, UnderlineStyle, UrlKind,
+    UrlRewriteFn, UrlRewriter, WikiLinkOptions,
+};
+pub use sourcemap::SourceMapEntry;
+#[cfg(feature = "profile")]
+pub use stats::PhaseTimings;
+pub use stats::{
+    CapturedAttributes, ConversionMetadata, ConversionStats, ExtractedImage, ExtractedLink,
+};
+#[cfg(feature = "token-estimate")]
+pub use tokens::estimate_tokens;
 
 /// Convert HTML to Markdown with default options.
 ///
@@ -70,10 +115,182 @@ pub fn convert(html: &str) -> String {
 /// assert!(!markdown.contains("Nav"));
 /// ```
 pub fn convert_with_options(html: &str, options: &Options) -> String {
-    let converter = Converter::new();
+    let converter = Converter::with_options(options);
     converter.convert(html, options)
 }
 
+/// Convert HTML to Markdown into `out`, replacing its contents.
+///
+/// Produces the same markdown as [`convert_with_options`], but reuses
+/// `out`'s allocation, which saves a copy of the output per document when
+/// converting many documents in a loop.
+///
+/// # Example
+///
+/// ```rust
+/// use supermarkdown::{convert_into, Options};
+///
+/// let options = Options::default();
+/// let mut markdown = String::new();
+/// for html in ["<h1>One</h1>", "<p>Two</p>"] {
+///     convert_into(html, &options, &mut markdown);
+///     assert!(!markdown.is_empty());
+/// }
+/// assert_eq!(markdown, "Two\n");
+/// ```
+pub fn convert_into(html: &str, options: &Options, out: &mut String) {
+    let converter = Converter::with_options(options);
+    converter.convert_into(html, options, out)
+}
+
+/// Convert HTML to Markdown, rejecting invalid options (see
+/// [`Options::validate`]) and input larger than [`Options::max_input_bytes`]
+/// instead of falling back or truncating.
+///
+/// # Example
+///
+/// ```rust
+/// use supermarkdown::{try_convert_with_options, ConvertError, Options};
+///
+/// let options = Options::new().max_input_bytes(Some(16));
+/// assert_eq!(try_convert_with_options("<p>Hi</p>", &options).unwrap(), "Hi\n");
+/// assert_eq!(
+///     try_convert_with_options("<p>Far too long</p>", &options),
+///     Err(ConvertError::InputTooLarge { len: 19, max: 16 })
+/// );
+/// ```
+pub fn try_convert_with_options(html: &str, options: &Options) -> Result<String, ConvertError> {
+    let converter = Converter::with_options(options);
+    converter.try_convert(html, options)
+}
+
+/// Convert HTML to Markdown, also returning metadata about the conversion.
+///
+/// Collecting the metadata adds a little bookkeeping per element, so prefer
+/// [`convert_with_options`] when it isn't needed.
+///
+/// # Example
+///
+/// ```rust
+/// use supermarkdown::{convert_with_metadata, Options};
+///
+/// let options = Options::new().exclude_selectors(vec!["nav".to_string()]);
+/// let html = "<nav>Menu</nav><p>See <a href='/docs'>the docs</a>.</p>";
+///
+/// let (markdown, metadata) = convert_with_metadata(html, &options);
+/// assert_eq!(markdown, "See [the docs](/docs).\n");
+/// assert_eq!(metadata.stats.links_converted, 1);
+/// assert_eq!(metadata.stats.skipped_by_selector["nav"], 1);
+/// ```
+pub fn convert_with_metadata(html: &str, options: &Options) -> (String, ConversionMetadata) {
+    let converter = Converter::with_options(options);
+    converter.convert_with_metadata(html, options)
+}
+
+/// Convert HTML to Markdown, also returning which block-level element
+/// produced each range of the output.
+///
+/// Ranges are byte offsets into the returned markdown, ordered by start,
+/// with enclosing blocks (lists, blockquotes) before the blocks inside them.
+/// Blocks inside table cells are covered by their table's entry.
+///
+/// # Example
+///
+/// ```rust
+/// use supermarkdown::{convert_with_sourcemap, Options};
+///
+/// let html = "<h1 id='top'>Title</h1><p class='lead'>Intro text.</p>";
+/// let (markdown, map) = convert_with_sourcemap(html, &Options::default());
+///
+/// assert_eq!(&markdown[map[0].md_start..map[0].md_end], "# Title");
+/// assert_eq!(map[0].html_id.as_deref(), Some("top"));
+/// assert_eq!(&markdown[map[1].md_start..map[1].md_end], "Intro text.");
+/// assert_eq!(map[1].html_class.as_deref(), Some("lead"));
+/// ```
+pub fn convert_with_sourcemap(html: &str, options: &Options) -> (String, Vec<SourceMapEntry>) {
+    let converter = Converter::with_options(options);
+    converter.convert_with_sourcemap(html, options)
+}
+
+/// Convert HTML to Markdown, calling `progress` with the percentage done.
+///
+/// Progress is reported after parsing, after metadata is precomputed, as
+/// elements are converted and after post-processing. Each value is reported
+/// once and in increasing order, and 100 is always the last call.
+///
+/// # Example
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use supermarkdown::{convert_with_progress, Options};
+///
+/// let seen = RefCell::new(Vec::new());
+/// let markdown = convert_with_progress("<p>Hello</p>", &Options::default(), |percent| {
+///     seen.borrow_mut().push(percent)
+/// });
+///
+/// assert_eq!(markdown, "Hello\n");
+/// assert_eq!(seen.borrow().last(), Some(&100));
+/// ```
+pub fn convert_with_progress(html: &str, options: &Options, progress: impl Fn(u8)) -> String {
+    let converter = Converter::with_options(options);
+    converter.convert_with_progress(html, options, &progress)
+}
+
+/// Describe the element tree the converter would walk, for debugging
+/// selector configurations.
+///
+/// Each element is listed on its own line, indented by depth, with the rule
+/// that converts it, whether it is skipped or force-kept and by which
+/// selector, and up to 40 characters of its text.
+///
+/// # Example
+///
+/// ```rust
+/// use supermarkdown::{convert_outline, Options};
+///
+/// let options = Options::new().exclude_selectors(vec![".ad".to_string()]);
+/// let outline = convert_outline("<div class='ad'>Buy</div><p>Text</p>", &options);
+/// assert!(outline.contains("div.ad [skipped by \".ad\"] \"Buy\""));
+/// assert!(outline.contains("p -> ParagraphRule \"Text\""));
+/// ```
+pub fn convert_outline(html: &str, options: &Options) -> String {
+    let converter = Converter::with_options(options);
+    converter.outline(html, options)
+}
+
+/// Convert HTML with two option sets and diff the outputs line by line.
+///
+/// Meant for tuning options: the returned [`ConversionDiff`] holds both
+/// outputs and a unified diff from the first to the second.
+///
+/// # Example
+///
+/// ```rust
+/// use supermarkdown::{diff_conversions, Options};
+///
+/// let html = "<nav>Menu</nav><p>Text</p>";
+/// let without_nav = Options::new().exclude_selectors(vec!["nav".to_string()]);
+/// let diff = diff_conversions(html, &Options::default(), &without_nav);
+///
+/// assert_eq!(diff.right, "Text\n");
+/// assert!(diff.diff.contains("-Menu\n"));
+/// ```
+pub fn diff_conversions(html: &str, left: &Options, right: &Options) -> ConversionDiff {
+    ConversionDiff::new(html, left, right)
+}
+
+/// Run only the post-processing passes over already converted markdown.
+///
+/// Not part of the supported API; it exists so the benchmarks can measure
+/// post-processing on its own.
+#[doc(hidden)]
+pub fn postprocess_markdown(markdown: &str, options: &Options) -> String {
+    let mut out = String::new();
+    postprocess::postprocess_into(markdown.to_string(), options, false, &mut out);
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,134 @@
+//! Resource limits for converting untrusted input.
+//!
+//! Limits are checked as the converter visits elements. Element counting is
+//! a single increment per element, and the clock is only read every
+//! [`DEADLINE_CHECK_INTERVAL`] elements, so the guards stay cheap.
+
+use std::cell::Cell;
+use std::time::Instant;
+
+use crate::options::Options;
+
+/// Elements visited between deadline checks.
+const DEADLINE_CHECK_INTERVAL: usize = 256;
+
+/// Why a conversion stopped before the end of the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Truncation {
+    /// The input was cut at `max_input_bytes` before parsing.
+    MaxInputBytes,
+    /// The traversal stopped after `max_elements` elements.
+    MaxElements,
+    /// The traversal stopped when `deadline` passed.
+    Deadline,
+}
+
+/// Limit state for one conversion.
+pub(crate) struct Limits {
+    max_elements: Option<usize>,
+    deadline: Option<Instant>,
+    visited: Cell<usize>,
+    hit: Cell<Option<Truncation>>,
+}
+
+impl Limits {
+    /// Start tracking the limits in `options`; the deadline runs from now.
+    pub(crate) fn new(options: &Options) -> Self {
+        Self {
+            max_elements: options.max_elements,
+            // Instant::now() is unavailable on some targets (wasm32), so only
+            // read the clock when a deadline is set
+            deadline: options.deadline.map(|d| Instant::now() + d),
+            visited: Cell::new(0),
+            hit: Cell::new(None),
+        }
+    }
+
+    /// Record an element visit, returning false once a limit has been hit.
+    pub(crate) fn visit(&self) -> bool {
+        if self.hit.get().is_some() {
+            return false;
+        }
+
+        let visited = self.visited.get() + 1;
+        self.visited.set(visited);
+
+        if self.max_elements.is_some_and(|max| visited > max) {
+            self.hit.set(Some(Truncation::MaxElements));
+            return false;
+        }
+        if let Some(deadline) = self.deadline {
+            // Check on the first element too, in case parsing used up the time
+            if visited % DEADLINE_CHECK_INTERVAL == 1 && Instant::now() >= deadline {
+                self.hit.set(Some(Truncation::Deadline));
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether a limit has been hit and the traversal should wind down.
+    pub(crate) fn exhausted(&self) -> bool {
+        self.hit.get().is_some()
+    }
+
+    /// The limit that stopped the conversion, if any.
+    pub(crate) fn truncation(&self) -> Option<Truncation> {
+        self.hit.get()
+    }
+}
+
+/// Cut `html` to at most `max` bytes, backing up to a character boundary.
+pub(crate) fn truncate_input(html: &str, max: usize) -> &str {
+    if html.len() <= max {
+        return html;
+    }
+    let mut end = max;
+    while !html.is_char_boundary(end) {
+        end -= 1;
+    }
+    &html[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_element_limit() {
+        let limits = Limits::new(&Options::new().max_elements(Some(2)));
+        assert!(limits.visit());
+        assert!(limits.visit());
+        assert!(!limits.exhausted());
+        assert!(!limits.visit());
+        assert!(limits.exhausted());
+        assert_eq!(limits.truncation(), Some(Truncation::MaxElements));
+    }
+
+    #[test]
+    fn test_expired_deadline() {
+        let limits = Limits::new(&Options::new().deadline(Some(Duration::ZERO)));
+        assert!(!limits.visit());
+        assert_eq!(limits.truncation(), Some(Truncation::Deadline));
+    }
+
+    #[test]
+    fn test_no_limits() {
+        let limits = Limits::new(&Options::default());
+        for _ in 0..1000 {
+            assert!(limits.visit());
+        }
+        assert_eq!(limits.truncation(), None);
+    }
+
+    #[test]
+    fn test_truncate_input_char_boundary() {
+        assert_eq!(truncate_input("hello", 10), "hello");
+        assert_eq!(truncate_input("hello", 3), "hel");
+        // "é" is two bytes; never split it
+        assert_eq!(truncate_input("aé", 2), "a");
+    }
+}
@@ -1,7 +1,18 @@
 //! Configuration options for HTML to Markdown conversion.
 
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::ConvertError;
+
 /// Configuration options for HTML to Markdown conversion.
+///
+/// With the `serde` feature, options (de)serialize with snake_case field
+/// names and unspecified fields take their default values.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Options {
     /// CSS selectors for elements to exclude from output.
     /// Default: []
@@ -11,6 +22,57 @@ pub struct Options {
     /// Default: []
     pub include_selectors: Vec<String>,
 
+    /// Text patterns for short elements to exclude from output, such as
+    /// "Advertisement" or "Share this article". An element is skipped when
+    /// its own text (not counting child elements) contains a pattern,
+    /// ignoring case, and its whole text is at most `exclude_text_max_len`
+    /// characters. With the `regex-filters` feature, a pattern written as
+    /// `/.../` is a case-insensitive regex that must match all of the
+    /// element's own text. Include selectors still keep a matching element.
+    /// Default: []
+    pub exclude_text_patterns: Vec<String>,
+
+    /// Longest text, in characters, an element may have and still be
+    /// excluded by `exclude_text_patterns`, so an article that mentions
+    /// "advertisement" once is never dropped.
+    /// Default: 200
+    pub exclude_text_max_len: usize,
+
+    /// Leave an HTML comment like `<!-- excluded: nav (selector "nav") -->`
+    /// where each element removed by `exclude_selectors` or
+    /// `exclude_text_patterns` was. Elements inside an excluded element get
+    /// no marker of their own.
+    /// Default: false
+    pub mark_exclusions: bool,
+
+    /// Drop paragraphs whose text is shorter than this many characters and
+    /// which hold no image or code, like stray "Read more" or "Next →"
+    /// pager links. Headings and blocks in table cells are never dropped.
+    /// Default: None
+    pub min_block_text_len: Option<usize>,
+
+    /// Also drop list items shorter than `min_block_text_len`.
+    /// Default: false
+    pub min_block_text_len_lists: bool,
+
+    /// Convert HTML email: drop Outlook conditional comment content like
+    /// `<!--[if mso]>`, skip quoted replies matching
+    /// `email_quote_selectors`, and write runs of `<div>` lines as
+    /// paragraphs, with `<div><br></div>` between them.
+    /// Default: false
+    pub email_mode: bool,
+
+    /// CSS selectors of quoted reply history skipped by `email_mode`.
+    /// `include_selectors` can keep a match anyway.
+    /// Default: `.gmail_quote`, `.yahoo_quoted` and `#divRplyFwdMsg`
+    pub email_quote_selectors: Vec<String>,
+
+    /// Attributes to record from elements matching a CSS selector, as
+    /// `(selector, attribute names)` pairs. Values are returned by
+    /// `convert_with_metadata` in `ConversionMetadata::captured_attributes`.
+    /// Default: []
+    pub capture_attributes: Vec<(String, Vec<String>)>,
+
     /// Heading style: ATX (###) or Setext (underline).
     /// Default: Atx
     pub heading_style: HeadingStyle,
@@ -19,17 +81,332 @@ pub struct Options {
     /// Default: '`'
     pub code_fence: char,
 
+    /// Where the file name of a code block (`data-filename`, or a class
+    /// like `language-src/main.rs`) is written.
+    /// Default: Omit
+    pub code_filename: CodeFilename,
+
+    /// What goes in a code fence's info string after the language.
+    /// Default: LanguageOnly
+    pub fence_info: FenceInfo,
+
+    /// Writes the info string from a code block's details when
+    /// `fence_info` is Full, in place of the default
+    /// `rust,should_panic title="main.rs" linenums` format. Not serialized.
+    /// Default: None
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub fence_info_formatter: Option<FenceInfoFormatter>,
+
     /// Link style: Inline `[text](url)` or Referenced `[text][1]`.
     /// Default: Inline
     pub link_style: LinkStyle,
 
+    /// Order of reference definitions when `link_style` or `image_style`
+    /// is Referenced.
+    /// Default: UseOrder
+    pub reference_sort: ReferenceSort,
+
+    /// Image style: Inline `![alt](src)` or Referenced `![alt][img1]`.
+    /// Image definitions are numbered apart from link definitions and
+    /// follow them.
+    /// Default: Inline
+    pub image_style: ImageStyle,
+
+    /// How `<del>`, `<s>` and `<strike>` are rendered.
+    /// Default: DoubleTilde
+    pub strikethrough_style: StrikethroughStyle,
+
+    /// How `<u>` underlines are written; markdown has no underline.
+    /// Default: Html
+    pub underline_style: UnderlineStyle,
+
+    /// How `<abbr title>` abbreviations are written.
+    /// Default: Html
+    pub abbr_style: AbbrStyle,
+
+    /// Drop `<small>` elements and their content, which are mostly
+    /// disclaimers and fine print.
+    /// Default: false
+    pub drop_small: bool,
+
     /// Bullet character for unordered lists.
     /// Default: '-'
     pub bullet_marker: char,
 
+    /// Bullet markers cycled by unordered list nesting depth, e.g.
+    /// `['-', '*', '+']`. Overrides `bullet_marker` when set; only `-`, `*`
+    /// and `+` are valid, see [`Options::validate`].
+    /// Default: None
+    pub bullet_cycle: Option<Vec<char>>,
+
     /// Base URL for resolving relative links.
     /// Default: None
     pub base_url: Option<String>,
+
+    /// Called with each link, image and embedded media URL once resolved
+    /// against `base_url`; a returned URL replaces it. Not serialized.
+    /// Default: None
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub url_rewriter: Option<UrlRewriter>,
+
+    /// Called with the source of each image without alt text, resolved
+    /// against `base_url`; the returned text is used as its alt text. Each
+    /// source is looked up once per conversion, and images that are
+    /// excluded or dropped aren't looked up. Not serialized.
+    /// Default: None
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub alt_text_provider: Option<AltTextProvider>,
+
+    /// How inline `<svg>` elements are rendered.
+    /// Default: Auto
+    pub svg_handling: SvgHandling,
+
+    /// How tables are rendered.
+    /// Default: Pipes
+    pub table_style: TableStyle,
+
+    /// How pipe tables whose rows each start with a `<th>` row header, and
+    /// which have no header row, are rendered.
+    /// Default: EmptyHeader
+    pub row_header_style: RowHeaderStyle,
+
+    /// Where and how a pipe table's `<caption>` is written.
+    /// Default: After
+    pub table_caption: TableCaption,
+
+    /// How table cells holding only images or icons are written.
+    /// Default: Markdown
+    pub table_image_cells: TableImageCells,
+
+    /// Size the delimiter row of a pipe table from the `width` attributes
+    /// or styles of its `<col>` elements or first row's cells, in percent
+    /// or pixels, so the dash counts give the columns' relative widths
+    /// (`| :------- | :--- |` for 70% and 30%). Padded tables are widened
+    /// until every cell fits its column.
+    /// Default: false
+    pub table_width_hints: bool,
+
+    /// How tables with a single row, often badges or metadata strips, are
+    /// written.
+    /// Default: Table
+    pub single_row_tables: SingleRowTables,
+
+    /// Class names that set a table cell or column's alignment, as used by
+    /// CSS frameworks (`text-right`, `has-text-centered`). Checked after the
+    /// `align` attribute and inline `text-align` style.
+    /// Default: Bootstrap, Bulma and Tailwind alignment classes
+    pub alignment_classes: Vec<(String, TextAlign)>,
+
+    /// How `<select>` and `<datalist>` options are rendered.
+    /// Default: Text
+    pub select_handling: SelectHandling,
+
+    /// How the value of a `<meter>` or `<progress>` without text content is
+    /// written.
+    /// Default: Fraction
+    pub gauge_style: GaugeStyle,
+
+    /// Bold the values written for `<meter>` and `<progress>` (`**70%**`).
+    /// Default: false
+    pub bold_gauges: bool,
+
+    /// Add the text of `title` attributes and `aria-describedby` targets in
+    /// parentheses after inline elements like `<span>` and `<dfn>`.
+    /// Default: false
+    pub inline_tooltips: bool,
+
+    /// Deduplicate repeated headings with identical text at the same level.
+    /// Default: false
+    pub dedupe_headings: bool,
+
+    /// How repeated headings are deduplicated when `dedupe_headings` is set.
+    /// Default: Suffix
+    pub heading_dedupe: HeadingDedupe,
+
+    /// Keep at most this many copies of a paragraph or blockquote repeated
+    /// word for word, like a newsletter prompt between every few
+    /// paragraphs; None keeps them all. Headings, lists, tables and code
+    /// are never removed. `Some(0)` keeps one copy, like `Some(1)`.
+    /// Default: None
+    pub dedupe_repeated_blocks: Option<usize>,
+
+    /// Longest text, in characters, a block may have and still be removed
+    /// by `dedupe_repeated_blocks`, so repeated article text is kept.
+    /// Default: 300
+    pub dedupe_block_max_len: usize,
+
+    /// Heading level for paragraphs that are only a short bold phrase, like
+    /// `<p><strong>Pricing</strong></p>`; None keeps them as bold text.
+    /// Default: None
+    pub promote_bold_headings: Option<u8>,
+
+    /// Number headings hierarchically, like `## 2.3 Configuration`; None
+    /// leaves them as they are.
+    /// Default: None
+    pub number_headings: Option<NumberingOptions>,
+
+    /// Remove links whose visible text is empty, like `[](/home)` left
+    /// behind by icon-only links.
+    /// Default: true
+    pub remove_empty_links: bool,
+
+    /// Remove images with an empty alt text.
+    /// Default: false
+    pub require_alt: bool,
+
+    /// Add the page's `og:image` as an image after its leading level-1
+    /// heading, unless the markdown already links or shows that URL.
+    /// A relative URL resolves against `base_url`, or else the page's
+    /// `og:url` or canonical URL.
+    /// Default: false
+    pub inject_og_image: bool,
+
+    /// Leave `<script type="application/ld+json">` structured data out of
+    /// the markdown and return it parsed in
+    /// [`ConversionMetadata::json_ld`](crate::ConversionMetadata), which
+    /// needs the `serde` feature. Blocks are collected even inside excluded
+    /// elements, so excluding all scripts keeps them.
+    /// Default: false
+    pub extract_json_ld: bool,
+
+    /// Write internal links as wiki links, `[[Page Name]]` or
+    /// `[[page-slug|Anchor text]]`, for note apps like Obsidian. None keeps
+    /// markdown links.
+    /// Default: None
+    pub wiki_links: Option<WikiLinkOptions>,
+
+    /// Merge back-to-back links to the same URL, like a card's image link
+    /// followed by its title link, into one link; repeated text is dropped.
+    /// Default: false
+    pub merge_adjacent_links: bool,
+
+    /// How `<ol type="a|A|i|I">` lettering and roman numerals are rendered.
+    /// Default: Numbers
+    pub ol_type_handling: OlTypeHandling,
+
+    /// Whether list items are separated by blank lines.
+    /// Default: Auto
+    pub list_spacing: ListSpacing,
+
+    /// Pad ordered list numbers to the width of the list's last number
+    /// (`1.  ` ... `10. `), so every item's content starts in one column.
+    /// Default: false
+    pub pad_ordered_markers: bool,
+
+    /// Deepest blockquote nesting written as `>` prefixes; quotes nested
+    /// deeper are handled per `blockquote_overflow`. None is unlimited.
+    /// Default: None
+    pub max_blockquote_depth: Option<usize>,
+
+    /// What happens to blockquotes nested beyond `max_blockquote_depth`.
+    /// Default: Flatten
+    pub blockquote_overflow: BlockquoteOverflow,
+
+    /// How `<details>` disclosure widgets are written.
+    /// Default: Blockquote
+    pub details_style: DetailsStyle,
+
+    /// How `<aside>` pull quotes and boxes like "related articles" are
+    /// written.
+    /// Default: Inline
+    pub aside_handling: AsideHandling,
+
+    /// How `<dialog>` content, mostly newsletter and cookie prompts, is
+    /// written.
+    /// Default: Skip
+    pub dialog_handling: AsideHandling,
+
+    /// How breadcrumb trails, a `nav`, `ol` or `ul` labelled or classed as
+    /// a breadcrumb or typed as a schema.org `BreadcrumbList`, are written.
+    /// Default: List
+    pub breadcrumbs: BreadcrumbHandling,
+
+    /// What to do with images whose source is an inline `data:` URI.
+    /// Default: Keep
+    pub data_uri_images: DataUriImages,
+
+    /// How `<` that would start an HTML tag is escaped in text. Unless
+    /// `None`, text at the start of a line that would begin a list item,
+    /// heading, quote or code fence, like `2024. It was`, is also
+    /// backslash-escaped.
+    /// Default: Entities
+    pub escape_mode: EscapeMode,
+
+    /// Write raw HTML where markdown has no equivalent. When false, for
+    /// renderers that strip HTML, `<kbd>` and `<samp>` become code spans,
+    /// `<mark>` bold, `<var>` emphasis, `<sup>` a caret (`x^2`), and `<sub>`
+    /// and `<abbr>` plain text; options set to an HTML style use their
+    /// markdown form instead, and `EscapeMode::None` escapes as `Entities`.
+    /// Default: true
+    pub allow_raw_html: bool,
+
+    /// Largest input accepted, in bytes. Longer input is cut at this size,
+    /// or rejected by the `try_` conversion functions.
+    /// Default: None
+    pub max_input_bytes: Option<usize>,
+
+    /// Stop converting after this many elements, keeping what was converted.
+    /// Default: None
+    pub max_elements: Option<usize>,
+
+    /// How many times longer than the raw text decoding entities may make a
+    /// text node; past that, the rest of the node is kept undecoded. Values
+    /// below 1 count as 1.
+    /// Default: 1.0
+    pub max_entity_expansion_ratio: f64,
+
+    /// Stop converting once this much time has passed since the conversion
+    /// started, keeping what was converted. Not supported on wasm32.
+    /// Default: None
+    pub deadline: Option<Duration>,
+
+    /// How links wrapping block content such as headings are rendered.
+    /// Default: ReadMore
+    pub block_link_style: BlockLinkStyle,
+
+    /// Render runs of two or more `<br>` as a paragraph break instead of
+    /// stacked hard breaks.
+    /// Default: true
+    pub collapse_double_br: bool,
+
+    /// Strip control characters other than newline and tab from the output,
+    /// code blocks included, and turn U+2028 and U+2029 separators into
+    /// newlines.
+    /// Default: true
+    pub strip_control_chars: bool,
+
+    /// Write the smallest faithful markdown, for prompts: tables without
+    /// padding, no link or image titles, `<https://...>` autolinks for links
+    /// showing their URL without the scheme, only repeated URLs turned into
+    /// references, and no horizontal rules at the document's ends or right
+    /// after another. The [`Options::compact`] builder also sets the other
+    /// options that shrink output.
+    /// Default: false
+    pub compact: bool,
+
+    /// End non-empty output with exactly one newline.
+    /// Default: true
+    pub trailing_newline: bool,
+
+    /// Trim whitespace from the start and end of the document. When off,
+    /// leading blank lines and indentation are kept.
+    /// Default: true
+    pub trim_document: bool,
+
+    /// Conversions to turn off. Elements of a disabled kind are converted
+    /// like unknown elements: their content is kept as flowed text. Applied
+    /// when the [`Converter`](crate::Converter) is built with
+    /// [`Converter::with_options`](crate::Converter::with_options).
+    /// Default: []
+    pub disable_rules: Vec<DisabledRule>,
+
+    /// With the `validate` feature, parse the markdown of
+    /// [`convert_with_metadata`](crate::convert_with_metadata) again and add
+    /// a warning when it has fewer or more headings or table rows than were
+    /// written, or a code fence that never closes. Meant for tests and CI;
+    /// the markdown itself is unchanged.
+    /// Default: false
+    pub validate_output: bool,
 }
 
 impl Default for Options {
@@ -37,11 +414,79 @@ impl Default for Options {
         Self {
             exclude_selectors: vec![],
             include_selectors: vec![],
+            exclude_text_patterns: vec![],
+            exclude_text_max_len: 200,
+            mark_exclusions: false,
+            min_block_text_len: None,
+            min_block_text_len_lists: false,
+            email_mode: false,
+            email_quote_selectors: default_email_quote_selectors(),
+            capture_attributes: vec![],
             heading_style: HeadingStyle::Atx,
             code_fence: '`',
+            code_filename: CodeFilename::Omit,
+            fence_info: FenceInfo::LanguageOnly,
+            fence_info_formatter: None,
             link_style: LinkStyle::Inline,
+            reference_sort: ReferenceSort::UseOrder,
+            image_style: ImageStyle::Inline,
+            strikethrough_style: StrikethroughStyle::DoubleTilde,
+            underline_style: UnderlineStyle::Html,
+            abbr_style: AbbrStyle::Html,
+            drop_small: false,
             bullet_marker: '-',
+            bullet_cycle: None,
             base_url: None,
+            url_rewriter: None,
+            alt_text_provider: None,
+            svg_handling: SvgHandling::Auto,
+            table_style: TableStyle::Pipes,
+            row_header_style: RowHeaderStyle::EmptyHeader,
+            table_caption: TableCaption::After,
+            table_image_cells: TableImageCells::Markdown,
+            table_width_hints: false,
+            single_row_tables: SingleRowTables::Table,
+            alignment_classes: default_alignment_classes(),
+            select_handling: SelectHandling::Text,
+            gauge_style: GaugeStyle::Fraction,
+            bold_gauges: false,
+            inline_tooltips: false,
+            dedupe_headings: false,
+            heading_dedupe: HeadingDedupe::Suffix,
+            dedupe_repeated_blocks: None,
+            dedupe_block_max_len: 300,
+            promote_bold_headings: None,
+            number_headings: None,
+            remove_empty_links: true,
+            require_alt: false,
+            inject_og_image: false,
+            extract_json_ld: false,
+            wiki_links: None,
+            merge_adjacent_links: false,
+            ol_type_handling: OlTypeHandling::Numbers,
+            list_spacing: ListSpacing::Auto,
+            pad_ordered_markers: false,
+            max_blockquote_depth: None,
+            blockquote_overflow: BlockquoteOverflow::Flatten,
+            details_style: DetailsStyle::Blockquote,
+            aside_handling: AsideHandling::Inline,
+            dialog_handling: AsideHandling::Skip,
+            breadcrumbs: BreadcrumbHandling::List,
+            data_uri_images: DataUriImages::Keep,
+            escape_mode: EscapeMode::Entities,
+            allow_raw_html: true,
+            max_input_bytes: None,
+            max_elements: None,
+            max_entity_expansion_ratio: 1.0,
+            deadline: None,
+            block_link_style: BlockLinkStyle::ReadMore,
+            collapse_double_br: true,
+            strip_control_chars: true,
+            compact: false,
+            trailing_newline: true,
+            trim_document: true,
+            disable_rules: vec![],
+            validate_output: false,
         }
     }
 }
@@ -64,6 +509,54 @@ impl Options {
         self
     }
 
+    /// Set text patterns for short elements to exclude.
+    pub fn exclude_text_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.exclude_text_patterns = patterns;
+        self
+    }
+
+    /// Set the longest text an element excluded by text pattern may have.
+    pub fn exclude_text_max_len(mut self, len: usize) -> Self {
+        self.exclude_text_max_len = len;
+        self
+    }
+
+    /// Enable or disable comments marking excluded elements.
+    pub fn mark_exclusions(mut self, enabled: bool) -> Self {
+        self.mark_exclusions = enabled;
+        self
+    }
+
+    /// Set the shortest paragraph text kept.
+    pub fn min_block_text_len(mut self, len: Option<usize>) -> Self {
+        self.min_block_text_len = len;
+        self
+    }
+
+    /// Set whether `min_block_text_len` also drops short list items.
+    pub fn min_block_text_len_lists(mut self, enabled: bool) -> Self {
+        self.min_block_text_len_lists = enabled;
+        self
+    }
+
+    /// Set whether to convert HTML email.
+    pub fn email_mode(mut self, enabled: bool) -> Self {
+        self.email_mode = enabled;
+        self
+    }
+
+    /// Set the selectors of quoted replies skipped by `email_mode`.
+    pub fn email_quote_selectors(mut self, selectors: Vec<String>) -> Self {
+        self.email_quote_selectors = selectors;
+        self
+    }
+
+    /// Set the attributes to capture, per CSS selector.
+    pub fn capture_attributes(mut self, captures: Vec<(String, Vec<String>)>) -> Self {
+        self.capture_attributes = captures;
+        self
+    }
+
     /// Set heading style.
     pub fn heading_style(mut self, style: HeadingStyle) -> Self {
         self.heading_style = style;
@@ -76,27 +569,457 @@ impl Options {
         self
     }
 
+    /// Set where code block file names are written.
+    pub fn code_filename(mut self, placement: CodeFilename) -> Self {
+        self.code_filename = placement;
+        self
+    }
+
+    /// Set what goes in code fence info strings.
+    pub fn fence_info(mut self, info: FenceInfo) -> Self {
+        self.fence_info = info;
+        self
+    }
+
+    /// Set the function that writes code fence info strings.
+    pub fn fence_info_formatter(mut self, formatter: Option<Arc<FenceInfoFn>>) -> Self {
+        self.fence_info_formatter = formatter.map(FenceInfoFormatter);
+        self
+    }
+
     /// Set link style.
     pub fn link_style(mut self, style: LinkStyle) -> Self {
         self.link_style = style;
         self
     }
 
+    /// Set the order of reference definitions.
+    pub fn reference_sort(mut self, sort: ReferenceSort) -> Self {
+        self.reference_sort = sort;
+        self
+    }
+
+    /// Set image style.
+    pub fn image_style(mut self, style: ImageStyle) -> Self {
+        self.image_style = style;
+        self
+    }
+
+    /// Set strikethrough style.
+    pub fn strikethrough_style(mut self, style: StrikethroughStyle) -> Self {
+        self.strikethrough_style = style;
+        self
+    }
+
+    /// Set how `<u>` underlines are written.
+    pub fn underline_style(mut self, style: UnderlineStyle) -> Self {
+        self.underline_style = style;
+        self
+    }
+
+    /// Set how `<abbr title>` abbreviations are written.
+    pub fn abbr_style(mut self, style: AbbrStyle) -> Self {
+        self.abbr_style = style;
+        self
+    }
+
+    /// Set whether `<small>` elements are dropped.
+    pub fn drop_small(mut self, enabled: bool) -> Self {
+        self.drop_small = enabled;
+        self
+    }
+
     /// Set bullet marker for unordered lists.
     pub fn bullet_marker(mut self, marker: char) -> Self {
         self.bullet_marker = marker;
         self
     }
 
+    /// Set bullet markers to alternate between nesting levels.
+    pub fn bullet_cycle(mut self, cycle: Option<Vec<char>>) -> Self {
+        self.bullet_cycle = cycle;
+        self
+    }
+
     /// Set base URL for resolving relative links.
     pub fn base_url(mut self, url: Option<String>) -> Self {
         self.base_url = url;
         self
     }
+
+    /// Set the function that rewrites link, image and media URLs.
+    pub fn url_rewriter(mut self, rewriter: Option<Arc<UrlRewriteFn>>) -> Self {
+        self.url_rewriter = rewriter.map(UrlRewriter);
+        self
+    }
+
+    /// Set the function that supplies alt text for images without any.
+    pub fn alt_text_provider(mut self, provider: Option<Arc<AltTextFn>>) -> Self {
+        self.alt_text_provider = provider.map(AltTextProvider);
+        self
+    }
+
+    /// Set how inline SVG elements are rendered.
+    pub fn svg_handling(mut self, handling: SvgHandling) -> Self {
+        self.svg_handling = handling;
+        self
+    }
+
+    /// Set how tables are rendered.
+    pub fn table_style(mut self, style: TableStyle) -> Self {
+        self.table_style = style;
+        self
+    }
+
+    /// Set how tables with row headers and no header row are rendered.
+    pub fn row_header_style(mut self, style: RowHeaderStyle) -> Self {
+        self.row_header_style = style;
+        self
+    }
+
+    /// Set where and how table captions are written.
+    pub fn table_caption(mut self, caption: TableCaption) -> Self {
+        self.table_caption = caption;
+        self
+    }
+
+    /// Set how table cells holding only images or icons are written.
+    pub fn table_image_cells(mut self, cells: TableImageCells) -> Self {
+        self.table_image_cells = cells;
+        self
+    }
+
+    /// Set whether column widths from the HTML size the delimiter row.
+    pub fn table_width_hints(mut self, enabled: bool) -> Self {
+        self.table_width_hints = enabled;
+        self
+    }
+
+    /// Set how tables with a single row are written.
+    pub fn single_row_tables(mut self, tables: SingleRowTables) -> Self {
+        self.single_row_tables = tables;
+        self
+    }
+
+    /// Set the class names that align table columns.
+    pub fn alignment_classes(mut self, classes: Vec<(String, TextAlign)>) -> Self {
+        self.alignment_classes = classes;
+        self
+    }
+
+    /// Set how select and datalist options are rendered.
+    pub fn select_handling(mut self, handling: SelectHandling) -> Self {
+        self.select_handling = handling;
+        self
+    }
+
+    /// Set how `<meter>` and `<progress>` values are written.
+    pub fn gauge_style(mut self, style: GaugeStyle) -> Self {
+        self.gauge_style = style;
+        self
+    }
+
+    /// Enable or disable bolding `<meter>` and `<progress>` values.
+    pub fn bold_gauges(mut self, enabled: bool) -> Self {
+        self.bold_gauges = enabled;
+        self
+    }
+
+    /// Enable or disable inlining tooltip text after inline elements.
+    pub fn inline_tooltips(mut self, enabled: bool) -> Self {
+        self.inline_tooltips = enabled;
+        self
+    }
+
+    /// Enable deduplication of repeated headings.
+    pub fn dedupe_headings(mut self, enabled: bool) -> Self {
+        self.dedupe_headings = enabled;
+        self
+    }
+
+    /// Set how repeated headings are deduplicated.
+    pub fn heading_dedupe(mut self, mode: HeadingDedupe) -> Self {
+        self.heading_dedupe = mode;
+        self
+    }
+
+    /// Set how many copies of an identical short block to keep.
+    pub fn dedupe_repeated_blocks(mut self, max: Option<usize>) -> Self {
+        self.dedupe_repeated_blocks = max;
+        self
+    }
+
+    /// Set the longest block `dedupe_repeated_blocks` may remove.
+    pub fn dedupe_block_max_len(mut self, len: usize) -> Self {
+        self.dedupe_block_max_len = len;
+        self
+    }
+
+    /// Set the heading level for bold-only paragraphs (clamped to 1-6).
+    pub fn promote_bold_headings(mut self, level: Option<u8>) -> Self {
+        self.promote_bold_headings = level;
+        self
+    }
+
+    /// Set hierarchical heading numbering.
+    pub fn number_headings(mut self, numbering: Option<NumberingOptions>) -> Self {
+        self.number_headings = numbering;
+        self
+    }
+
+    /// Set whether links with empty text are removed.
+    pub fn remove_empty_links(mut self, enabled: bool) -> Self {
+        self.remove_empty_links = enabled;
+        self
+    }
+
+    /// Set whether images without alt text are removed.
+    pub fn require_alt(mut self, enabled: bool) -> Self {
+        self.require_alt = enabled;
+        self
+    }
+
+    /// Enable adding the page's `og:image` as a hero image.
+    pub fn inject_og_image(mut self, enabled: bool) -> Self {
+        self.inject_og_image = enabled;
+        self
+    }
+
+    /// Enable collecting JSON-LD structured data instead of converting it.
+    pub fn extract_json_ld(mut self, enabled: bool) -> Self {
+        self.extract_json_ld = enabled;
+        self
+    }
+
+    /// Set how internal links are written as wiki links.
+    pub fn wiki_links(mut self, wiki_links: Option<WikiLinkOptions>) -> Self {
+        self.wiki_links = wiki_links;
+        self
+    }
+
+    /// Set whether adjacent links to the same URL are merged.
+    pub fn merge_adjacent_links(mut self, enabled: bool) -> Self {
+        self.merge_adjacent_links = enabled;
+        self
+    }
+
+    /// Set how lettered and roman numeral ordered lists are rendered.
+    pub fn ol_type_handling(mut self, handling: OlTypeHandling) -> Self {
+        self.ol_type_handling = handling;
+        self
+    }
+
+    /// Set whether list items are separated by blank lines.
+    pub fn list_spacing(mut self, spacing: ListSpacing) -> Self {
+        self.list_spacing = spacing;
+        self
+    }
+
+    /// Set whether ordered list numbers are padded to one width.
+    pub fn pad_ordered_markers(mut self, enabled: bool) -> Self {
+        self.pad_ordered_markers = enabled;
+        self
+    }
+
+    /// Set the deepest blockquote nesting kept as `>` prefixes.
+    pub fn max_blockquote_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_blockquote_depth = depth;
+        self
+    }
+
+    /// Set how blockquotes beyond `max_blockquote_depth` are handled.
+    pub fn blockquote_overflow(mut self, overflow: BlockquoteOverflow) -> Self {
+        self.blockquote_overflow = overflow;
+        self
+    }
+
+    /// Set how `<details>` elements are written.
+    pub fn details_style(mut self, style: DetailsStyle) -> Self {
+        self.details_style = style;
+        self
+    }
+
+    /// Set how `<aside>` elements are written.
+    pub fn aside_handling(mut self, handling: AsideHandling) -> Self {
+        self.aside_handling = handling;
+        self
+    }
+
+    /// Set how `<dialog>` elements are written.
+    pub fn dialog_handling(mut self, handling: AsideHandling) -> Self {
+        self.dialog_handling = handling;
+        self
+    }
+
+    /// Set how breadcrumb trails are written.
+    pub fn breadcrumbs(mut self, handling: BreadcrumbHandling) -> Self {
+        self.breadcrumbs = handling;
+        self
+    }
+
+    /// Set the policy for images with inline `data:` URIs.
+    pub fn data_uri_images(mut self, policy: DataUriImages) -> Self {
+        self.data_uri_images = policy;
+        self
+    }
+
+    /// Set how tag-like `<` in text is escaped.
+    pub fn escape_mode(mut self, mode: EscapeMode) -> Self {
+        self.escape_mode = mode;
+        self
+    }
+
+    /// Enable or disable raw HTML in the output.
+    pub fn allow_raw_html(mut self, allow: bool) -> Self {
+        self.allow_raw_html = allow;
+        self
+    }
+
+    /// Set the largest input size in bytes.
+    pub fn max_input_bytes(mut self, max: Option<usize>) -> Self {
+        self.max_input_bytes = max;
+        self
+    }
+
+    /// Set the maximum number of elements to convert.
+    pub fn max_elements(mut self, max: Option<usize>) -> Self {
+        self.max_elements = max;
+        self
+    }
+
+    /// Set how much decoding entities may grow a text node.
+    pub fn max_entity_expansion_ratio(mut self, ratio: f64) -> Self {
+        self.max_entity_expansion_ratio = ratio;
+        self
+    }
+
+    /// Set the time budget for a conversion.
+    pub fn deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Set how links around block content are rendered.
+    pub fn block_link_style(mut self, style: BlockLinkStyle) -> Self {
+        self.block_link_style = style;
+        self
+    }
+
+    /// Enable or disable turning `<br><br>` into a paragraph break.
+    pub fn collapse_double_br(mut self, enabled: bool) -> Self {
+        self.collapse_double_br = enabled;
+        self
+    }
+
+    /// Enable or disable stripping control characters.
+    pub fn strip_control_chars(mut self, enabled: bool) -> Self {
+        self.strip_control_chars = enabled;
+        self
+    }
+
+    /// Enable or disable compact output. Enabling it also switches to
+    /// referenced links and merges adjacent links to the same URL.
+    pub fn compact(mut self, enabled: bool) -> Self {
+        self.compact = enabled;
+        if enabled {
+            self.link_style = LinkStyle::Referenced;
+            self.merge_adjacent_links = true;
+        }
+        self
+    }
+
+    /// Enable or disable the trailing newline.
+    pub fn trailing_newline(mut self, enabled: bool) -> Self {
+        self.trailing_newline = enabled;
+        self
+    }
+
+    /// Enable or disable trimming the document.
+    pub fn trim_document(mut self, enabled: bool) -> Self {
+        self.trim_document = enabled;
+        self
+    }
+
+    /// Set the conversions to turn off.
+    pub fn disable_rules(mut self, rules: Vec<DisabledRule>) -> Self {
+        self.disable_rules = rules;
+        self
+    }
+
+    /// Set whether to check the structure of the markdown after converting.
+    pub fn validate_output(mut self, enabled: bool) -> Self {
+        self.validate_output = enabled;
+        self
+    }
+
+    /// Check for option values that can't be honored.
+    ///
+    /// The `try_` conversion functions reject invalid options; the others
+    /// fall back to defaults for them (a bad `bullet_cycle` uses
+    /// `bullet_marker`, and an invalid regex in `exclude_text_patterns` is
+    /// left out).
+    pub fn validate(&self) -> Result<(), ConvertError> {
+        self.validate_bullet_cycle()?;
+        #[cfg(feature = "regex-filters")]
+        for pattern in &self.exclude_text_patterns {
+            let Some(source) = crate::precompute::text_regex_source(pattern) else {
+                continue;
+            };
+            if let Err(e) = crate::precompute::compile_text_regex(source) {
+                return Err(ConvertError::InvalidOption {
+                    option: "exclude_text_patterns",
+                    reason: format!("invalid regex '{}': {}", pattern, e),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `bullet_cycle`, if set, is a non-empty list of markers.
+    fn validate_bullet_cycle(&self) -> Result<(), ConvertError> {
+        let Some(cycle) = &self.bullet_cycle else {
+            return Ok(());
+        };
+        if cycle.is_empty() {
+            return Err(ConvertError::InvalidOption {
+                option: "bullet_cycle",
+                reason: "must not be empty".to_string(),
+            });
+        }
+        if let Some(c) = cycle.iter().find(|c| !matches!(c, '-' | '*' | '+')) {
+            return Err(ConvertError::InvalidOption {
+                option: "bullet_cycle",
+                reason: format!("invalid marker '{}', expected '-', '*' or '+'", c),
+            });
+        }
+        Ok(())
+    }
+
+    /// Bullet marker for an unordered list item at `depth` (0 for top level).
+    ///
+    /// Only `bullet_cycle` itself is checked, so other invalid options
+    /// don't turn the cycle off.
+    pub(crate) fn bullet_for_depth(&self, depth: usize) -> char {
+        match &self.bullet_cycle {
+            Some(cycle) if self.validate_bullet_cycle().is_ok() => cycle[depth % cycle.len()],
+            _ => self.bullet_marker,
+        }
+    }
+
+    /// How tag-like `<` in text is escaped: unescaped text would be raw
+    /// HTML, so `None` escapes as `Entities` without `allow_raw_html`.
+    pub(crate) fn text_escape_mode(&self) -> EscapeMode {
+        match self.escape_mode {
+            EscapeMode::None if !self.allow_raw_html => EscapeMode::Entities,
+            mode => mode,
+        }
+    }
 }
 
 /// Heading style for markdown output.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum HeadingStyle {
     /// ATX style: ### Heading
     #[default]
@@ -107,6 +1030,8 @@ pub enum HeadingStyle {
 
 /// Link style for markdown output.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum LinkStyle {
     /// Inline style: `[text](url)`
     #[default]
@@ -115,6 +1040,640 @@ pub enum LinkStyle {
     Referenced,
 }
 
+/// Image style for markdown output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ImageStyle {
+    /// Inline style: `![alt](src)`
+    #[default]
+    Inline,
+    /// Referenced style: `![alt][img1] ... [img1]: src`
+    Referenced,
+}
+
+/// Order of reference definitions for referenced links and images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ReferenceSort {
+    /// Order of first use in the text
+    #[default]
+    UseOrder,
+    /// Sorted by URL
+    Url,
+    /// Grouped by domain, with a blank line between groups; relative URLs
+    /// come first and each group keeps first-use order
+    Domain,
+}
+
+/// Strikethrough style for markdown output.
+///
+/// Text that itself contains `~` is always rendered as `Html`, since tildes
+/// inside the markers would be ambiguous.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum StrikethroughStyle {
+    /// GFM style: ~~text~~
+    #[default]
+    DoubleTilde,
+    /// Single tilde: ~text~
+    SingleTilde,
+    /// HTML: <del>text</del>
+    Html,
+}
+
+/// Rendering of `<u>` underlined text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum UnderlineStyle {
+    /// HTML: <u>text</u>
+    #[default]
+    Html,
+    /// Emphasis: *text*
+    Emphasis,
+}
+
+/// Rendering of `<abbr title>` abbreviations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AbbrStyle {
+    /// HTML: <abbr title="World Health Organization">WHO</abbr>
+    #[default]
+    Html,
+    /// The title in parentheses after the first use of each abbreviation:
+    /// WHO (World Health Organization), then WHO. A use with a different
+    /// title is expanded again
+    Expanded,
+    /// Plain text, with a PHP Markdown Extra definition per abbreviation
+    /// appended to the document: `*[WHO]: World Health Organization`. The
+    /// first title given for an abbreviation is used
+    DefinitionList,
+}
+
+/// Rendering of inline `<svg>` elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SvgHandling {
+    /// Skip icons: svgs that are `aria-hidden`, presentational, or at most
+    /// 48px wide and high by their `width`/`height` or `viewBox`, or that
+    /// give no size. Others, like charts, are written as their
+    /// `aria-label` or `<title>` text.
+    #[default]
+    Auto,
+    /// Emit nothing
+    Skip,
+    /// Emit the text of the svg's `<title>` child, if any
+    TitleText,
+    /// Pass the svg through as raw HTML
+    Html,
+}
+
+/// What a URL passed to `Options::url_rewriter` points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlKind {
+    /// An `<a href>`
+    Link,
+    /// An `<img>` or `<picture>` source
+    Image,
+    /// An embedded document or media file (`<object>`, `<embed>`)
+    Media,
+}
+
+/// Signature of a URL rewriter: the URL's kind and resolved value in, a
+/// replacement (or None to keep it) out.
+pub type UrlRewriteFn = dyn Fn(UrlKind, &str) -> Option<String> + Send + Sync;
+
+/// A shared URL rewriter, so `Options` stays cheap to clone.
+#[derive(Clone)]
+pub struct UrlRewriter(pub Arc<UrlRewriteFn>);
+
+impl fmt::Debug for UrlRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UrlRewriter(..)")
+    }
+}
+
+/// Signature of an alt text provider: the image's resolved source in, its
+/// alt text (or None to leave it empty) out.
+pub type AltTextFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// A shared alt text provider, so `Options` stays cheap to clone.
+#[derive(Clone)]
+pub struct AltTextProvider(pub Arc<AltTextFn>);
+
+impl fmt::Debug for AltTextProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AltTextProvider(..)")
+    }
+}
+
+/// Rendering of `<table>` elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TableStyle {
+    /// GFM pipe table
+    #[default]
+    Pipes,
+    /// One bullet item per data row of `**Header**: value` lines, for narrow
+    /// outputs where wide tables wrap badly
+    KeyValueList,
+    /// Pass the table through as raw HTML
+    Html,
+}
+
+/// Rendering of tables labelled by row headers rather than a header row,
+/// like spec-style attribute tables of `<th scope="row">` and `<td>` pairs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum RowHeaderStyle {
+    /// Add an empty header row so every row stays a data row
+    #[default]
+    EmptyHeader,
+    /// Add an empty header row and bold the row header cells
+    /// (`| **Key** | value |`)
+    Bold,
+    /// Promote the first row to the header, as for other tables
+    FirstRow,
+}
+
+/// Placement of a pipe table's caption.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TableCaption {
+    /// `*caption*` after the table
+    #[default]
+    After,
+    /// `**caption**` before the table
+    Before,
+    /// Pandoc's `: caption` after the table
+    Pandoc,
+    /// Leave the caption out
+    Drop,
+}
+
+/// Rendering of tables with a single row, like badge strips.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SingleRowTables {
+    /// A header row and delimiter row with no body, like any other table
+    #[default]
+    Table,
+    /// One line of cells separated by ` · `, header cells in bold
+    Text,
+}
+
+/// Rendering of table cells that hold only images or icons, like the
+/// checkmarks of a comparison table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TableImageCells {
+    /// Convert the images like anywhere else
+    #[default]
+    Markdown,
+    /// Just the alt text (`Yes`, `No`); icons without one fall back to
+    /// `Symbol`
+    AltText,
+    /// ✓, ✗ or — for icons whose alt text, title, class or file name reads as
+    /// check, cross or dash; other icons fall back to their alt text
+    Symbol,
+}
+
+/// Alignment of a table column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TextAlign {
+    /// `:---`
+    #[default]
+    Left,
+    /// `:---:`
+    Center,
+    /// `---:`
+    Right,
+}
+
+/// Reply history containers of Gmail, Yahoo Mail and Outlook.
+fn default_email_quote_selectors() -> Vec<String> {
+    [".gmail_quote", ".yahoo_quoted", "#divRplyFwdMsg"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+/// Alignment classes of common CSS frameworks.
+fn default_alignment_classes() -> Vec<(String, TextAlign)> {
+    [
+        // Bootstrap 4 and Tailwind
+        ("text-left", TextAlign::Left),
+        ("text-center", TextAlign::Center),
+        ("text-right", TextAlign::Right),
+        // Bootstrap 5 logical names
+        ("text-start", TextAlign::Left),
+        ("text-end", TextAlign::Right),
+        // Bulma
+        ("has-text-left", TextAlign::Left),
+        ("has-text-centered", TextAlign::Center),
+        ("has-text-right", TextAlign::Right),
+    ]
+    .into_iter()
+    .map(|(class, align)| (class.to_string(), align))
+    .collect()
+}
+
+/// Rendering of `<select>` and `<datalist>` elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SelectHandling {
+    /// Emit the option texts as inline text, like any other element
+    #[default]
+    Text,
+    /// A bullet list of the options, with `<optgroup>` labels as nested
+    /// list headers and the selected option in bold. Disabled options and
+    /// placeholders with an empty value are left out.
+    List,
+}
+
+/// Format of a `<meter>` or `<progress>` value written from its attributes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GaugeStyle {
+    /// Value over maximum: `70/100`, `3/5`
+    #[default]
+    Fraction,
+    /// Share of the range: `70%`, `60%`
+    Percent,
+}
+
+/// Strategy for repeated headings when `dedupe_headings` is enabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum HeadingDedupe {
+    /// Append ` (2)`, ` (3)`, ... to later headings anywhere in the document
+    #[default]
+    Suffix,
+    /// Drop a heading that repeats the previous sibling heading, and its
+    /// whole section when the content is identical too
+    Merge,
+}
+
+/// Settings for `Options::number_headings`.
+///
+/// Headings are numbered by nesting, so a skipped level adds no part: an
+/// h4 directly under the h2 numbered 1 is 1.1. Headings above `start_level`
+/// restart the numbering, and headings inside blockquotes or already
+/// starting with a number like `2.` or `1.4` are left alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct NumberingOptions {
+    /// Shallowest heading level that is numbered.
+    /// Default: 2
+    pub start_level: u8,
+
+    /// Text between the parts of a number.
+    /// Default: "."
+    pub separator: String,
+}
+
+impl Default for NumberingOptions {
+    fn default() -> Self {
+        Self {
+            start_level: 2,
+            separator: ".".to_string(),
+        }
+    }
+}
+
+/// Wiki link output for [`Options::wiki_links`].
+///
+/// A link is internal when its resolved URL starts with one of
+/// `internal_prefixes`, or, without a `base_url`, when it is relative. The
+/// page name is the rest of the URL without its query, file extension and
+/// trailing slash, percent-decoded; a fragment is kept as `[[page#heading]]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WikiLinkOptions {
+    /// URL prefixes of internal pages, like `https://wiki.example.com/pages/`.
+    /// Default: []
+    pub internal_prefixes: Vec<String>,
+
+    /// Keep link text that differs from the page name as an alias:
+    /// `[[page-slug|Anchor text]]`.
+    /// Default: true
+    pub use_text_as_alias: bool,
+}
+
+impl Default for WikiLinkOptions {
+    fn default() -> Self {
+        Self {
+            internal_prefixes: Vec::new(),
+            use_text_as_alias: true,
+        }
+    }
+}
+
+/// Rendering of ordered lists with a letter or roman numeral `type`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum OlTypeHandling {
+    /// Plain markdown numbers: `1. `, `2. `
+    #[default]
+    Numbers,
+    /// Keep the list and add the marker as text: `3. (c) item`, `1. (iv) item`
+    LettersAsText,
+    /// Pass the `<ol>` through as raw HTML
+    Html,
+}
+
+/// Spacing of list items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ListSpacing {
+    /// Loose (items separated by blank lines) when any item holds more than
+    /// one block, like `<li><p>a</p><p>b</p></li>`; tight otherwise
+    #[default]
+    Auto,
+    /// No blank lines between items, nor before nested lists. Blocks within
+    /// an item keep theirs, as paragraphs would otherwise merge
+    Tight,
+    /// A blank line between every pair of items
+    Loose,
+}
+
+/// Placement of a code block's file name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CodeFilename {
+    /// Leave the file name out
+    #[default]
+    Omit,
+    /// In the info string: ```` ```rust title="main.rs" ````
+    InfoString,
+    /// As a comment opening the code, `// main.rs`, for languages whose
+    /// comment syntax is known
+    Comment,
+}
+
+/// Contents of a code fence's info string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FenceInfo {
+    /// Just the language: ```` ```rust ````
+    #[default]
+    LanguageOnly,
+    /// The language followed by the block's attribute classes, file name
+    /// and line numbering: ```` ```rust,should_panic title="main.rs" linenums ````
+    Full,
+}
+
+/// What is known about a code block, passed to
+/// `Options::fence_info_formatter`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CodeBlockInfo {
+    /// Detected language, like `rust`.
+    pub language: Option<String>,
+    /// File name from `data-filename`, `data-file` or a class naming a file.
+    pub filename: Option<String>,
+    /// Whether a line number gutter was stripped from the code.
+    pub line_numbers: bool,
+    /// Attribute classes listed after the language class, like
+    /// `should_panic` in `class="language-rust should_panic"`.
+    pub attributes: Vec<String>,
+}
+
+impl CodeBlockInfo {
+    /// The default Full info string: the language, then the attributes
+    /// joined by commas, then `title="..."` and `linenums` when they apply.
+    /// A block with details but no language is labelled `text`.
+    pub fn to_info_string(&self) -> String {
+        let mut info = self.language.clone().unwrap_or_default();
+        let has_details =
+            !self.attributes.is_empty() || self.filename.is_some() || self.line_numbers;
+        if info.is_empty() && has_details {
+            info.push_str("text");
+        }
+        for attribute in &self.attributes {
+            info.push(',');
+            info.push_str(attribute);
+        }
+        if let Some(filename) = &self.filename {
+            info.push_str(&format!(" title=\"{}\"", filename));
+        }
+        if self.line_numbers {
+            info.push_str(" linenums");
+        }
+        info
+    }
+}
+
+/// Signature of a fence info formatter: a code block's details in, its
+/// info string out.
+pub type FenceInfoFn = dyn Fn(&CodeBlockInfo) -> String + Send + Sync;
+
+/// A shared fence info formatter, so `Options` stays cheap to clone.
+#[derive(Clone)]
+pub struct FenceInfoFormatter(pub Arc<FenceInfoFn>);
+
+impl fmt::Debug for FenceInfoFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FenceInfoFormatter(..)")
+    }
+}
+
+/// Handling of blockquotes nested deeper than `Options::max_blockquote_depth`,
+/// such as the quoted history of an email reply chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BlockquoteOverflow {
+    /// Keep the content, quoted at the maximum depth
+    #[default]
+    Flatten,
+    /// Replace the content with a `[…]` marker
+    Truncate,
+}
+
+/// Output format for `<details>` elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DetailsStyle {
+    /// A blockquote led by the summary in bold: `> **Summary**`
+    #[default]
+    Blockquote,
+    /// `<details><summary>…</summary>` HTML around the converted content,
+    /// keeping `open`, which GitHub renders as a collapsible section
+    HtmlDetails,
+}
+
+/// Rendering of `<aside>` and `<dialog>` content set apart from the page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AsideHandling {
+    /// Content flows in with the surrounding text
+    #[default]
+    Inline,
+    /// A `>` blockquote
+    Blockquote,
+    /// A block led by a bold label from `aria-label`, or else from the
+    /// element's leading heading, which it replaces: `**Related articles**`
+    Labeled,
+    /// Dropped, like an excluded element, so `include_selectors` can still
+    /// keep it or parts of it
+    Skip,
+}
+
+/// Rendering of breadcrumb navigation trails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BreadcrumbHandling {
+    /// Converted like any other list
+    #[default]
+    List,
+    /// One line of steps, `Home > Docs > API`, with links kept. The first
+    /// trail goes before the page's first heading even when it comes after
+    /// it in the HTML
+    Inline,
+    /// Dropped, like an excluded element, so `include_selectors` can still
+    /// keep it or parts of it
+    Skip,
+}
+
+/// Policy for images whose source is a `data:` URI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DataUriImages {
+    /// Keep the image with its full data URI
+    #[default]
+    Keep,
+    /// Remove the image
+    Drop,
+    /// Keep data URIs up to `max_len` bytes; longer ones become
+    /// `![alt](data-image-omitted)`
+    Placeholder {
+        /// Longest data URI kept verbatim, in bytes
+        max_len: usize,
+    },
+}
+
+/// Escaping of `<` in text that a markdown renderer would read as raw HTML.
+///
+/// Only `<` followed by an ASCII letter, `/` or `!` is escaped, so prose
+/// like `a < b` is left alone. Code spans and blocks are never escaped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EscapeMode {
+    /// Write the entity: `Vec&lt;T>`
+    #[default]
+    Entities,
+    /// Backslash-escape for CommonMark renderers: `Vec\<T>`
+    Backslash,
+    /// Leave text as decoded
+    None,
+}
+
+/// Rendering of links that wrap block content, like card links
+/// `<a href="/post"><h2>Title</h2><p>Excerpt</p></a>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BlockLinkStyle {
+    /// Keep the blocks and add a `[Read more](url)` line after them
+    #[default]
+    ReadMore,
+    /// Link the first heading's text: `## [Title](url)`; falls back to
+    /// `ReadMore` when the content has no heading
+    HeadingLink,
+}
+
+/// A conversion that can be turned off with `Options::disable_rules`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DisabledRule {
+    /// `<h1>` to `<h6>`
+    Headings,
+    /// `<pre>` code blocks
+    CodeBlocks,
+    /// `<blockquote>`
+    Blockquotes,
+    /// `<ul>`, `<ol>` and `<li>`
+    Lists,
+    /// `<dl>`, `<dt>` and `<dd>`
+    DefinitionLists,
+    /// `<table>`
+    Tables,
+    /// `<hr>`
+    HorizontalRules,
+    /// `<details>` and `<summary>`
+    Details,
+    /// `<figure>`
+    Figures,
+    /// `<a>`
+    Links,
+    /// `<strong>`, `<b>`, `<em>` and `<i>`
+    Emphasis,
+    /// `<del>`, `<s>` and `<strike>`
+    Strikethrough,
+    /// Inline `<code>`
+    InlineCode,
+    /// `<sup>` and `<sub>`
+    SuperscriptSubscript,
+    /// `<select>` and `<datalist>`
+    Select,
+    /// `<meter>` and `<progress>`
+    Gauges,
+    /// `<kbd>`, `<mark>`, `<abbr>`, `<samp>` and `<var>` passed through as HTML
+    Passthrough,
+}
+
+impl DisabledRule {
+    /// Tags whose rules this switch removes.
+    pub(crate) fn tags(self) -> &'static [&'static str] {
+        match self {
+            DisabledRule::Headings => &["h1", "h2", "h3", "h4", "h5", "h6"],
+            DisabledRule::CodeBlocks => &["pre"],
+            DisabledRule::Blockquotes => &["blockquote"],
+            DisabledRule::Lists => &["ul", "ol", "li"],
+            DisabledRule::DefinitionLists => &["dl", "dt", "dd"],
+            DisabledRule::Tables => &["table", "tr"],
+            DisabledRule::HorizontalRules => &["hr"],
+            DisabledRule::Details => &["details", "summary"],
+            DisabledRule::Figures => &["figure"],
+            DisabledRule::Links => &["a"],
+            DisabledRule::Emphasis => &["strong", "b", "em", "i"],
+            DisabledRule::Strikethrough => &["del", "s", "strike"],
+            DisabledRule::InlineCode => &["code"],
+            DisabledRule::SuperscriptSubscript => &["sup", "sub"],
+            DisabledRule::Select => &["select", "datalist"],
+            DisabledRule::Gauges => &["meter", "progress"],
+            DisabledRule::Passthrough => &["kbd", "mark", "abbr", "samp", "var"],
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,11 +1683,79 @@ mod tests {
         let opts = Options::default();
         assert!(opts.exclude_selectors.is_empty());
         assert!(opts.include_selectors.is_empty());
+        assert!(opts.exclude_text_patterns.is_empty());
+        assert_eq!(opts.exclude_text_max_len, 200);
+        assert!(opts.capture_attributes.is_empty());
+        assert!(!opts.email_mode);
+        assert_eq!(
+            opts.email_quote_selectors,
+            [".gmail_quote", ".yahoo_quoted", "#divRplyFwdMsg"]
+        );
         assert_eq!(opts.heading_style, HeadingStyle::Atx);
         assert_eq!(opts.code_fence, '`');
+        assert_eq!(opts.code_filename, CodeFilename::Omit);
         assert_eq!(opts.link_style, LinkStyle::Inline);
+        assert_eq!(opts.reference_sort, ReferenceSort::UseOrder);
+        assert_eq!(opts.image_style, ImageStyle::Inline);
+        assert_eq!(opts.strikethrough_style, StrikethroughStyle::DoubleTilde);
+        assert_eq!(opts.underline_style, UnderlineStyle::Html);
+        assert_eq!(opts.abbr_style, AbbrStyle::Html);
+        assert!(!opts.drop_small);
         assert_eq!(opts.bullet_marker, '-');
+        assert!(opts.bullet_cycle.is_none());
         assert!(opts.base_url.is_none());
+        assert!(opts.url_rewriter.is_none());
+        assert!(opts.alt_text_provider.is_none());
+        assert_eq!(opts.svg_handling, SvgHandling::Auto);
+        assert_eq!(opts.table_style, TableStyle::Pipes);
+        assert_eq!(opts.row_header_style, RowHeaderStyle::EmptyHeader);
+        assert_eq!(opts.table_caption, TableCaption::After);
+        assert_eq!(opts.table_image_cells, TableImageCells::Markdown);
+        assert!(!opts.table_width_hints);
+        assert_eq!(opts.single_row_tables, SingleRowTables::Table);
+        assert!(opts
+            .alignment_classes
+            .contains(&("has-text-centered".to_string(), TextAlign::Center)));
+        assert_eq!(opts.select_handling, SelectHandling::Text);
+        assert_eq!(opts.gauge_style, GaugeStyle::Fraction);
+        assert!(!opts.bold_gauges);
+        assert!(!opts.inline_tooltips);
+        assert!(!opts.dedupe_headings);
+        assert_eq!(opts.heading_dedupe, HeadingDedupe::Suffix);
+        assert!(opts.dedupe_repeated_blocks.is_none());
+        assert_eq!(opts.dedupe_block_max_len, 300);
+        assert!(opts.promote_bold_headings.is_none());
+        assert!(opts.number_headings.is_none());
+        assert!(opts.remove_empty_links);
+        assert!(!opts.require_alt);
+        assert!(!opts.inject_og_image);
+        assert!(!opts.extract_json_ld);
+        assert!(!opts.merge_adjacent_links);
+        assert_eq!(opts.ol_type_handling, OlTypeHandling::Numbers);
+        assert_eq!(opts.list_spacing, ListSpacing::Auto);
+        assert!(!opts.pad_ordered_markers);
+        assert!(opts.max_blockquote_depth.is_none());
+        assert_eq!(opts.blockquote_overflow, BlockquoteOverflow::Flatten);
+        assert_eq!(opts.details_style, DetailsStyle::Blockquote);
+        assert_eq!(opts.aside_handling, AsideHandling::Inline);
+        assert_eq!(opts.dialog_handling, AsideHandling::Skip);
+        assert_eq!(opts.breadcrumbs, BreadcrumbHandling::List);
+        assert_eq!(opts.data_uri_images, DataUriImages::Keep);
+        assert_eq!(opts.escape_mode, EscapeMode::Entities);
+        assert!(opts.allow_raw_html);
+        assert!(opts.max_input_bytes.is_none());
+        assert!(opts.max_elements.is_none());
+        assert_eq!(opts.max_entity_expansion_ratio, 1.0);
+        assert!(opts.deadline.is_none());
+        assert_eq!(opts.block_link_style, BlockLinkStyle::ReadMore);
+        assert!(opts.collapse_double_br);
+        assert!(opts.strip_control_chars);
+        assert!(opts.trailing_newline);
+        assert!(opts.trim_document);
+        assert!(!opts.compact);
+        assert!(opts.wiki_links.is_none());
+        assert!(opts.disable_rules.is_empty());
+        assert!(!opts.validate_output);
     }
 
     #[test]
@@ -146,4 +1773,86 @@ mod tests {
         assert_eq!(opts.code_fence, '~');
         assert_eq!(opts.base_url, Some("https://example.com".to_string()));
     }
+
+    #[test]
+    fn test_validate_bullet_cycle() {
+        assert!(Options::default().validate().is_ok());
+        assert!(Options::new()
+            .bullet_cycle(Some(vec!['-', '*', '+']))
+            .validate()
+            .is_ok());
+
+        let err = Options::new()
+            .bullet_cycle(Some(vec!['-', '#']))
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid bullet_cycle: invalid marker '#', expected '-', '*' or '+'"
+        );
+        assert!(Options::new()
+            .bullet_cycle(Some(vec![]))
+            .validate()
+            .is_err());
+    }
+
+    #[cfg(feature = "regex-filters")]
+    #[test]
+    fn test_validate_text_pattern_regex() {
+        let valid = Options::new().exclude_text_patterns(vec![
+            "/share on (twitter|x)/".to_string(),
+            "(not a regex".to_string(),
+        ]);
+        assert!(valid.validate().is_ok());
+
+        let err = Options::new()
+            .exclude_text_patterns(vec!["/(unclosed/".to_string()])
+            .validate()
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid exclude_text_patterns: invalid regex '/(unclosed/'"));
+    }
+
+    #[test]
+    fn test_bullet_for_depth() {
+        let opts = Options::new().bullet_cycle(Some(vec!['-', '*']));
+        assert_eq!(opts.bullet_for_depth(0), '-');
+        assert_eq!(opts.bullet_for_depth(1), '*');
+        assert_eq!(opts.bullet_for_depth(2), '-');
+
+        // Invalid cycles fall back to the single marker
+        let opts = Options::new()
+            .bullet_marker('+')
+            .bullet_cycle(Some(vec!['x']));
+        assert_eq!(opts.bullet_for_depth(1), '+');
+    }
+
+    #[cfg(feature = "regex-filters")]
+    #[test]
+    fn test_bullet_cycle_with_invalid_regex() {
+        let opts = Options::new()
+            .bullet_cycle(Some(vec!['*', '+']))
+            .exclude_text_patterns(vec!["/(/".to_string()]);
+        assert!(opts.validate().is_err());
+        let markdown = crate::convert_with_options("<ul><li>a<ul><li>b</li></ul></li></ul>", &opts);
+        assert!(markdown.starts_with("* a\n"), "{}", markdown);
+        assert!(markdown.ends_with("\n  + b\n"), "{}", markdown);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_partial_json() {
+        let opts: Options = serde_json::from_str(
+            r#"{"heading_style": "setext", "exclude_selectors": ["nav"], "svg_handling": "title_text"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(opts.heading_style, HeadingStyle::Setext);
+        assert_eq!(opts.exclude_selectors, vec!["nav".to_string()]);
+        assert_eq!(opts.svg_handling, SvgHandling::TitleText);
+        // Unspecified fields keep their defaults
+        assert_eq!(opts.bullet_marker, '-');
+        assert_eq!(opts.link_style, LinkStyle::Inline);
+    }
 }
@@ -0,0 +1,135 @@
+//! Structural outline of a document, for debugging selector configurations.
+//!
+//! The outline lists every element with the rule that would convert it and
+//! its skip/keep state, so it is easy to see which exclude selector removed
+//! content that was expected in the markdown.
+
+use std::fmt::Write;
+
+use scraper::{ElementRef, Html};
+
+use crate::precompute::{CompiledSelectors, ExcludeMatch, MetadataMap};
+use crate::rules::{find_rule, Rule};
+use crate::whitespace::normalize_block_whitespace;
+
+/// Characters of element text shown per line.
+const TEXT_PREVIEW_CHARS: usize = 40;
+
+/// Render the outline of a parsed document, two spaces of indent per level.
+///
+/// Each line reads `tag#id.class -> Rule [state] "text"`; the rule, state
+/// and text parts are left out when they don't apply.
+pub(crate) fn render_outline(
+    dom: &Html,
+    metadata: &MetadataMap,
+    selectors: &CompiledSelectors,
+    rules: &[Box<dyn Rule>],
+) -> String {
+    let mut out = String::new();
+    outline_element(dom.root_element(), 0, metadata, selectors, rules, &mut out);
+    out
+}
+
+fn outline_element(
+    element: ElementRef,
+    depth: usize,
+    metadata: &MetadataMap,
+    selectors: &CompiledSelectors,
+    rules: &[Box<dyn Rule>],
+    out: &mut String,
+) {
+    let tag = element.value().name();
+    out.push_str(&"  ".repeat(depth));
+    out.push_str(&element_label(&element));
+
+    if let Some(rule) = find_rule(rules, tag) {
+        let _ = write!(out, " -> {}", rule.name());
+    }
+
+    if let Some(meta) = metadata.get(&element.id()) {
+        if meta.force_keep {
+            match selectors.first_include_match(&element) {
+                Some(selector) => {
+                    let _ = write!(out, " [kept by \"{}\"]", selector);
+                }
+                None => out.push_str(" [kept]"),
+            }
+        } else if meta.skip {
+            match selectors.first_exclude_match(&element) {
+                Some(selector) => {
+                    let _ = write!(out, " [skipped by \"{}\"]", selector);
+                }
+                None => match meta.exclude_match {
+                    Some(ExcludeMatch::Handling(option)) => {
+                        let _ = write!(out, " [skipped by {}]", option);
+                    }
+                    _ => out.push_str(" [skipped: inside excluded element]"),
+                },
+            }
+        }
+    }
+
+    let text: String = element.text().collect();
+    let text = normalize_block_whitespace(&text);
+    let text = text.trim();
+    if !text.is_empty() {
+        let preview: String = text.chars().take(TEXT_PREVIEW_CHARS).collect();
+        let ellipsis = if preview.len() < text.len() {
+            "…"
+        } else {
+            ""
+        };
+        let _ = write!(out, " \"{}{}\"", preview, ellipsis);
+    }
+    out.push('\n');
+
+    for child in element.children().filter_map(ElementRef::wrap) {
+        outline_element(child, depth + 1, metadata, selectors, rules, out);
+    }
+}
+
+/// CSS-like label for an element: `div#main.content.wide`.
+pub(crate) fn element_label(element: &ElementRef) -> String {
+    let mut label = element.value().name().to_string();
+    if let Some(id) = element.value().id() {
+        label.push('#');
+        label.push_str(id);
+    }
+    for class in element.value().classes() {
+        label.push('.');
+        label.push_str(class);
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{convert_outline, Options};
+
+    #[test]
+    fn test_outline_lists_rules_and_text() {
+        let outline = convert_outline("<p>Hello <em>world</em></p>", &Options::default());
+        assert!(outline.starts_with("html \"Hello world\"\n  head\n  body \"Hello world\"\n"));
+        assert!(outline.contains("\n    p -> ParagraphRule \"Hello world\"\n"));
+        assert!(outline.contains("\n      em -> EmphasisRule \"world\"\n"));
+    }
+
+    #[test]
+    fn test_outline_truncates_text() {
+        let text = "a".repeat(50);
+        let outline = convert_outline(&format!("<p>{}</p>", text), &Options::default());
+        assert!(outline.contains(&format!("p -> ParagraphRule \"{}…\"", "a".repeat(40))));
+    }
+
+    #[test]
+    fn test_outline_skip_annotations() {
+        let options = Options::new().exclude_selectors(vec!["nav".to_string()]);
+        let outline = convert_outline(
+            "<nav id='top' class='menu'><a href='/'>Home</a></nav><p>Body</p>",
+            &options,
+        );
+        assert!(outline.contains("nav#top.menu [skipped by \"nav\"] \"Home\""));
+        assert!(outline.contains("a -> LinkRule [skipped: inside excluded element] \"Home\""));
+        assert!(outline.contains("p -> ParagraphRule \"Body\""));
+    }
+}
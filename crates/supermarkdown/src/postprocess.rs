@@ -1,170 +1,967 @@
 //! Post-processing for markdown output.
 
+use std::borrow::Cow;
+
 use once_cell::sync::Lazy;
 use regex::Regex;
 use rustc_hash::FxHashMap;
 
-use crate::options::{LinkStyle, Options};
-
-/// Regex for collapsing excessive newlines.
-static EXCESSIVE_NEWLINES_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n{3,}").unwrap());
+use crate::headings::{dedupe_headings, normalize_heading_lines, number_headings};
+use crate::options::{ImageStyle, LinkStyle, Options, ReferenceSort};
+use crate::sourcemap::{is_marker, unmatched_markers};
+use crate::url::url_domain;
 
 /// Regex for matching inline links (not images).
 /// Matches [text](url) or [text](url "title") but not ![alt](src)
 /// Uses a capture group to detect if preceded by ! (for images)
-static INLINE_LINK_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r#"(^|[^!])\[([^\]]+)\]\(([^)\s]+)(?:\s+"([^"]*)")?\)"#).unwrap());
+///
+/// Link text may hold one level of brackets, so a linked image
+/// `[![alt](src)](url)` is matched as the outer link.
+static INLINE_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(^|[^!])\[((?:\\.|[^\\\[\]]|\[(?:\\.|[^\\\[\]])*\])+)\]\(([^)\s]+)(?:\s+"([^"]*)")?\)"#,
+    )
+    .unwrap()
+});
+
+/// Regex for matching inline images.
+/// Capture groups: 1=alt, 2=src, 3=title
+static INLINE_IMAGE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"!\[((?:\\.|[^\\\[\]])*)\]\(([^)\s]+)(?:\s+"([^"]*)")?\)"#).unwrap());
 
-/// Post-process the markdown output.
-pub fn postprocess(markdown: String, options: &Options) -> String {
+/// Regex for inline links and images without nested brackets in their text.
+/// Capture groups: 1=`!` for images, 2=text
+static SIMPLE_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(!?)\[([^\[\]]*)\]\([^)\s]*(?:\s+"[^"]*")?\)"#).unwrap());
+
+/// Post-process the markdown output into `out`, reusing its capacity.
+///
+/// Passes that find nothing to change hand their input back rather than
+/// copying it, and the final whitespace passes write straight into `out`.
+/// `marked` is set when the markdown carries source map markers.
+pub fn postprocess_into(markdown: String, options: &Options, marked: bool, out: &mut String) {
     let mut result = markdown;
 
-    // 1. Escape newlines in link text [text\nmore](url) → [text\\nmore](url)
-    result = escape_link_newlines(&result);
+    // 1. Strip control characters, which break JSON and database consumers
+    if options.strip_control_chars && has_control_chars(&result) {
+        result = strip_control_chars(&result);
+    }
+
+    // 2. Escape newlines in link text [text\nmore](url) → [text\\nmore](url)
+    result = escape_link_newlines(result);
+
+    // 3. Drop links and images left without visible text
+    if options.remove_empty_links || options.require_alt {
+        result = remove_empty_links(&result, options, marked);
+    }
+
+    // 4. Merge back-to-back links to the same URL
+    if options.merge_adjacent_links && result.contains("](") {
+        result = merge_adjacent_links(&result);
+    }
+
+    // 5. Drop copies of short blocks repeated word for word
+    if let Some(max) = options.dedupe_repeated_blocks {
+        let max_len = options.dedupe_block_max_len;
+        result = dedupe_repeated_blocks(&result, max.max(1), max_len, marked);
+    }
+
+    // 6. Drop horizontal rules left separating nothing, before reference
+    // definitions are appended
+    if options.compact && result.contains("---") {
+        result = drop_redundant_rules(&result);
+    }
+
+    // 7. Convert to referenced links and images if requested
+    if options.link_style == LinkStyle::Referenced || options.image_style == ImageStyle::Referenced
+    {
+        result = convert_to_references(result, options);
+    }
+
+    // 8. Put headings on their own lines, apart from list markers
+    if result.contains('#') {
+        result = normalize_heading_lines(&result);
+    }
+
+    // 9. Deduplicate repeated headings
+    if options.dedupe_headings {
+        result = dedupe_headings(&result, options.heading_dedupe);
+    }
+
+    // 10. Number headings
+    if let Some(numbering) = &options.number_headings {
+        result = number_headings(&result, numbering);
+    }
+
+    // 11. Collapse 3+ newlines to 2 and 12. trim trailing whitespace per line
+    out.clear();
+    out.reserve(result.len() + 1);
+    collapse_and_trim_lines(&result, out);
+
+    // 13. Trim document, ending it with one newline if requested
+    finish_document(out, options);
+}
+
+/// Drop copies of a paragraph or blockquote after the first `max` with
+/// the same text, ignoring blocks longer than `max_len` characters.
+///
+/// Blocks are runs of lines between blank lines, and only those made of
+/// plain text or quoted text are counted, so headings, lists, tables and
+/// anything touching a code fence stay. With `marked`, source map markers
+/// are ignored when comparing, and those of enclosing blocks are moved off
+/// dropped copies.
+fn dedupe_repeated_blocks(markdown: &str, max: usize, max_len: usize, marked: bool) -> String {
+    let lines: Vec<&str> = markdown.split('\n').collect();
+    let mut fence = FenceState::default();
+    let in_code: Vec<bool> = lines.iter().map(|line| fence.update(line)).collect();
+
+    let mut seen: FxHashMap<String, usize> = FxHashMap::default();
+    let mut kept: Vec<String> = Vec::with_capacity(lines.len());
+    // Source map markers of enclosing blocks that started in a dropped block
+    let mut carried_starts = String::new();
+    let mut start = 0;
+
+    while start < lines.len() {
+        if lines[start].trim().is_empty() {
+            kept.push(lines[start].to_string());
+            start += 1;
+            continue;
+        }
+        let end = start
+            + lines[start..]
+                .iter()
+                .take_while(|line| !line.trim().is_empty())
+                .count();
+        let block = &lines[start..end];
+
+        if !in_code[start..end].contains(&true)
+            && block.iter().all(|line| is_plain_line(line, marked))
+        {
+            let text = block_text(block, marked);
+            if !text.is_empty() && text.chars().count() <= max_len {
+                let count = seen.entry(text).or_default();
+                *count += 1;
+                if *count > max {
+                    if marked {
+                        let (starts, ends) = unmatched_markers(&block.join("\n"));
+                        carried_starts.push_str(&starts);
+                        if let Some(last) = kept.iter_mut().rev().find(|l| !l.trim().is_empty()) {
+                            last.push_str(&ends);
+                        }
+                    }
+                    start = end;
+                    continue;
+                }
+            }
+        }
 
-    // 2. Convert to referenced links if requested
-    if matches!(options.link_style, LinkStyle::Referenced) {
-        result = convert_to_referenced_links(&result);
+        for (i, line) in block.iter().enumerate() {
+            let mut line = line.to_string();
+            if i == 0 && !carried_starts.is_empty() {
+                let indent = line.len() - line.trim_start().len();
+                line.insert_str(indent, &std::mem::take(&mut carried_starts));
+            }
+            kept.push(line);
+        }
+        start = end;
     }
 
-    // 3. Collapse 3+ newlines to 2
-    result = EXCESSIVE_NEWLINES_RE
-        .replace_all(&result, "\n\n")
-        .into_owned();
+    kept.join("\n")
+}
 
-    // 4. Trim trailing whitespace per line
-    result = trim_trailing_whitespace(&result);
+/// Whether a line is paragraph or blockquote text: not indented, and not a
+/// heading, list item, table row, rule or setext underline.
+fn is_plain_line(line: &str, marked: bool) -> bool {
+    let marker = |c: char| marked && is_marker(c);
+    let mut text = line.trim_start_matches(marker);
+    if text.starts_with(char::is_whitespace) {
+        return false;
+    }
+    while let Some(rest) = text.strip_prefix('>') {
+        text = rest.trim_start_matches(|c: char| c.is_whitespace() || marker(c));
+    }
+    let text = text.trim_end_matches(|c: char| c.is_whitespace() || marker(c));
+    let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+    let ordered = digits > 0 && matches!(text.as_bytes().get(digits), Some(b'.' | b')'));
+    let bullet = matches!(
+        text.as_bytes(),
+        [b'-' | b'*' | b'+'] | [b'-' | b'*' | b'+', b' ', ..]
+    );
+    let rule = text.len() >= 3
+        && text
+            .chars()
+            .all(|c| matches!(c, '-' | '=' | '*' | '_' | ' '));
+    !(text.starts_with('#') || text.starts_with('|') || ordered || bullet || rule)
+}
 
-    // 5. Trim document
-    result.trim().to_string()
+/// The text of a block for comparison, without the whitespace around each
+/// line or, with `marked`, source map markers.
+fn block_text(block: &[&str], marked: bool) -> String {
+    block
+        .iter()
+        .map(|line| {
+            let line = if marked {
+                line.replace(is_marker, "")
+            } else {
+                line.to_string()
+            };
+            line.trim().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-/// Convert inline links to referenced style.
-/// [text](url) → [text][1] with [1]: url at document end
-fn convert_to_referenced_links(markdown: &str) -> String {
-    let mut url_to_ref: FxHashMap<String, usize> = FxHashMap::default();
-    let mut references: Vec<(usize, String, Option<String>)> = Vec::new();
-    let mut ref_counter = 0;
+/// Drop horizontal rules at either end of the document or right after
+/// another rule, as left behind when the content between them was excluded.
+///
+/// Only a `---` line after a blank line is a rule; right under text it
+/// underlines a setext heading.
+fn drop_redundant_rules(markdown: &str) -> String {
+    let mut fence = FenceState::default();
+    let mut kept: Vec<&str> = Vec::new();
+    // Index in `kept` of a rule with only blank lines after it so far
+    let mut trailing_rule: Option<usize> = None;
+    let mut seen_text = false;
+    let mut after_blank = true;
 
-    // Replace inline links with reference-style
-    // Capture groups: 1=prefix (empty or non-!), 2=text, 3=url, 4=title
-    let result = INLINE_LINK_RE.replace_all(markdown, |caps: &regex::Captures| {
-        let prefix = &caps[1]; // Character before [ (or empty at start)
-        let text = &caps[2];
-        let url = &caps[3];
-        let title = caps.get(4).map(|m| m.as_str().to_string());
+    for line in markdown.split('\n') {
+        let in_code = fence.update(line);
+        let blank = line.trim().is_empty();
+        if !in_code && after_blank && line == "---" {
+            if !seen_text || trailing_rule.is_some() {
+                continue;
+            }
+            trailing_rule = Some(kept.len());
+        } else if !blank {
+            seen_text = true;
+            trailing_rule = None;
+        }
+        after_blank = blank;
+        kept.push(line);
+    }
+
+    if let Some(rule) = trailing_rule {
+        kept.truncate(rule);
+    }
+    kept.join("\n")
+}
 
-        // Check if we've seen this URL before (deduplicate)
-        let ref_num = if let Some(&existing_ref) = url_to_ref.get(url) {
-            existing_ref
+/// Write `text` to `out` with runs of 3+ newlines collapsed to 2 and
+/// trailing whitespace trimmed from each line.
+///
+/// Only lines that were empty to begin with count towards a run, so a
+/// whitespace-only line between blank lines still leaves them apart.
+fn collapse_and_trim_lines(text: &str, out: &mut String) {
+    // Empty lines in a row; before any text each one is a whole newline,
+    // after text the first is the newline ending that text
+    let mut empty_run = 0;
+    let mut seen_text = false;
+    for (i, line) in text.lines().enumerate() {
+        if line.is_empty() {
+            empty_run += 1;
+            let limit = if seen_text { 1 } else { 2 };
+            if empty_run > limit {
+                continue;
+            }
         } else {
-            ref_counter += 1;
-            url_to_ref.insert(url.to_string(), ref_counter);
-            references.push((ref_counter, url.to_string(), title));
-            ref_counter
-        };
+            empty_run = 0;
+            seen_text = true;
+        }
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(line.trim_end());
+    }
+}
 
-        format!("{}[{}][{}]", prefix, text, ref_num)
-    });
+/// Trim the ends of the document in place and apply `trailing_newline`.
+fn finish_document(markdown: &mut String, options: &Options) {
+    markdown.truncate(markdown.trim_end().len());
+    if options.trim_document {
+        let leading = markdown.len() - markdown.trim_start().len();
+        markdown.drain(..leading);
+    }
+    if markdown.trim_start().is_empty() {
+        markdown.clear();
+        return;
+    }
+    if options.trailing_newline {
+        markdown.push('\n');
+    }
+}
 
-    // If no links found, return as-is
-    if references.is_empty() {
-        return markdown.to_string();
+/// Convert inline links and images to referenced style, as set by
+/// `link_style` and `image_style`.
+/// [text](url) → [text][1] with [1]: url at document end, and
+/// ![alt](src) → ![alt][img1] with [img1]: src after the link definitions
+///
+/// Definitions are numbered in `reference_sort` order, so the numbers in
+/// the text always count up through the definition block. Compact output
+/// keeps URLs used once inline, where a definition would only add bytes.
+fn convert_to_references(markdown: String, options: &Options) -> String {
+    let sort = options.reference_sort;
+    let repeated_only = options.compact;
+    let mut result = markdown;
+
+    // Images first, so a linked image leaves plain link text behind
+    let mut image_definitions = String::new();
+    if options.image_style == ImageStyle::Referenced {
+        // Capture groups: 1=alt, 2=src, 3=title
+        (result, image_definitions) = to_references(
+            &INLINE_IMAGE_RE,
+            result,
+            (2, 3),
+            sort,
+            repeated_only,
+            "img",
+            |caps, num| format!("![{}][img{}]", &caps[1], num),
+        );
+    }
+
+    let mut link_definitions = String::new();
+    if options.link_style == LinkStyle::Referenced {
+        // Capture groups: 1=prefix (empty or non-!), 2=text, 3=url, 4=title
+        (result, link_definitions) = to_references(
+            &INLINE_LINK_RE,
+            result,
+            (3, 4),
+            sort,
+            repeated_only,
+            "",
+            |caps, num| {
+                // Character before [ (or empty at start)
+                format!("{}[{}][{}]", &caps[1], &caps[2], num)
+            },
+        );
+    }
+
+    if link_definitions.is_empty() && image_definitions.is_empty() {
+        return result;
     }
 
     // Append reference definitions at end
-    let mut output = result.into_owned();
-    output.push_str("\n\n");
+    result.push_str("\n\n");
+    result.push_str(&link_definitions);
+    result.push_str(&image_definitions);
+    result
+}
 
-    for (num, url, title) in references {
-        match title {
-            Some(t) => output.push_str(&format!("[{}]: {} \"{}\"\n", num, url, t)),
-            None => output.push_str(&format!("[{}]: {}\n", num, url)),
+/// Replace each match of `re` with `replace(caps, number)`, numbering
+/// unique URLs from the `(url, title)` capture groups, and return the
+/// text with the definition lines for it.
+///
+/// A URL's first title is used for its definition. With `repeated_only`,
+/// matches of URLs used once are left as they are.
+fn to_references(
+    re: &Regex,
+    markdown: String,
+    (url, title): (usize, usize),
+    sort: ReferenceSort,
+    repeated_only: bool,
+    label_prefix: &str,
+    replace: impl Fn(&regex::Captures, usize) -> String,
+) -> (String, String) {
+    // Matched once and reused for the replacement pass
+    let matches: Vec<regex::Captures> = re.captures_iter(&markdown).collect();
+    let references = references(&matches, url, title, sort, repeated_only);
+
+    // If nothing found, return as-is
+    if references.is_empty() {
+        return (markdown, String::new());
+    }
+
+    let url_to_ref: FxHashMap<&str, usize> = references
+        .iter()
+        .enumerate()
+        .map(|(i, (url, _))| (*url, i + 1))
+        .collect();
+    let mut result = String::with_capacity(markdown.len());
+    let mut last = 0;
+    for caps in &matches {
+        let m = caps.get(0).unwrap();
+        result.push_str(&markdown[last..m.start()]);
+        match url_to_ref.get(&caps[url]) {
+            Some(&num) => result.push_str(&replace(caps, num)),
+            None => result.push_str(m.as_str()),
+        }
+        last = m.end();
+    }
+    result.push_str(&markdown[last..]);
+
+    let definitions = definitions(&references, sort, label_prefix);
+    (result, definitions)
+}
+
+/// Unique URLs and their first titles from the `url` and `title` capture
+/// groups of `matches`, in first-use order rearranged by `sort`. With
+/// `repeated_only`, only URLs matched more than once.
+fn references<'a>(
+    matches: &[regex::Captures<'a>],
+    url: usize,
+    title: usize,
+    sort: ReferenceSort,
+    repeated_only: bool,
+) -> Vec<(&'a str, Option<&'a str>)> {
+    let mut references: Vec<(&str, Option<&str>)> = Vec::new();
+    let mut uses: FxHashMap<&str, usize> = FxHashMap::default();
+    for caps in matches {
+        let url = caps.get(url).unwrap().as_str();
+        let count = uses.entry(url).or_default();
+        if *count == 0 {
+            references.push((url, caps.get(title).map(|m| m.as_str())));
         }
+        *count += 1;
+    }
+    if repeated_only {
+        references.retain(|(url, _)| uses[url] > 1);
     }
 
+    // Stable sorts keep first-use order among equal keys
+    match sort {
+        ReferenceSort::UseOrder => {}
+        ReferenceSort::Url => references.sort_by(|a, b| a.0.cmp(b.0)),
+        ReferenceSort::Domain => references.sort_by_cached_key(|(url, _)| url_domain(url)),
+    }
+    references
+}
+
+/// Definition lines `[<prefix>N]: url "title"` for `references`.
+fn definitions(references: &[(&str, Option<&str>)], sort: ReferenceSort, prefix: &str) -> String {
+    let mut output = String::new();
+    let mut last_domain: Option<String> = None;
+    for (i, (url, title)) in references.iter().enumerate() {
+        // Blank line between domain groups
+        if sort == ReferenceSort::Domain {
+            let domain = url_domain(url);
+            if last_domain.as_ref().is_some_and(|last| *last != domain) {
+                output.push('\n');
+            }
+            last_domain = Some(domain);
+        }
+
+        let num = i + 1;
+        match title {
+            Some(t) => output.push_str(&format!("[{}{}]: {} \"{}\"\n", prefix, num, url, t)),
+            None => output.push_str(&format!("[{}{}]: {}\n", prefix, num, url)),
+        }
+    }
     output
 }
 
 /// Escape newlines inside link text, handling escaped brackets correctly.
-fn escape_link_newlines(text: &str) -> String {
-    let mut result = String::with_capacity(text.len());
-    let mut bracket_depth: i32 = 0;
-    let chars: Vec<char> = text.chars().collect();
+///
+/// Returns `text` itself when no link text spans a line.
+fn escape_link_newlines(text: String) -> String {
+    let newlines = link_text_newlines(&text);
+    if newlines.is_empty() {
+        return text;
+    }
+
+    let mut result = String::with_capacity(text.len() + newlines.len());
+    let mut last = 0;
+    for at in newlines {
+        result.push_str(&text[last..at]);
+        result.push_str("\\n");
+        last = at + 1;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+/// Byte offsets of newlines inside link and image text: between a `[` and
+/// the `]` matching it when that is followed by `(`. A blank line ends any
+/// open brackets, so a stray `[` can't reach past its paragraph. Brackets
+/// and newlines are ASCII, so scanning bytes never splits a character.
+fn link_text_newlines(text: &str) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut newlines = Vec::new();
+    // Newlines inside open brackets, in order, and where those opened
+    let mut pending: Vec<usize> = Vec::new();
+    let mut opens: Vec<usize> = Vec::new();
     let mut i = 0;
 
-    while i < chars.len() {
-        let c = chars[i];
-
-        // Check for escaped bracket
-        if c == '\\' && i + 1 < chars.len() {
-            let next = chars[i + 1];
-            if next == '[' || next == ']' {
-                // Escaped bracket - don't change depth, output both chars
-                result.push(c);
-                result.push(next);
-                i += 2;
+    while i < bytes.len() {
+        match bytes[i] {
+            // Escaped bracket - don't change depth
+            b'\\' if matches!(bytes.get(i + 1), Some(b'[' | b']')) => i += 1,
+            b'[' => opens.push(i),
+            b']' => {
+                if let Some(open) = opens.pop() {
+                    let inside = pending.partition_point(|&at| at < open);
+                    if bytes.get(i + 1) == Some(&b'(') {
+                        newlines.extend(pending.drain(inside..));
+                    } else if opens.is_empty() {
+                        pending.clear();
+                    }
+                }
+            }
+            b'\n' if !opens.is_empty() => {
+                if bytes.get(i + 1) == Some(&b'\n') {
+                    opens.clear();
+                    pending.clear();
+                } else {
+                    pending.push(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    // Links inside unlinked brackets are found before their outer link
+    newlines.sort_unstable();
+    newlines
+}
+
+/// Remove empty links (`[](url)`, `[ ](#)`, `[**](x)`) and, with
+/// `require_alt`, images with empty alt text. Code blocks and code spans
+/// are left alone. With `marked`, source map markers of enclosing blocks
+/// move off dropped lines.
+fn remove_empty_links(markdown: &str, options: &Options, marked: bool) -> String {
+    let mut fence = FenceState::default();
+    let mut result = String::with_capacity(markdown.len());
+    // Source map markers of enclosing blocks that started on a dropped line
+    let mut carried_starts = String::new();
+    let mut first = true;
+
+    for line in markdown.split('\n') {
+        let mut line = Cow::Borrowed(line);
+        if !carried_starts.is_empty() && !line.trim().is_empty() {
+            let indent = line.len() - line.trim_start().len();
+            line.to_mut()
+                .insert_str(indent, &std::mem::take(&mut carried_starts));
+        }
+
+        if !fence.update(&line) && line.contains("](") {
+            let cleaned = map_outside_code_spans(&line, |text| strip_empty_links(text, options));
+            // A list item that only held an icon link goes away entirely
+            if cleaned != *line && is_empty_list_item(&cleaned) {
+                if marked {
+                    let (starts, ends) = unmatched_markers(&cleaned);
+                    carried_starts.push_str(&starts);
+                    // The previous line is the end of the result
+                    result.push_str(&ends);
+                }
                 continue;
             }
+            line = Cow::Owned(cleaned);
         }
 
-        match c {
-            '[' => {
-                bracket_depth += 1;
-                result.push(c);
+        if !first {
+            result.push('\n');
+        }
+        first = false;
+        result.push_str(&line);
+    }
+
+    result
+}
+
+/// Remove empty links and images from a run of text outside code spans.
+fn strip_empty_links(text: &str, options: &Options) -> String {
+    let mut current = text.to_string();
+
+    // Removing an image can leave its wrapping link empty: [![](a.png)](x)
+    loop {
+        let mut result = String::with_capacity(current.len());
+        let mut last = 0;
+
+        for caps in SIMPLE_LINK_RE.captures_iter(&current) {
+            let is_image = !caps[1].is_empty();
+            let empty = if is_image {
+                options.require_alt && caps[2].trim().is_empty()
+            } else {
+                options.remove_empty_links && is_blank_link_text(&caps[2])
+            };
+            if !empty {
+                continue;
             }
-            ']' => {
-                bracket_depth = bracket_depth.saturating_sub(1);
-                result.push(c);
+
+            let m = caps.get(0).unwrap();
+            result.push_str(&current[last..m.start()]);
+            last = m.end();
+            // Don't leave a double space where the link was
+            if (result.is_empty() || result.ends_with(' ')) && current[last..].starts_with(' ') {
+                last += 1;
             }
-            '\n' if bracket_depth > 0 => {
-                result.push_str("\\n");
+        }
+
+        if last == 0 {
+            return current;
+        }
+        result.push_str(&current[last..]);
+        current = result;
+    }
+}
+
+/// Whether link text has nothing visible, ignoring emphasis markers.
+fn is_blank_link_text(text: &str) -> bool {
+    text.replace("\\n", "")
+        .chars()
+        .all(|c| c.is_whitespace() || matches!(c, '*' | '_' | '~'))
+}
+
+/// Merge links to the same URL that follow each other on a line, separated
+/// by nothing but spaces. Links on different lines are in different blocks
+/// or list items and stay apart. Code blocks and code spans are left alone.
+fn merge_adjacent_links(markdown: &str) -> String {
+    let mut fence = FenceState::default();
+    let mut result = String::with_capacity(markdown.len());
+    for (i, line) in markdown.split('\n').enumerate() {
+        if i > 0 {
+            result.push('\n');
+        }
+        if fence.update(line) || !line.contains("](") {
+            result.push_str(line);
+        } else {
+            result.push_str(&map_outside_code_spans(line, merge_links_in_text));
+        }
+    }
+    result
+}
+
+/// An inline link: `[text](url "title")`.
+struct InlineLink<'a> {
+    text: &'a str,
+    url: &'a str,
+    /// The title with its leading space and quotes, or ""
+    title: &'a str,
+    /// Byte length of the whole link
+    len: usize,
+}
+
+/// Parse an inline link at the start of `s`. Text may hold nested brackets,
+/// as in an image link `[![alt](src)](url)`.
+fn parse_inline_link(s: &str) -> Option<InlineLink<'_>> {
+    let bytes = s.as_bytes();
+    if bytes.first() != Some(&b'[') {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut i = 0;
+    let text_end = loop {
+        match *bytes.get(i)? {
+            b'\\' => i += 1,
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break i;
+                }
             }
-            _ => result.push(c),
+            _ => {}
         }
         i += 1;
+    };
+
+    let dest = s[text_end + 1..].strip_prefix('(')?;
+    let url_len = dest.find(|c: char| c == ')' || c.is_whitespace())?;
+    let mut title_len = 0;
+    if let Some(title) = dest[url_len..].strip_prefix(" \"") {
+        let mut escaped = false;
+        let close = title.find(|c: char| {
+            let close = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            close
+        })?;
+        title_len = 2 + close + 1;
+    }
+    if dest.as_bytes().get(url_len + title_len) != Some(&b')') {
+        return None;
+    }
+
+    let dest_start = text_end + 2;
+    Some(InlineLink {
+        text: &s[1..text_end],
+        url: &dest[..url_len],
+        title: &dest[url_len..url_len + title_len],
+        len: dest_start + url_len + title_len + 1,
+    })
+}
+
+/// Merge adjacent links in a run of text outside code spans.
+fn merge_links_in_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        let Some(offset) = rest.find('[') else {
+            result.push_str(rest);
+            break;
+        };
+        let start = i + offset;
+        result.push_str(&text[i..start]);
+
+        // Images merge only as part of a link, and escaped brackets are text
+        let prefix = &text[..start];
+        let backslashes = prefix.len() - prefix.trim_end_matches('\\').len();
+        let link = (!prefix.ends_with('!') && backslashes.is_multiple_of(2))
+            .then(|| parse_inline_link(&text[start..]))
+            .flatten();
+        let Some(link) = link else {
+            result.push('[');
+            i = start + 1;
+            continue;
+        };
+
+        let mut parts = vec![link.text.trim()];
+        let mut title = link.title;
+        let mut end = start + link.len;
+        loop {
+            let gap = text[end..].len() - text[end..].trim_start_matches(' ').len();
+            let Some(next) = parse_inline_link(&text[end + gap..]) else {
+                break;
+            };
+            if next.url != link.url {
+                break;
+            }
+            if !parts.contains(&next.text.trim()) {
+                parts.push(next.text.trim());
+            }
+            if title.is_empty() {
+                title = next.title;
+            }
+            end += gap + next.len;
+        }
+
+        if parts.len() == 1 && end == start + link.len {
+            result.push_str(&text[start..end]);
+        } else {
+            result.push_str(&format!("[{}]({}{})", parts.join(" "), link.url, title));
+        }
+        i = end;
     }
 
     result
 }
 
-/// Trim trailing whitespace from each line.
-fn trim_trailing_whitespace(text: &str) -> String {
-    text.lines()
-        .map(|line| line.trim_end())
-        .collect::<Vec<_>>()
-        .join("\n")
+/// Whether a line is a bullet or ordered list marker with no content.
+fn is_empty_list_item(line: &str) -> bool {
+    let item = line.trim_matches(|c: char| c.is_whitespace() || is_marker(c));
+    let marker = item.trim_end_matches(['.', ')']);
+    matches!(item, "-" | "*" | "+")
+        || (marker.len() < item.len()
+            && !marker.is_empty()
+            && marker.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Apply `f` to the parts of a line outside inline code spans.
+pub(crate) fn map_outside_code_spans(line: &str, f: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find('`') {
+        let run = rest[start..].bytes().take_while(|&b| b == b'`').count();
+        let Some(close) = find_backtick_run(&rest[start + run..], run) else {
+            // Unmatched backticks are literal text
+            break;
+        };
+
+        let end = start + run + close + run;
+        result.push_str(&f(&rest[..start]));
+        result.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+
+    result.push_str(&f(rest));
+    result
+}
+
+/// Byte offset of the next run of exactly `len` backticks in `s`.
+fn find_backtick_run(s: &str, len: usize) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+            if run == len {
+                return Some(i);
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Tracks whether successive lines are inside a fenced code block.
+#[derive(Debug, Default)]
+pub(crate) struct FenceState {
+    /// Fence character and length of the open fence.
+    open: Option<(char, usize)>,
+}
+
+impl FenceState {
+    /// Feed the next line, returning true if it is a fence line or inside
+    /// a fenced block. Fences nested in lists and blockquotes are recognized.
+    pub(crate) fn update(&mut self, line: &str) -> bool {
+        let run = fence_run(line);
+
+        match self.open {
+            Some((c, len)) => {
+                if let Some((rc, rlen, rest)) = run {
+                    if rc == c
+                        && rlen >= len
+                        && rest
+                            .trim_matches(|c: char| c.is_whitespace() || is_marker(c))
+                            .is_empty()
+                    {
+                        self.open = None;
+                    }
+                }
+                true
+            }
+            None => match run {
+                Some((c, len, _)) => {
+                    self.open = Some((c, len));
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// Trim leading whitespace and source map markers, which may sit in front
+/// of a fence.
+fn trim_markers_start(line: &str) -> &str {
+    line.trim_start_matches(|c: char| c.is_whitespace() || is_marker(c))
+}
+
+/// Parse a fence marker at the start of a line as (char, run length, rest).
+fn fence_run(line: &str) -> Option<(char, usize, &str)> {
+    let mut line = trim_markers_start(line);
+    while let Some(rest) = line.strip_prefix('>') {
+        line = trim_markers_start(rest);
+    }
+
+    let c = line.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = line.chars().take_while(|&ch| ch == c).count();
+    (len >= 3).then(|| (c, len, &line[len..]))
+}
+
+/// Whether `text` has characters for [`strip_control_chars`] to change.
+fn has_control_chars(text: &str) -> bool {
+    text.chars()
+        .any(|c| is_stripped_control(c) || is_line_separator(c))
+}
+
+/// Remove control characters other than `\n` and `\t`, and replace the
+/// Unicode line and paragraph separators with `\n`.
+fn strip_control_chars(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            c if is_line_separator(c) => Some('\n'),
+            c if is_stripped_control(c) => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+/// C0 controls other than tab and newline, DEL and C1 controls.
+fn is_stripped_control(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+fn is_line_separator(c: char) -> bool {
+    c == '\u{2028}' || c == '\u{2029}'
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn convert_to_referenced_links(markdown: String, sort: ReferenceSort) -> String {
+        let options = Options::new()
+            .link_style(LinkStyle::Referenced)
+            .reference_sort(sort);
+        convert_to_references(markdown, &options)
+    }
+
+    fn postprocess(markdown: String, options: &Options) -> String {
+        let mut out = String::new();
+        postprocess_into(markdown, options, false, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_strip_control_chars() {
+        assert_eq!(
+            strip_control_chars("a\x0cb\x0bc\x01\x08d\x7f\u{85}e\r\n\tf"),
+            "abcde\n\tf"
+        );
+        assert_eq!(
+            strip_control_chars("one\u{2028}two\u{2029}three"),
+            "one\ntwo\nthree"
+        );
+        assert!(!has_control_chars("plain\ttext\n"));
+    }
+
+    #[test]
+    fn test_control_chars_in_document() {
+        // Prose whitespace is already normalized, so these matter in code
+        let html =
+            "<p>Pa\x01ge\x0c one</p><pre><code>let x\x0b = 1;\u{2028}let y = 2;\x0c</code></pre>";
+        assert_eq!(
+            crate::convert(html),
+            "Page one\n\n```\nlet x = 1;\nlet y = 2;\n```\n"
+        );
+
+        let options = Options::new().strip_control_chars(false);
+        let result = crate::convert_with_options(html, &options);
+        assert!(result.contains('\x01'));
+        assert!(result.contains('\u{2028}'));
+    }
+
     #[test]
     fn test_escape_link_newlines() {
         let input = "[text\nwith newline](url)";
-        let result = escape_link_newlines(input);
+        let result = escape_link_newlines(input.to_string());
         assert_eq!(result, "[text\\nwith newline](url)");
     }
 
     #[test]
     fn test_escape_link_newlines_escaped_bracket() {
         let input = r"text with \[ escaped bracket";
-        let result = escape_link_newlines(input);
+        let result = escape_link_newlines(input.to_string());
         assert_eq!(result, r"text with \[ escaped bracket");
     }
 
     #[test]
     fn test_escape_link_newlines_no_change() {
         let input = "normal text\nwith newline outside brackets";
-        let result = escape_link_newlines(input);
+        let result = escape_link_newlines(input.to_string());
         assert_eq!(result, input);
     }
 
+    #[test]
+    fn test_escape_link_newlines_stray_bracket() {
+        // An unclosed bracket doesn't escape the newlines after it
+        let input = "Array [ of\nints\n\nNext\n- a";
+        assert_eq!(escape_link_newlines(input.to_string()), input);
+        let input = "[not a link\n] but [a\nlink](url)";
+        assert_eq!(
+            escape_link_newlines(input.to_string()),
+            "[not a link\n] but [a\\nlink](url)"
+        );
+    }
+
+    #[test]
+    fn test_escape_link_newlines_nested() {
+        let input = "[![a\nb](src)\nc](url) [x [y\nz](u)]";
+        assert_eq!(
+            escape_link_newlines(input.to_string()),
+            "[![a\\nb](src)\\nc](url) [x [y\\nz](u)]"
+        );
+    }
+
     #[test]
     fn test_convert_to_referenced_links() {
         let input = "Check [this](https://a.com) and [that](https://b.com).";
-        let result = convert_to_referenced_links(input);
+        let result = convert_to_referenced_links(input.to_string(), ReferenceSort::UseOrder);
         assert!(result.contains("[this][1]"));
         assert!(result.contains("[that][2]"));
         assert!(result.contains("[1]: https://a.com"));
@@ -174,7 +971,7 @@ mod tests {
     #[test]
     fn test_convert_to_referenced_links_dedup() {
         let input = "[a](https://x.com) and [b](https://x.com)";
-        let result = convert_to_referenced_links(input);
+        let result = convert_to_referenced_links(input.to_string(), ReferenceSort::UseOrder);
         assert!(result.contains("[a][1]"));
         assert!(result.contains("[b][1]")); // Same reference
                                             // Should only have one reference
@@ -184,39 +981,411 @@ mod tests {
     #[test]
     fn test_convert_to_referenced_links_with_title() {
         let input = r#"[link](https://a.com "Title")"#;
-        let result = convert_to_referenced_links(input);
+        let result = convert_to_referenced_links(input.to_string(), ReferenceSort::UseOrder);
         assert!(result.contains("[link][1]"));
         assert!(result.contains(r#"[1]: https://a.com "Title""#));
     }
 
+    const MIXED_LINKS: &str = "[z](https://b.com/z) [a](https://www.a.com/a) [rel](/docs) \
+                               [again](https://b.com/z) [y](https://B.com/y \"Why\")";
+
+    #[test]
+    fn test_reference_sort_url_renumbers() {
+        let result = convert_to_referenced_links(MIXED_LINKS.to_string(), ReferenceSort::Url);
+        assert_eq!(
+            result,
+            "[z][3] [a][4] [rel][1] [again][3] [y][2]\n\n\
+             [1]: /docs\n\
+             [2]: https://B.com/y \"Why\"\n\
+             [3]: https://b.com/z\n\
+             [4]: https://www.a.com/a\n"
+        );
+    }
+
+    #[test]
+    fn test_reference_sort_domain_groups() {
+        let result = convert_to_referenced_links(MIXED_LINKS.to_string(), ReferenceSort::Domain);
+        assert_eq!(
+            result,
+            "[z][3] [a][2] [rel][1] [again][3] [y][4]\n\n\
+             [1]: /docs\n\
+             \n\
+             [2]: https://www.a.com/a\n\
+             \n\
+             [3]: https://b.com/z\n\
+             [4]: https://B.com/y \"Why\"\n"
+        );
+    }
+
+    #[test]
+    fn test_reference_numbers_match_definitions() {
+        for sort in [
+            ReferenceSort::UseOrder,
+            ReferenceSort::Url,
+            ReferenceSort::Domain,
+        ] {
+            let result = convert_to_referenced_links(MIXED_LINKS.to_string(), sort);
+            let (body, definitions) = result.split_once("\n\n").unwrap();
+            for (text, url) in [
+                ("z", "https://b.com/z"),
+                ("rel", "/docs"),
+                ("y", "https://B.com/y"),
+            ] {
+                let start = body.find(&format!("[{}][", text)).unwrap() + text.len() + 3;
+                let num = &body[start..start + 1];
+                assert!(
+                    definitions.contains(&format!("[{}]: {}", num, url)),
+                    "{:?}: {}",
+                    sort,
+                    result
+                );
+            }
+        }
+    }
+
+    fn referenced(links: bool, images: bool) -> Options {
+        let mut options = Options::new();
+        if links {
+            options = options.link_style(LinkStyle::Referenced);
+        }
+        if images {
+            options = options.image_style(ImageStyle::Referenced);
+        }
+        options
+    }
+
+    #[test]
+    fn test_referenced_images_only() {
+        let input = "![a](x.png) [link](/l) ![b](y.png \"Why\") ![c](x.png \"Late\")";
+        assert_eq!(
+            convert_to_references(input.to_string(), &referenced(false, true)),
+            "![a][img1] [link](/l) ![b][img2] ![c][img1]\n\n\
+             [img1]: x.png\n\
+             [img2]: y.png \"Why\"\n"
+        );
+    }
+
+    #[test]
+    fn test_referenced_links_and_images() {
+        let input = "[![CI](ci.svg)](/build) [docs](/docs \"Docs\") ![Logo](logo.png) \
+                     [again](/docs) ![Logo](logo.png)";
+        assert_eq!(
+            convert_to_references(input.to_string(), &referenced(true, true)),
+            "[![CI][img1]][1] [docs][2] ![Logo][img2] [again][2] ![Logo][img2]\n\n\
+             [1]: /build\n\
+             [2]: /docs \"Docs\"\n\
+             [img1]: ci.svg\n\
+             [img2]: logo.png\n"
+        );
+    }
+
+    #[test]
+    fn test_referenced_links_keep_linked_images_inline() {
+        let input = "[![CI](ci.svg)](/build) and [a \\[b\\] c](/x)";
+        assert_eq!(
+            convert_to_references(input.to_string(), &referenced(true, false)),
+            "[![CI](ci.svg)][1] and [a \\[b\\] c][2]\n\n[1]: /build\n[2]: /x\n"
+        );
+    }
+
+    #[test]
+    fn test_referenced_images_sorted_like_links() {
+        let input = "![z](https://b.com/z.png) ![a](/a.png) [l](https://b.com/)";
+        let options = referenced(true, true).reference_sort(ReferenceSort::Domain);
+        assert_eq!(
+            convert_to_references(input.to_string(), &options),
+            "![z][img2] ![a][img1] [l][1]\n\n\
+             [1]: https://b.com/\n\
+             [img1]: /a.png\n\
+             \n\
+             [img2]: https://b.com/z.png\n"
+        );
+    }
+
     #[test]
     fn test_convert_to_referenced_links_no_images() {
         let input = "![image](img.png) and [link](url)";
-        let result = convert_to_referenced_links(input);
+        let result = convert_to_referenced_links(input.to_string(), ReferenceSort::UseOrder);
         // Image should NOT be converted
         assert!(result.contains("![image](img.png)"));
         // Link should be converted
         assert!(result.contains("[link][1]"));
     }
 
+    fn collapse_and_trim(input: &str) -> String {
+        let mut out = String::new();
+        collapse_and_trim_lines(input, &mut out);
+        out
+    }
+
     #[test]
     fn test_trim_trailing_whitespace() {
         let input = "line 1   \nline 2  \nline 3";
-        let result = trim_trailing_whitespace(input);
-        assert_eq!(result, "line 1\nline 2\nline 3");
+        assert_eq!(collapse_and_trim(input), "line 1\nline 2\nline 3");
     }
 
     #[test]
     fn test_collapse_newlines() {
-        let input = "a\n\n\n\nb";
-        let result = EXCESSIVE_NEWLINES_RE.replace_all(input, "\n\n");
-        assert_eq!(result, "a\n\nb");
+        assert_eq!(collapse_and_trim("a\n\n\n\nb"), "a\n\nb");
+        assert_eq!(collapse_and_trim("a\n\nb\n"), "a\n\nb");
+        assert_eq!(collapse_and_trim("\n\n\n\na"), "\n\na");
+        assert_eq!(collapse_and_trim("\n\na\n\n\n"), "\n\na\n");
+        // A whitespace-only line isn't part of a run
+        assert_eq!(collapse_and_trim("a\n\n  \n\nb"), "a\n\n\n\nb");
+    }
+
+    #[test]
+    fn test_finish_document_in_place() {
+        let mut markdown = String::from("\n\n  # Title\n\n");
+        finish_document(&mut markdown, &Options::default());
+        assert_eq!(markdown, "# Title\n");
+
+        let mut markdown = String::from("\n  code\n\n");
+        finish_document(&mut markdown, &Options::new().trim_document(false));
+        assert_eq!(markdown, "\n  code\n");
+
+        let mut markdown = String::from(" \n\t\n");
+        finish_document(&mut markdown, &Options::new().trim_document(false));
+        assert_eq!(markdown, "");
+    }
+
+    #[test]
+    fn test_postprocess_into_reuses_buffer() {
+        let options = Options::default();
+        let mut out = String::with_capacity(256);
+        out.push_str("stale");
+        let capacity = out.capacity();
+        postprocess_into(
+            "[a\nb](x)  \n\n\n\nend".to_string(),
+            &options,
+            false,
+            &mut out,
+        );
+        assert_eq!(out, "[a\\nb](x)\n\nend\n");
+        assert_eq!(out.capacity(), capacity);
+    }
+
+    fn remove_empty(input: &str, require_alt: bool) -> String {
+        remove_empty_links(input, &Options::new().require_alt(require_alt), false)
+    }
+
+    #[test]
+    fn test_remove_empty_links() {
+        assert_eq!(remove_empty("Home [](/) About", false), "Home About");
+        assert_eq!(remove_empty("[ ](#top) and [**](x)", false), "and ");
+        assert_eq!(remove_empty("[](/a)[](/b)", false), "");
+        assert_eq!(
+            remove_empty("[Docs](/docs) [](/x)", false),
+            "[Docs](/docs) "
+        );
+    }
+
+    #[test]
+    fn test_remove_empty_links_keeps_images_by_default() {
+        assert_eq!(remove_empty("![](img.png)", false), "![](img.png)");
+        assert_eq!(
+            remove_empty("[![](logo.png)](/)", false),
+            "[![](logo.png)](/)"
+        );
+        assert_eq!(
+            remove_empty("[![Logo](logo.png)](/)", true),
+            "[![Logo](logo.png)](/)"
+        );
+    }
+
+    #[test]
+    fn test_require_alt() {
+        assert_eq!(
+            remove_empty("Chart: ![](c.png) ![Pie](p.png)", true),
+            "Chart: ![Pie](p.png)"
+        );
+        // The wrapping link is empty once its image is gone
+        assert_eq!(remove_empty("Go [![](logo.png)](/) home", true), "Go home");
+    }
+
+    #[test]
+    fn test_remove_empty_links_drops_empty_list_items() {
+        let input = "- [Home](/)\n- [](/search)\n1. [](/x)\n- kept";
+        assert_eq!(remove_empty(input, false), "- [Home](/)\n- kept");
+    }
+
+    #[test]
+    fn test_remove_empty_links_ignores_code() {
+        let input = "Use `[](url)` here\n\n```\n[](url)\n```\n[](url)";
+        assert_eq!(
+            remove_empty(input, false),
+            "Use `[](url)` here\n\n```\n[](url)\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_dedupe_repeated_blocks() {
+        let input = "Sign up!\n\ntext\n\n> Quote\n\nSign up!\n\n> Quote\n\nSign up!";
+        assert_eq!(
+            dedupe_repeated_blocks(input, 1, 300, false),
+            "Sign up!\n\ntext\n\n> Quote\n\n\n"
+        );
+        assert_eq!(
+            dedupe_repeated_blocks(input, 2, 300, false),
+            "Sign up!\n\ntext\n\n> Quote\n\nSign up!\n\n> Quote\n"
+        );
+        // Longer than the threshold
+        assert_eq!(dedupe_repeated_blocks(input, 1, 5, false), input);
+    }
+
+    #[test]
+    fn test_dedupe_repeated_blocks_skips_structure() {
+        let input =
+            "## Hi\n\n## Hi\n\n- a\n\n- a\n\n| a |\n\n| a |\n\n---\n\n---\n\n    x\n\n    x";
+        assert_eq!(dedupe_repeated_blocks(input, 1, 300, false), input);
+
+        // Code isn't counted, and text touching a fence stays
+        let input = "```\nx\n\nx\n```\n\nx\n\nx\n```\ny\n```";
+        assert_eq!(
+            dedupe_repeated_blocks(input, 1, 300, false),
+            "```\nx\n\nx\n```\n\nx\n\nx\n```\ny\n```"
+        );
+    }
+
+    #[test]
+    fn test_dedupe_repeated_blocks_keeps_markers() {
+        // A block enclosing the dropped copy keeps its markers
+        let input = "x\n\n\u{E000}\u{E010}x\n\ny\u{E001}\u{E010}";
+        assert_eq!(
+            dedupe_repeated_blocks(input, 1, 300, true),
+            "x\n\n\n\u{E000}\u{E010}y\u{E001}\u{E010}"
+        );
+    }
+
+    #[test]
+    fn test_parse_inline_link() {
+        let link = parse_inline_link(r#"[![a](b.png) \] c](/x "T \" t") rest"#).unwrap();
+        assert_eq!(link.text, r"![a](b.png) \] c");
+        assert_eq!(link.url, "/x");
+        assert_eq!(link.title, r#" "T \" t""#);
+        assert_eq!(link.len, r#"[![a](b.png) \] c](/x "T \" t")"#.len());
+
+        assert!(parse_inline_link("[text] (/x)").is_none());
+        assert!(parse_inline_link("[text](/x").is_none());
+        assert!(parse_inline_link("[unclosed").is_none());
+    }
+
+    #[test]
+    fn test_merge_adjacent_links() {
+        assert_eq!(merge_adjacent_links("[A](/x)[B](/x)"), "[A B](/x)");
+        assert_eq!(merge_adjacent_links("[A](/x) [A](/x) [B](/x)"), "[A B](/x)");
+        assert_eq!(merge_adjacent_links("[A](/x)[B](/y)"), "[A](/x)[B](/y)");
+        assert_eq!(
+            merge_adjacent_links("[A](/x#a) [B](/x#b)"),
+            "[A](/x#a) [B](/x#b)"
+        );
+        assert_eq!(merge_adjacent_links("[A](/x), [B](/x)"), "[A](/x), [B](/x)");
+        assert_eq!(
+            merge_adjacent_links("- [A](/x)\n- [A](/x)"),
+            "- [A](/x)\n- [A](/x)"
+        );
+    }
+
+    #[test]
+    fn test_merge_adjacent_links_skips_images_and_code() {
+        assert_eq!(merge_adjacent_links("![a](/x)[B](/x)"), "![a](/x)[B](/x)");
+        assert_eq!(merge_adjacent_links(r"\[A](/x)[B](/x)"), r"\[A](/x)[B](/x)");
+        assert_eq!(merge_adjacent_links("`[A](/x)[B](/x)`"), "`[A](/x)[B](/x)`");
+        assert_eq!(
+            merge_adjacent_links("```\n[A](/x)[B](/x)\n```"),
+            "```\n[A](/x)[B](/x)\n```"
+        );
+    }
+
+    #[test]
+    fn test_map_outside_code_spans() {
+        let upper = |s: &str| s.to_uppercase();
+        assert_eq!(map_outside_code_spans("a `b` c", upper), "A `b` C");
+        assert_eq!(
+            map_outside_code_spans("a ``b ` c`` d", upper),
+            "A ``b ` c`` D"
+        );
+        assert_eq!(map_outside_code_spans("a `b", upper), "A `B");
+    }
+
+    #[test]
+    fn test_fence_state() {
+        let mut fence = FenceState::default();
+        let flags: Vec<bool> = ["text", "```rust", "# not a heading", "```", "after"]
+            .iter()
+            .map(|line| fence.update(line))
+            .collect();
+        assert_eq!(flags, vec![false, true, true, true, false]);
+    }
+
+    #[test]
+    fn test_fence_state_longer_fence() {
+        let mut fence = FenceState::default();
+        assert!(fence.update("````"));
+        assert!(fence.update("```"));
+        assert!(fence.update("````"));
+        assert!(!fence.update("done"));
+    }
+
+    #[test]
+    fn test_fence_state_in_blockquote() {
+        let mut fence = FenceState::default();
+        assert!(fence.update("> ~~~"));
+        assert!(fence.update("> code"));
+        assert!(fence.update("> ~~~"));
+        assert!(!fence.update("> quote"));
     }
 
     #[test]
     fn test_postprocess_full() {
         let input = "# Title\n\n\n\nParagraph   \n";
         let result = postprocess(input.to_string(), &Options::default());
-        assert_eq!(result, "# Title\n\nParagraph");
+        assert_eq!(result, "# Title\n\nParagraph\n");
+    }
+
+    #[test]
+    fn test_exactly_one_trailing_newline() {
+        for input in ["Text", "Text\n", "Text\n\n\n", "Text  \n \n\t\n"] {
+            let result = postprocess(input.to_string(), &Options::default());
+            assert_eq!(result, "Text\n", "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_trailing_newline_disabled() {
+        let options = Options::new().trailing_newline(false);
+        assert_eq!(postprocess("Text\n\n".to_string(), &options), "Text");
+    }
+
+    #[test]
+    fn test_empty_document_has_no_newline() {
+        assert_eq!(postprocess(" \n\n".to_string(), &Options::default()), "");
+    }
+
+    #[test]
+    fn test_trim_document_disabled() {
+        let options = Options::new().trim_document(false);
+        let result = postprocess("\n\n  Indented\n\n\n".to_string(), &options);
+        assert_eq!(result, "\n\n  Indented\n");
+    }
+
+    #[test]
+    fn test_drop_redundant_rules() {
+        let input = "---\n\nIntro\n\n---\n\n---\n\nBody\n---\n\n```\n---\n\n---\n```\n\n---\n\n";
+        // The setext underline and the fenced lines stay
+        assert_eq!(
+            drop_redundant_rules(input),
+            "\nIntro\n\n---\n\n\nBody\n---\n\n```\n---\n\n---\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_compact_references_repeated_urls_only() {
+        let input = "[a](/x) [b](/y \"Why\") [c](/x)";
+        let options = referenced(true, false).compact(true);
+        assert_eq!(
+            convert_to_references(input.to_string(), &options),
+            "[a][1] [b](/y \"Why\") [c][1]\n\n[1]: /x\n"
+        );
     }
 }
@@ -7,7 +7,13 @@ use ego_tree::NodeId;
 use rustc_hash::FxHashMap;
 use scraper::{ElementRef, Html, Selector};
 
-use crate::options::Options;
+use crate::breadcrumb;
+use crate::ids::fragment;
+use crate::options::{
+    AbbrStyle, AsideHandling, BreadcrumbHandling, ListSpacing, OlTypeHandling, Options,
+};
+use crate::rules::{abbreviation, alt_text_src};
+use crate::stats::{CapturedAttributes, ConversionStats};
 
 /// Pre-computed metadata for O(1) access during conversion.
 #[derive(Debug, Default, Clone)]
@@ -15,14 +21,68 @@ pub struct NodeMetadata {
     /// For `<li>`: the prefix string ("- ", "1. ", "2. ", etc.)
     pub list_prefix: Option<String>,
 
-    /// For `<li>`: total indentation from all ancestor lists (in spaces)
+    /// For `<li>`: indentation before the marker (in spaces) that no
+    /// enclosing `<li>` supplies; items indent their own content
     pub ancestor_indent: usize,
 
+    /// For `<li>` in a lettered or roman `<ol type>`: marker text placed
+    /// after the prefix, e.g. "(c) "
+    pub list_label: Option<String>,
+
+    /// For `<li>`: whether its list is loose, so items are followed by a
+    /// blank line
+    pub loose_list: bool,
+
+    /// For `<blockquote>`: its nesting depth, 1 for a quote outside any other
+    pub quote_depth: usize,
+
     /// Should skip this node and its subtree (matches exclude selector)
     pub skip: bool,
 
     /// Force keep this node (matches include selector, overrides parent skip)
     pub force_keep: bool,
+
+    /// Set on a skipped node whose subtree contains a force-kept node, so
+    /// the converter still descends to it
+    pub has_kept_descendants: bool,
+
+    /// For `<img usemap>`: the `<map>` it names, wherever it is in the
+    /// document
+    pub image_map: Option<NodeId>,
+
+    /// For the outermost node of a skipped subtree: the exclude selector or
+    /// text pattern that matched it
+    pub exclude_match: Option<ExcludeMatch>,
+
+    /// For `<img>` without alt text: the text from `alt_text_provider`
+    pub generated_alt: Option<String>,
+
+    /// For `<abbr>` with `abbr_style` other than Html: its title, when this
+    /// use expands or defines the abbreviation
+    pub abbr_title: Option<String>,
+}
+
+/// The option entry that excluded an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExcludeMatch {
+    /// Index into `Options::exclude_selectors`.
+    Selector(usize),
+    /// Index into `Options::exclude_text_patterns`.
+    TextPattern(usize),
+    /// An element dropped by a handling option set to skip, like
+    /// `aside_handling`; holds the option name.
+    Handling(&'static str),
+}
+
+impl ExcludeMatch {
+    /// The selector or pattern string as given in options.
+    pub fn source(self, options: &Options) -> &str {
+        match self {
+            ExcludeMatch::Selector(i) => &options.exclude_selectors[i],
+            ExcludeMatch::TextPattern(i) => &options.exclude_text_patterns[i],
+            ExcludeMatch::Handling(option) => option,
+        }
+    }
 }
 
 /// Type alias for the metadata map.
@@ -32,34 +92,272 @@ pub type MetadataMap = FxHashMap<NodeId, NodeMetadata>;
 pub struct CompiledSelectors {
     pub exclude: Vec<Selector>,
     pub include: Vec<Selector>,
+    /// Source strings of `exclude`, index-aligned, for reporting.
+    exclude_sources: Vec<String>,
+    /// Indices of `exclude` in `Options::exclude_selectors`, index-aligned;
+    /// invalid selectors are left out of `exclude`.
+    exclude_positions: Vec<usize>,
+    /// Source strings of `include`, index-aligned, for reporting.
+    include_sources: Vec<String>,
+    /// `capture_attributes` selectors, with the index of their entry in
+    /// the options.
+    capture: Vec<(Selector, usize)>,
+    /// Compiled `exclude_text_patterns`.
+    text: Vec<TextPattern>,
+    /// Source strings of `text`, index-aligned, for reporting.
+    text_sources: Vec<String>,
+    /// Indices of `text` in `Options::exclude_text_patterns`, index-aligned.
+    text_positions: Vec<usize>,
+    /// `exclude_text_max_len`.
+    text_max_len: usize,
+    /// `email_quote_selectors`, compiled with `email_mode` only.
+    email_quotes: Vec<Selector>,
+}
+
+/// A compiled `exclude_text_patterns` entry.
+enum TextPattern {
+    /// Lowercase substring of the element's own text.
+    Contains(String),
+    /// `/.../` pattern, anchored to the whole own text.
+    #[cfg(feature = "regex-filters")]
+    Regex(regex::Regex),
+}
+
+impl TextPattern {
+    fn compile(pattern: &str) -> Option<Self> {
+        #[cfg(feature = "regex-filters")]
+        if let Some(source) = text_regex_source(pattern) {
+            return compile_text_regex(source).ok().map(Self::Regex);
+        }
+
+        let needle = pattern.trim().to_lowercase();
+        (!needle.is_empty()).then_some(Self::Contains(needle))
+    }
+
+    /// Check `text`, already whitespace-collapsed, and its lowercase form.
+    #[cfg_attr(not(feature = "regex-filters"), allow(unused_variables))]
+    fn matches(&self, text: &str, lowercase: &str) -> bool {
+        match self {
+            Self::Contains(needle) => lowercase.contains(needle.as_str()),
+            #[cfg(feature = "regex-filters")]
+            Self::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Body of a `/.../` text pattern.
+#[cfg(feature = "regex-filters")]
+pub(crate) fn text_regex_source(pattern: &str) -> Option<&str> {
+    pattern
+        .strip_prefix('/')
+        .and_then(|p| p.strip_suffix('/'))
+        .filter(|p| !p.is_empty())
+}
+
+/// Compile the body of a `/.../` text pattern.
+#[cfg(feature = "regex-filters")]
+pub(crate) fn compile_text_regex(source: &str) -> Result<regex::Regex, regex::Error> {
+    let compiled = regex::RegexBuilder::new(&format!("^(?:{})$", source))
+        .case_insensitive(true)
+        .build();
+    #[cfg(feature = "logging")]
+    if let Err(e) = &compiled {
+        log::warn!("Invalid text pattern '/{}/': {}", source, e);
+    }
+    compiled
 }
 
 impl CompiledSelectors {
     /// Compile selectors from options.
     pub fn new(options: &Options) -> Self {
-        Self {
-            exclude: options
-                .exclude_selectors
+        let (exclude, (exclude_sources, exclude_positions)) = options
+            .exclude_selectors
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| compile_selector(s).map(|sel| (sel, (s.clone(), i))))
+            .unzip();
+
+        let (include, include_sources) = options
+            .include_selectors
+            .iter()
+            .filter_map(|s| compile_selector(s).map(|sel| (sel, s.clone())))
+            .unzip();
+
+        let capture = options
+            .capture_attributes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (s, _))| compile_selector(s).map(|sel| (sel, i)))
+            .collect();
+
+        let (text, (text_sources, text_positions)) = options
+            .exclude_text_patterns
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| TextPattern::compile(p).map(|pattern| (pattern, (p.clone(), i))))
+            .unzip();
+
+        let email_quotes = if options.email_mode {
+            options
+                .email_quote_selectors
                 .iter()
                 .filter_map(|s| compile_selector(s))
-                .collect(),
-            include: options
-                .include_selectors
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            exclude,
+            include,
+            exclude_sources,
+            exclude_positions,
+            include_sources,
+            capture,
+            text,
+            text_sources,
+            text_positions,
+            text_max_len: options.exclude_text_max_len,
+            email_quotes,
+        }
+    }
+
+    /// Check whether no exclude or include selectors or text patterns were
+    /// compiled.
+    ///
+    /// Lets the traversal skip selector matching entirely for the default
+    /// options, which is the common case.
+    pub fn is_empty(&self) -> bool {
+        self.exclude.is_empty() && self.include.is_empty() && self.text.is_empty()
+    }
+
+    /// Record the requested attributes of an element for each capture
+    /// selector it matches.
+    fn capture(
+        &self,
+        element: &ElementRef,
+        index: usize,
+        options: &Options,
+        out: &mut Vec<CapturedAttributes>,
+    ) {
+        for (selector, i) in &self.capture {
+            if !selector.matches(element) {
+                continue;
+            }
+            let (source, names) = &options.capture_attributes[*i];
+            let attrs = names
                 .iter()
-                .filter_map(|s| compile_selector(s))
-                .collect(),
+                .filter_map(|name| {
+                    let value = element.value().attr(name)?;
+                    Some((name.clone(), value.to_string()))
+                })
+                .collect();
+            out.push(CapturedAttributes {
+                selector: source.clone(),
+                tag: element.value().name().to_string(),
+                index,
+                attrs,
+            });
         }
     }
 
-    /// Check if an element matches any exclude selector.
-    pub fn matches_exclude(&self, element: &ElementRef) -> bool {
-        self.exclude.iter().any(|sel| sel.matches(element))
+    /// The first exclude selector matching an element, or else the first
+    /// text pattern.
+    pub fn first_exclude(&self, element: &ElementRef) -> Option<ExcludeMatch> {
+        match self.exclude.iter().position(|sel| sel.matches(element)) {
+            Some(i) => Some(ExcludeMatch::Selector(self.exclude_positions[i])),
+            None => self
+                .first_text_match(element)
+                .map(|i| ExcludeMatch::TextPattern(self.text_positions[i])),
+        }
+    }
+
+    /// Source string of the first exclude selector matching an element, or
+    /// else of the first text pattern.
+    pub fn first_exclude_match(&self, element: &ElementRef) -> Option<&str> {
+        self.exclude
+            .iter()
+            .position(|sel| sel.matches(element))
+            .map(|i| self.exclude_sources[i].as_str())
+            .or_else(|| {
+                self.first_text_match(element)
+                    .map(|i| self.text_sources[i].as_str())
+            })
+    }
+
+    /// Index of the first text pattern matching an element's own text, if
+    /// its whole text is short enough to exclude.
+    fn first_text_match(&self, element: &ElementRef) -> Option<usize> {
+        if self.text.is_empty() {
+            return None;
+        }
+        let own = own_text(element);
+        if own.is_empty() {
+            return None;
+        }
+        let lowercase = own.to_lowercase();
+        let i = self
+            .text
+            .iter()
+            .position(|pattern| pattern.matches(&own, &lowercase))?;
+        if text_longer_than(element, self.text_max_len) {
+            return None;
+        }
+        Some(i)
+    }
+
+    /// Check if an element is quoted email history, with `email_mode`.
+    pub fn matches_email_quote(&self, element: &ElementRef) -> bool {
+        self.email_quotes.iter().any(|sel| sel.matches(element))
     }
 
     /// Check if an element matches any include selector.
     pub fn matches_include(&self, element: &ElementRef) -> bool {
         self.include.iter().any(|sel| sel.matches(element))
     }
+
+    /// Source string of the first include selector matching an element.
+    pub fn first_include_match(&self, element: &ElementRef) -> Option<&str> {
+        self.include
+            .iter()
+            .position(|sel| sel.matches(element))
+            .map(|i| self.include_sources[i].as_str())
+    }
+}
+
+/// Text of an element's direct text children, whitespace-collapsed.
+fn own_text(element: &ElementRef) -> String {
+    let mut text = String::new();
+    for child in element.children() {
+        if let Some(t) = child.value().as_text() {
+            for word in t.split_whitespace() {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(word);
+            }
+        }
+    }
+    text
+}
+
+/// Check whether an element's whole text, counting each whitespace run as
+/// one character, is longer than `max` characters. Stops counting at `max`.
+fn text_longer_than(element: &ElementRef, max: usize) -> bool {
+    let mut len = 0;
+    let mut in_space = true;
+    for c in element.text().flat_map(str::chars) {
+        let space = c.is_whitespace();
+        if space && in_space {
+            continue;
+        }
+        in_space = space;
+        len += 1;
+        if len > max {
+            return true;
+        }
+    }
+    false
 }
 
 /// Compile a CSS selector string, returning None on error.
@@ -82,6 +380,196 @@ struct ListContext {
     indent: usize,
     /// Length of the prefix (e.g., "- " is 2, "10. " is 4).
     prefix_len: usize,
+    /// Marker style from `<ol type>` when rendered as text labels.
+    marker: Option<ListMarker>,
+    /// Width numbers are padded to (e.g. 3 for "10."), 0 for no padding.
+    number_width: usize,
+    /// For unordered lists: the bullet marker for this level.
+    bullet: char,
+    /// Whether items are separated by blank lines.
+    loose: bool,
+}
+
+/// Non-numeric `<ol type>` marker styles.
+#[derive(Debug, Clone, Copy)]
+enum ListMarker {
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl ListMarker {
+    fn from_type(list_type: &str) -> Option<Self> {
+        match list_type.trim() {
+            "a" => Some(Self::LowerAlpha),
+            "A" => Some(Self::UpperAlpha),
+            "i" => Some(Self::LowerRoman),
+            "I" => Some(Self::UpperRoman),
+            _ => None,
+        }
+    }
+
+    /// Marker text for a 1-based index.
+    fn format(self, index: usize) -> String {
+        match self {
+            Self::LowerAlpha => to_alpha(index),
+            Self::UpperAlpha => to_alpha(index).to_uppercase(),
+            Self::LowerRoman => to_roman(index),
+            Self::UpperRoman => to_roman(index).to_uppercase(),
+        }
+    }
+}
+
+/// Spreadsheet-style letters as browsers render them: a..z, aa, ab, ...
+fn to_alpha(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    while index > 0 {
+        index -= 1;
+        letters.push(b'a' + (index % 26) as u8);
+        index /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+/// Lowercase roman numerals; falls back to digits outside 1..=3999.
+fn to_roman(mut index: usize) -> String {
+    if !(1..=3999).contains(&index) {
+        return index.to_string();
+    }
+
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS {
+        while index >= value {
+            result.push_str(numeral);
+            index -= value;
+        }
+    }
+    result
+}
+
+/// Block elements that make a list item hold more than one block.
+///
+/// Nested lists are left out: a line of text followed by a sublist is the
+/// ordinary shape of a tight list.
+const ITEM_BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "pre",
+    "blockquote",
+    "table",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "dl",
+    "figure",
+    "details",
+    "section",
+    "article",
+];
+
+/// Whether a list sits inside an item of the nearest enclosing list, rather
+/// than straight inside that list.
+fn in_list_item(list: &ElementRef) -> bool {
+    list.ancestors()
+        .filter_map(ElementRef::wrap)
+        .map(|ancestor| ancestor.value().name())
+        .find(|name| matches!(*name, "li" | "ul" | "ol"))
+        == Some("li")
+}
+
+/// Whether a list item holds two or more blocks, or a block next to loose
+/// text, counting direct children only so the check stays linear.
+fn has_multiple_blocks(li: &ElementRef) -> bool {
+    let mut blocks = 0;
+    let mut inline = false;
+    for child in li.children() {
+        match child.value() {
+            scraper::Node::Text(text) => inline |= !text.trim().is_empty(),
+            scraper::Node::Element(el) => match el.name() {
+                "ul" | "ol" => {}
+                name if ITEM_BLOCK_TAGS.contains(&name) => blocks += 1,
+                _ => inline = true,
+            },
+            _ => {}
+        }
+    }
+    blocks >= 2 || (blocks == 1 && inline)
+}
+
+/// Flag the skipped ancestors of a force-kept node.
+///
+/// Stops at the first ancestor already flagged, so each node is flagged
+/// once however many kept nodes it contains.
+fn mark_kept_descendant(node: ego_tree::NodeRef<scraper::Node>, metadata: &mut MetadataMap) {
+    for ancestor in node.ancestors() {
+        match metadata.get_mut(&ancestor.id()) {
+            Some(meta) if meta.skip => {
+                if meta.has_kept_descendants {
+                    return;
+                }
+                meta.has_kept_descendants = true;
+            }
+            // Nodes outside the skipped region have no entry or aren't skipped
+            _ => {}
+        }
+    }
+}
+
+/// `<map>` elements by name, and the images naming them, gathered during
+/// the traversal since a map may come after its image.
+#[derive(Default)]
+struct ImageMaps {
+    maps: FxHashMap<String, NodeId>,
+    images: Vec<(NodeId, String)>,
+}
+
+impl ImageMaps {
+    fn record(&mut self, element: &ElementRef) {
+        match element.value().name() {
+            "map" => {
+                // The first map with a name wins
+                if let Some(name) = element.value().attr("name").filter(|n| !n.is_empty()) {
+                    self.maps.entry(name.to_string()).or_insert(element.id());
+                }
+            }
+            "img" => {
+                if let Some(name) = element.value().attr("usemap").and_then(fragment) {
+                    self.images.push((element.id(), name.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve(self, metadata: &mut MetadataMap) {
+        for (image, name) in self.images {
+            if let Some(&map) = self.maps.get(&name) {
+                metadata.entry(image).or_default().image_map = Some(map);
+            }
+        }
+    }
 }
 
 /// Single O(n) traversal to compute all node metadata.
@@ -89,36 +577,106 @@ pub fn precompute_metadata(
     dom: &Html,
     selectors: &CompiledSelectors,
     options: &Options,
+) -> MetadataMap {
+    precompute_metadata_with_stats(dom, selectors, options, None, None)
+}
+
+/// The handling option that drops `element`, if one is set to skip it.
+fn skip_handling(
+    element: ElementRef,
+    selectors: &CompiledSelectors,
+    options: &Options,
+) -> Option<&'static str> {
+    match element.value().name() {
+        "aside" if options.aside_handling == AsideHandling::Skip => Some("aside_handling"),
+        "dialog" if options.dialog_handling == AsideHandling::Skip => Some("dialog_handling"),
+        _ if options.breadcrumbs == BreadcrumbHandling::Skip
+            && breadcrumb::is_breadcrumb(element) =>
+        {
+            Some("breadcrumbs")
+        }
+        _ if selectors.matches_email_quote(&element) => Some("email_quote_selectors"),
+        _ => None,
+    }
+}
+
+/// Like [`precompute_metadata`], also counting visited and excluded elements
+/// and recording the attributes requested by `capture_attributes`.
+pub fn precompute_metadata_with_stats(
+    dom: &Html,
+    selectors: &CompiledSelectors,
+    options: &Options,
+    mut stats: Option<&mut ConversionStats>,
+    mut captures: Option<&mut Vec<CapturedAttributes>>,
 ) -> MetadataMap {
     let mut metadata = FxHashMap::default();
     let mut list_stack: Vec<ListContext> = Vec::with_capacity(8);
     let mut skip_depth: Option<usize> = None;
+    let mut keep_depth: Option<usize> = None;
     let mut depth: usize = 0;
+    let mut quote_depth: usize = 0;
+    let mut element_index: usize = 0;
+    let mut image_maps = ImageMaps::default();
+    // Provider results by image source, so each is asked for once
+    let mut alt_texts: FxHashMap<String, Option<String>> = FxHashMap::default();
+    // Title last written for each abbreviation
+    let mut abbrs: FxHashMap<String, String> = FxHashMap::default();
 
     // Use scraper's select to traverse all elements
     // We'll use a manual traversal for proper edge handling
     let root = dom.root_element();
 
+    #[allow(clippy::too_many_arguments)]
     fn traverse(
         node: ego_tree::NodeRef<scraper::Node>,
         metadata: &mut MetadataMap,
         list_stack: &mut Vec<ListContext>,
         skip_depth: &mut Option<usize>,
+        keep_depth: &mut Option<usize>,
         depth: &mut usize,
+        quote_depth: &mut usize,
+        element_index: &mut usize,
+        image_maps: &mut ImageMaps,
+        alt_texts: &mut FxHashMap<String, Option<String>>,
+        abbrs: &mut FxHashMap<String, String>,
         selectors: &CompiledSelectors,
         options: &Options,
+        stats: &mut Option<&mut ConversionStats>,
+        captures: &mut Option<&mut Vec<CapturedAttributes>>,
     ) {
         *depth += 1;
+        // Skip and keep scopes nest, so restore the outer ones on exit
+        let outer_skip_depth = *skip_depth;
+        let outer_keep_depth = *keep_depth;
 
         if let Some(element) = ElementRef::wrap(node) {
             let tag = element.value().name();
 
+            if let Some(stats) = stats.as_deref_mut() {
+                stats.elements_visited += 1;
+            }
+            let index = *element_index;
+            *element_index += 1;
+
+            if tag == "map" || tag == "img" {
+                image_maps.record(&element);
+            }
+
             // Track list context
             if tag == "ul" || tag == "ol" {
-                let current_indent = list_stack
-                    .last()
-                    .map(|ctx| ctx.indent + ctx.prefix_len)
-                    .unwrap_or(0);
+                // Inside a skipped subtree the enclosing items aren't
+                // rendered, so items rescued by include_selectors start at
+                // the left edge of their kept block
+                let current_indent = if *skip_depth > *keep_depth || in_list_item(&element) {
+                    0
+                } else {
+                    // A list placed straight inside another list has no item
+                    // indenting it
+                    list_stack
+                        .last()
+                        .map(|ctx| ctx.indent + ctx.prefix_len)
+                        .unwrap_or(0)
+                };
 
                 // Check for start attribute on ordered lists
                 let start_index = if tag == "ol" {
@@ -132,23 +690,70 @@ pub fn precompute_metadata(
                     0
                 };
 
+                // Without raw HTML the markers are kept as text instead
+                let markers_as_text = match options.ol_type_handling {
+                    OlTypeHandling::LettersAsText => true,
+                    OlTypeHandling::Html => !options.allow_raw_html,
+                    OlTypeHandling::Numbers => false,
+                };
+                let marker = if tag == "ol" && markers_as_text {
+                    element.value().attr("type").and_then(ListMarker::from_type)
+                } else {
+                    None
+                };
+
+                // The last number is known up front from the item count
+                let number_width = if tag == "ol" && options.pad_ordered_markers {
+                    let items = element
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .filter(|child| child.value().name() == "li")
+                        .count();
+                    (start_index + items.max(1)).to_string().len() + 1
+                } else {
+                    0
+                };
+
+                // Ordered levels don't advance the bullet cycle
+                let bullet_depth = list_stack.iter().filter(|ctx| !ctx.ordered).count();
+                let bullet = options.bullet_for_depth(bullet_depth);
+
+                let loose = match options.list_spacing {
+                    ListSpacing::Auto => element
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .any(|li| li.value().name() == "li" && has_multiple_blocks(&li)),
+                    ListSpacing::Tight => false,
+                    ListSpacing::Loose => true,
+                };
+
                 list_stack.push(ListContext {
                     ordered: tag == "ol",
                     index: start_index,
                     indent: current_indent,
                     prefix_len: 2, // Will be updated when processing li
+                    marker,
+                    number_width,
+                    bullet,
+                    loose,
                 });
             }
 
+            if tag == "blockquote" {
+                *quote_depth += 1;
+                metadata.entry(node.id()).or_default().quote_depth = *quote_depth;
+            }
+
             // Compute list item metadata
             if tag == "li" {
                 if let Some(ctx) = list_stack.last_mut() {
                     ctx.index += 1;
 
                     let prefix = if ctx.ordered {
-                        format!("{}. ", ctx.index)
+                        let number = format!("{}.", ctx.index);
+                        format!("{:<width$} ", number, width = ctx.number_width)
                     } else {
-                        format!("{} ", options.bullet_marker)
+                        format!("{} ", ctx.bullet)
                     };
 
                     ctx.prefix_len = prefix.len();
@@ -156,39 +761,130 @@ pub fn precompute_metadata(
                     let meta = metadata.entry(node.id()).or_default();
                     meta.list_prefix = Some(prefix);
                     meta.ancestor_indent = ctx.indent;
+                    meta.list_label = ctx
+                        .marker
+                        .map(|marker| format!("({}) ", marker.format(ctx.index)));
+                    meta.loose_list = ctx.loose;
+                }
+            }
+
+            // Fast path: without selectors or skipped landmarks nothing can
+            // be skipped or kept
+            let mut skipped = false;
+            let handling_skip = skip_handling(element, selectors, options);
+            if !selectors.is_empty() || handling_skip.is_some() || skip_depth.is_some() {
+                // Check include selectors first (force_keep)
+                let force_keep = selectors.matches_include(&element);
+
+                // Check exclude selectors, then skipping handling options
+                let exclude_match = selectors
+                    .first_exclude(&element)
+                    .or(handling_skip.map(ExcludeMatch::Handling));
+
+                // Determine skip state; the innermost scope wins, so an
+                // exclude inside a kept subtree skips again
+                let inherited_skip = *skip_depth > *keep_depth;
+                let skip = if force_keep {
+                    if *keep_depth <= *skip_depth {
+                        *keep_depth = Some(*depth);
+                    }
+                    if inherited_skip {
+                        mark_kept_descendant(node, metadata);
+                    }
+                    false // force_keep overrides everything
+                } else if let Some(exclude_match) = exclude_match {
+                    if !inherited_skip {
+                        *skip_depth = Some(*depth);
+                        metadata.entry(node.id()).or_default().exclude_match = Some(exclude_match);
+
+                        #[cfg(feature = "logging")]
+                        log::debug!(
+                            "Skipping <{}>: matched exclude selector '{}'",
+                            tag,
+                            exclude_match.source(options)
+                        );
+
+                        if let Some(stats) = stats.as_deref_mut() {
+                            stats.elements_skipped += 1;
+                            *stats
+                                .skipped_by_selector
+                                .entry(exclude_match.source(options).to_string())
+                                .or_default() += 1;
+                        }
+                    }
+                    true
+                } else {
+                    inherited_skip
+                };
+
+                if skip || force_keep {
+                    let meta = metadata.entry(node.id()).or_default();
+                    meta.skip = skip;
+                    meta.force_keep = force_keep;
                 }
+                skipped = skip;
             }
 
-            // Check include selectors first (force_keep)
-            let force_keep = selectors.matches_include(&element);
+            if let Some(out) = captures.as_deref_mut() {
+                if !skipped {
+                    selectors.capture(&element, index, options, out);
+                }
+            }
 
-            // Check exclude selectors
-            let matches_exclude = selectors.matches_exclude(&element);
+            if tag == "img" && !skipped {
+                if let Some(provider) = &options.alt_text_provider {
+                    let has_alt = element
+                        .value()
+                        .attr("alt")
+                        .is_some_and(|alt| !alt.trim().is_empty());
+                    if let Some(src) = alt_text_src(element, options).filter(|_| !has_alt) {
+                        let alt = alt_texts
+                            .entry(src)
+                            .or_insert_with_key(|src| (provider.0)(src))
+                            .clone();
+                        if alt.is_some() {
+                            metadata.entry(node.id()).or_default().generated_alt = alt;
+                        }
+                    }
+                }
+            }
 
-            // Determine skip state
-            let inherited_skip = skip_depth.is_some();
-            let skip = if force_keep {
-                false // force_keep overrides everything
-            } else if matches_exclude {
-                if skip_depth.is_none() {
-                    *skip_depth = Some(*depth);
+            if tag == "abbr" && !skipped && options.abbr_style != AbbrStyle::Html {
+                if let Some((abbr, title)) = abbreviation(element) {
+                    // Expanded again when the title changes; defined once,
+                    // and only where the definition syntax allows
+                    let write = match options.abbr_style {
+                        AbbrStyle::Expanded => abbrs.get(&abbr) != Some(&title),
+                        _ => !abbrs.contains_key(&abbr) && !abbr.contains(']'),
+                    };
+                    if write {
+                        metadata.entry(node.id()).or_default().abbr_title = Some(title.clone());
+                    }
+                    if write || options.abbr_style == AbbrStyle::Expanded {
+                        abbrs.insert(abbr, title);
+                    }
                 }
-                true
-            } else {
-                inherited_skip
-            };
-
-            if skip || force_keep {
-                let meta = metadata.entry(node.id()).or_default();
-                meta.skip = skip;
-                meta.force_keep = force_keep;
             }
         }
 
         // Recurse into children
         for child in node.children() {
             traverse(
-                child, metadata, list_stack, skip_depth, depth, selectors, options,
+                child,
+                metadata,
+                list_stack,
+                skip_depth,
+                keep_depth,
+                depth,
+                quote_depth,
+                element_index,
+                image_maps,
+                alt_texts,
+                abbrs,
+                selectors,
+                options,
+                stats,
+                captures,
             );
         }
 
@@ -198,12 +894,13 @@ pub fn precompute_metadata(
             if tag == "ul" || tag == "ol" {
                 list_stack.pop();
             }
+            if tag == "blockquote" {
+                *quote_depth -= 1;
+            }
         }
 
-        // Reset skip_depth when leaving the element that started the skip
-        if *skip_depth == Some(*depth) {
-            *skip_depth = None;
-        }
+        *skip_depth = outer_skip_depth;
+        *keep_depth = outer_keep_depth;
         *depth -= 1;
     }
 
@@ -214,12 +911,21 @@ pub fn precompute_metadata(
             &mut metadata,
             &mut list_stack,
             &mut skip_depth,
+            &mut keep_depth,
             &mut depth,
+            &mut quote_depth,
+            &mut element_index,
+            &mut image_maps,
+            &mut alt_texts,
+            &mut abbrs,
             selectors,
             options,
+            &mut stats,
+            &mut captures,
         );
     }
 
+    image_maps.resolve(&mut metadata);
     metadata
 }
 
@@ -227,6 +933,29 @@ pub fn precompute_metadata(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_image_map_index() {
+        let html = r##"<img id="a" usemap="#m"><img id="b" usemap="#other">
+            <map name="m"></map><map name="m" id="second"></map>"##;
+        let dom = Html::parse_document(html);
+        let options = Options::default();
+        let selectors = CompiledSelectors::new(&options);
+        let metadata = precompute_metadata(&dom, &selectors, &options);
+
+        let by_id = |id: &str| {
+            let selector = Selector::parse(&format!("#{}", id)).unwrap();
+            dom.select(&selector).next().unwrap().id()
+        };
+        let map = dom
+            .select(&Selector::parse("map").unwrap())
+            .next()
+            .unwrap()
+            .id();
+        // Maps after the image are found, and the first of a name wins
+        assert_eq!(metadata[&by_id("a")].image_map, Some(map));
+        assert!(!metadata.contains_key(&by_id("b")));
+    }
+
     #[test]
     fn test_list_metadata() {
         let html = r#"<ul><li>First</li><li>Second</li></ul>"#;
@@ -245,7 +974,7 @@ mod tests {
 
     #[test]
     fn test_nested_list_indentation() {
-        let html = r#"<ul><li>First<ul><li>Nested</li></ul></li></ul>"#;
+        let html = r#"<ul><li>First</li><ul><li>Nested</li></ul></ul>"#;
         let dom = Html::parse_document(html);
         let options = Options::default();
         let selectors = CompiledSelectors::new(&options);
@@ -256,11 +985,27 @@ mod tests {
             .filter(|m| m.list_prefix.is_some())
             .collect();
 
-        // Check that nested item has indentation
+        // A list straight inside a list has no item to indent it
         let has_nested = li_metadata.iter().any(|m| m.ancestor_indent > 0);
         assert!(has_nested);
     }
 
+    #[test]
+    fn test_quote_depth() {
+        let html = r#"<blockquote><blockquote><p>a</p><blockquote>b</blockquote></blockquote></blockquote><blockquote>c</blockquote>"#;
+        let dom = Html::parse_document(html);
+        let options = Options::default();
+        let selectors = CompiledSelectors::new(&options);
+        let metadata = precompute_metadata(&dom, &selectors, &options);
+
+        let selector = Selector::parse("blockquote").unwrap();
+        let depths: Vec<usize> = dom
+            .select(&selector)
+            .map(|quote| metadata[&quote.id()].quote_depth)
+            .collect();
+        assert_eq!(depths, vec![1, 2, 3, 1]);
+    }
+
     #[test]
     fn test_ordered_list() {
         let html = r#"<ol><li>First</li><li>Second</li><li>Third</li></ol>"#;
@@ -297,6 +1042,21 @@ mod tests {
         assert!(prefixes.contains(&&"7. ".to_string()));
     }
 
+    #[test]
+    fn test_no_selectors_only_list_items_recorded() {
+        let html = r#"<div><p>Text</p><ul><li>One</li><li>Two</li></ul></div>"#;
+        let dom = Html::parse_document(html);
+        let options = Options::default();
+        let selectors = CompiledSelectors::new(&options);
+        assert!(selectors.is_empty());
+
+        let metadata = precompute_metadata(&dom, &selectors, &options);
+        assert_eq!(metadata.len(), 2);
+        assert!(metadata
+            .values()
+            .all(|m| m.list_prefix.is_some() && !m.skip));
+    }
+
     #[test]
     fn test_exclude_selector() {
         let html = r#"<div><p>Keep</p><nav>Skip</nav></div>"#;
@@ -312,6 +1072,35 @@ mod tests {
         assert!(!skipped.is_empty());
     }
 
+    #[test]
+    fn test_exclude_match_recorded_on_outermost_node() {
+        let html = r#"<div><nav><p class="ad">Skip</p></nav><p class="ad">Ad</p></div>"#;
+        let dom = Html::parse_document(html);
+        let options = Options::new().exclude_selectors(vec![
+            "(".to_string(),
+            ".ad".to_string(),
+            "nav".to_string(),
+        ]);
+        let selectors = CompiledSelectors::new(&options);
+        let metadata = precompute_metadata(&dom, &selectors, &options);
+
+        let mut matches: Vec<_> = metadata
+            .values()
+            .filter(|m| m.skip)
+            .map(|m| m.exclude_match)
+            .collect();
+        matches.sort_by_key(|m| format!("{:?}", m));
+        // The nav's paragraph is skipped with it but has no match of its own
+        assert_eq!(
+            matches,
+            [
+                None,
+                Some(ExcludeMatch::Selector(1)),
+                Some(ExcludeMatch::Selector(2))
+            ]
+        );
+    }
+
     #[test]
     fn test_include_overrides_exclude() {
         let html = r#"<nav><div class="keep">Important</div></nav>"#;
@@ -327,4 +1116,267 @@ mod tests {
         let force_kept: Vec<_> = metadata.values().filter(|m| m.force_keep).collect();
         assert!(!force_kept.is_empty());
     }
+
+    fn skip_flags(html: &str, options: &Options) -> Vec<(String, bool, bool)> {
+        let dom = Html::parse_document(html);
+        let selectors = CompiledSelectors::new(options);
+        let metadata = precompute_metadata(&dom, &selectors, options);
+
+        dom.root_element()
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .filter_map(|el| {
+                let id = el.value().id()?;
+                let meta = metadata.get(&el.id()).cloned().unwrap_or_default();
+                Some((id.to_string(), meta.skip, meta.has_kept_descendants))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_force_keep_covers_subtree() {
+        let html = r#"<nav id="n"><a id="a">Menu</a>
+            <div id="k" class="keep"><p id="p"><em id="em">Deep</em></p></div></nav>"#;
+        let options = Options::new()
+            .exclude_selectors(vec!["nav".to_string()])
+            .include_selectors(vec![".keep".to_string()]);
+
+        assert_eq!(
+            skip_flags(html, &options),
+            vec![
+                ("n".to_string(), true, true),
+                ("a".to_string(), true, false),
+                ("k".to_string(), false, false),
+                ("p".to_string(), false, false),
+                ("em".to_string(), false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclude_inside_kept_subtree() {
+        let html = r#"<nav id="n"><div id="k" class="keep">
+            <p id="p">Kept</p>
+            <div id="ad" class="ad"><span id="s">Ad</span><b id="b" class="keep">Back</b></div>
+        </div></nav><p id="after">After</p>"#;
+        let options = Options::new()
+            .exclude_selectors(vec!["nav".to_string(), ".ad".to_string()])
+            .include_selectors(vec![".keep".to_string()]);
+
+        assert_eq!(
+            skip_flags(html, &options),
+            vec![
+                ("n".to_string(), true, true),
+                ("k".to_string(), false, false),
+                ("p".to_string(), false, false),
+                ("ad".to_string(), true, true),
+                ("s".to_string(), true, false),
+                ("b".to_string(), false, false),
+                ("after".to_string(), false, false),
+            ]
+        );
+    }
+
+    fn text_patterns(patterns: &[&str]) -> Options {
+        Options::new().exclude_text_patterns(patterns.iter().map(|p| p.to_string()).collect())
+    }
+
+    #[test]
+    fn test_exclude_text_pattern_skips_share_cluster() {
+        let html = r#"<article id="art"><p id="p">Body text.</p>
+            <div id="share">Share this   article: <a id="tw">Twitter</a> <a id="fb">Facebook</a></div>
+            <div id="ad"><span id="label">ADVERTISEMENT</span></div></article>"#;
+        let options = text_patterns(&["share this article", "Advertisement"]);
+
+        assert_eq!(
+            skip_flags(html, &options),
+            vec![
+                ("art".to_string(), false, false),
+                ("p".to_string(), false, false),
+                ("share".to_string(), true, false),
+                ("tw".to_string(), true, false),
+                ("fb".to_string(), true, false),
+                ("ad".to_string(), false, false),
+                ("label".to_string(), true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclude_text_pattern_spares_long_elements() {
+        let long = "word ".repeat(50);
+        let html = format!(
+            r#"<p id="long">This advertisement study found {long}</p><p id="short">Advertisement</p>"#
+        );
+        let options = text_patterns(&["advertisement"]);
+
+        assert_eq!(
+            skip_flags(&html, &options),
+            vec![
+                ("long".to_string(), false, false),
+                ("short".to_string(), true, false),
+            ]
+        );
+        assert_eq!(
+            skip_flags(&html, &options.exclude_text_max_len(1000)),
+            vec![
+                ("long".to_string(), true, false),
+                ("short".to_string(), true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclude_text_pattern_counts_child_text() {
+        // The own text is short, but the links under it make the element
+        // too long to drop
+        let links = "<a>A link with quite a long title</a>".repeat(8);
+        let html = format!(r#"<div id="d">Sponsored {links}</div>"#);
+
+        assert_eq!(
+            skip_flags(&html, &text_patterns(&["sponsored"])),
+            vec![("d".to_string(), false, false)]
+        );
+    }
+
+    #[test]
+    fn test_include_selector_rescues_text_match() {
+        let html = r#"<p id="a">Advertisement</p><p id="b" class="keep">Advertisement</p>"#;
+        let options =
+            text_patterns(&["advertisement"]).include_selectors(vec![".keep".to_string()]);
+
+        assert_eq!(
+            skip_flags(html, &options),
+            vec![
+                ("a".to_string(), true, false),
+                ("b".to_string(), false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclude_text_pattern_stats() {
+        let dom = Html::parse_document("<p>Advertisement</p><p>Text</p><p>advertisement</p>");
+        let options = text_patterns(&["", "Advertisement"]);
+        let selectors = CompiledSelectors::new(&options);
+        let mut stats = ConversionStats::default();
+        precompute_metadata_with_stats(&dom, &selectors, &options, Some(&mut stats), None);
+
+        assert_eq!(stats.elements_skipped, 2);
+        assert_eq!(stats.skipped_by_selector["Advertisement"], 2);
+    }
+
+    #[cfg(feature = "regex-filters")]
+    #[test]
+    fn test_exclude_text_regex_is_anchored() {
+        let html = r#"<p id="a">Share on Twitter</p><p id="b">How to share on twitter well</p>
+            <p id="c">/share/</p>"#;
+        let options = text_patterns(&["/share on (twitter|facebook)/"]);
+
+        assert_eq!(
+            skip_flags(html, &options),
+            vec![
+                ("a".to_string(), true, false),
+                ("b".to_string(), false, false),
+                ("c".to_string(), false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_markers() {
+        assert_eq!(to_alpha(1), "a");
+        assert_eq!(to_alpha(26), "z");
+        assert_eq!(to_alpha(27), "aa");
+        assert_eq!(to_alpha(52), "az");
+        assert_eq!(to_roman(4), "iv");
+        assert_eq!(to_roman(1994), "mcmxciv");
+        assert_eq!(to_roman(0), "0");
+        assert_eq!(ListMarker::UpperRoman.format(9), "IX");
+        assert_eq!(ListMarker::UpperAlpha.format(3), "C");
+    }
+
+    #[test]
+    fn test_list_labels_only_when_requested() {
+        let html = r#"<ol type="a" start="3"><li>C</li><li>D</li></ol>"#;
+        let dom = Html::parse_document(html);
+
+        let options = Options::default();
+        let metadata = precompute_metadata(&dom, &CompiledSelectors::new(&options), &options);
+        assert!(metadata.values().all(|m| m.list_label.is_none()));
+
+        let options = Options::new().ol_type_handling(OlTypeHandling::LettersAsText);
+        let metadata = precompute_metadata(&dom, &CompiledSelectors::new(&options), &options);
+        let mut labels: Vec<_> = metadata
+            .values()
+            .filter_map(|m| Some((m.list_prefix.clone()?, m.list_label.clone()?)))
+            .collect();
+        labels.sort();
+        assert_eq!(
+            labels,
+            vec![
+                ("3. ".to_string(), "(c) ".to_string()),
+                ("4. ".to_string(), "(d) ".to_string())
+            ]
+        );
+    }
+
+    fn captures(html: &str, options: &Options) -> Vec<CapturedAttributes> {
+        let dom = Html::parse_document(html);
+        let mut captured = Vec::new();
+        precompute_metadata_with_stats(
+            &dom,
+            &CompiledSelectors::new(options),
+            options,
+            None,
+            Some(&mut captured),
+        );
+        captured
+    }
+
+    #[test]
+    fn test_capture_attributes() {
+        let options = Options::new().capture_attributes(vec![
+            (
+                "a[data-id]".to_string(),
+                vec!["data-id".to_string(), "href".to_string()],
+            ),
+            (
+                "img".to_string(),
+                vec!["loading".to_string(), "missing".to_string()],
+            ),
+        ]);
+        let html = r#"<p><a data-id="7" href="/x">X</a><a href="/y">Y</a><img src="a.png" loading="lazy"></p>"#;
+        let captured = captures(html, &options);
+
+        assert_eq!(captured.len(), 2);
+        assert_eq!(captured[0].selector, "a[data-id]");
+        assert_eq!(captured[0].tag, "a");
+        assert_eq!(captured[0].attrs["data-id"], "7");
+        assert_eq!(captured[0].attrs["href"], "/x");
+        assert_eq!(captured[1].tag, "img");
+        assert_eq!(captured[1].attrs.len(), 1);
+        assert_eq!(captured[1].attrs["loading"], "lazy");
+        // head, body, p and the first a come before the img
+        assert!(captured[0].index < captured[1].index);
+        assert_eq!(captured[1].index, captured[0].index + 2);
+    }
+
+    #[test]
+    fn test_capture_skips_excluded_elements() {
+        let options = Options::new()
+            .exclude_selectors(vec!["nav".to_string()])
+            .capture_attributes(vec![("a".to_string(), vec!["href".to_string()])]);
+        let html = r#"<nav><a href="/menu">Menu</a></nav><a href="/body">Body</a>"#;
+        let captured = captures(html, &options);
+
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].attrs["href"], "/body");
+    }
+
+    #[test]
+    fn test_invalid_capture_selector_ignored() {
+        let options = Options::new().capture_attributes(vec![("a[".to_string(), vec![])]);
+        assert!(captures("<a href='/'>x</a>", &options).is_empty());
+    }
 }
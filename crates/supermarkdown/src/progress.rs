@@ -0,0 +1,138 @@
+//! Progress reporting for `convert_with_progress`.
+//!
+//! Progress is a percentage reported at phase boundaries. Parsing and
+//! precomputing are quick next to conversion, so most of the range follows
+//! the share of precomputed elements converted so far. Each value is
+//! reported once, in increasing order, ending at 100.
+
+use std::cell::Cell;
+
+/// Reported once the document is parsed.
+const PARSED: u8 = 10;
+/// Reported once metadata is precomputed; conversion starts here.
+const PRECOMPUTED: u8 = 20;
+/// Reported once every element is converted; post-processing remains.
+const CONVERTED: u8 = 90;
+/// Reported once the output is post-processed.
+const DONE: u8 = 100;
+
+/// Progress state for one conversion.
+pub(crate) struct Progress<'a> {
+    report: &'a dyn Fn(u8),
+    last: Cell<u8>,
+    elements: Cell<usize>,
+    converted: Cell<usize>,
+}
+
+impl<'a> Progress<'a> {
+    pub(crate) fn new(report: &'a dyn Fn(u8)) -> Self {
+        Self {
+            report,
+            last: Cell::new(0),
+            elements: Cell::new(0),
+            converted: Cell::new(0),
+        }
+    }
+
+    pub(crate) fn parsed(&self) {
+        self.report(PARSED);
+    }
+
+    /// Record the element count the conversion share is measured against.
+    pub(crate) fn precomputed(&self, elements: usize) {
+        self.elements.set(elements.max(1));
+        self.report(PRECOMPUTED);
+    }
+
+    /// Record one converted element; reports only when the percentage moves.
+    pub(crate) fn element(&self) {
+        let converted = (self.converted.get() + 1).min(self.elements.get());
+        self.converted.set(converted);
+        let span = (CONVERTED - PRECOMPUTED) as usize;
+        let percent = PRECOMPUTED as usize + converted * span / self.elements.get().max(1);
+        self.report(percent as u8);
+    }
+
+    /// Skipped subtrees are never visited, so this closes the gap they leave.
+    pub(crate) fn converted(&self) {
+        self.report(CONVERTED);
+    }
+
+    pub(crate) fn finished(&self) {
+        self.report(DONE);
+    }
+
+    fn report(&self, percent: u8) {
+        if percent > self.last.get() {
+            self.last.set(percent);
+            (self.report)(percent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::{convert_with_progress, Options};
+
+    fn progress_of(html: &str, options: &Options) -> (String, Vec<u8>) {
+        let values = RefCell::new(Vec::new());
+        let markdown = convert_with_progress(html, options, |p| values.borrow_mut().push(p));
+        (markdown, values.into_inner())
+    }
+
+    fn large_document() -> String {
+        let mut html = String::from("<html><body>");
+        for i in 0..2000 {
+            html.push_str(&format!(
+                "<h2>Section {i}</h2><p>Paragraph <em>{i}</em> with <a href=\"/{i}\">a link</a>.</p>\
+                 <ul><li>One</li><li>Two</li></ul>"
+            ));
+        }
+        html.push_str("</body></html>");
+        html
+    }
+
+    #[test]
+    fn test_large_document_progress_increases() {
+        let html = large_document();
+        let (markdown, values) = progress_of(&html, &Options::default());
+
+        assert_eq!(markdown, crate::convert(&html));
+        assert!(values.windows(2).all(|w| w[0] < w[1]), "{:?}", values);
+        assert_eq!(values.first(), Some(&10));
+        assert_eq!(values.last(), Some(&100));
+        // Conversion reports every percent between the phase boundaries
+        assert!((20..=90).all(|p| values.contains(&p)), "{:?}", values);
+    }
+
+    #[test]
+    fn test_small_document_still_finishes() {
+        let (markdown, values) = progress_of("<p>Hi</p>", &Options::default());
+        assert_eq!(markdown, "Hi\n");
+        assert!(values.windows(2).all(|w| w[0] < w[1]), "{:?}", values);
+        assert_eq!(values.last(), Some(&100));
+    }
+
+    #[test]
+    fn test_empty_input_reports_done() {
+        let (markdown, values) = progress_of("", &Options::default());
+        assert_eq!(markdown, "");
+        assert_eq!(values, vec![100]);
+    }
+
+    #[test]
+    fn test_skipped_and_truncated_conversions_finish() {
+        let html = large_document();
+        let options = Options::new().exclude_selectors(vec!["ul".to_string()]);
+        let (_, values) = progress_of(&html, &options);
+        assert!(values.windows(2).all(|w| w[0] < w[1]), "{:?}", values);
+        assert_eq!(values.last(), Some(&100));
+
+        let options = Options::new().max_elements(Some(100));
+        let (_, values) = progress_of(&html, &options);
+        assert!(values.windows(2).all(|w| w[0] < w[1]), "{:?}", values);
+        assert_eq!(values.last(), Some(&100));
+    }
+}
@@ -0,0 +1,245 @@
+//! Property tests over generated input.
+//!
+//! Each property runs against a few hundred inputs from a seeded generator,
+//! so failures reproduce exactly; set `SUPERMARKDOWN_PROPTEST_SEED` to try
+//! other inputs. The cargo-fuzz target in `fuzz/` explores further.
+
+use crate::entities::decode_entities;
+use crate::escape::resolve_url;
+use crate::options::{
+    DetailsStyle, EscapeMode, OlTypeHandling, Options, StrikethroughStyle, SvgHandling, TableStyle,
+};
+use crate::{convert, convert_with_options};
+
+const CASES: usize = 500;
+
+/// xorshift64* generator; good enough to spread inputs, and dependency free.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let seed = std::env::var("SUPERMARKDOWN_PROPTEST_SEED")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0x5eed_0fc0_ffee);
+        Rng(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    /// Concatenate `1..=max` picks from `items`.
+    fn string(&mut self, items: &[&str], max: usize) -> String {
+        let len = 1 + self.below(max);
+        (0..len).map(|_| self.pick(items)).collect()
+    }
+}
+
+/// Pieces of HTML that exercise entities, control characters and markup.
+const HTML_PIECES: &[&str] = &[
+    "<p>",
+    "</p>",
+    "<div>",
+    "</div>",
+    "<pre>",
+    "</pre>",
+    "<code>",
+    "</code>",
+    "<a href=\"",
+    "\">",
+    "</a>",
+    "<img alt=\"",
+    "\" src=\"x.png\">",
+    "<table><tr><td>",
+    "</td></tr></table>",
+    "<ul><li>",
+    "</li></ul>",
+    "<br>",
+    "<h2>",
+    "</h2>",
+    "text",
+    " ",
+    "\n",
+    "\0",
+    "\u{1}",
+    "&amp;",
+    "&#0;",
+    "&#x0;",
+    "&amp;#0;",
+    "&amp;#x0;",
+    "&#x110000;",
+    "&amp;#x110000;",
+    "&amp;#xD800;",
+    "&#99999999999;",
+    "&lt;",
+    "&lt;b&gt;",
+    "&nbsp;",
+    "&",
+    "#",
+    ";",
+    "é",
+    "`",
+    "*",
+    "|",
+    "<!-- c -->",
+    "<script>",
+    "</script>",
+];
+
+/// Pieces of text that look like entities.
+const ENTITY_PIECES: &[&str] = &[
+    "&",
+    "#",
+    "x",
+    "X",
+    ";",
+    "0",
+    "1",
+    "9",
+    "D800",
+    "110000",
+    "FFFFFFFFFF",
+    "99999999999",
+    "amp",
+    "lt",
+    "hellip",
+    "le",
+    "a",
+    " ",
+    "é",
+    "\u{10FFFF}",
+];
+
+/// Words and punctuation of plain prose.
+const PROSE_PIECES: &[&str] = &[
+    "the", "quick", "Fox", "jumps", "over", "42", "a", " ", " ", " ", ".", ",", "!", "?", "'",
+    "\"", "(", ")", "-", "é", "—",
+];
+
+/// Elements written as raw HTML by default or by some option, and text that
+/// looks like tags.
+const RAW_HTML_PIECES: &[&str] = &[
+    "<kbd>Ctrl</kbd>",
+    "<kbd><kbd>Alt</kbd>+<kbd>F4</kbd></kbd>",
+    "<mark>hit</mark>",
+    "<abbr title=\"Title\">ABBR</abbr>",
+    "<samp>out</samp>",
+    "<var>n</var>",
+    "<u>under</u>",
+    "<sup>2</sup>",
+    "<sub>i</sub>",
+    "<del>~old</del>",
+    "<details open><summary>More</summary><p>Hidden</p></details>",
+    "<svg><title>Icon</title></svg>",
+    "<table><tr><th>A</th></tr><tr><td>1</td></tr></table>",
+    "<ol type=\"i\"><li>item</li></ol>",
+    "&lt;div&gt;",
+    "&lt;/p",
+    "a &lt; b",
+    "<p>",
+    "</p>",
+    " ",
+    "text",
+];
+
+/// Relative URL pieces; no `:` so nothing reads as a scheme.
+const URL_PIECES: &[&str] = &[
+    "/", "//", "..", ".", "a", "page", "x.html", "?", "q=1", "#", "frag", "%20", " ", "é",
+];
+
+const BASES: &[&str] = &[
+    "https://example.com",
+    "https://example.com/",
+    "https://example.com/dir/page",
+    "http://example.com/dir/",
+    "https://example.com/page?x=1#top",
+];
+
+#[test]
+fn prop_output_never_contains_nul() {
+    let mut rng = Rng::new();
+    for _ in 0..CASES {
+        let html = rng.string(HTML_PIECES, 40);
+        let markdown = convert(&html);
+        assert!(!markdown.contains('\0'), "NUL in output for {:?}", html);
+    }
+}
+
+#[test]
+fn prop_prose_roundtrip_is_idempotent() {
+    let mut rng = Rng::new();
+    for _ in 0..CASES {
+        let prose = rng.string(PROSE_PIECES, 30);
+        let once = convert(&format!("<p>{}</p>", prose));
+        let twice = convert(&format!("<p>{}</p>", once.trim_end()));
+        assert_eq!(once, twice, "not idempotent for {:?}", prose);
+    }
+}
+
+#[test]
+fn prop_no_raw_html_when_disallowed() {
+    let options = Options::new()
+        .allow_raw_html(false)
+        .strikethrough_style(StrikethroughStyle::Html)
+        .svg_handling(SvgHandling::Html)
+        .details_style(DetailsStyle::HtmlDetails)
+        .table_style(TableStyle::Html)
+        .ol_type_handling(OlTypeHandling::Html)
+        .escape_mode(EscapeMode::None);
+    let mut rng = Rng::new();
+    for _ in 0..CASES {
+        let html = rng.string(RAW_HTML_PIECES, 12);
+        let markdown = convert_with_options(&html, &options);
+        let tag = markdown
+            .as_bytes()
+            .windows(2)
+            .any(|pair| pair[0] == b'<' && (pair[1].is_ascii_alphabetic() || pair[1] == b'/'));
+        assert!(!tag, "raw HTML in {:?} for {:?}", markdown, html);
+    }
+}
+
+#[test]
+fn prop_decode_entities_never_grows() {
+    let mut rng = Rng::new();
+    for _ in 0..CASES {
+        let text = rng.string(ENTITY_PIECES, 20);
+        let decoded = decode_entities(&text, 1.0);
+        assert!(
+            decoded.len() <= text.len(),
+            "{:?} decoded to longer {:?}",
+            text,
+            decoded
+        );
+        assert!(!decoded.contains('\0'), "NUL decoded from {:?}", text);
+    }
+}
+
+#[test]
+fn prop_resolve_url_keeps_base_scheme() {
+    let mut rng = Rng::new();
+    for _ in 0..CASES {
+        let base = rng.pick(BASES);
+        let relative = rng.string(URL_PIECES, 8);
+        let scheme = &base[..base.find("://").unwrap() + 3];
+        let resolved = resolve_url(base, &relative);
+        assert!(
+            resolved.starts_with(scheme),
+            "{:?} against {:?} resolved to {:?}",
+            relative,
+            base,
+            resolved
+        );
+    }
+}
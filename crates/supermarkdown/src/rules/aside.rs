@@ -0,0 +1,179 @@
+//! Aside and dialog rule.
+//!
+//! Pull quotes and "related articles" boxes read as part of the article
+//! when their content flows inline, so `aside_handling` can set them
+//! apart. Skipped elements never reach this rule: they are dropped at
+//! precompute like excluded ones, so `include_selectors` can keep them.
+
+use scraper::ElementRef;
+
+use crate::options::{AsideHandling, Options};
+use crate::precompute::MetadataMap;
+use crate::rules::blockquote::quote_lines;
+use crate::rules::heading::format_heading;
+use crate::rules::{ConversionContext, Rule};
+use crate::whitespace::normalize_block_whitespace;
+
+pub struct AsideRule;
+
+impl Rule for AsideRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["aside", "dialog"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        let handling = if element.value().name() == "dialog" {
+            options.dialog_handling
+        } else {
+            options.aside_handling
+        };
+
+        let content = convert_children(element, metadata, options);
+        match handling {
+            // Skip reaches here only for a force-kept element
+            AsideHandling::Inline | AsideHandling::Skip => content,
+            AsideHandling::Blockquote => {
+                let content = content.trim();
+                if content.is_empty() {
+                    return String::new();
+                }
+                format!("\n\n{}\n\n", quote_lines(content))
+            }
+            AsideHandling::Labeled => {
+                let (label, content) =
+                    label(element, &content, metadata, options, convert_children);
+                match (label.is_empty(), content.is_empty()) {
+                    (true, true) => String::new(),
+                    (true, false) => format!("\n\n{}\n\n", content),
+                    (false, true) => format!("\n\n**{}**\n\n", label),
+                    (false, false) => format!("\n\n**{}**\n\n{}\n\n", label, content),
+                }
+            }
+        }
+    }
+}
+
+/// The label for an element and its content without the heading the label
+/// came from.
+fn label<'a>(
+    element: ElementRef,
+    content: &'a str,
+    metadata: &MetadataMap,
+    options: &Options,
+    convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+) -> (String, &'a str) {
+    let content = content.trim();
+    if let Some(label) = element.value().attr("aria-label") {
+        return (
+            normalize_block_whitespace(label.trim()).into_owned(),
+            content,
+        );
+    }
+
+    let heading = element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .next()
+        .filter(|child| {
+            matches!(
+                child.value().name(),
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+            )
+        });
+    let Some(heading) = heading else {
+        return (String::new(), content);
+    };
+    let level: usize = heading.value().name()[1..].parse().unwrap_or(1);
+    let text = convert_children(heading, metadata, options);
+    let written = format_heading(level, &text, options);
+    match content.strip_prefix(written.trim()) {
+        Some(rest) => (
+            normalize_block_whitespace(text.trim()).into_owned(),
+            rest.trim_start(),
+        ),
+        // Changed on the way, e.g. by a source map marker: keep it
+        None => (String::new(), content),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELATED: &str = r#"<p>Before.</p>
+        <aside><h3>Related articles</h3><ul><li><a href="/a">First</a></li>
+            <li><a href="/b">Second</a></li></ul></aside>
+        <p>After.</p>"#;
+
+    fn convert_with(html: &str, handling: AsideHandling) -> String {
+        crate::convert_with_options(html, &Options::new().aside_handling(handling))
+    }
+
+    #[test]
+    fn test_inline_by_default() {
+        assert_eq!(
+            crate::convert(RELATED),
+            "Before.\n\n### Related articles\n\n- [First](/a)\n- [Second](/b)\n\nAfter.\n"
+        );
+    }
+
+    #[test]
+    fn test_blockquote() {
+        assert_eq!(
+            convert_with(RELATED, AsideHandling::Blockquote),
+            "Before.\n\n> ### Related articles\n>\n> - [First](/a)\n> - [Second](/b)\n\nAfter.\n"
+        );
+    }
+
+    #[test]
+    fn test_labeled_from_heading() {
+        assert_eq!(
+            convert_with(RELATED, AsideHandling::Labeled),
+            "Before.\n\n**Related articles**\n\n- [First](/a)\n- [Second](/b)\n\nAfter.\n"
+        );
+    }
+
+    #[test]
+    fn test_labeled_from_aria_label() {
+        let html = r#"<aside aria-label="Pull quote"><p>Small teams ship.</p></aside>
+            <aside><p>No label here.</p></aside>"#;
+        assert_eq!(
+            convert_with(html, AsideHandling::Labeled),
+            "**Pull quote**\n\nSmall teams ship.\n\nNo label here.\n"
+        );
+    }
+
+    #[test]
+    fn test_skip_overridden_by_include() {
+        let html = r#"<p>Text.</p><aside class="keep">Kept.</aside>
+            <aside>Gone.<span class="keep">Kept too.</span></aside>"#;
+        let options = Options::new()
+            .aside_handling(AsideHandling::Skip)
+            .include_selectors(vec![".keep".to_string()]);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "Text.\n\nKept.\n\nKept too.\n"
+        );
+        assert_eq!(convert_with(html, AsideHandling::Skip), "Text.\n");
+    }
+
+    #[test]
+    fn test_dialog_skipped_by_default() {
+        let html = r#"<p>Article.</p><dialog open><p>Subscribe!</p></dialog>"#;
+        assert_eq!(crate::convert(html), "Article.\n");
+
+        let options = Options::new().dialog_handling(AsideHandling::Labeled);
+        let html = r#"<dialog aria-label="Newsletter"><p>Subscribe!</p></dialog>"#;
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "**Newsletter**\n\nSubscribe!\n"
+        );
+    }
+}
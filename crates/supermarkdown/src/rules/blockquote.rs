@@ -2,9 +2,10 @@
 
 use scraper::ElementRef;
 
-use crate::options::Options;
+use crate::options::{BlockquoteOverflow, Options};
+use crate::postprocess::FenceState;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 pub struct BlockquoteRule;
 
@@ -18,8 +19,19 @@ impl Rule for BlockquoteRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
+        let depth = metadata
+            .get(&element.id())
+            .map_or(1, |meta| meta.quote_depth);
+        let overflow = options.max_blockquote_depth.filter(|&max| depth > max);
+
+        if overflow.is_some() && options.blockquote_overflow == BlockquoteOverflow::Truncate {
+            // Deeper quotes are inside this one, so never reached
+            return "\n\n[…]\n\n".to_string();
+        }
+
         let content = convert_children(element, metadata, options);
         let content = content.trim();
 
@@ -27,21 +39,40 @@ impl Rule for BlockquoteRule {
             return String::new();
         }
 
-        // Prefix each line with "> "
-        let quoted: String = content
-            .lines()
-            .map(|line| {
-                if line.is_empty() {
-                    ">".to_string()
-                } else {
-                    format!("> {}", line)
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        format!("\n\n{}\n\n", quoted)
+        // Past the limit, content joins the enclosing quote unprefixed
+        if overflow.is_some() {
+            return format!("\n\n{}\n\n", content);
+        }
+
+        format!("\n\n{}\n\n", quote_lines(content))
+    }
+}
+
+/// Prefix each line of converted block content with "> ".
+///
+/// Block children are separated by runs of blank lines; exactly one is kept
+/// so each becomes a single ">" line (the output is no longer blank-line
+/// collapsed once quoted). Blank lines inside code fences are kept as is.
+pub(crate) fn quote_lines(content: &str) -> String {
+    let mut lines = Vec::new();
+    let mut fence = FenceState::default();
+    let mut prev_blank = false;
+
+    for line in content.lines() {
+        let in_fence = fence.update(line);
+        let blank = line.trim().is_empty();
+        if blank && prev_blank && !in_fence {
+            continue;
+        }
+        prev_blank = blank && !in_fence;
+
+        lines.push(if blank {
+            ">".to_string()
+        } else {
+            format!("> {}", line)
+        });
     }
+    lines.join("\n")
 }
 
 #[cfg(test)]
@@ -55,9 +86,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        BlockquoteRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        BlockquoteRule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -73,6 +108,111 @@ mod tests {
         assert!(result.contains("> Line 2"));
     }
 
+    #[test]
+    fn test_blank_line_runs_collapsed() {
+        let result = convert_test("<blockquote>One\n\n\n\nTwo\n \nThree</blockquote>");
+        assert_eq!(result, "\n\n> One\n>\n> Two\n>\n> Three\n\n");
+    }
+
+    #[test]
+    fn test_multiple_paragraphs() {
+        let result = crate::convert("<blockquote><p>First para</p><p>Second para</p></blockquote>");
+        assert_eq!(result, "> First para\n>\n> Second para\n");
+    }
+
+    #[test]
+    fn test_paragraphs_and_list() {
+        let result = crate::convert(
+            "<blockquote><p>Intro</p><ul><li>a</li><li>b</li></ul><p>End</p></blockquote>",
+        );
+        assert_eq!(result, "> Intro\n>\n> - a\n> - b\n>\n> End\n");
+    }
+
+    #[test]
+    fn test_nested_blockquote_separators() {
+        let result = crate::convert(
+            "<blockquote><p>Outer</p><blockquote><p>Inner</p><p>Inner 2</p></blockquote><p>After</p></blockquote>",
+        );
+        assert_eq!(
+            result,
+            "> Outer\n>\n> > Inner\n> >\n> > Inner 2\n>\n> After\n"
+        );
+    }
+
+    #[test]
+    fn test_blank_lines_in_code_kept() {
+        let result = crate::convert("<blockquote><pre><code>a\n\n\nb</code></pre></blockquote>");
+        assert_eq!(result, "> ```\n> a\n>\n>\n> b\n> ```\n");
+    }
+
+    #[test]
+    fn test_code_fence_between_text() {
+        let expected = "> Some text\n>\n> ```\n> code\n> ```\n>\n> More text\n";
+        let result = crate::convert("<blockquote>Some text<pre>code</pre>More text</blockquote>");
+        assert_eq!(result, expected);
+        let result = crate::convert(
+            "<blockquote><p>Some text</p><pre>code</pre><p>More text</p></blockquote>",
+        );
+        assert_eq!(result, expected);
+
+        // Flattened past the depth limit, the fence still sits apart
+        let html =
+            "<blockquote><blockquote>Some text<pre>code</pre>More text</blockquote></blockquote>";
+        let options = Options::new().max_blockquote_depth(Some(1));
+        assert_eq!(crate::convert_with_options(html, &options), expected);
+    }
+
+    /// An email reply chain: each reply quotes the previous message.
+    fn reply_chain(levels: usize) -> String {
+        let mut html = String::new();
+        for i in 0..levels {
+            html.push_str(&format!("<blockquote type=\"cite\"><p>Reply {}</p>", i + 1));
+        }
+        html.push_str(&"</blockquote>".repeat(levels));
+        html
+    }
+
+    fn convert_chain(options: Options) -> String {
+        crate::convert_with_options(&reply_chain(12), &options.max_blockquote_depth(Some(3)))
+    }
+
+    #[test]
+    fn test_quote_depth_unlimited_by_default() {
+        let result = crate::convert(&reply_chain(12));
+        assert!(result.ends_with(&format!("{}Reply 12\n", "> ".repeat(12))));
+    }
+
+    #[test]
+    fn test_max_depth_flatten() {
+        let result = convert_chain(Options::new());
+        assert!(result.starts_with("> Reply 1\n>\n> > Reply 2\n> >\n> > > Reply 3\n> > >\n"));
+        assert!(result.ends_with("> > > Reply 11\n> > >\n> > > Reply 12\n"));
+        assert!(!result.contains("> > > >"));
+        assert_eq!(result.matches("Reply ").count(), 12);
+    }
+
+    #[test]
+    fn test_max_depth_truncate() {
+        let result =
+            convert_chain(Options::new().blockquote_overflow(BlockquoteOverflow::Truncate));
+        assert_eq!(
+            result,
+            "> Reply 1\n>\n> > Reply 2\n> >\n> > > Reply 3\n> > >\n> > > […]\n"
+        );
+    }
+
+    #[test]
+    fn test_max_depth_counts_quotes_not_siblings() {
+        let html = "<blockquote><p>A</p></blockquote><blockquote><p>B</p><blockquote><p>C</p></blockquote></blockquote>";
+        let options = Options::new()
+            .max_blockquote_depth(Some(1))
+            .blockquote_overflow(BlockquoteOverflow::Truncate);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "> A\n\n> B\n>\n> […]\n"
+        );
+    }
+
     #[test]
     fn test_empty_blockquote() {
         let result = convert_test("<blockquote></blockquote>");
@@ -1,10 +1,11 @@
 //! Line break rule.
 
-use scraper::ElementRef;
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
 
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 pub struct BreakRule;
 
@@ -15,12 +16,166 @@ impl Rule for BreakRule {
 
     fn convert(
         &self,
-        _element: ElementRef,
+        element: ElementRef,
         _metadata: &MetadataMap,
-        _options: &Options,
+        options: &Options,
+        context: &ConversionContext,
         _convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
+        // A break before or after all the text of a block breaks nothing
+        if is_block_edge(element) {
+            return String::new();
+        }
+
+        // A table row is one line, so only raw HTML can break it
+        if context.in_table_cell {
+            return if options.allow_raw_html { "<br>" } else { " " }.to_string();
+        }
+
+        // Legacy content separates paragraphs with <br><br>; the first <br>
+        // of a run becomes the paragraph break and the rest emit nothing
+        if options.collapse_double_br {
+            if is_break(element.prev_siblings().find(|n| !is_blank_text(n))) {
+                return String::new();
+            }
+            if is_break(element.next_siblings().find(|n| !is_blank_text(n))) {
+                return "\n\n".to_string();
+            }
+        }
+
         // Use two trailing spaces for line break (CommonMark)
         "  \n".to_string()
     }
 }
+
+/// Inline elements, which a break at their edge may still be inside the
+/// text of a block from.
+const INLINE_TAGS: &[&str] = &[
+    "a", "abbr", "b", "cite", "code", "del", "em", "font", "i", "ins", "kbd", "label", "mark", "q",
+    "s", "samp", "small", "span", "strike", "strong", "sub", "sup", "time", "u", "var",
+];
+
+/// Whether nothing visible comes before, or nothing after, the `<br>` in
+/// its block: the paragraph, heading, cell or other element around it,
+/// past any inline elements.
+fn is_block_edge(br: ElementRef) -> bool {
+    let mut node = *br;
+    let mut first = true;
+    let mut last = true;
+    loop {
+        first &= !node.prev_siblings().any(|n| is_visible(&n));
+        last &= !node.next_siblings().any(|n| is_visible(&n));
+        if !first && !last {
+            return false;
+        }
+        match node.parent() {
+            Some(parent)
+                if parent
+                    .value()
+                    .as_element()
+                    .is_some_and(|el| INLINE_TAGS.contains(&el.name())) =>
+            {
+                node = parent;
+            }
+            _ => return true,
+        }
+    }
+}
+
+/// Whether a node shows anything: text, or an image or other replaced
+/// element. Line breaks don't count, so `<br><br>text` starts with text.
+fn is_visible(node: &NodeRef<Node>) -> bool {
+    match node.value() {
+        Node::Text(text) => !text.trim().is_empty(),
+        Node::Element(el) => match el.name() {
+            "br" => false,
+            "img" | "svg" | "input" | "select" | "textarea" | "video" | "audio" | "iframe"
+            | "object" | "embed" | "meter" | "progress" => true,
+            _ => node.children().any(|child| is_visible(&child)),
+        },
+        _ => false,
+    }
+}
+
+fn is_break(node: Option<NodeRef<Node>>) -> bool {
+    node.and_then(|n| n.value().as_element())
+        .is_some_and(|el| el.name() == "br")
+}
+
+fn is_blank_text(node: &NodeRef<Node>) -> bool {
+    match node.value() {
+        Node::Text(text) => text.trim().is_empty(),
+        // Comments don't render, so they don't break up a run either
+        Node::Comment(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{convert, convert_with_options, Options};
+
+    #[test]
+    fn test_single_break_unchanged() {
+        let html = "<p>one<br>two</p>";
+        let options = Options::new().collapse_double_br(false);
+        assert_eq!(convert(html), convert_with_options(html, &options));
+        assert_eq!(convert(html), "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_double_break_is_paragraph_break() {
+        assert_eq!(convert("text<br><br>more"), "text\n\nmore\n");
+        assert_eq!(convert("text<br>\n  <br>more"), "text\n\nmore\n");
+    }
+
+    #[test]
+    fn test_break_run_of_three() {
+        assert_eq!(convert("a<br><br><br>b"), "a\n\nb\n");
+        assert_eq!(convert("a<br> <br> <!-- gap --> <br>b<br>c"), "a\n\nb\nc\n");
+    }
+
+    #[test]
+    fn test_break_run_in_blockquote() {
+        assert_eq!(
+            convert("<blockquote>a<br><br><br>b</blockquote>"),
+            "> a\n>\n> b\n"
+        );
+    }
+
+    #[test]
+    fn test_break_in_table_cell() {
+        let html = "<table><tr><th>A</th></tr><tr><td>one<br>two</td></tr></table>";
+        assert!(convert(html).ends_with("\n| one<br>two |\n"));
+        let options = Options::new().allow_raw_html(false);
+        assert!(convert_with_options(html, &options).ends_with("\n| one two |\n"));
+    }
+
+    #[test]
+    fn test_break_at_block_edge_dropped() {
+        assert_eq!(convert("<p><br>text</p>"), "text\n");
+        assert_eq!(convert("<p>text<br></p>"), "text\n");
+        assert_eq!(convert("<h2>Title<br></h2>"), "## Title\n");
+        assert_eq!(
+            convert("<p><b><br>bold</b> text<br><br></p>"),
+            "**bold** text\n"
+        );
+        let cell = convert("<table><tr><th>A</th></tr><tr><td><br>x<br></td></tr></table>");
+        assert!(cell.contains("| x ") && !cell.contains("<br>"));
+        // Not at an edge
+        assert_eq!(convert("<p><span>a<br></span>b</p>"), "a\nb\n");
+        assert_eq!(
+            convert("<p><img src=\"a.png\" alt=\"A\"><br>b</p>"),
+            "![A](a.png)\nb\n"
+        );
+    }
+
+    #[test]
+    fn test_collapse_disabled_stacks_breaks() {
+        let options = Options::new().collapse_double_br(false);
+        assert_eq!(
+            convert_with_options("<p>a<br><br><br>b</p>", &options),
+            "a\n\n\nb\n"
+        );
+    }
+}
@@ -1,11 +1,16 @@
 //! Inline code rule.
 
+use once_cell::sync::Lazy;
+use regex::Regex;
 use scraper::ElementRef;
 
 use crate::escape::calculate_code_backticks;
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{encode_markdown, ConversionContext, Rule};
+
+/// Regex for collapsing whitespace runs, including source line wraps.
+static WS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
 
 pub struct CodeRule;
 
@@ -19,6 +24,7 @@ impl Rule for CodeRule {
         element: ElementRef,
         _metadata: &MetadataMap,
         _options: &Options,
+        context: &ConversionContext,
         _convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         // Check if this is inside a <pre> - if so, let PreRule handle it
@@ -37,21 +43,37 @@ impl Rule for CodeRule {
             return String::new();
         }
 
-        // Calculate required number of backticks
-        let backticks = calculate_code_backticks(&code);
-        let delim = "`".repeat(backticks);
-
-        // Add padding if code starts or ends with backtick
-        let (prefix, suffix) = if code.starts_with('`') || code.ends_with('`') {
-            (" ", " ")
-        } else {
-            ("", "")
-        };
+        // Backticks inside raw HTML like <kbd> show literally in some
+        // renderers, so the code stays HTML too
+        if context.in_raw_html {
+            let code = WS_RE.replace_all(&code, " ");
+            return format!("<code>{}</code>", encode_markdown(&code));
+        }
 
-        format!("{}{}{}{}{}", delim, prefix, code, suffix, delim)
+        code_span(&code)
     }
 }
 
+/// Format text as an inline code span.
+pub(crate) fn code_span(code: &str) -> String {
+    // Inline code renders with collapsed whitespace, and a raw newline
+    // would break the span in strict renderers
+    let code = WS_RE.replace_all(code, " ");
+
+    // Calculate required number of backticks
+    let backticks = calculate_code_backticks(&code);
+    let delim = "`".repeat(backticks);
+
+    // Add padding if code starts or ends with backtick
+    let (prefix, suffix) = if code.starts_with('`') || code.ends_with('`') {
+        (" ", " ")
+    } else {
+        ("", "")
+    };
+
+    format!("{}{}{}{}{}", delim, prefix, code, suffix, delim)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,9 +85,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        CodeRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        CodeRule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -83,6 +109,17 @@ mod tests {
         assert_eq!(convert_test("<code>`start</code>"), "`` `start ``");
     }
 
+    #[test]
+    fn test_code_with_newlines() {
+        assert_eq!(convert_test("<code>foo(a,\n     b)</code>"), "`foo(a, b)`");
+        assert_eq!(convert_test("<code>x\t\ty</code>"), "`x y`");
+    }
+
+    #[test]
+    fn test_code_with_pipe_unescaped() {
+        assert_eq!(convert_test("<code>a | b</code>"), "`a | b`");
+    }
+
     #[test]
     fn test_empty_code() {
         assert_eq!(convert_test("<code></code>"), "");
@@ -4,7 +4,7 @@ use scraper::ElementRef;
 
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 /// Rule for definition list container `<dl>`.
 pub struct DefListRule;
@@ -19,6 +19,7 @@ impl Rule for DefListRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let mut result = String::from("\n\n");
@@ -81,11 +82,17 @@ impl Rule for DefTermRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        // When standalone (not inside dl), just return the text
+        // When standalone (not inside dl), the term gets a line of its own
         let content = convert_children(element, metadata, options);
-        content.trim().to_string()
+        let content = content.trim();
+        if content.is_empty() {
+            String::new()
+        } else {
+            format!("\n\n{}\n", content)
+        }
     }
 }
 
@@ -102,16 +109,24 @@ impl Rule for DefDescRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        // When standalone (not inside dl), just return the text with colon prefix
+        // When standalone (not inside dl), keep the colon prefix on its own
+        // line, directly below a preceding term or definition
         let content = convert_children(element, metadata, options);
         let content = content.trim();
         if content.is_empty() {
-            String::new()
-        } else {
-            format!(": {}", content)
+            return String::new();
         }
+
+        let follows_entry = element
+            .prev_siblings()
+            .find_map(ElementRef::wrap)
+            .is_some_and(|prev| matches!(prev.value().name(), "dt" | "dd"));
+        let lead = if follows_entry { "" } else { "\n\n" };
+
+        format!("{}: {}\n", lead, content.replace('\n', "\n  "))
     }
 }
 
@@ -126,9 +141,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        DefListRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        DefListRule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -178,4 +197,24 @@ mod tests {
         let result = convert_test("<dl></dl>");
         assert!(result.trim().is_empty());
     }
+
+    #[test]
+    fn test_orphan_dt_sequence() {
+        let result = crate::convert("<div><dt>First</dt><dt>Second</dt></div>");
+        assert_eq!(result, "First\n\nSecond\n");
+    }
+
+    #[test]
+    fn test_orphan_dt_dd_pairs() {
+        let html =
+            "<div><dt>Term 1</dt><dd>Def 1</dd><dd>Def 1b</dd><dt>Term 2</dt><dd>Def 2</dd></div>";
+        let result = crate::convert(html);
+        assert_eq!(result, "Term 1\n: Def 1\n: Def 1b\n\nTerm 2\n: Def 2\n");
+    }
+
+    #[test]
+    fn test_orphan_dd_after_paragraph() {
+        let result = crate::convert("<div><p>Intro</p><dd>Loose definition</dd><p>After</p></div>");
+        assert_eq!(result, "Intro\n\n: Loose definition\n\nAfter\n");
+    }
 }
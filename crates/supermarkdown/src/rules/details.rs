@@ -4,9 +4,10 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use scraper::ElementRef;
 
-use crate::options::Options;
+use crate::options::{DetailsStyle, Options};
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::blockquote::quote_lines;
+use crate::rules::{ConversionContext, Rule};
 
 /// Regex for normalizing whitespace.
 static WS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
@@ -23,63 +24,127 @@ impl Rule for DetailsRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        let mut summary = String::new();
-        let mut content = String::new();
-
-        for child in element.children() {
-            if let Some(el) = ElementRef::wrap(child) {
-                if el.value().name() == "summary" {
-                    let s = convert_children(el, metadata, options);
-                    summary = WS_RE.replace_all(s.trim(), " ").to_string();
+        let summary = own_summary(element)
+            .map(|el| {
+                let s = convert_children(el, metadata, options);
+                WS_RE.replace_all(s.trim(), " ").into_owned()
+            })
+            .unwrap_or_default();
+
+        // The summary converts to nothing here (see SummaryRule), so this is
+        // every other child through its own rule: code blocks, tables, lists
+        let content = convert_children(element, metadata, options);
+        let content = content.trim();
+
+        if summary.is_empty() && content.is_empty() {
+            return String::new();
+        }
+
+        match options.details_style {
+            DetailsStyle::HtmlDetails if options.allow_raw_html => {
+                let open = element.value().attr("open").is_some();
+                let mut result = String::from(if open {
+                    "\n\n<details open>\n"
                 } else {
-                    content.push_str(&convert_children(el, metadata, options));
+                    "\n\n<details>\n"
+                });
+                if !summary.is_empty() {
+                    result.push_str(&format!("<summary>{}</summary>\n", summary));
+                }
+                // GitHub only renders markdown set off from the tags by
+                // blank lines
+                if !content.is_empty() {
+                    result.push('\n');
+                    result.push_str(content);
+                    result.push('\n');
+                }
+                result.push_str("\n</details>\n\n");
+                result
+            }
+            DetailsStyle::Blockquote | DetailsStyle::HtmlDetails => {
+                // Format as blockquote with summary as bold header
+                let mut result = String::from("\n\n");
+                if !summary.is_empty() {
+                    result.push_str(&format!("> **{}**", summary));
+                    if !content.is_empty() {
+                        result.push_str("\n>\n");
+                    }
                 }
-            } else if let Some(text) = child.value().as_text() {
-                content.push_str(text);
+                result.push_str(&quote_lines(content));
+                result.push_str("\n\n");
+                result
             }
         }
+    }
+}
 
-        let content = content.trim();
+/// The `<summary>` labelling a details element: its first summary child.
+fn own_summary(details: ElementRef) -> Option<ElementRef> {
+    details
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|el| el.value().name() == "summary")
+}
 
-        if summary.is_empty() && content.is_empty() {
+/// Rule for `<summary>` outside `<details>`, or a second one inside (the
+/// first is the details' label, written by DetailsRule).
+pub struct SummaryRule;
+
+impl Rule for SummaryRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["summary"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        // A details' own summary is written by DetailsRule
+        let parent = element.parent().and_then(ElementRef::wrap);
+        if parent.is_some_and(|p| p.value().name() == "details" && own_summary(p) == Some(element))
+        {
             return String::new();
         }
 
-        // Format as blockquote with summary as bold header
-        let mut result = String::from("\n\n");
-        if !summary.is_empty() {
-            result.push_str(&format!("> **{}**\n>\n", summary));
-        }
-        for line in content.lines() {
-            if line.is_empty() {
-                result.push_str(">\n");
-            } else {
-                result.push_str(&format!("> {}\n", line));
-            }
+        let content = convert_children(element, metadata, options);
+        let content = WS_RE.replace_all(content.trim(), " ");
+        if content.is_empty() {
+            return String::new();
         }
-        result.push('\n');
-        result
+
+        // Same bold header a details summary gets, as its own block
+        format!("\n\n**{}**\n\n", content)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use scraper::Html;
 
     fn convert_test(html: &str) -> String {
-        let dom = Html::parse_fragment(html);
-        let element = dom.root_element().first_child().unwrap();
-        let element = ElementRef::wrap(element).unwrap();
-        let metadata = MetadataMap::default();
+        crate::convert(html)
+    }
 
-        DetailsRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+    fn convert_html_details(html: &str) -> String {
+        let options = Options::new().details_style(DetailsStyle::HtmlDetails);
+        crate::convert_with_options(html, &options)
     }
 
+    const CODE_DETAILS: &str = r#"<details open>
+        <summary>Show <em>example</em></summary>
+        <p>Run it with:</p>
+        <pre><code class="language-bash">cargo run
+
+cargo test</code></pre>
+    </details>"#;
+
     #[test]
     fn test_details_with_summary() {
         let result = convert_test(
@@ -88,20 +153,102 @@ mod tests {
                 <p>Hidden content</p>
             </details>"#,
         );
-        assert!(result.contains("> **Click to expand**"));
-        assert!(result.contains("> Hidden content"));
+        assert_eq!(result, "> **Click to expand**\n>\n> Hidden content\n");
     }
 
     #[test]
     fn test_details_without_summary() {
         let result = convert_test("<details><p>Just content</p></details>");
-        assert!(result.contains("> Just content"));
-        assert!(!result.contains("**"));
+        assert_eq!(result, "> Just content\n");
     }
 
     #[test]
     fn test_empty_details() {
-        let result = convert_test("<details></details>");
-        assert!(result.is_empty());
+        assert!(convert_test("<details></details>").is_empty());
+        assert!(convert_html_details("<details open> </details>").is_empty());
+    }
+
+    #[test]
+    fn test_details_with_code_block() {
+        assert_eq!(
+            convert_test(CODE_DETAILS),
+            "> **Show *example***\n>\n> Run it with:\n>\n\
+             > ```bash\n> cargo run\n>\n> cargo test\n> ```\n"
+        );
+    }
+
+    #[test]
+    fn test_html_details_with_code_block() {
+        assert_eq!(
+            convert_html_details(CODE_DETAILS),
+            "<details open>\n<summary>Show *example*</summary>\n\n\
+             Run it with:\n\n```bash\ncargo run\n\ncargo test\n```\n\n</details>\n"
+        );
+    }
+
+    #[test]
+    fn test_html_details_closed_without_summary() {
+        assert_eq!(
+            convert_html_details("<details><p>Text</p></details>"),
+            "<details>\n\nText\n\n</details>\n"
+        );
+    }
+
+    #[test]
+    fn test_details_with_table_and_list() {
+        let html = "<details><summary>Specs</summary>
+            <table><tr><th>Size</th><th>Weight</th></tr><tr><td>M</td><td>2 kg</td></tr></table>
+            <ul><li>Steel</li><li>Oak</li></ul>
+        </details>";
+        assert_eq!(
+            convert_test(html),
+            "> **Specs**\n>\n> | Size | Weight |\n> | ---- | ------ |\n> | M    | 2 kg   |\n>\n\
+             > - Steel\n> - Oak\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_details() {
+        let html = "<details><summary>Outer</summary><details><summary>Inner</summary>\
+            <p>Deep</p></details></details>";
+        assert_eq!(
+            convert_test(html),
+            "> **Outer**\n>\n> > **Inner**\n> >\n> > Deep\n"
+        );
+    }
+
+    #[test]
+    fn test_second_summary_is_content() {
+        let html = "<details><summary>First</summary><summary>Second</summary>Text</details>";
+        assert_eq!(
+            convert_test(html),
+            "> **First**\n>\n> **Second**\n>\n> Text\n"
+        );
+    }
+
+    #[test]
+    fn test_bare_summary_between_paragraphs() {
+        let html = "<div><p>Before</p><summary>Release notes</summary><p>After</p></div>";
+        let result = crate::convert(html);
+        assert_eq!(result, "Before\n\n**Release notes**\n\nAfter\n");
+    }
+
+    #[test]
+    fn test_html_details_without_raw_html() {
+        let options = Options::new()
+            .details_style(DetailsStyle::HtmlDetails)
+            .allow_raw_html(false);
+        let html = "<details open><summary>More</summary>Hidden text</details>";
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "> **More**\n>\n> Hidden text\n"
+        );
+    }
+
+    #[test]
+    fn test_summary_inside_details_unchanged() {
+        let html = "<details><summary>More</summary>Hidden text</details>";
+        let result = crate::convert(html);
+        assert_eq!(result, "> **More**\n>\n> Hidden text\n");
     }
 }
@@ -4,7 +4,7 @@ use scraper::ElementRef;
 
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 /// Strong/bold rule (** or __).
 pub struct StrongRule;
@@ -19,6 +19,7 @@ impl Rule for StrongRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let content = convert_children(element, metadata, options);
@@ -45,6 +46,7 @@ impl Rule for EmphasisRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let content = convert_children(element, metadata, options);
@@ -69,9 +71,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        StrongRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        StrongRule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     fn convert_em(html: &str) -> String {
@@ -80,9 +86,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        EmphasisRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        EmphasisRule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
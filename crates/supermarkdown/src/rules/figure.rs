@@ -6,13 +6,23 @@ use scraper::ElementRef;
 
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::image::format_image;
+use crate::rules::{ConversionContext, ImageRule, PreRule, Rule, SvgRule, TableRule};
+use crate::url::srcset_url;
 
 /// Regex for normalizing whitespace.
 static WS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
 
 pub struct FigureRule;
 
+/// A converted piece of figure content.
+enum Block {
+    /// A markdown image, kept on its own line
+    Image(String),
+    /// Any other content, set off by blank lines
+    Other(String),
+}
+
 impl Rule for FigureRule {
     fn tags(&self) -> &'static [&'static str] {
         &["figure"]
@@ -23,80 +33,155 @@ impl Rule for FigureRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        let mut image_md = String::new();
+        let mut blocks: Vec<Block> = Vec::new();
         let mut caption = String::new();
 
         for child in element.children() {
             if let Some(el) = ElementRef::wrap(child) {
-                let tag = el.value().name();
-                match tag {
-                    "img" => {
-                        // Convert img directly
-                        let src = el.value().attr("src").unwrap_or("");
-                        let alt = el.value().attr("alt").unwrap_or("");
-
-                        if !src.is_empty() {
-                            image_md = format!("![{}]({})", alt, src);
-                        }
-                    }
+                let block = match el.value().name() {
+                    "img" => Block::Image(ImageRule.convert(
+                        el,
+                        metadata,
+                        options,
+                        context,
+                        convert_children,
+                    )),
+                    "picture" => Block::Image(picture_image(
+                        el,
+                        metadata,
+                        options,
+                        context,
+                        convert_children,
+                    )),
                     "figcaption" => {
                         let c = convert_children(el, metadata, options);
                         caption = WS_RE.replace_all(c.trim(), " ").to_string();
+                        continue;
                     }
-                    "picture" => {
-                        // Handle <picture> element - find the img inside
-                        for pic_child in el.children() {
-                            if let Some(pic_el) = ElementRef::wrap(pic_child) {
-                                if pic_el.value().name() == "img" {
-                                    let src = pic_el.value().attr("src").unwrap_or("");
-                                    let alt = pic_el.value().attr("alt").unwrap_or("");
-                                    if !src.is_empty() {
-                                        image_md = format!("![{}]({})", alt, src);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    _ => {
-                        // Handle other nested elements that might contain images
-                        let nested = convert_children(el, metadata, options);
-                        if image_md.is_empty() && nested.contains("![") {
-                            image_md = nested;
-                        }
-                    }
+                    // Code listings and tables are figures too
+                    "pre" => Block::Other(PreRule.convert(
+                        el,
+                        metadata,
+                        options,
+                        context,
+                        convert_children,
+                    )),
+                    "table" => Block::Other(TableRule.convert(
+                        el,
+                        metadata,
+                        options,
+                        context,
+                        convert_children,
+                    )),
+                    // A chart's label, not its title and axis text
+                    "svg" => Block::Other(SvgRule.convert(
+                        el,
+                        metadata,
+                        options,
+                        context,
+                        convert_children,
+                    )),
+                    _ => Block::Other(convert_children(el, metadata, options)),
+                };
+                blocks.push(block);
+            } else if let Some(text) = child.value().as_text() {
+                blocks.push(Block::Other(WS_RE.replace_all(text, " ").into_owned()));
+            }
+        }
+
+        let mut result = String::new();
+        let mut last_was_image = false;
+        for block in blocks {
+            let (content, is_image) = match &block {
+                Block::Image(content) => (content.trim(), true),
+                // A wrapper around images lays out like the images
+                Block::Other(content) => {
+                    let content = content.trim();
+                    (content, content.lines().all(|line| line.starts_with("![")))
                 }
+            };
+            if content.is_empty() {
+                continue;
             }
+            // Images stack line by line; anything else starts a new block
+            result.push_str(if result.is_empty() {
+                "\n\n"
+            } else if is_image && last_was_image {
+                "\n"
+            } else {
+                "\n\n"
+            });
+            result.push_str(content);
+            last_was_image = is_image;
         }
 
-        if image_md.is_empty() {
+        if result.is_empty() {
             return String::new();
         }
 
-        let mut result = format!("\n\n{}", image_md.trim());
         if !caption.is_empty() {
-            result.push_str(&format!("\n*{}*", caption));
+            // A caption line directly after a table would read as a row
+            let separator = if last_was_image { "\n" } else { "\n\n" };
+            result.push_str(&format!("{}*{}*", separator, caption));
         }
         result.push_str("\n\n");
         result
     }
 }
 
+/// Markdown image for a `<picture>`: its `<img>`, or the first `<source>`
+/// candidate when the img has no usable URL.
+fn picture_image(
+    picture: ElementRef,
+    metadata: &MetadataMap,
+    options: &Options,
+    context: &ConversionContext,
+    convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+) -> String {
+    let children: Vec<ElementRef> = picture.children().filter_map(ElementRef::wrap).collect();
+    let img = children.iter().find(|el| el.value().name() == "img");
+
+    if let Some(img) = img {
+        let image = ImageRule.convert(*img, metadata, options, context, convert_children);
+        if !image.is_empty() {
+            return image;
+        }
+    }
+
+    let alt = img.and_then(|img| img.value().attr("alt")).unwrap_or("");
+    children
+        .iter()
+        .filter(|el| el.value().name() == "source")
+        .find_map(|source| srcset_url(source, options))
+        .map(|src| format_image(alt, &src, None, options))
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use scraper::Html;
 
     fn convert_test(html: &str) -> String {
+        convert_test_with(html, &Options::default())
+    }
+
+    fn convert_test_with(html: &str, options: &Options) -> String {
         let dom = Html::parse_fragment(html);
         let element = dom.root_element().first_child().unwrap();
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        FigureRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        FigureRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -135,9 +220,118 @@ mod tests {
         assert!(result.contains("![A photo](photo.jpg)"));
     }
 
+    #[test]
+    fn test_figure_resolves_against_base() {
+        let options = Options::new().base_url(Some("https://example.com/posts/2024/".to_string()));
+        let result = convert_test_with(
+            r#"<figure><img src="photo.jpg" alt="A photo"></figure>"#,
+            &options,
+        );
+        assert!(result.contains("![A photo](https://example.com/posts/2024/photo.jpg)"));
+    }
+
+    #[test]
+    fn test_figure_picture_source_fallback() {
+        let options = Options::new().base_url(Some("https://example.com/posts/2024/".to_string()));
+        let result = convert_test_with(
+            r#"<figure>
+                <picture>
+                    <source srcset="img/photo.avif 1x, img/photo@2x.avif 2x" type="image/avif">
+                    <img alt="A photo">
+                </picture>
+            </figure>"#,
+            &options,
+        );
+        assert!(result.contains("![A photo](https://example.com/posts/2024/img/photo.avif)"));
+    }
+
+    #[test]
+    fn test_figure_with_two_images() {
+        let result = crate::convert(
+            r#"<figure>
+                <img src="before.png" alt="Before">
+                <img src="after.png" alt="After">
+                <figcaption>Before and after</figcaption>
+            </figure>"#,
+        );
+        assert_eq!(
+            result,
+            "![Before](before.png)\n![After](after.png)\n*Before and after*\n"
+        );
+    }
+
+    #[test]
+    fn test_figure_with_wrapped_image() {
+        let result = crate::convert(
+            r#"<figure><div class="frame"><img src="a.png" alt="A"></div><figcaption>Framed</figcaption></figure>"#,
+        );
+        assert_eq!(result, "![A](a.png)\n*Framed*\n");
+    }
+
+    #[test]
+    fn test_figure_with_code_listing() {
+        let result = crate::convert(
+            r#"<figure>
+                <pre><code class="language-rust">fn main() {}</code></pre>
+                <figcaption>Listing 1: the entry point</figcaption>
+            </figure>"#,
+        );
+        assert_eq!(
+            result,
+            "```rust\nfn main() {}\n```\n\n*Listing 1: the entry point*\n"
+        );
+    }
+
+    #[test]
+    fn test_figure_with_svg_chart() {
+        let result = convert_test(
+            r#"<figure>
+                <svg width="600" height="300" aria-label="Sales by region"><title>Chart</title></svg>
+                <figcaption>Figure 2</figcaption>
+            </figure>"#,
+        );
+        assert_eq!(result, "\n\nSales by region\n\n*Figure 2*\n\n");
+    }
+
+    #[test]
+    fn test_figure_with_table() {
+        // The caption goes after the table even when it comes first
+        let result = crate::convert(
+            r#"<figure>
+                <figcaption>Figure 3: results</figcaption>
+                <table>
+                    <tr><th>Run</th><th>Time</th></tr>
+                    <tr><td>1</td><td>3s</td></tr>
+                </table>
+            </figure>"#,
+        );
+        assert_eq!(
+            result,
+            "| Run | Time |\n| --- | ---- |\n| 1   | 3s   |\n\n*Figure 3: results*\n"
+        );
+    }
+
     #[test]
     fn test_empty_figure() {
         let result = convert_test("<figure></figure>");
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn test_figure_drops_data_uri_image() {
+        use crate::options::DataUriImages;
+
+        let options = Options::new().data_uri_images(DataUriImages::Drop);
+        let result = convert_test_with(
+            r#"<figure><img src="data:image/png;base64,AAAA" alt="x"><figcaption>Shot</figcaption></figure>"#,
+            &options,
+        );
+        assert!(result.is_empty());
+
+        let result = convert_test_with(
+            r#"<figure><picture><source srcset="data:image/webp;base64,AAAA"><img alt="x"></picture></figure>"#,
+            &options,
+        );
+        assert!(result.is_empty());
+    }
 }
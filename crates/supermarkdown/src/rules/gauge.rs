@@ -0,0 +1,273 @@
+//! Gauge rules (meter, progress).
+//!
+//! Dashboards and review sites show scores and completion with `<meter>`
+//! and `<progress>`, which render as bars and have no text of their own.
+//! Fallback text inside the element wins; otherwise the value is written
+//! from the attributes, as `70/100` or `70%` per `Options::gauge_style`.
+
+use scraper::ElementRef;
+
+use crate::options::{GaugeStyle, Options};
+use crate::precompute::MetadataMap;
+use crate::rules::{ConversionContext, Rule};
+
+/// Rule for `<meter>`: a scalar within a known range.
+pub struct MeterRule;
+
+impl Rule for MeterRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["meter"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        if let Some(text) = fallback_text(element, metadata, options, convert_children) {
+            return text;
+        }
+
+        // The spec reads a missing value as 0, but a gauge nobody filled in
+        // says nothing worth writing
+        let Some(value) = number_attr(&element, "value") else {
+            return String::new();
+        };
+        let min = number_attr(&element, "min").unwrap_or(0.0);
+        let max = number_attr(&element, "max").unwrap_or(1.0).max(min);
+        let value = value.clamp(min, max);
+
+        let ratio = if max > min {
+            (value - min) / (max - min)
+        } else {
+            1.0
+        };
+        format_gauge(value, max, ratio, options)
+    }
+}
+
+/// Rule for `<progress>`: completion of a task.
+pub struct ProgressRule;
+
+impl Rule for ProgressRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["progress"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        if let Some(text) = fallback_text(element, metadata, options, convert_children) {
+            return text;
+        }
+
+        // Without a value the progress is indeterminate
+        let Some(value) = number_attr(&element, "value") else {
+            return String::new();
+        };
+        let max = number_attr(&element, "max")
+            .filter(|&max| max > 0.0)
+            .unwrap_or(1.0);
+        let value = value.clamp(0.0, max);
+
+        format_gauge(value, max, value / max, options)
+    }
+}
+
+/// The element's converted content, when it has any.
+fn fallback_text(
+    element: ElementRef,
+    metadata: &MetadataMap,
+    options: &Options,
+    convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+) -> Option<String> {
+    let text = convert_children(element, metadata, options);
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// A numeric attribute, or None when missing or not a finite number.
+fn number_attr(element: &ElementRef, name: &str) -> Option<f64> {
+    element
+        .value()
+        .attr(name)?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+}
+
+fn format_gauge(value: f64, max: f64, ratio: f64, options: &Options) -> String {
+    let text = match options.gauge_style {
+        GaugeStyle::Fraction => format!("{}/{}", format_number(value), format_number(max)),
+        GaugeStyle::Percent => format!("{}%", format_number((ratio * 100.0).round())),
+    };
+    if options.bold_gauges {
+        format!("**{}**", text)
+    } else {
+        text
+    }
+}
+
+/// Shortest decimal form: `70`, `0.5`, never `-0`.
+fn format_number(n: f64) -> String {
+    if n == 0.0 {
+        return "0".to_string();
+    }
+    n.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    fn convert_test(html: &str, options: &Options) -> String {
+        let dom = Html::parse_fragment(html);
+        let element = dom
+            .root_element()
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|el| matches!(el.value().name(), "meter" | "progress"))
+            .unwrap();
+        let metadata = MetadataMap::default();
+        let convert_children =
+            |e: ElementRef, _: &MetadataMap, _: &Options| e.text().collect::<String>();
+
+        match element.value().name() {
+            "meter" => MeterRule.convert(
+                element,
+                &metadata,
+                options,
+                &ConversionContext::default(),
+                &convert_children,
+            ),
+            _ => ProgressRule.convert(
+                element,
+                &metadata,
+                options,
+                &ConversionContext::default(),
+                &convert_children,
+            ),
+        }
+    }
+
+    fn convert(html: &str) -> String {
+        convert_test(html, &Options::default())
+    }
+
+    fn percent() -> Options {
+        Options::new().gauge_style(GaugeStyle::Percent)
+    }
+
+    #[test]
+    fn test_text_content_wins() {
+        let html = r#"<meter value="3" min="0" max="5">3 of 5</meter>"#;
+        assert_eq!(convert(html), "3 of 5");
+        let html = r#"<progress value="70" max="100">70 %</progress>"#;
+        assert_eq!(convert_test(html, &percent()), "70 %");
+    }
+
+    #[test]
+    fn test_progress_value_and_max() {
+        let html = r#"<progress value="70" max="100"></progress>"#;
+        assert_eq!(convert(html), "70/100");
+        assert_eq!(convert_test(html, &percent()), "70%");
+    }
+
+    #[test]
+    fn test_progress_default_max() {
+        let html = r#"<progress value="0.25"></progress>"#;
+        assert_eq!(convert(html), "0.25/1");
+        assert_eq!(convert_test(html, &percent()), "25%");
+    }
+
+    #[test]
+    fn test_progress_invalid_max_falls_back_to_one() {
+        assert_eq!(
+            convert(r#"<progress value="0.5" max="0"></progress>"#),
+            "0.5/1"
+        );
+        assert_eq!(
+            convert(r#"<progress value="0.5" max="lots"></progress>"#),
+            "0.5/1"
+        );
+    }
+
+    #[test]
+    fn test_progress_value_clamped() {
+        assert_eq!(
+            convert(r#"<progress value="150" max="100"></progress>"#),
+            "100/100"
+        );
+        assert_eq!(
+            convert(r#"<progress value="-5" max="100"></progress>"#),
+            "0/100"
+        );
+    }
+
+    #[test]
+    fn test_progress_indeterminate() {
+        assert_eq!(convert("<progress></progress>"), "");
+        assert_eq!(convert(r#"<progress max="100"></progress>"#), "");
+        assert_eq!(
+            convert(r#"<progress value="abc" max="100"></progress>"#),
+            ""
+        );
+        assert_eq!(convert(r#"<progress value="NaN"></progress>"#), "");
+    }
+
+    #[test]
+    fn test_meter_range() {
+        let html = r#"<meter value="3" min="0" max="5"></meter>"#;
+        assert_eq!(convert(html), "3/5");
+        assert_eq!(convert_test(html, &percent()), "60%");
+
+        // The percentage is of the range between min and max
+        let html = r#"<meter value="15" min="10" max="20"></meter>"#;
+        assert_eq!(convert_test(html, &percent()), "50%");
+    }
+
+    #[test]
+    fn test_meter_defaults() {
+        assert_eq!(convert(r#"<meter value="0.6"></meter>"#), "0.6/1");
+        assert_eq!(convert(r#"<meter value="7" max="5"></meter>"#), "5/5");
+        assert_eq!(
+            convert(r#"<meter value="2" min="4" max="1"></meter>"#),
+            "4/4"
+        );
+        assert_eq!(
+            convert_test(r#"<meter value="2" min="4" max="1"></meter>"#, &percent()),
+            "100%"
+        );
+    }
+
+    #[test]
+    fn test_meter_without_value() {
+        assert_eq!(convert(r#"<meter min="0" max="5"></meter>"#), "");
+        assert_eq!(convert(r#"<meter value="high" max="5"></meter>"#), "");
+    }
+
+    #[test]
+    fn test_bold_gauges() {
+        let options = percent().bold_gauges(true);
+        let html = r#"<progress value="70" max="100"></progress>"#;
+        assert_eq!(convert_test(html, &options), "**70%**");
+        // Fallback text is left as written
+        let html = r#"<meter value="3" max="5">3 of 5</meter>"#;
+        assert_eq!(convert_test(html, &options), "3 of 5");
+    }
+
+    #[test]
+    fn test_in_document() {
+        let html = r#"<p>Upload: <progress value="70" max="100"></progress> done</p>"#;
+        assert_eq!(crate::convert(html), "Upload: 70/100 done\n");
+    }
+}
@@ -6,7 +6,8 @@ use scraper::ElementRef;
 
 use crate::options::{HeadingStyle, Options};
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
+use crate::sourcemap::strip_markers;
 
 /// Regex for normalizing whitespace in headings.
 static WS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
@@ -23,30 +24,38 @@ impl Rule for HeadingRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let tag = element.value().name();
         let level: usize = tag[1..].parse().unwrap_or(1);
 
         let content = convert_children(element, metadata, options);
-        let content = WS_RE.replace_all(content.trim(), " ");
+        format_heading(level, &content, options)
+    }
+}
 
-        if content.is_empty() {
-            return String::new();
-        }
+/// Format converted content as a heading of `level` (1-6) in the
+/// configured style. Returns an empty string for empty content.
+pub(crate) fn format_heading(level: usize, content: &str, options: &Options) -> String {
+    let content = WS_RE.replace_all(content.trim(), " ");
 
-        match options.heading_style {
-            HeadingStyle::Atx => {
-                format!("\n\n{} {}\n\n", "#".repeat(level), content)
-            }
-            HeadingStyle::Setext if level <= 2 => {
-                let underline = if level == 1 { "=" } else { "-" };
-                // Use char count for proper unicode handling
-                let len = content.chars().count();
-                format!("\n\n{}\n{}\n\n", content, underline.repeat(len))
-            }
-            _ => format!("\n\n{} {}\n\n", "#".repeat(level), content),
+    if content.is_empty() {
+        return String::new();
+    }
+
+    match options.heading_style {
+        HeadingStyle::Atx => {
+            format!("\n\n{} {}\n\n", "#".repeat(level), content)
+        }
+        HeadingStyle::Setext if level <= 2 => {
+            let underline = if level == 1 { "=" } else { "-" };
+            // Use char count for proper unicode handling, leaving out
+            // source map markers of nested blocks
+            let len = strip_markers(&content).chars().count();
+            format!("\n\n{}\n{}\n\n", content, underline.repeat(len))
         }
+        _ => format!("\n\n{} {}\n\n", "#".repeat(level), content),
     }
 }
 
@@ -61,9 +70,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        HeadingRule.convert(element, &metadata, options, &|e, m, o| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        HeadingRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -4,7 +4,7 @@ use scraper::ElementRef;
 
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 pub struct HorizontalRule;
 
@@ -18,6 +18,7 @@ impl Rule for HorizontalRule {
         _element: ElementRef,
         _metadata: &MetadataMap,
         _options: &Options,
+        _context: &ConversionContext,
         _convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         "\n\n---\n\n".to_string()
@@ -39,6 +40,7 @@ mod tests {
             element,
             &MetadataMap::default(),
             &Options::default(),
+            &ConversionContext::default(),
             &|_, _, _| String::new(),
         );
         assert_eq!(result, "\n\n---\n\n");
@@ -2,10 +2,12 @@
 
 use scraper::ElementRef;
 
-use crate::escape::{escape_title, escape_url, resolve_url};
-use crate::options::Options;
+use crate::escape::{escape_link_text, escape_title, escape_url, resolve_url};
+use crate::options::{DataUriImages, Options, UrlKind};
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{format_link, ConversionContext, Rule};
+use crate::stats::ExtractedImage;
+use crate::url::{attr_url, rewrite_url, srcset_url};
 
 pub struct ImageRule;
 
@@ -17,32 +19,137 @@ impl Rule for ImageRule {
     fn convert(
         &self,
         element: ElementRef,
-        _metadata: &MetadataMap,
+        metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         _convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        let src = element.value().attr("src").unwrap_or("");
-        let alt = element.value().attr("alt").unwrap_or("");
+        let alt = match element.value().attr("alt") {
+            Some(alt) if !alt.trim().is_empty() => alt.to_string(),
+            // Text from the alt text provider, found in precompute
+            _ => metadata
+                .get(&element.id())
+                .and_then(|meta| meta.generated_alt.as_deref())
+                .map(escape_link_text)
+                .unwrap_or_default(),
+        };
+        let alt = alt.as_str();
         let title = element.value().attr("title");
 
         // Skip images without src
-        if src.is_empty() {
-            return String::new();
+        let image = match image_src(element, options) {
+            Some(src) => format_image(alt, &src, title, options),
+            None => String::new(),
+        };
+
+        // An image map's links follow the image as a list
+        let map = metadata
+            .get(&element.id())
+            .and_then(|meta| meta.image_map)
+            .and_then(|id| element.tree().get(id))
+            .and_then(ElementRef::wrap);
+        match map.map(|map| area_links(map, options)) {
+            Some(links) if !links.is_empty() => format!("\n\n{}\n\n{}\n\n", image, links),
+            _ => image,
+        }
+    }
+}
+
+/// A bullet list linking to each `<area href>` of an image map, labelled
+/// by its alt text or else its URL.
+fn area_links(map: ElementRef, options: &Options) -> String {
+    let bullet = options.bullet_for_depth(0);
+    let mut links = Vec::new();
+    for area in map.descendants().filter_map(ElementRef::wrap) {
+        if area.value().name() != "area" {
+            continue;
+        }
+        let Some(href) = area.value().attr("href").map(str::trim) else {
+            continue;
+        };
+        if href.is_empty() {
+            continue;
         }
 
-        // Resolve relative URLs if base_url provided
-        let src = if let Some(base) = &options.base_url {
-            resolve_url(base, src)
-        } else {
-            src.to_string()
+        let href = match &options.base_url {
+            Some(base) => resolve_url(base, href),
+            None => href.to_string(),
         };
+        let href = rewrite_url(UrlKind::Link, &href, options);
+        let text = match area.value().attr("alt").map(str::trim) {
+            Some(alt) if !alt.is_empty() => alt,
+            _ => &href,
+        };
+        let title = area.value().attr("title").filter(|_| !options.compact);
+        links.push(format!(
+            "{} {}",
+            bullet,
+            format_link(text, &escape_url(&href), title)
+        ));
+    }
+    links.join("\n")
+}
+
+/// Resolved image URL; lazy-loaded images keep the real URL in data-src
+/// or srcset.
+fn image_src(element: ElementRef, options: &Options) -> Option<String> {
+    attr_url(&element, &["src", "data-src"], options).or_else(|| srcset_url(&element, options))
+}
+
+/// Source of an image to pass to `alt_text_provider`, or None when the
+/// image is dropped or its data URI replaced by a placeholder.
+pub(crate) fn alt_text_src(element: ElementRef, options: &Options) -> Option<String> {
+    let src = image_src(element, options)?;
+    if !is_data_uri(&src) {
+        return Some(src);
+    }
+    match options.data_uri_images {
+        DataUriImages::Keep => Some(src),
+        DataUriImages::Drop => None,
+        DataUriImages::Placeholder { max_len } => (src.len() <= max_len).then_some(src),
+    }
+}
 
-        let src = escape_url(&src);
+fn is_data_uri(src: &str) -> bool {
+    src.get(..5)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:"))
+}
+
+/// Describe an image for `ConversionMetadata::images`.
+pub(crate) fn extract_image(element: ElementRef, options: &Options) -> ExtractedImage {
+    let src = image_src(element, options);
+    ExtractedImage {
+        dropped: src.is_none(),
+        src: src.unwrap_or_default(),
+        alt: element.value().attr("alt").unwrap_or("").to_string(),
+    }
+}
+
+/// Placeholder URL for data URI images over the `Placeholder` limit.
+const DATA_URI_PLACEHOLDER: &str = "data-image-omitted";
 
-        match title {
-            Some(t) => format!("![{}]({} \"{}\")", alt, src, escape_title(t)),
-            None => format!("![{}]({})", alt, src),
+/// Format a resolved image source as markdown, applying the data URI
+/// policy and, to other URLs, the URL rewriter. Returns an empty string
+/// when the image is dropped.
+pub(crate) fn format_image(alt: &str, src: &str, title: Option<&str>, options: &Options) -> String {
+    let rewritten;
+    let src = if is_data_uri(src) {
+        match options.data_uri_images {
+            DataUriImages::Keep => src,
+            DataUriImages::Drop => return String::new(),
+            DataUriImages::Placeholder { max_len } if src.len() > max_len => DATA_URI_PLACEHOLDER,
+            DataUriImages::Placeholder { .. } => src,
         }
+    } else {
+        rewritten = rewrite_url(UrlKind::Image, src, options);
+        &rewritten
+    };
+
+    let src = escape_url(src);
+
+    match title.filter(|_| !options.compact) {
+        Some(t) => format!("![{}]({} \"{}\")", alt, src, escape_title(t)),
+        None => format!("![{}]({})", alt, src),
     }
 }
 
@@ -57,7 +164,36 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        ImageRule.convert(element, &metadata, options, &|_, _, _| String::new())
+        ImageRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|_, _, _| String::new(),
+        )
+    }
+
+    #[test]
+    fn test_image_map_before_image() {
+        let html = r##"<map name="m"><area href="/a" alt="A" title="Go to A"></map>
+            <p>Pick one: <img src="m.png" alt="Map" usemap="#m"></p>"##;
+        let options = Options::new().bullet_marker('*');
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "Pick one:\n\n![Map](m.png)\n\n* [A](/a \"Go to A\")\n"
+        );
+    }
+
+    #[test]
+    fn test_image_map_without_links() {
+        // An unknown map, or one without usable areas, leaves the image alone
+        for html in [
+            r##"<img src="m.png" alt="Map" usemap="#missing">"##,
+            r##"<img src="m.png" alt="Map" usemap="#m"><map name="m"><area nohref></map>"##,
+            r##"<img src="m.png" alt="Map" usemap="m"><map name="m"><area href="/a"></map>"##,
+        ] {
+            assert_eq!(crate::convert(html), "![Map](m.png)\n", "{}", html);
+        }
     }
 
     #[test]
@@ -96,4 +232,99 @@ mod tests {
         let result = convert_test(r#"<img src="images/photo.jpg" alt="Photo">"#, &options);
         assert!(result.contains("https://example.com/images/photo.jpg"));
     }
+
+    #[test]
+    fn test_lazy_data_src() {
+        let result = convert_test(
+            r#"<img data-src="lazy.png" alt="Lazy">"#,
+            &Options::default(),
+        );
+        assert_eq!(result, "![Lazy](lazy.png)");
+    }
+
+    #[test]
+    fn test_srcset_only_with_base() {
+        let options = Options::new().base_url(Some("https://example.com/blog/post".to_string()));
+        let result = convert_test(
+            r#"<img srcset="img/small.png 480w, img/large.png 960w" alt="Chart">"#,
+            &options,
+        );
+        assert_eq!(result, "![Chart](https://example.com/blog/img/small.png)");
+    }
+
+    const PIXEL: &str =
+        "data:image/gif;base64,R0lGODlhAQABAIAAAAAAAP///yH5BAEAAAAALAAAAAABAAEAAAIBRAA7";
+
+    fn screenshot() -> String {
+        format!("data:image/png;base64,{}", "iVBORw0KGgo".repeat(5000))
+    }
+
+    #[test]
+    fn test_data_uri_kept_by_default() {
+        let result = convert_test(
+            &format!(r#"<img src="{}" alt="pixel">"#, PIXEL),
+            &Options::default(),
+        );
+        assert_eq!(result, format!("![pixel]({})", PIXEL));
+    }
+
+    #[test]
+    fn test_data_uri_drop() {
+        let options = Options::new().data_uri_images(DataUriImages::Drop);
+        let result = convert_test(&format!(r#"<img src="{}" alt="pixel">"#, PIXEL), &options);
+        assert!(result.is_empty());
+
+        // Regular images are unaffected
+        let result = convert_test(r#"<img src="a.png" alt="A">"#, &options);
+        assert_eq!(result, "![A](a.png)");
+    }
+
+    #[test]
+    fn test_data_uri_placeholder() {
+        let options = Options::new().data_uri_images(DataUriImages::Placeholder { max_len: 256 });
+
+        let result = convert_test(&format!(r#"<img src="{}" alt="pixel">"#, PIXEL), &options);
+        assert_eq!(result, format!("![pixel]({})", PIXEL));
+
+        let result = convert_test(
+            &format!(r#"<img src="{}" alt="Screenshot">"#, screenshot()),
+            &options,
+        );
+        assert_eq!(result, "![Screenshot](data-image-omitted)");
+    }
+
+    #[test]
+    fn test_alt_text_provider() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let options = Options::new()
+            .base_url(Some("https://example.com/".to_string()))
+            .exclude_selectors(vec![".ad".to_string()])
+            .data_uri_images(DataUriImages::Drop)
+            .alt_text_provider(Some(Arc::new(move |src: &str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                match src {
+                    "https://example.com/chart.png" => Some("Sales [2024]\nby month".to_string()),
+                    _ => None,
+                }
+            })));
+        let html = r#"<p><img src="chart.png"> <img src="/chart.png" alt=" "></p>
+            <p><img src="logo.png" alt="Logo"> <img src="photo.jpg"> <img src="photo.jpg"></p>
+            <div class="ad"><img src="banner.png"></div>
+            <p><img src="data:image/png;base64,iVBORw0KGgo="> <img></p>"#;
+
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "![Sales \\[2024\\] by month](https://example.com/chart.png) \
+             ![Sales \\[2024\\] by month](https://example.com/chart.png)\n\n\
+             ![Logo](https://example.com/logo.png) ![](https://example.com/photo.jpg) \
+             ![](https://example.com/photo.jpg)\n"
+        );
+        // Once for the chart and once for the photo; never for the image
+        // with alt text, the excluded one or the dropped ones
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
 }
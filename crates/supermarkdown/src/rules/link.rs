@@ -5,9 +5,14 @@ use regex::Regex;
 use scraper::ElementRef;
 
 use crate::escape::{escape_title, escape_url, resolve_url};
-use crate::options::Options;
+use crate::headings::{parse_atx, setext_level};
+use crate::options::{BlockLinkStyle, Options, UrlKind, WikiLinkOptions};
+use crate::postprocess::FenceState;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
+use crate::sourcemap::{split_leading_markers, split_trailing_markers};
+use crate::stats::ExtractedLink;
+use crate::url::{is_internal, percent_decode, rewrite_url};
 
 /// Regex for normalizing whitespace in link text.
 static WS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
@@ -24,17 +29,27 @@ impl Rule for LinkRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let href = element.value().attr("href").unwrap_or("");
-        let title = element.value().attr("title");
+        let mut title = element.value().attr("title").filter(|_| !options.compact);
 
         let content = convert_children(element, metadata, options);
-        let content = WS_RE.replace_all(content.trim(), " ");
+        let blocks = has_block_content(element, &content);
+        let content = if blocks {
+            content.trim().into()
+        } else {
+            WS_RE.replace_all(content.trim(), " ")
+        };
 
         // Handle empty or fragment-only href
         if href.is_empty() || href == "#" {
-            return content.to_string();
+            return if blocks {
+                format!("\n\n{}\n\n", content)
+            } else {
+                content.to_string()
+            };
         }
 
         // Resolve relative URLs if base_url provided
@@ -43,6 +58,36 @@ impl Rule for LinkRule {
         } else {
             href.to_string()
         };
+        let wiki = options
+            .wiki_links
+            .as_ref()
+            .and_then(|wiki| Some((wiki, wiki_target(&href, wiki, options)?)));
+        let href = rewrite_url(UrlKind::Link, &href, options).into_owned();
+
+        // Icon-only links take their text from their accessible name
+        let content = match accessible_name(element) {
+            Some((name, from_title)) if content.is_empty() => {
+                if from_title {
+                    title = None;
+                }
+                WS_RE.replace_all(name, " ").into_owned().into()
+            }
+            _ => content,
+        };
+
+        if blocks {
+            let link = |text: &str| format_link(text, &escape_url(&href), title);
+            if options.block_link_style == BlockLinkStyle::HeadingLink {
+                if let Some(linked) = link_first_heading(&content, link) {
+                    return format!("\n\n{}\n\n", linked);
+                }
+            }
+            return format!("\n\n{}\n\n{}\n\n", content, link("Read more"));
+        }
+
+        if let Some(link) = wiki.and_then(|(wiki, target)| wiki_link(&target, &content, wiki)) {
+            return link;
+        }
 
         // Check for autolink: when link text equals URL or email
         // Email autolink: <a href="mailto:test@example.com">test@example.com</a> → <test@example.com>
@@ -58,22 +103,183 @@ impl Rule for LinkRule {
             if content == href {
                 return format!("<{}>", href);
             }
+            // Compact output also takes text that drops the scheme
+            if options.compact && is_bare_url(&content, &href) {
+                return format!("<{}>", href);
+            }
         }
 
-        let href = escape_url(&href);
+        format_link(&content, &escape_url(&href), title)
+    }
+}
 
-        // All links output as inline format - referenced conversion happens in postprocess
-        match title {
-            Some(t) => {
-                format!("[{}]({} \"{}\")", content, href, escape_title(t))
-            }
-            None => {
-                format!("[{}]({})", content, href)
-            }
+/// Whether `text` is `href` without its `http://` or `https://` scheme
+/// (and trailing slash), as in `<a href="https://example.com/docs">example.com/docs</a>`.
+fn is_bare_url(text: &str, href: &str) -> bool {
+    let rest = href
+        .strip_prefix("https://")
+        .or_else(|| href.strip_prefix("http://"));
+    rest.is_some_and(|rest| text == rest || rest.strip_suffix('/') == Some(text))
+}
+
+/// Page an internal link points at, with its fragment, as written inside a
+/// wiki link, or None for external links.
+fn wiki_target(href: &str, wiki: &WikiLinkOptions, options: &Options) -> Option<String> {
+    let path = match wiki
+        .internal_prefixes
+        .iter()
+        .find_map(|prefix| href.strip_prefix(prefix.as_str()))
+    {
+        Some(path) => path,
+        None if options.base_url.is_none() && is_internal(href, None) => {
+            href.trim_start_matches("./").trim_start_matches('/')
+        }
+        None => return None,
+    };
+
+    let (path, fragment) = match path.split_once('#') {
+        Some((path, fragment)) => (path, fragment),
+        None => (path, ""),
+    };
+    let path = path.split('?').next().unwrap_or(path).trim_end_matches('/');
+    let mut target = percent_decode(strip_extension(path)).into_owned();
+    if !fragment.is_empty() {
+        target.push('#');
+        target.push_str(&percent_decode(fragment));
+    }
+    (!target.is_empty()).then_some(target)
+}
+
+/// `path` without the file extension of its last segment, like `.html`.
+/// Dotted names like `release-1.2` are left alone.
+fn strip_extension(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, extension))
+            if !stem.is_empty()
+                && extension.len() <= 5
+                && !extension.is_empty()
+                && extension.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            &path[..path.len() - extension.len() - 1]
         }
+        _ => path,
+    }
+}
+
+/// `[[target]]`, or `[[target|text]]` when the text is kept as an alias.
+/// None when the text would break the wiki link syntax.
+fn wiki_link(target: &str, text: &str, wiki: &WikiLinkOptions) -> Option<String> {
+    if text.contains("]]") || text.contains('|') {
+        return None;
+    }
+    if wiki.use_text_as_alias && !text.is_empty() && text != target {
+        Some(format!("[[{}|{}]]", target, text))
+    } else {
+        Some(format!("[[{}]]", target))
     }
 }
 
+/// Describe a link for `ConversionMetadata::links`.
+pub(crate) fn extract_link(element: ElementRef, options: &Options) -> ExtractedLink {
+    let href = element.value().attr("href").unwrap_or("");
+    let url = match &options.base_url {
+        Some(base) if !href.is_empty() => resolve_url(base, href),
+        _ => href.to_string(),
+    };
+    let text = element.text().collect::<String>();
+
+    ExtractedLink {
+        internal: is_internal(&url, options.base_url.as_deref()),
+        // Without a target the rule keeps only the text
+        dropped: href.is_empty() || href == "#",
+        href: href.to_string(),
+        url,
+        text: WS_RE.replace_all(text.trim(), " ").into_owned(),
+    }
+}
+
+/// Text for a link without any: its `aria-label`, else its `title`, else
+/// the alt text of an image inside it. The flag is set when the text is the
+/// title.
+fn accessible_name<'a>(element: ElementRef<'a>) -> Option<(&'a str, bool)> {
+    let attr = |name| {
+        element
+            .value()
+            .attr(name)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    if let Some(label) = attr("aria-label") {
+        return Some((label, false));
+    }
+    if let Some(title) = attr("title") {
+        return Some((title, true));
+    }
+    element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|el| el.value().name() == "img")
+        .filter_map(|img| img.value().attr("alt").map(str::trim))
+        .find(|alt| !alt.is_empty())
+        .map(|alt| (alt, false))
+}
+
+/// Format an inline link; referenced conversion happens in postprocess.
+pub(crate) fn format_link(text: &str, href: &str, title: Option<&str>) -> String {
+    match title {
+        Some(t) => format!("[{}]({} \"{}\")", text, href, escape_title(t)),
+        None => format!("[{}]({})", text, href),
+    }
+}
+
+/// Whether a link's converted content is block-level: several blocks, or a
+/// heading, which can't be flattened into link text without losing it.
+fn has_block_content(element: ElementRef, content: &str) -> bool {
+    content.trim().contains("\n\n")
+        || element.descendants().any(|node| {
+            node.value()
+                .as_element()
+                .is_some_and(|el| matches!(el.name(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6"))
+        })
+}
+
+/// Turn the text of the first heading in `content` into a link.
+fn link_first_heading(content: &str, link: impl Fn(&str) -> String) -> Option<String> {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let mut fence = FenceState::default();
+
+    for i in 0..lines.len() {
+        if fence.update(&lines[i]) {
+            continue;
+        }
+
+        // Source map markers stay outside the heading syntax
+        let (markers, line) = split_leading_markers(&lines[i]);
+        if let Some((level, text)) = parse_atx(line) {
+            lines[i] = format!("{}{} {}", markers, "#".repeat(level), link(text));
+            return Some(lines.join("\n"));
+        }
+        let next = lines.get(i + 1).map(|next| split_trailing_markers(next));
+        if let Some((level, end_markers)) =
+            next.and_then(|(next, end)| Some((setext_level(line, next)?, end)))
+        {
+            let linked = link(line);
+            let underline = if level == 1 { "=" } else { "-" };
+            let underline = format!(
+                "{}{}",
+                underline.repeat(linked.chars().count()),
+                end_markers
+            );
+            lines[i] = format!("{}{}", markers, linked);
+            lines[i + 1] = underline;
+            return Some(lines.join("\n"));
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,9 +291,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        LinkRule.convert(element, &metadata, options, &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        LinkRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -175,6 +385,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compact_drops_title_and_scheme() {
+        let options = Options::new().compact(true);
+        let result = convert_test(
+            r#"<a href="https://example.com/docs/" title="tip">example.com/docs</a>"#,
+            &options,
+        );
+        assert_eq!(result, "<https://example.com/docs/>");
+
+        let result = convert_test(
+            r#"<a href="http://example.com" title="tip">Example</a>"#,
+            &options,
+        );
+        assert_eq!(result, "[Example](http://example.com)");
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let options = Options::new().wiki_links(Some(WikiLinkOptions {
+            internal_prefixes: vec!["https://wiki.example.com/pages/".to_string()],
+            ..Default::default()
+        }));
+        let html = r#"<p>See <a href="https://wiki.example.com/pages/Getting%20Started.html">Getting Started</a>,
+            the <a href="https://wiki.example.com/pages/guides/deploy/">deploy guide</a>,
+            <a href="https://wiki.example.com/pages/faq.html?rev=3#billing">billing</a>
+            and <a href="https://example.org/docs">the upstream docs</a>.</p>"#;
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "See [[Getting Started]], the [[guides/deploy|deploy guide]], \
+             [[faq#billing|billing]] and [the upstream docs](https://example.org/docs).\n"
+        );
+    }
+
+    #[test]
+    fn test_wiki_links_relative_without_base_url() {
+        let options = Options::new().wiki_links(Some(WikiLinkOptions {
+            use_text_as_alias: false,
+            ..Default::default()
+        }));
+        let html = r##"<p><a href="./release-1.2">Release</a> <a href="/notes/setup.md#install">Install</a>
+            <a href="#usage">Usage</a> <a href="mailto:team@example.com">Mail</a></p>"##;
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "[[release-1.2]] [[notes/setup#install]] [[#usage]] [Mail](mailto:team@example.com)\n"
+        );
+
+        // With a base URL, relative links resolve to external ones
+        let options = options.base_url(Some("https://example.com/".to_string()));
+        assert_eq!(
+            crate::convert_with_options(r#"<a href="/setup">Setup</a>"#, &options),
+            "[Setup](https://example.com/setup)\n"
+        );
+    }
+
+    #[test]
+    fn test_card_link_keeps_heading() {
+        let result = crate::convert(r#"<a href="/post"><h2>Post title</h2><p>excerpt</p></a>"#);
+        assert_eq!(result, "## Post title\n\nexcerpt\n\n[Read more](/post)\n");
+    }
+
+    #[test]
+    fn test_card_link_heading_link_style() {
+        let options = Options::new().block_link_style(BlockLinkStyle::HeadingLink);
+        let result = crate::convert_with_options(
+            r#"<a href="/post" title="Open"><h2>Post title</h2><p>excerpt</p></a>"#,
+            &options,
+        );
+        assert_eq!(result, "## [Post title](/post \"Open\")\n\nexcerpt\n");
+    }
+
+    #[test]
+    fn test_card_link_setext_heading_link() {
+        let options = Options::new()
+            .block_link_style(BlockLinkStyle::HeadingLink)
+            .heading_style(crate::options::HeadingStyle::Setext);
+        let result =
+            crate::convert_with_options(r#"<a href="/a"><h1>Title</h1><p>Body</p></a>"#, &options);
+        assert_eq!(result, "[Title](/a)\n===========\n\nBody\n");
+    }
+
+    #[test]
+    fn test_block_link_without_heading_falls_back() {
+        let options = Options::new().block_link_style(BlockLinkStyle::HeadingLink);
+        let result =
+            crate::convert_with_options(r#"<a href="/x"><p>One</p><p>Two</p></a>"#, &options);
+        assert_eq!(result, "One\n\nTwo\n\n[Read more](/x)\n");
+    }
+
+    #[test]
+    fn test_adjacent_card_links() {
+        let result = crate::convert(
+            r#"<div><a href="/1"><h3>First</h3><p>A</p></a><a href="/2"><h3>Second</h3><p>B</p></a></div>"#,
+        );
+        assert_eq!(
+            result,
+            "### First\n\nA\n\n[Read more](/1)\n\n### Second\n\nB\n\n[Read more](/2)\n"
+        );
+    }
+
+    #[test]
+    fn test_single_paragraph_link_stays_inline() {
+        let result = crate::convert(r#"<p>See <a href="/x"><span>the docs</span></a></p>"#);
+        assert_eq!(result, "See [the docs](/x)\n");
+    }
+
     #[test]
     fn test_fragment_link_with_section() {
         // Fragment links like #section should be preserved, unlike bare #
@@ -190,4 +505,33 @@ mod tests {
         );
         assert_eq!(result, "[Installation](#user-guide-installation)");
     }
+
+    #[test]
+    fn test_icon_link_uses_aria_label() {
+        let result = crate::convert(
+            r#"<nav><a href="/search" aria-label="Search"><svg><path d="M0 0"/></svg></a></nav>"#,
+        );
+        assert_eq!(result, "[Search](/search)\n");
+    }
+
+    #[test]
+    fn test_icon_link_falls_back_to_title_then_alt() {
+        let result = convert_test(
+            r#"<a href="/home" title="Home"><i class="icon"></i></a>"#,
+            &Options::default(),
+        );
+        assert_eq!(result, "[Home](/home)");
+
+        let result = crate::convert(r#"<p><a href="/cart"><img alt="Cart"></a></p>"#);
+        assert_eq!(result, "[Cart](/cart)\n");
+    }
+
+    #[test]
+    fn test_accessible_name_does_not_replace_text() {
+        let result = convert_test(
+            r#"<a href="/search" aria-label="Search the site">Search</a>"#,
+            &Options::default(),
+        );
+        assert_eq!(result, "[Search](/search)");
+    }
 }
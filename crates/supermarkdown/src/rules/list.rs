@@ -2,9 +2,9 @@
 
 use scraper::ElementRef;
 
-use crate::options::Options;
+use crate::options::{ListSpacing, OlTypeHandling, Options};
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{is_short_block, ConversionContext, Rule};
 
 /// Rule for ul and ol elements - delegates to children.
 pub struct ListRule;
@@ -19,15 +19,41 @@ impl Rule for ListRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
+        if options.ol_type_handling == OlTypeHandling::Html
+            && options.allow_raw_html
+            && has_marker_type(&element)
+        {
+            return format!("\n\n{}\n\n", element.html());
+        }
+
         let content = convert_children(element, metadata, options);
-        let content = content.trim_end();
+        let content = content.trim();
 
         if content.is_empty() {
             return String::new();
         }
 
+        // Whitespace before the first item isn't content; only the items'
+        // own indent leads the list
+        let indent = element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .find_map(|li| metadata.get(&li.id()).filter(|m| m.list_prefix.is_some()))
+            .map_or(0, |meta| meta.ancestor_indent);
+        let content = format!("{}{}", " ".repeat(indent), content);
+
+        // A tight sublist follows its item's text without a blank line
+        let nested = element
+            .parent()
+            .and_then(ElementRef::wrap)
+            .is_some_and(|parent| parent.value().name() == "li");
+        if nested && options.list_spacing == ListSpacing::Tight {
+            return format!("\n{}\n", content);
+        }
+
         format!("\n\n{}\n\n", content)
     }
 }
@@ -45,8 +71,13 @@ impl Rule for ListItemRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
+        if is_short_block(element, options) {
+            return String::new();
+        }
+
         let content = convert_children(element, metadata, options);
         let content = content.trim();
 
@@ -55,23 +86,44 @@ impl Rule for ListItemRule {
         }
 
         // O(1) lookup from pre-computed metadata
-        let (prefix, indent): (String, usize) = if let Some(meta) = metadata.get(&element.id()) {
-            (
-                meta.list_prefix.clone().unwrap_or_else(|| "- ".to_string()),
-                meta.ancestor_indent,
-            )
-        } else {
-            // Fallback if not found
-            (format!("{} ", options.bullet_marker), 0)
-        };
+        let (prefix, indent, label, loose): (String, usize, &str, bool) =
+            if let Some(meta) = metadata.get(&element.id()) {
+                (
+                    meta.list_prefix.clone().unwrap_or_else(|| "- ".to_string()),
+                    meta.ancestor_indent,
+                    meta.list_label.as_deref().unwrap_or(""),
+                    meta.loose_list,
+                )
+            } else {
+                // Fallback if not found
+                (format!("{} ", options.bullet_marker), 0, "", false)
+            };
 
-        // Indent continuation lines
+        // Indent continuation lines (the label is text, not part of the marker)
         let indented = indent_continuation(content, prefix.len() + indent);
-
-        format!("{}{}{}\n", " ".repeat(indent), prefix, indented)
+        // The list trims the blank line after its last item
+        let end = if loose { "\n\n" } else { "\n" };
+
+        format!(
+            "{}{}{}{}{}",
+            " ".repeat(indent),
+            prefix,
+            label,
+            indented,
+            end
+        )
     }
 }
 
+/// Whether an `<ol>` uses letters or roman numerals.
+fn has_marker_type(element: &ElementRef) -> bool {
+    element.value().name() == "ol"
+        && matches!(
+            element.value().attr("type").map(str::trim),
+            Some("a" | "A" | "i" | "I")
+        )
+}
+
 /// Indent continuation lines of multi-line content.
 fn indent_continuation(text: &str, spaces: usize) -> String {
     let indent = " ".repeat(spaces);
@@ -111,20 +163,29 @@ mod tests {
             .next()
             .unwrap();
 
-        let result =
-            ListRule.convert(ul, &metadata, &options, &|e, m, o| {
+        let result = ListRule.convert(
+            ul,
+            &metadata,
+            &options,
+            &ConversionContext::default(),
+            &|e, m, o| {
                 let mut s = String::new();
                 for child in e.children() {
                     if let Some(el) = ElementRef::wrap(child) {
                         if el.value().name() == "li" {
-                            s.push_str(&ListItemRule.convert(el, m, o, &|e, _, _| {
-                                e.text().collect::<Vec<_>>().join("")
-                            }));
+                            s.push_str(&ListItemRule.convert(
+                                el,
+                                m,
+                                o,
+                                &ConversionContext::default(),
+                                &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+                            ));
                         }
                     }
                 }
                 s
-            });
+            },
+        );
 
         assert!(result.contains("- First"));
         assert!(result.contains("- Second"));
@@ -143,20 +204,29 @@ mod tests {
             .next()
             .unwrap();
 
-        let result =
-            ListRule.convert(ol, &metadata, &options, &|e, m, o| {
+        let result = ListRule.convert(
+            ol,
+            &metadata,
+            &options,
+            &ConversionContext::default(),
+            &|e, m, o| {
                 let mut s = String::new();
                 for child in e.children() {
                     if let Some(el) = ElementRef::wrap(child) {
                         if el.value().name() == "li" {
-                            s.push_str(&ListItemRule.convert(el, m, o, &|e, _, _| {
-                                e.text().collect::<Vec<_>>().join("")
-                            }));
+                            s.push_str(&ListItemRule.convert(
+                                el,
+                                m,
+                                o,
+                                &ConversionContext::default(),
+                                &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+                            ));
                         }
                     }
                 }
                 s
-            });
+            },
+        );
 
         assert!(result.contains("1. One"));
         assert!(result.contains("2. Two"));
@@ -176,9 +246,13 @@ mod tests {
             .next()
             .unwrap();
 
-        let result = ListItemRule.convert(li, &metadata, &options, &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        });
+        let result = ListItemRule.convert(
+            li,
+            &metadata,
+            &options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        );
 
         assert!(result.contains("* Item"));
     }
@@ -203,20 +277,29 @@ mod tests {
             .next()
             .unwrap();
 
-        let result =
-            ListRule.convert(ol, &metadata, &options, &|e, m, o| {
+        let result = ListRule.convert(
+            ol,
+            &metadata,
+            &options,
+            &ConversionContext::default(),
+            &|e, m, o| {
                 let mut s = String::new();
                 for child in e.children() {
                     if let Some(el) = ElementRef::wrap(child) {
                         if el.value().name() == "li" {
-                            s.push_str(&ListItemRule.convert(el, m, o, &|e, _, _| {
-                                e.text().collect::<Vec<_>>().join("")
-                            }));
+                            s.push_str(&ListItemRule.convert(
+                                el,
+                                m,
+                                o,
+                                &ConversionContext::default(),
+                                &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+                            ));
                         }
                     }
                 }
                 s
-            });
+            },
+        );
 
         assert!(result.contains("5. Fifth"));
         assert!(result.contains("6. Sixth"));
@@ -225,13 +308,13 @@ mod tests {
 
     #[test]
     fn test_nested_list() {
-        let html = r#"<ul><li>Level 1<ul><li>Level 2</li></ul></li></ul>"#;
+        let html = r#"<ul><li>Level 1</li><ul><li>Level 2</li></ul></ul>"#;
         let dom = Html::parse_document(html);
         let options = Options::default();
         let selectors = CompiledSelectors::new(&options);
         let metadata = precompute_metadata(&dom, &selectors, &options);
 
-        // Verify nested list has indentation
+        // A list straight inside a list has no item to indent it
         let li_metadata: Vec<_> = metadata
             .values()
             .filter(|m| m.list_prefix.is_some())
@@ -258,20 +341,29 @@ mod tests {
             .next()
             .unwrap();
 
-        let result =
-            ListRule.convert(ul, &metadata, &options, &|e, m, o| {
+        let result = ListRule.convert(
+            ul,
+            &metadata,
+            &options,
+            &ConversionContext::default(),
+            &|e, m, o| {
                 let mut s = String::new();
                 for child in e.children() {
                     if let Some(el) = ElementRef::wrap(child) {
                         if el.value().name() == "li" {
-                            s.push_str(&ListItemRule.convert(el, m, o, &|e, _, _| {
-                                e.text().collect::<Vec<_>>().join("")
-                            }));
+                            s.push_str(&ListItemRule.convert(
+                                el,
+                                m,
+                                o,
+                                &ConversionContext::default(),
+                                &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+                            ));
                         }
                     }
                 }
                 s
-            });
+            },
+        );
 
         // Empty list items should be skipped
         assert!(result.contains("- Item 1"));
@@ -294,10 +386,232 @@ mod tests {
             .next()
             .unwrap();
 
-        let result = ListItemRule.convert(li, &metadata, &options, &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        });
+        let result = ListItemRule.convert(
+            li,
+            &metadata,
+            &options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        );
 
         assert!(result.contains("+ Item"));
     }
+
+    #[test]
+    fn test_letter_type_ignored_by_default() {
+        let html = r#"<ol type="a"><li>Alpha</li><li>Beta</li></ol>"#;
+        let result = crate::convert(html);
+        assert_eq!(result, "1. Alpha\n2. Beta\n");
+    }
+
+    #[test]
+    fn test_letters_as_text_with_start() {
+        let html =
+            r#"<p>See item (c).</p><ol type="a" start="3"><li>Third</li><li>Fourth</li></ol>"#;
+        let options = Options::new().ol_type_handling(OlTypeHandling::LettersAsText);
+        let result = crate::convert_with_options(html, &options);
+        assert_eq!(result, "See item (c).\n\n3. (c) Third\n4. (d) Fourth\n");
+    }
+
+    #[test]
+    fn test_letters_as_text_nested_roman() {
+        let html = r#"<ol type="A">
+            <li>Terms
+                <ol type="i"><li>Scope</li><li>Duration</li><li>Renewal</li><li>Exit</li></ol>
+            </li>
+            <li>Payment</li>
+        </ol>"#;
+        let options = Options::new().ol_type_handling(OlTypeHandling::LettersAsText);
+        let result = crate::convert_with_options(html, &options);
+        assert_eq!(
+            result,
+            "1. (A) Terms\n\n   1. (i) Scope\n   2. (ii) Duration\n   3. (iii) Renewal\n   4. (iv) Exit\n2. (B) Payment\n"
+        );
+    }
+
+    #[test]
+    fn test_ol_type_html_passthrough() {
+        let html = r#"<ol type="I"><li>Second</li></ol><ol><li>Plain</li></ol>"#;
+        let options = Options::new().ol_type_handling(OlTypeHandling::Html);
+        let result = crate::convert_with_options(html, &options);
+        assert!(result.contains(r#"<ol type="I"><li>Second</li></ol>"#));
+        assert!(result.contains("1. Plain"));
+    }
+
+    #[test]
+    fn test_ol_type_html_without_raw_html() {
+        let html = r#"<ol type="a"><li>First</li><li>Second</li></ol>"#;
+        let options = Options::new()
+            .ol_type_handling(OlTypeHandling::Html)
+            .allow_raw_html(false);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            crate::convert_with_options(
+                html,
+                &Options::new().ol_type_handling(OlTypeHandling::LettersAsText)
+            )
+        );
+    }
+
+    #[test]
+    fn test_bullet_cycle_three_levels() {
+        let html = "<ul><li>One<ul><li>Two<ul><li>Three<ul><li>Four</li></ul></li></ul></li></ul></li></ul>";
+        let options = Options::new().bullet_cycle(Some(vec!['-', '*', '+']));
+        let result = crate::convert_with_options(html, &options);
+        let markers: Vec<&str> = result
+            .lines()
+            .filter_map(|line| line.trim_start().split(' ').next())
+            .filter(|m| !m.is_empty())
+            .collect();
+        assert_eq!(markers, vec!["-", "*", "+", "-"]);
+    }
+
+    #[test]
+    fn test_bullet_cycle_skips_ordered_levels() {
+        let html = "<ul><li>A<ol><li>B<ul><li>C</li></ul></li></ol></li></ul>";
+        let options = Options::new().bullet_cycle(Some(vec!['-', '*', '+']));
+        let result = crate::convert_with_options(html, &options);
+        assert!(result.starts_with("- A"));
+        assert!(result.contains("1. B"));
+        // The second unordered level gets the second marker
+        assert!(result.contains("* C"));
+        assert!(!result.contains("+ C"));
+    }
+
+    #[test]
+    fn test_paragraphs_in_item_make_loose_list() {
+        let html = "<ul><li><p>First para</p><p>Second para</p></li><li><p>Next</p></li></ul>";
+        // Both paragraphs sit under the first item: the second is indented to
+        // the item's content column after a blank line
+        assert_eq!(
+            crate::convert(html),
+            "- First para\n\n  Second para\n\n- Next\n"
+        );
+    }
+
+    #[test]
+    fn test_loose_ordered_item_with_code() {
+        let html =
+            "<ol><li><p>Run:</p><pre><code>make</code></pre></li><li>Done</li></ol><p>After</p>";
+        assert_eq!(
+            crate::convert(html),
+            "1. Run:\n\n   ```\n   make\n   ```\n\n2. Done\n\nAfter\n"
+        );
+    }
+
+    #[test]
+    fn test_single_paragraph_items_stay_tight() {
+        let html = "<ul><li><p>a</p></li><li><p>b</p></li><li>c<ul><li>d</li></ul></li></ul>";
+        assert!(crate::convert(html).starts_with("- a\n- b\n- c\n"));
+    }
+
+    #[test]
+    fn test_list_spacing_loose() {
+        let html = "<ul><li>a</li><li>b</li></ul>";
+        let options = Options::new().list_spacing(ListSpacing::Loose);
+        assert_eq!(crate::convert_with_options(html, &options), "- a\n\n- b\n");
+    }
+
+    #[test]
+    fn test_list_spacing_tight() {
+        let html = "<ul><li><p>a</p><p>b</p></li><li>c<ul><li>d</li></ul></li></ul>";
+        let options = Options::new().list_spacing(ListSpacing::Tight);
+        // Paragraphs still need their blank line, or they would merge
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "- a\n\n  b\n- c\n  - d\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_bullet_cycle() {
+        let html = "<ul><li>A<ul><li>B</li></ul></li></ul>";
+        let options = Options::new().bullet_cycle(Some(vec!['-', '>']));
+        let result = crate::convert_with_options(html, &options);
+        assert!(result.starts_with("- A"));
+        assert!(result.contains("- B"));
+        assert!(crate::try_convert_with_options(html, &options).is_err());
+    }
+
+    /// Twelve items with an ordered sublist under item 2 and under item 11.
+    fn twelve_item_list() -> String {
+        let items: String = (1..=12)
+            .map(|n| match n {
+                2 | 11 => format!(
+                    "<li>Item {n}<p>More {n}</p><ol><li>Sub {n}</li><li>Sub {n}b</li></ol></li>"
+                ),
+                n => format!("<li>Item {n}</li>"),
+            })
+            .collect();
+        format!("<ol>{items}</ol>")
+    }
+
+    /// Leading spaces of the line containing `needle`.
+    fn indent_of(markdown: &str, needle: &str) -> usize {
+        let line = markdown.lines().find(|line| line.contains(needle)).unwrap();
+        line.len() - line.trim_start().len()
+    }
+
+    #[test]
+    fn test_pad_ordered_markers() {
+        let options = Options::new().pad_ordered_markers(true);
+        let result = crate::convert_with_options(&twelve_item_list(), &options);
+        // Continuation lines and sublists start in the padded content column
+        assert_eq!(
+            result,
+            "1.  Item 1
+
+2.  Item 2
+
+    More 2
+
+    1. Sub 2
+    2. Sub 2b
+
+3.  Item 3
+
+4.  Item 4
+
+5.  Item 5
+
+6.  Item 6
+
+7.  Item 7
+
+8.  Item 8
+
+9.  Item 9
+
+10. Item 10
+
+11. Item 11
+
+    More 11
+
+    1. Sub 11
+    2. Sub 11b
+
+12. Item 12
+"
+        );
+    }
+
+    #[test]
+    fn test_unpadded_markers_shift_past_nine() {
+        let result = crate::convert(&twelve_item_list());
+        assert_eq!(
+            indent_of(&result, "More 2") + 1,
+            indent_of(&result, "More 11")
+        );
+    }
+
+    #[test]
+    fn test_pad_ordered_markers_with_start() {
+        let html = r#"<ol start="98"><li>a</li><li>b</li><li>c</li></ol>"#;
+        let options = Options::new().pad_ordered_markers(true);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "98.  a\n99.  b\n100. c\n"
+        );
+    }
 }
@@ -2,6 +2,7 @@
 //!
 //! Each rule handles specific HTML tags and converts them to markdown.
 
+mod aside;
 mod blockquote;
 mod br;
 mod code;
@@ -9,17 +10,21 @@ mod deflist;
 mod details;
 mod emphasis;
 mod figure;
+mod gauge;
 mod heading;
 mod hr;
 mod image;
 mod link;
 mod list;
+mod object;
 mod paragraph;
 mod passthrough;
 mod pre;
+mod select;
 mod strikethrough;
 mod subscript;
 mod superscript;
+mod svg;
 mod table;
 
 use scraper::ElementRef;
@@ -27,31 +32,102 @@ use scraper::ElementRef;
 use crate::options::Options;
 use crate::precompute::MetadataMap;
 
+pub use aside::AsideRule;
 pub use blockquote::BlockquoteRule;
 pub use br::BreakRule;
 pub use code::CodeRule;
 pub use deflist::{DefDescRule, DefListRule, DefTermRule};
-pub use details::DetailsRule;
+pub use details::{DetailsRule, SummaryRule};
 pub use emphasis::{EmphasisRule, StrongRule};
 pub use figure::FigureRule;
+pub use gauge::{MeterRule, ProgressRule};
 pub use heading::HeadingRule;
 pub use hr::HorizontalRule;
 pub use image::ImageRule;
 pub use link::LinkRule;
 pub use list::{ListItemRule, ListRule};
+pub use object::ObjectRule;
 pub use paragraph::ParagraphRule;
-pub use passthrough::{AbbrRule, KbdRule, MarkRule, SampRule, VarRule};
+pub use passthrough::{
+    AbbrRule, FontRule, KbdRule, MarkRule, SampRule, SmallRule, UnderlineRule, VarRule,
+};
 pub use pre::PreRule;
+pub use select::SelectRule;
 pub use strikethrough::StrikethroughRule;
 pub use subscript::SubscriptRule;
 pub use superscript::SuperscriptRule;
-pub use table::TableRule;
+pub use svg::SvgRule;
+pub use table::{TableRowRule, TableRule};
+
+pub(crate) use code::code_span;
+pub(crate) use image::{alt_text_src, extract_image, format_image};
+pub(crate) use link::{extract_link, format_link};
+pub(crate) use paragraph::is_short_block;
+pub(crate) use passthrough::{abbreviation, append_abbreviations, encode_markdown};
+
+/// Where an element sits in the document, as far as its markdown depends on
+/// it: a line break or code block in a table cell can't span lines, for
+/// instance.
+///
+/// The converter builds it from the element's ancestors while traversing;
+/// rules called outside a conversion can pass `ConversionContext::default()`,
+/// the document's top level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConversionContext {
+    /// Inside a `<td>` or `<th>`.
+    pub in_table_cell: bool,
+    /// Inside an `<li>`.
+    pub in_list_item: bool,
+    /// Inside a `<blockquote>`.
+    pub in_blockquote: bool,
+    /// Inside a `<pre>`.
+    pub in_pre: bool,
+    /// Inside an element written out as raw HTML, like `<kbd>`, where some
+    /// renderers show markdown syntax literally.
+    pub in_raw_html: bool,
+    /// Number of enclosing `<blockquote>` elements.
+    pub quote_depth: usize,
+    /// Number of enclosing `<ul>` and `<ol>` elements.
+    pub list_depth: usize,
+}
+
+impl ConversionContext {
+    /// The context of the children of `element`, which sits in this one.
+    pub fn enter(&self, element: ElementRef, options: &Options) -> Self {
+        let mut context = *self;
+        let tag = element.value().name();
+        if passthrough::writes_raw_html(tag, options) {
+            context.in_raw_html = true;
+        }
+        match tag {
+            "td" | "th" => context.in_table_cell = true,
+            "li" => context.in_list_item = true,
+            "ul" | "ol" => context.list_depth += 1,
+            "blockquote" => {
+                context.in_blockquote = true;
+                context.quote_depth += 1;
+            }
+            "pre" => context.in_pre = true,
+            _ => {}
+        }
+        context
+    }
+}
 
 /// Trait for HTML to Markdown conversion rules.
 pub trait Rule: Send + Sync {
     /// Tags this rule handles.
     fn tags(&self) -> &'static [&'static str];
 
+    /// Name shown in diagnostics such as [`convert_outline`](crate::convert_outline).
+    ///
+    /// Defaults to the implementing type's name.
+    fn name(&self) -> &'static str {
+        let path = std::any::type_name::<Self>();
+        path.rsplit("::").next().unwrap_or(path)
+    }
+
     /// Convert the element to markdown.
     ///
     /// # Arguments
@@ -59,12 +135,14 @@ pub trait Rule: Send + Sync {
     /// * `element` - The HTML element to convert
     /// * `metadata` - Pre-computed metadata for O(1) lookups
     /// * `options` - Conversion options
+    /// * `context` - Where the element sits, like inside a table cell
     /// * `convert_children` - Function to convert child nodes
     fn convert(
         &self,
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String;
 }
@@ -83,9 +161,12 @@ pub fn default_rules() -> Vec<Box<dyn Rule>> {
         Box::new(DefTermRule),
         Box::new(DefDescRule),
         Box::new(TableRule),
+        Box::new(TableRowRule),
         Box::new(HorizontalRule),
         Box::new(DetailsRule),
+        Box::new(SummaryRule),
         Box::new(FigureRule),
+        Box::new(AsideRule),
         // Inline elements
         Box::new(LinkRule),
         Box::new(ImageRule),
@@ -96,12 +177,23 @@ pub fn default_rules() -> Vec<Box<dyn Rule>> {
         Box::new(SuperscriptRule),
         Box::new(SubscriptRule),
         Box::new(BreakRule),
+        // Embedded content
+        Box::new(SvgRule),
+        Box::new(ObjectRule),
+        // Form controls
+        Box::new(SelectRule),
+        Box::new(MeterRule),
+        Box::new(ProgressRule),
         // HTML passthrough elements
         Box::new(KbdRule),
         Box::new(MarkRule),
         Box::new(AbbrRule),
         Box::new(SampRule),
         Box::new(VarRule),
+        Box::new(UnderlineRule),
+        // Legacy presentational elements
+        Box::new(SmallRule),
+        Box::new(FontRule),
     ]
 }
 
@@ -0,0 +1,172 @@
+//! Embedded object rule (object, embed).
+//!
+//! Embedded documents have no markdown form, so they become a link to the
+//! resource labelled by its MIME type, followed by any fallback content.
+
+use scraper::ElementRef;
+
+use crate::escape::{escape_link_text, escape_url};
+use crate::options::{Options, UrlKind};
+use crate::precompute::MetadataMap;
+use crate::rules::{ConversionContext, Rule};
+use crate::url::{attr_url, rewrite_url};
+
+pub struct ObjectRule;
+
+impl Rule for ObjectRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["object", "embed"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        let attr = if element.value().name() == "object" {
+            "data"
+        } else {
+            "src"
+        };
+        // Without a resource, only the fallback content is left
+        let Some(url) = attr_url(&element, &[attr], options) else {
+            return convert_children(element, metadata, options);
+        };
+        let url = rewrite_url(UrlKind::Media, &url, options);
+
+        let label = element
+            .value()
+            .attr("type")
+            .map(type_label)
+            .unwrap_or_else(|| file_name(&url).to_string());
+
+        let link = format!("[{}]({})", escape_link_text(&label), escape_url(&url));
+
+        let fallback = convert_children(element, metadata, options);
+        match fallback.trim() {
+            "" => link,
+            text if fallback.contains("\n\n") => format!("{}\n\n{}\n\n", link, text),
+            text => format!("{} {}", link, text),
+        }
+    }
+}
+
+/// Human-readable label for a MIME type.
+fn type_label(mime: &str) -> String {
+    let mime = mime.trim().to_lowercase();
+    match mime.as_str() {
+        "application/pdf" => "PDF document".to_string(),
+        "image/svg+xml" => "SVG image".to_string(),
+        "application/x-shockwave-flash" => "Flash content".to_string(),
+        "text/html" => "Embedded page".to_string(),
+        _ => match mime.split('/').next() {
+            Some("image") => "Image".to_string(),
+            Some("video") => "Video".to_string(),
+            Some("audio") => "Audio".to_string(),
+            _ => mime,
+        },
+    }
+}
+
+/// Last path segment of a URL, ignoring query and fragment.
+fn file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    fn convert_test(html: &str, options: &Options) -> String {
+        let dom = Html::parse_fragment(html);
+        let element = dom.root_element().first_child().unwrap();
+        let element = ElementRef::wrap(element).unwrap();
+        let metadata = MetadataMap::default();
+
+        ObjectRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|_, _, _| String::new(),
+        )
+    }
+
+    #[test]
+    fn test_object_pdf() {
+        let result = convert_test(
+            r#"<object data="report.pdf" type="application/pdf"></object>"#,
+            &Options::default(),
+        );
+        assert_eq!(result, "[PDF document](report.pdf)");
+    }
+
+    #[test]
+    fn test_embed_src() {
+        let result = convert_test(
+            r#"<embed src="/media/clip.mp4" type="video/mp4">"#,
+            &Options::default(),
+        );
+        assert_eq!(result, "[Video](/media/clip.mp4)");
+    }
+
+    #[test]
+    fn test_label_falls_back_to_file_name() {
+        let result = convert_test(
+            r#"<object data="docs/manual.pdf?v=2"></object>"#,
+            &Options::default(),
+        );
+        assert_eq!(result, "[manual.pdf](docs/manual.pdf?v=2)");
+    }
+
+    #[test]
+    fn test_relative_url_with_base() {
+        let options = Options::new().base_url(Some("https://example.com/".to_string()));
+        let result = convert_test(
+            r#"<object data="files/a.pdf" type="application/pdf"></object>"#,
+            &options,
+        );
+        assert_eq!(result, "[PDF document](https://example.com/files/a.pdf)");
+    }
+
+    #[test]
+    fn test_missing_url() {
+        assert!(convert_test("<object></object>", &Options::default()).is_empty());
+        assert!(convert_test("<embed>", &Options::default()).is_empty());
+    }
+
+    #[test]
+    fn test_label_brackets_escaped() {
+        let result = convert_test(
+            r#"<object data="files/[draft].pdf"></object>"#,
+            &Options::default(),
+        );
+        assert_eq!(result, r"[\[draft\].pdf](files/[draft].pdf)");
+    }
+
+    #[test]
+    fn test_fallback_content_kept() {
+        assert_eq!(
+            crate::convert(r#"<object data="a.pdf"><p>Fallback text</p></object>"#),
+            "[a.pdf](a.pdf)\n\nFallback text\n"
+        );
+        assert_eq!(
+            crate::convert(r#"<p><object data="a.pdf">Download it</object></p>"#),
+            "[a.pdf](a.pdf) Download it\n"
+        );
+        // Without a resource the fallback stands in for the object
+        assert_eq!(
+            crate::convert(r#"<object type="application/pdf"><p>Fallback text</p></object>"#),
+            "Fallback text\n"
+        );
+    }
+}
@@ -1,10 +1,14 @@
 //! Paragraph rule.
 
-use scraper::ElementRef;
+use scraper::{ElementRef, Node};
 
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::heading::format_heading;
+use crate::rules::{ConversionContext, Rule};
+
+/// Longest bold phrase, in characters, promoted by `promote_bold_headings`.
+const MAX_BOLD_HEADING_CHARS: usize = 80;
 
 pub struct ParagraphRule;
 
@@ -18,8 +22,20 @@ impl Rule for ParagraphRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
+        if is_short_block(element, options) {
+            return String::new();
+        }
+
+        if let Some(level) = options.promote_bold_headings {
+            if let Some(bold) = bold_heading(element) {
+                let level = level.clamp(1, 6) as usize;
+                return format_heading(level, &convert_children(bold, metadata, options), options);
+            }
+        }
+
         let content = convert_children(element, metadata, options);
         let content = content.trim();
 
@@ -31,9 +47,65 @@ impl Rule for ParagraphRule {
     }
 }
 
+/// Whether a paragraph or list item falls under `min_block_text_len`: its
+/// text is shorter than the threshold and it holds no image or code. Blocks
+/// inside table cells are kept, and list items only count with
+/// `min_block_text_len_lists`.
+pub(crate) fn is_short_block(element: ElementRef, options: &Options) -> bool {
+    let Some(min_len) = options.min_block_text_len else {
+        return false;
+    };
+    if element.value().name() == "li" && !options.min_block_text_len_lists {
+        return false;
+    }
+
+    // Characters with whitespace runs counted as one space
+    let text: String = element.text().collect();
+    let words = text.split_whitespace();
+    let len = words.map(|word| word.chars().count() + 1).sum::<usize>();
+    if len.saturating_sub(1) >= min_len {
+        return false;
+    }
+
+    let has_media = element.descendants().any(|node| {
+        node.value()
+            .as_element()
+            .is_some_and(|el| matches!(el.name(), "img" | "picture" | "svg" | "code" | "pre"))
+    });
+    let in_table_cell = element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|el| matches!(el.value().name(), "td" | "th"));
+    !has_media && !in_table_cell
+}
+
+/// The `<strong>` or `<b>` making up all of a paragraph when it reads like a
+/// heading: short, and not a sentence ending in a period.
+fn bold_heading(paragraph: ElementRef) -> Option<ElementRef> {
+    let mut bold = None;
+    for child in paragraph.children() {
+        match child.value() {
+            Node::Text(text) if text.trim().is_empty() => {}
+            Node::Comment(_) => {}
+            Node::Element(el) if bold.is_none() && matches!(el.name(), "strong" | "b") => {
+                bold = ElementRef::wrap(child);
+            }
+            _ => return None,
+        }
+    }
+
+    let bold = bold?;
+    let text: String = bold.text().collect();
+    let text = text.trim();
+    let looks_like_heading =
+        !text.is_empty() && text.chars().count() < MAX_BOLD_HEADING_CHARS && !text.ends_with('.');
+    looks_like_heading.then_some(bold)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::convert_with_options;
     use scraper::Html;
 
     fn convert_test(html: &str) -> String {
@@ -42,9 +114,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        ParagraphRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        ParagraphRule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -66,4 +142,53 @@ mod tests {
         let result = convert_test("<p>   </p>");
         assert!(result.is_empty());
     }
+
+    fn promote(html: &str) -> String {
+        convert_with_options(html, &Options::new().promote_bold_headings(Some(3)))
+    }
+
+    #[test]
+    fn test_bold_paragraph_promoted() {
+        assert_eq!(
+            promote("<p><strong>Pricing</strong></p><p>From $5.</p>"),
+            "### Pricing\n\nFrom $5.\n"
+        );
+        assert_eq!(
+            promote("<p>\n  <b class=\"section-title\">Getting <em>started</em></b>\n</p>"),
+            "### Getting *started*\n"
+        );
+    }
+
+    #[test]
+    fn test_bold_paragraph_kept_by_default() {
+        assert_eq!(
+            convert_with_options("<p><strong>Pricing</strong></p>", &Options::default()),
+            "**Pricing**\n"
+        );
+    }
+
+    #[test]
+    fn test_bold_with_trailing_text_not_promoted() {
+        assert_eq!(
+            promote("<p><strong>Note:</strong> back up first</p>"),
+            "**Note:** back up first\n"
+        );
+        assert_eq!(
+            promote("<p><strong>Pricing</strong><br>From $5</p>"),
+            "**Pricing**\nFrom $5\n"
+        );
+    }
+
+    #[test]
+    fn test_sentences_not_promoted() {
+        assert_eq!(
+            promote("<p><strong>Back up before upgrading.</strong></p>"),
+            "**Back up before upgrading.**\n"
+        );
+        let long = "word ".repeat(20);
+        assert_eq!(
+            promote(&format!("<p><strong>{}</strong></p>", long)),
+            format!("**{}**\n", long.trim())
+        );
+    }
 }
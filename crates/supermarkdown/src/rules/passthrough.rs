@@ -1,13 +1,29 @@
 //! Passthrough rule for HTML elements without Markdown equivalents.
 //!
 //! Elements like `<kbd>`, `<mark>`, `<abbr>`, `<samp>`, `<var>` are passed through
-//! as raw HTML since Markdown supports inline HTML.
+//! as raw HTML since Markdown supports inline HTML. `<u>` is too unless
+//! configured otherwise, while the presentational `<small>` and `<font>`
+//! are unwrapped (or dropped, for `small`).
+//!
+//! With `abbr_style` other than `Html`, `<abbr>` becomes plain text: the
+//! abbreviations to expand or define are picked at precompute, so each is
+//! written out once per document.
+//!
+//! Without `allow_raw_html` every element here has a markdown stand-in:
+//! `<kbd>` and `<samp>` text becomes a code span, `<mark>` bold, `<var>` and
+//! `<u>` emphasis, and `<abbr>` plain text.
+//!
+//! Renderers still parse markdown inside inline HTML, so plain text in a
+//! passthrough element has its markdown characters entity-encoded: `Ctrl+*`
+//! must not start emphasis. Backslash escapes aren't used because some
+//! renderers show them literally inside HTML.
 
-use scraper::ElementRef;
+use scraper::{ElementRef, Html};
 
-use crate::options::Options;
+use crate::options::{AbbrStyle, Options, UnderlineStyle};
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{code_span, ConversionContext, Rule};
+use crate::whitespace::normalize_block_whitespace;
 
 /// Rule for keyboard input `<kbd>`.
 pub struct KbdRule;
@@ -22,9 +38,14 @@ impl Rule for KbdRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        let content = convert_children(element, metadata, options);
+        if !options.allow_raw_html {
+            return text_code_span(element);
+        }
+
+        let content = passthrough_content(element, metadata, options, convert_children);
         let content = content.trim();
         if content.is_empty() {
             String::new()
@@ -47,9 +68,14 @@ impl Rule for MarkRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        let content = convert_children(element, metadata, options);
+        if !options.allow_raw_html {
+            return markdown_content(element, metadata, options, convert_children, "**");
+        }
+
+        let content = passthrough_content(element, metadata, options, convert_children);
         let content = content.trim();
         if content.is_empty() {
             String::new()
@@ -72,9 +98,24 @@ impl Rule for AbbrRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        let content = convert_children(element, metadata, options);
+        if options.abbr_style != AbbrStyle::Html || !options.allow_raw_html {
+            let content = convert_children(element, metadata, options);
+            let content = content.trim();
+            let title = metadata
+                .get(&element.id())
+                .and_then(|meta| meta.abbr_title.as_deref());
+            return match title {
+                Some(title) if options.abbr_style == AbbrStyle::Expanded && !content.is_empty() => {
+                    format!("{} ({})", content, title)
+                }
+                _ => content.to_string(),
+            };
+        }
+
+        let content = passthrough_content(element, metadata, options, convert_children);
         let content = content.trim();
         if content.is_empty() {
             return String::new();
@@ -102,9 +143,14 @@ impl Rule for SampRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        let content = convert_children(element, metadata, options);
+        if !options.allow_raw_html {
+            return text_code_span(element);
+        }
+
+        let content = passthrough_content(element, metadata, options, convert_children);
         let content = content.trim();
         if content.is_empty() {
             String::new()
@@ -127,9 +173,14 @@ impl Rule for VarRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        let content = convert_children(element, metadata, options);
+        if !options.allow_raw_html {
+            return markdown_content(element, metadata, options, convert_children, "*");
+        }
+
+        let content = passthrough_content(element, metadata, options, convert_children);
         let content = content.trim();
         if content.is_empty() {
             String::new()
@@ -139,6 +190,197 @@ impl Rule for VarRule {
     }
 }
 
+/// Rule for underlined text `<u>`.
+pub struct UnderlineRule;
+
+impl Rule for UnderlineRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["u"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        let content = convert_children(element, metadata, options);
+        let content = content.trim();
+        if content.is_empty() {
+            return String::new();
+        }
+
+        match options.underline_style {
+            UnderlineStyle::Html if options.allow_raw_html => format!("<u>{}</u>", content),
+            _ => format!("*{}*", content),
+        }
+    }
+}
+
+/// Rule for fine print `<small>`: unwrapped, or dropped with `drop_small`.
+pub struct SmallRule;
+
+impl Rule for SmallRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["small"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        if options.drop_small {
+            return String::new();
+        }
+        convert_children(element, metadata, options)
+    }
+}
+
+/// Rule for `<font>`: always unwrapped, since its color, face and size
+/// have no markdown form.
+pub struct FontRule;
+
+impl Rule for FontRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["font"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        convert_children(element, metadata, options)
+    }
+}
+
+/// The text and title of an `<abbr>`, whitespace collapsed, when it has
+/// both.
+pub(crate) fn abbreviation(element: ElementRef) -> Option<(String, String)> {
+    let title = normalize_block_whitespace(element.value().attr("title")?.trim()).into_owned();
+    let text: String = element.text().collect();
+    let text = normalize_block_whitespace(text.trim()).into_owned();
+    (!title.is_empty() && !text.is_empty()).then_some((text, title))
+}
+
+/// Append a `*[WHO]: World Health Organization` line for each abbreviation
+/// picked at precompute, in document order.
+pub(crate) fn append_abbreviations(
+    mut markdown: String,
+    dom: &Html,
+    metadata: &MetadataMap,
+) -> String {
+    let mut definitions = String::new();
+    for element in dom
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+    {
+        if element.value().name() != "abbr" {
+            continue;
+        }
+        let picked = metadata
+            .get(&element.id())
+            .is_some_and(|meta| meta.abbr_title.is_some());
+        if let Some((text, title)) = abbreviation(element).filter(|_| picked) {
+            definitions.push_str(&format!("*[{}]: {}\n", text, title));
+        }
+    }
+    if !definitions.is_empty() {
+        markdown.push_str("\n\n");
+        markdown.push_str(&definitions);
+    }
+    markdown
+}
+
+/// Whether the rule for `tag` writes the element as raw HTML with these
+/// options.
+pub(crate) fn writes_raw_html(tag: &str, options: &Options) -> bool {
+    options.allow_raw_html
+        && match tag {
+            "kbd" | "samp" | "var" | "mark" => true,
+            "abbr" => options.abbr_style == AbbrStyle::Html,
+            "u" => options.underline_style == UnderlineStyle::Html,
+            _ => false,
+        }
+}
+
+/// Text of a `<kbd>` or `<samp>` as a code span, which renders monospaced
+/// like the element itself.
+fn text_code_span(element: ElementRef) -> String {
+    let text: String = element.text().collect();
+    let text = text.trim();
+    if text.is_empty() {
+        String::new()
+    } else {
+        code_span(text)
+    }
+}
+
+/// Converted children of an element between markdown `delimiter`s.
+fn markdown_content(
+    element: ElementRef,
+    metadata: &MetadataMap,
+    options: &Options,
+    convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    delimiter: &str,
+) -> String {
+    let content = convert_children(element, metadata, options);
+    let content = content.trim();
+    if content.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}{}", delimiter, content, delimiter)
+    }
+}
+
+/// Content of a passthrough element: its text with markdown characters
+/// encoded, or its converted children when it holds other elements (like
+/// `<kbd><kbd>Ctrl</kbd>+<kbd>C</kbd></kbd>`).
+fn passthrough_content(
+    element: ElementRef,
+    metadata: &MetadataMap,
+    options: &Options,
+    convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+) -> String {
+    if element.children().any(|child| child.value().is_element()) {
+        return convert_children(element, metadata, options);
+    }
+    let text: String = element.text().collect();
+    encode_markdown(&normalize_block_whitespace(&text))
+}
+
+/// Entity-encode characters markdown or HTML would interpret in raw HTML
+/// content.
+pub(crate) fn encode_markdown(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '*' => result.push_str("&#42;"),
+            '_' => result.push_str("&#95;"),
+            '`' => result.push_str("&#96;"),
+            '[' => result.push_str("&#91;"),
+            ']' => result.push_str("&#93;"),
+            '\\' => result.push_str("&#92;"),
+            '~' => result.push_str("&#126;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
 /// Escape special characters in HTML attribute values.
 fn escape_attr(text: &str) -> String {
     text.replace('&', "&amp;")
@@ -150,7 +392,6 @@ fn escape_attr(text: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use scraper::Html;
 
     fn convert_test<R: Rule>(rule: &R, html: &str) -> String {
         let dom = Html::parse_fragment(html);
@@ -158,9 +399,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        rule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        rule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -205,6 +450,138 @@ mod tests {
         assert_eq!(result, "<var>x</var>");
     }
 
+    #[test]
+    fn test_markdown_characters_encoded() {
+        let html = "<p>Press <kbd>Ctrl+*</kbd> on <mark>*important*</mark> lines</p>";
+        assert_eq!(
+            crate::convert(html),
+            "Press <kbd>Ctrl+&#42;</kbd> on <mark>&#42;important&#42;</mark> lines\n"
+        );
+
+        let html = "<p><samp>`make` failed: [E_1] ~x\\y</samp></p>";
+        assert_eq!(
+            crate::convert(html),
+            "<samp>&#96;make&#96; failed: &#91;E&#95;1&#93; &#126;x&#92;y</samp>\n"
+        );
+    }
+
+    #[test]
+    fn test_html_characters_encoded() {
+        let html = "<p><kbd>&lt;Enter&gt;</kbd> <var>a &amp;&amp; b</var></p>";
+        assert_eq!(
+            crate::convert(html),
+            "<kbd>&lt;Enter&gt;</kbd> <var>a &amp;&amp; b</var>\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_elements_converted() {
+        let html = "<p><kbd><kbd>Ctrl</kbd>+<kbd>*</kbd></kbd> <mark><em>new</em></mark></p>";
+        assert_eq!(
+            crate::convert(html),
+            "<kbd><kbd>Ctrl</kbd>+<kbd>&#42;</kbd></kbd> <mark>*new*</mark>\n"
+        );
+    }
+
+    #[test]
+    fn test_underline() {
+        assert_eq!(convert_test(&UnderlineRule, "<u>shall</u>"), "<u>shall</u>");
+
+        let options = Options::new().underline_style(UnderlineStyle::Emphasis);
+        let result = crate::convert_with_options("<p>You <u>must</u> sign.</p>", &options);
+        assert_eq!(result, "You *must* sign.\n");
+    }
+
+    #[test]
+    fn test_small_unwrapped_or_dropped() {
+        let html = "<p>Price <small>(excl. VAT)</small> today</p><p><small>© 2024 Example Corp</small></p>";
+        assert_eq!(
+            crate::convert(html),
+            "Price (excl. VAT) today\n\n© 2024 Example Corp\n"
+        );
+
+        let options = Options::new().drop_small(true);
+        assert_eq!(crate::convert_with_options(html, &options), "Price today\n");
+    }
+
+    #[test]
+    fn test_font_unwrapped() {
+        let html = r#"<p><font color="red" face="Arial" size="+2">Warning</font>: <font><b>hot</b></font></p>"#;
+        assert_eq!(crate::convert(html), "Warning: **hot**\n");
+    }
+
+    #[test]
+    fn test_abbr_expanded_once() {
+        let html = r#"<p><abbr title="World Health Organization">WHO</abbr> and
+            <abbr title="World  Health Organization">WHO</abbr> staff.</p>
+            <p>Ask the <abbr title="World Health Organization">WHO</abbr>.</p>"#;
+        let options = Options::new().abbr_style(AbbrStyle::Expanded);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "WHO (World Health Organization) and WHO staff.\n\nAsk the WHO.\n"
+        );
+    }
+
+    #[test]
+    fn test_abbr_expanded_again_for_other_title() {
+        let html = r#"<p><abbr title="World Health Organization">WHO</abbr>,
+            <abbr title="Wildlife Habitat Office">WHO</abbr>,
+            <abbr title="Wildlife Habitat Office">WHO</abbr> and
+            <abbr title="World Health Organization">WHO</abbr>.</p>"#;
+        let options = Options::new().abbr_style(AbbrStyle::Expanded);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "WHO (World Health Organization), WHO (Wildlife Habitat Office), WHO and \
+             WHO (World Health Organization).\n"
+        );
+    }
+
+    #[test]
+    fn test_abbr_definition_list() {
+        let html = r#"<p>The <abbr title="HyperText Markup Language">HTML</abbr> and
+            <abbr title="Cascading Style Sheets">CSS</abbr> specs.</p>
+            <p>More <abbr title="HyperText Markup Language">HTML</abbr>, a
+            <abbr title="Hot Tea Makes Life">HTML</abbr> and <abbr>XML</abbr>.</p>"#;
+        let options = Options::new().abbr_style(AbbrStyle::DefinitionList);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "The HTML and CSS specs.\n\nMore HTML, a HTML and XML.\n\n\
+             *[HTML]: HyperText Markup Language\n\
+             *[CSS]: Cascading Style Sheets\n"
+        );
+    }
+
+    #[test]
+    fn test_abbr_in_excluded_element_not_defined() {
+        let html = r#"<nav><abbr title="Frequently Asked Questions">FAQ</abbr></nav>
+            <p>See the <abbr title="Frequently asked questions">FAQ</abbr>.</p>"#;
+        let options = Options::new()
+            .abbr_style(AbbrStyle::DefinitionList)
+            .exclude_selectors(vec!["nav".to_string()]);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "See the FAQ.\n\n*[FAQ]: Frequently asked questions\n"
+        );
+    }
+
+    #[test]
+    fn test_without_raw_html() {
+        let html = r#"<p>Press <kbd>Ctrl+`</kbd>, read <samp>Saved</samp>, set <var>n</var>,
+            see <mark>this</mark>, <u>that</u> and <abbr title="World Health Organization">WHO</abbr></p>"#;
+        let options = Options::new().allow_raw_html(false);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "Press `` Ctrl+` ``, read `Saved`, set *n*, see **this**, *that* and WHO\n"
+        );
+    }
+
+    #[test]
+    fn test_without_raw_html_nested_kbd() {
+        let html = "<p><kbd><kbd>Ctrl</kbd>+<kbd>C</kbd></kbd></p>";
+        let options = Options::new().allow_raw_html(false);
+        assert_eq!(crate::convert_with_options(html, &options), "`Ctrl+C`\n");
+    }
+
     #[test]
     fn test_empty_elements() {
         assert!(convert_test(&KbdRule, "<kbd></kbd>").is_empty());
@@ -213,4 +590,31 @@ mod tests {
         assert!(convert_test(&SampRule, "<samp></samp>").is_empty());
         assert!(convert_test(&VarRule, "<var></var>").is_empty());
     }
+
+    #[test]
+    fn test_code_in_passthrough_is_html() {
+        let html = "<p><kbd><code>ls *.rs</code></kbd>, <samp>$ <code>make</code></samp>, \
+            <u><code>u</code></u> and <code>plain</code></p>";
+        assert_eq!(
+            crate::convert(html),
+            "<kbd><code>ls &#42;.rs</code></kbd>, <samp>$ <code>make</code></samp>, \
+             <u><code>u</code></u> and `plain`\n"
+        );
+
+        // Markdown stand-ins keep the code span
+        let options = Options::new().underline_style(UnderlineStyle::Emphasis);
+        assert!(crate::convert_with_options(html, &options).contains(" *`u`* "));
+        let options = Options::new().allow_raw_html(false);
+        assert_eq!(
+            crate::convert_with_options(html, &options),
+            "`ls *.rs`, `$ make`, *`u`* and `plain`\n"
+        );
+    }
+
+    #[test]
+    fn test_passthrough_in_code_is_text() {
+        let html = "<p><code><var>x</var> + <kbd>1</kbd></code> and \
+            <code><samp>$</samp> <mark>ls</mark></code></p>";
+        assert_eq!(crate::convert(html), "`x + 1` and `$ ls`\n");
+    }
 }
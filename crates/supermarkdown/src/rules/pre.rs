@@ -4,9 +4,12 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use scraper::ElementRef;
 
-use crate::options::Options;
+use crate::language::{
+    comment_syntax, is_mime_type, language_for_filename, normalize_language, sniff_language,
+};
+use crate::options::{CodeBlockInfo, CodeFilename, FenceInfo, Options};
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{code_span, ConversionContext, Rule};
 
 /// Cached regex for counting backtick runs.
 static BACKTICK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`+").unwrap());
@@ -24,49 +27,198 @@ impl Rule for PreRule {
         element: ElementRef,
         _metadata: &MetadataMap,
         options: &Options,
+        context: &ConversionContext,
         _convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
-        // Detect language from <code class="language-xxx">
-        let lang = detect_language(&element).unwrap_or_default();
-
         // Collect text, skipping line number gutters
-        let code = collect_code_text(&element);
+        let (code, line_numbers) = collect_code_text(&element);
         let code = code.trim_end_matches('\n');
 
         if code.is_empty() {
             return String::new();
         }
 
-        // Dynamic fence calculation (handles nested backticks)
-        let fence = calculate_fence(code, options.code_fence);
+        // A fence can't open in a table row, so the lines become code spans
+        if context.in_table_cell {
+            return inline_code(code, options);
+        }
 
-        format!("\n\n{}{}\n{}\n{}\n\n", fence, lang, code, fence)
-    }
-}
+        let lang = detect_language(&element, code);
 
-/// Detect language from pre or child code element class.
-fn detect_language(pre: &ElementRef) -> Option<String> {
-    // Check <pre class="language-xxx">
-    if let Some(class) = pre.value().attr("class") {
-        if let Some(lang) = extract_language_from_class(class) {
-            return Some(lang);
+        #[cfg(feature = "logging")]
+        if lang.is_none() {
+            log::debug!(
+                "No language detected for <pre> (class: {:?})",
+                element.value().attr("class")
+            );
         }
-    }
 
-    // Check child <code class="language-xxx">
-    for child in pre.children() {
-        if let Some(element) = ElementRef::wrap(child) {
-            if element.value().name() == "code" {
-                if let Some(class) = element.value().attr("class") {
-                    if let Some(lang) = extract_language_from_class(class) {
-                        return Some(lang);
+        let filename = match (options.code_filename, options.fence_info) {
+            (CodeFilename::Omit, FenceInfo::LanguageOnly) => None,
+            _ => detect_filename(&element),
+        };
+        let mut info = lang.clone().unwrap_or_default();
+        let mut code = code.to_string();
+        if let Some(filename) = &filename {
+            match options.code_filename {
+                CodeFilename::InfoString if options.fence_info == FenceInfo::LanguageOnly => {
+                    if info.is_empty() {
+                        info.push_str("text");
                     }
+                    info.push_str(&format!(" title=\"{}\"", filename));
                 }
+                CodeFilename::Comment => {
+                    let syntax = lang.as_deref().and_then(comment_syntax);
+                    let first_line = code.lines().next().unwrap_or_default();
+                    if let Some(syntax) = syntax.filter(|_| !first_line.contains(filename)) {
+                        code.insert_str(0, &format!("{}{}{}\n", syntax.0, filename, syntax.1));
+                    }
+                }
+                _ => {}
             }
         }
+
+        // Dynamic fence calculation (handles nested backticks)
+        let fence = calculate_fence(&code, options.code_fence);
+
+        if options.fence_info == FenceInfo::Full {
+            let block = CodeBlockInfo {
+                language: lang,
+                // A file name written as a comment isn't repeated
+                filename: filename.filter(|_| options.code_filename != CodeFilename::Comment),
+                line_numbers,
+                attributes: attribute_classes(&element),
+            };
+            info = match &options.fence_info_formatter {
+                Some(formatter) => clean_info_string(&(formatter.0)(&block), options.code_fence),
+                None => block.to_info_string(),
+            };
+        }
+
+        format!("\n\n{}{}\n{}\n{}\n\n", fence, info, code, fence)
     }
+}
 
-    None
+/// Code for a table cell: a code span per line, joined by `<br>`, or one
+/// span of all the lines without `allow_raw_html`.
+fn inline_code(code: &str, options: &Options) -> String {
+    let lines = code
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty());
+    if options.allow_raw_html {
+        lines.map(code_span).collect::<Vec<_>>().join("<br>")
+    } else {
+        code_span(&lines.collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Detect the language of a code block. Sources, in order:
+///
+/// 1. A class on the `<pre>`, then on its `<code>` (see
+///    [`extract_language_from_class`])
+/// 2. A `data-filename` or `data-file` attribute, by extension
+/// 3. A `#!` line or `$ ` prompt opening the code
+fn detect_language(pre: &ElementRef, code: &str) -> Option<String> {
+    label_elements(pre)
+        .find_map(|el| {
+            el.value()
+                .attr("class")
+                .and_then(extract_language_from_class)
+        })
+        .or_else(|| {
+            filename_attr(pre)
+                .and_then(|name| language_for_filename(&name))
+                .map(str::to_string)
+        })
+        .or_else(|| sniff_language(code).map(str::to_string))
+}
+
+/// The elements whose attributes label a code block: the `<pre>`, then its
+/// `<code>` children.
+fn label_elements<'a>(pre: &ElementRef<'a>) -> impl Iterator<Item = ElementRef<'a>> {
+    std::iter::once(*pre).chain(
+        pre.children()
+            .filter_map(ElementRef::wrap)
+            .filter(|el| el.value().name() == "code"),
+    )
+}
+
+/// File name of a code block from its `data-filename` or `data-file`
+/// attribute, or from a class naming a file.
+fn detect_filename(pre: &ElementRef) -> Option<String> {
+    filename_attr(pre)
+        .or_else(|| {
+            label_elements(pre).find_map(|el| {
+                let class = el.value().attr("class")?;
+                class
+                    .split_whitespace()
+                    .filter_map(prefixed_language)
+                    .find(|value| looks_like_filename(value))
+                    .map(str::to_string)
+            })
+        })
+        // Quotes and backticks would end the info string early
+        .filter(|name| !name.contains(['"', '`']) && !name.contains(char::is_control))
+}
+
+fn filename_attr(pre: &ElementRef) -> Option<String> {
+    label_elements(pre).find_map(|el| {
+        ["data-filename", "data-file"]
+            .iter()
+            .filter_map(|attr| el.value().attr(attr))
+            .map(str::trim)
+            .find(|name| !name.is_empty())
+            .map(str::to_string)
+    })
+}
+
+/// Classes listed after the language class that read as code block
+/// attributes, like mdBook's `should_panic` or `edition2021`. Styling
+/// classes such as `thin-scrollbar` or `codeBlock` are left out.
+fn attribute_classes(pre: &ElementRef) -> Vec<String> {
+    for el in label_elements(pre) {
+        let Some(class) = el.value().attr("class") else {
+            continue;
+        };
+        let mut parts = class.split_whitespace();
+        if parts.any(|part| prefixed_language(part).is_some()) {
+            return parts
+                .filter(|part| {
+                    part.chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                        && !KNOWN_LANGUAGES.contains(part)
+                        && !matches!(*part, "hljs" | "notranslate")
+                })
+                .map(str::to_string)
+                .collect();
+        }
+    }
+    Vec::new()
+}
+
+/// An info string from `fence_info_formatter`, on one line and without
+/// backticks, which can't appear in the info string of a backtick fence.
+fn clean_info_string(info: &str, fence: char) -> String {
+    let info: String = info.chars().filter(|&c| c != '`' || fence != '`').collect();
+    info.split(['\n', '\r'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether a class value names a file rather than a language:
+/// `language-src/main.rs`, `lang-main.rs`.
+fn looks_like_filename(value: &str) -> bool {
+    value.contains(['.', '/']) && !is_mime_type(value)
+}
+
+/// The value of a `language-`, `lang-` or `highlight-` class.
+fn prefixed_language(part: &str) -> Option<&str> {
+    part.strip_prefix("language-")
+        .or_else(|| part.strip_prefix("lang-"))
+        .or_else(|| part.strip_prefix("highlight-"))
 }
 
 /// Known programming language identifiers for bare class fallback.
@@ -109,20 +261,28 @@ static KNOWN_LANGUAGES: &[&str] = &[
 ];
 
 /// Extract language from class attribute.
+///
+/// Values are normalized with [`normalize_language`], so `language-c++`
+/// and `language-text/x-c++src` both give `cpp`.
 fn extract_language_from_class(class: &str) -> Option<String> {
+    // SyntaxHighlighter's `brush: js; gutter: false`
+    if let Some(lang) = brush_language(class) {
+        return Some(normalize_language(lang));
+    }
+
     // First pass: check for prefixed patterns (higher priority)
     for part in class.split_whitespace() {
-        // language-{lang} (standard)
-        if let Some(lang) = part.strip_prefix("language-") {
-            return Some(lang.to_string());
-        }
-        // lang-{lang} (common)
-        if let Some(lang) = part.strip_prefix("lang-") {
-            return Some(lang.to_string());
-        }
+        // language-{lang} (standard), lang-{lang} (common), and
         // highlight-{lang} (some highlighters)
-        if let Some(lang) = part.strip_prefix("highlight-") {
-            return Some(lang.to_string());
+        if let Some(lang) = prefixed_language(part) {
+            // A file name gives its language by extension
+            if looks_like_filename(lang) {
+                match language_for_filename(lang) {
+                    Some(lang) => return Some(lang.to_string()),
+                    None => continue,
+                }
+            }
+            return Some(normalize_language(lang));
         }
         // hljs-{lang} (highlight.js)
         if let Some(lang) = part.strip_prefix("hljs-") {
@@ -132,7 +292,7 @@ fn extract_language_from_class(class: &str) -> Option<String> {
             ]
             .contains(&lang)
             {
-                return Some(lang.to_string());
+                return Some(normalize_language(lang));
             }
         }
     }
@@ -141,13 +301,21 @@ fn extract_language_from_class(class: &str) -> Option<String> {
     for part in class.split_whitespace() {
         let lower = part.to_lowercase();
         if KNOWN_LANGUAGES.contains(&lower.as_str()) {
-            return Some(lower);
+            return Some(normalize_language(&lower));
         }
     }
 
     None
 }
 
+/// The language of a SyntaxHighlighter class, `brush: js;` or `brush:js`.
+fn brush_language(class: &str) -> Option<&str> {
+    let start = class.to_ascii_lowercase().find("brush:")? + "brush:".len();
+    class[start..]
+        .split(|c: char| c == ';' || c.is_whitespace())
+        .find(|value| !value.is_empty())
+}
+
 /// Calculate the fence string needed for code that may contain backticks/tildes.
 fn calculate_fence(code: &str, preferred: char) -> String {
     let re = match preferred {
@@ -165,34 +333,42 @@ fn calculate_fence(code: &str, preferred: char) -> String {
     std::iter::repeat_n(preferred, fence_len).collect()
 }
 
-/// Collect text from pre element, skipping gutter elements.
-fn collect_code_text(pre: &ElementRef) -> String {
+/// Collect text from pre element, skipping gutter elements. Also returns
+/// whether a gutter was skipped.
+fn collect_code_text(pre: &ElementRef) -> (String, bool) {
     let mut text = String::new();
-
-    fn collect_recursive(node: ego_tree::NodeRef<scraper::Node>, text: &mut String, skip: bool) {
+    let mut gutter = false;
+
+    fn collect_recursive(
+        node: ego_tree::NodeRef<scraper::Node>,
+        text: &mut String,
+        gutter: &mut bool,
+        skip: bool,
+    ) {
         if let Some(element) = ElementRef::wrap(node) {
-            // Skip gutter/line-number elements
+            // Skip gutter/line-number elements, like Docusaurus's
+            // `codeLineNumber` spans
             if let Some(class) = element.value().attr("class") {
+                let class = class.to_ascii_lowercase();
                 if class.contains("gutter")
                     || class.contains("line-number")
                     || class.contains("line-numbers")
                     || class.contains("lineno")
                     || class.contains("linenumber")
                 {
+                    *gutter = true;
                     return;
                 }
             }
         }
 
         match node.value() {
-            scraper::Node::Text(t) => {
-                if !skip {
-                    text.push_str(t);
-                }
+            scraper::Node::Text(t) if !skip => {
+                text.push_str(t);
             }
             scraper::Node::Element(_) => {
                 for child in node.children() {
-                    collect_recursive(child, text, false);
+                    collect_recursive(child, text, gutter, false);
                 }
             }
             _ => {}
@@ -200,10 +376,10 @@ fn collect_code_text(pre: &ElementRef) -> String {
     }
 
     for child in pre.children() {
-        collect_recursive(child, &mut text, false);
+        collect_recursive(child, &mut text, &mut gutter, false);
     }
 
-    text
+    (text, gutter)
 }
 
 #[cfg(test)]
@@ -217,7 +393,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        PreRule.convert(element, &metadata, options, &|_, _, _| String::new())
+        PreRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|_, _, _| String::new(),
+        )
     }
 
     #[test]
@@ -358,4 +540,233 @@ line 3</code></pre>"#,
         assert!(result.contains("    indented"));
         assert!(result.contains("        more"));
     }
+    fn fence_line(html: &str, options: &Options) -> String {
+        let result = convert_test(html, options);
+        result
+            .trim_start()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    #[test]
+    fn test_filename_class() {
+        let html = r#"<pre><code class="language-src/main.rs">fn main() {}</code></pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```rust");
+        let html = r#"<pre class="lang-config.yml">a: 1</pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```yaml");
+    }
+
+    #[test]
+    fn test_unknown_filename_class_skipped() {
+        let html = r#"<pre><code class="language-notes.txt">hello</code></pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```");
+        let html = r#"<pre><code class="language-notes.txt python">x = 1</code></pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```python");
+    }
+
+    #[test]
+    fn test_class_language_normalized() {
+        let cases = [
+            ("language-c++", "cpp"),
+            ("language-objective-c", "objectivec"),
+            ("lang-sh", "bash"),
+            ("language-js", "javascript"),
+            ("hljs language-JS", "javascript"),
+            ("brush: js;", "javascript"),
+            ("brush: cpp; gutter: false", "cpp"),
+            ("brush:python", "python"),
+            ("Brush: sh; toolbar: false", "bash"),
+            ("language-text/x-java", "java"),
+            ("language-text/x-c++src", "cpp"),
+            ("lang-application/json", "json"),
+            ("highlight-ts", "typescript"),
+            ("hljs-rb", "ruby"),
+            ("language-yml", "yaml"),
+            ("language-golang", "go"),
+            ("language-c#", "csharp"),
+            ("language-py3", "python"),
+            ("language-shell-session", "console"),
+            ("language-rust;", "rust"),
+            ("sourceCode js", "javascript"),
+            ("language-rust", "rust"),
+            ("language-elixir", "elixir"),
+            ("language-Haskell", "Haskell"),
+        ];
+        for (class, expected) in cases {
+            let html = format!(r#"<pre class="{}">x = 1</pre>"#, class);
+            assert_eq!(
+                fence_line(&html, &Options::default()),
+                format!("```{}", expected),
+                "{}",
+                class
+            );
+        }
+    }
+
+    #[test]
+    fn test_data_filename() {
+        let html = r#"<pre data-filename="index.ts"><code>let a = 1;</code></pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```typescript");
+        let html = r#"<pre><code data-file="deploy.sh">echo hi</code></pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```bash");
+    }
+
+    #[test]
+    fn test_shebang_and_prompt() {
+        let html = "<pre>#!/usr/bin/env python3
+print('hi')</pre>";
+        assert_eq!(fence_line(html, &Options::default()), "```python");
+        let html = "<pre>#!/bin/bash
+echo hi</pre>";
+        assert_eq!(fence_line(html, &Options::default()), "```bash");
+        let html = "<pre>$ npm install
+added 1 package</pre>";
+        assert_eq!(fence_line(html, &Options::default()), "```console");
+    }
+
+    #[test]
+    fn test_detection_priority() {
+        // Class beats data-filename, which beats the first line
+        let html =
+            r#"<pre data-filename="run.py"><code class="language-ruby">#!/bin/bash</code></pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```ruby");
+        let html = r#"<pre data-filename="run.py"><code>#!/bin/bash</code></pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```python");
+        // An unknown file name still leaves the first line to go by
+        let html = r#"<pre data-filename="run"><code>#!/bin/bash</code></pre>"#;
+        assert_eq!(fence_line(html, &Options::default()), "```bash");
+    }
+
+    #[test]
+    fn test_filename_info_string() {
+        let options = Options::new().code_filename(CodeFilename::InfoString);
+        let html = r#"<pre data-filename="main.rs"><code>fn main() {}</code></pre>"#;
+        assert_eq!(fence_line(html, &options), r#"```rust title="main.rs""#);
+        let html = r#"<pre><code class="language-src/lib.rs">mod a;</code></pre>"#;
+        assert_eq!(fence_line(html, &options), r#"```rust title="src/lib.rs""#);
+        let html = r#"<pre data-filename="notes.txt">hello</pre>"#;
+        assert_eq!(fence_line(html, &options), r#"```text title="notes.txt""#);
+        // Quotes would end the title early
+        let html = r#"<pre data-filename='my"file.rs'>fn a() {}</pre>"#;
+        assert_eq!(fence_line(html, &options), "```rust");
+    }
+
+    #[test]
+    fn test_filename_comment() {
+        let options = Options::new().code_filename(CodeFilename::Comment);
+        let html = r#"<pre data-filename="main.rs"><code>fn main() {}</code></pre>"#;
+        assert_eq!(
+            convert_test(html, &options),
+            "\n\n```rust\n// main.rs\nfn main() {}\n```\n\n"
+        );
+        let html = r#"<pre data-filename="ci.yml">on: push</pre>"#;
+        assert!(convert_test(html, &options).contains("```yaml\n# ci.yml\non: push\n"));
+        // No comments in JSON, and no repeating a name the code already has
+        let html = r#"<pre data-filename="package.json">{}</pre>"#;
+        assert_eq!(convert_test(html, &options), "\n\n```json\n{}\n```\n\n");
+        let html = r#"<pre data-filename="main.rs">// main.rs\nfn main() {}</pre>"#;
+        assert_eq!(convert_test(html, &options).matches("main.rs").count(), 1);
+    }
+
+    #[test]
+    fn test_filename_omitted_by_default() {
+        let html = r#"<pre data-filename="main.rs"><code>fn main() {}</code></pre>"#;
+        assert_eq!(
+            convert_test(html, &Options::default()),
+            "\n\n```rust\nfn main() {}\n```\n\n"
+        );
+    }
+
+    #[test]
+    fn test_full_info_mdbook_attributes() {
+        let options = Options::new().fence_info(FenceInfo::Full);
+        let html = r#"<pre class="playground"><code class="language-rust should_panic edition2021 hljs">fn main() { panic!() }</code></pre>"#;
+        assert_eq!(
+            convert_test(html, &options),
+            "\n\n```rust,should_panic,edition2021\nfn main() { panic!() }\n```\n\n"
+        );
+        // Language-only output ignores the attributes
+        assert!(convert_test(html, &Options::default()).starts_with("\n\n```rust\n"));
+    }
+
+    #[test]
+    fn test_full_info_docusaurus_line_numbers() {
+        let options = Options::new().fence_info(FenceInfo::Full);
+        let html = concat!(
+            r#"<pre class="prism-code language-jsx codeBlock_bY9V thin-scrollbar" data-filename="App.jsx">"#,
+            r#"<code class="codeBlockLines_e6Vv codeBlockLinesWithNumbering_o6Pm">"#,
+            r#"<span class="token-line"><span class="codeLineNumber_Tfdd">1</span>"#,
+            r#"<span class="codeLineContent_feaV">export default App;</span></span>"#,
+            "</code></pre>"
+        );
+        assert_eq!(
+            convert_test(html, &options),
+            "\n\n```jsx title=\"App.jsx\" linenums\nexport default App;\n```\n\n"
+        );
+    }
+
+    #[test]
+    fn test_full_info_without_details() {
+        let options = Options::new().fence_info(FenceInfo::Full);
+        assert_eq!(
+            convert_test("<pre>x</pre>", &options),
+            "\n\n```\nx\n```\n\n"
+        );
+        let html = r#"<pre data-filename="notes"><code>x</code></pre>"#;
+        assert_eq!(
+            convert_test(html, &options),
+            "\n\n```text title=\"notes\"\nx\n```\n\n"
+        );
+    }
+
+    #[test]
+    fn test_full_info_filename_as_comment_not_repeated() {
+        let options = Options::new()
+            .fence_info(FenceInfo::Full)
+            .code_filename(CodeFilename::Comment);
+        let html = r#"<pre data-filename="main.rs"><code>fn main() {}</code></pre>"#;
+        assert_eq!(
+            convert_test(html, &options),
+            "\n\n```rust\n// main.rs\nfn main() {}\n```\n\n"
+        );
+    }
+
+    #[test]
+    fn test_fence_info_formatter() {
+        let options = Options::new()
+            .fence_info(FenceInfo::Full)
+            .fence_info_formatter(Some(std::sync::Arc::new(|block: &CodeBlockInfo| {
+                let mut info = format!("{{.{}", block.language.as_deref().unwrap_or("text"));
+                for attribute in &block.attributes {
+                    info.push_str(&format!(" .{}", attribute));
+                }
+                info.push_str("}\n`");
+                info
+            })));
+        let html = r#"<pre><code class="language-rust ignore">let x;</code></pre>"#;
+        assert_eq!(
+            convert_test(html, &options),
+            "\n\n```{.rust .ignore}\nlet x;\n```\n\n"
+        );
+    }
+
+    #[test]
+    fn test_pre_in_table_cell() {
+        let html = "<table><tr><th>Code</th></tr>\
+            <tr><td><pre>let x = 1;\n\nlet y = x | 2;</pre></td></tr></table>";
+        assert!(crate::convert(html).ends_with("\n| `let x = 1;`<br>`let y = x \\| 2;` |\n"));
+        let options = Options::new().allow_raw_html(false);
+        assert!(crate::convert_with_options(html, &options)
+            .ends_with("\n| `let x = 1; let y = x \\| 2;` |\n"));
+    }
+
+    #[test]
+    fn test_pre_in_list_item_indented() {
+        assert_eq!(
+            crate::convert("<ul><li>Run:<pre>make\nmake test</pre></li></ul>"),
+            "- Run:\n\n  ```\n  make\n  make test\n  ```\n"
+        );
+    }
 }
@@ -0,0 +1,217 @@
+//! Select rule (select, datalist).
+//!
+//! A variant picker's options (sizes, colors) are often the point of a
+//! product page, so with `SelectHandling::List` they become a bullet list
+//! instead of running together as one word.
+
+use scraper::ElementRef;
+
+use crate::escape::escape_tag_start;
+use crate::options::{Options, SelectHandling};
+use crate::precompute::MetadataMap;
+use crate::rules::{ConversionContext, Rule};
+use crate::whitespace::normalize_block_whitespace;
+
+pub struct SelectRule;
+
+impl Rule for SelectRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["select", "datalist"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        if options.select_handling == SelectHandling::Text {
+            return convert_children(element, metadata, options);
+        }
+
+        let bullet = options.bullet_for_depth(0);
+        let nested_bullet = options.bullet_for_depth(1);
+        let mut lines = Vec::new();
+
+        for child in element.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                "option" => {
+                    if let Some(item) = option_item(&child, options) {
+                        lines.push(format!("{} {}", bullet, item));
+                    }
+                }
+                "optgroup" if !is_disabled(&child) => {
+                    let items: Vec<String> = child
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .filter(|el| el.value().name() == "option")
+                        .filter_map(|el| option_item(&el, options))
+                        .collect();
+                    if items.is_empty() {
+                        continue;
+                    }
+
+                    let label = child.value().attr("label").map(|l| clean_text(l, options));
+                    match label.filter(|l| !l.is_empty()) {
+                        Some(label) => {
+                            lines.push(format!("{} {}", bullet, label));
+                            for item in items {
+                                lines.push(format!("  {} {}", nested_bullet, item));
+                            }
+                        }
+                        // Without a label there is no header to nest under
+                        None => {
+                            for item in items {
+                                lines.push(format!("{} {}", bullet, item));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if lines.is_empty() {
+            return String::new();
+        }
+        format!("\n\n{}\n\n", lines.join("\n"))
+    }
+}
+
+/// List item text for an `<option>`, or None for disabled and placeholder
+/// options.
+///
+/// Datalist options usually have only a `value`, so it stands in for
+/// missing text. The selected option is bolded.
+fn option_item(option: &ElementRef, options: &Options) -> Option<String> {
+    if is_disabled(option) {
+        return None;
+    }
+    let value = option.value().attr("value");
+    // "Choose a size" placeholders submit an empty value
+    if value.is_some_and(|v| v.trim().is_empty()) {
+        return None;
+    }
+
+    let text: String = option.text().collect();
+    let mut text = clean_text(&text, options);
+    if text.is_empty() {
+        text = clean_text(value?, options);
+    }
+    if text.is_empty() {
+        return None;
+    }
+
+    if option.value().attr("selected").is_some() {
+        Some(format!("**{}**", text))
+    } else {
+        Some(text)
+    }
+}
+
+fn is_disabled(element: &ElementRef) -> bool {
+    element.value().attr("disabled").is_some()
+}
+
+/// Whitespace-normalized text, escaped like other text nodes.
+fn clean_text(text: &str, options: &Options) -> String {
+    let text = normalize_block_whitespace(text);
+    escape_tag_start(text.trim(), options.text_escape_mode()).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    fn convert_test(html: &str, options: &Options) -> String {
+        let dom = Html::parse_fragment(html);
+        let element = dom
+            .root_element()
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|el| matches!(el.value().name(), "select" | "datalist"))
+            .unwrap();
+        let metadata = MetadataMap::default();
+
+        SelectRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
+    }
+
+    fn list_options() -> Options {
+        Options::new().select_handling(SelectHandling::List)
+    }
+
+    #[test]
+    fn test_text_by_default() {
+        let html = "<select><option>S</option><option>M</option></select>";
+        assert_eq!(convert_test(html, &Options::default()), "SM");
+    }
+
+    #[test]
+    fn test_selected_option() {
+        let html = r#"<select name="size">
+            <option value="">Choose a size</option>
+            <option value="s">Small</option>
+            <option value="m" selected>Medium</option>
+            <option value="l" disabled>Large (sold out)</option>
+        </select>"#;
+        assert_eq!(
+            convert_test(html, &list_options()),
+            "\n\n- Small\n- **Medium**\n\n"
+        );
+    }
+
+    #[test]
+    fn test_optgroups() {
+        let html = r#"<select>
+            <optgroup label="Warm">
+                <option>Red</option>
+                <option>Orange</option>
+            </optgroup>
+            <optgroup label="Cool">
+                <option>Blue</option>
+            </optgroup>
+            <optgroup label="Retired" disabled>
+                <option>Beige</option>
+            </optgroup>
+            <option>Black</option>
+        </select>"#;
+        assert_eq!(
+            convert_test(html, &list_options()),
+            "\n\n- Warm\n  - Red\n  - Orange\n- Cool\n  - Blue\n- Black\n\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_bullets_follow_cycle() {
+        let html = r#"<select><optgroup label="G"><option>A</option></optgroup></select>"#;
+        let options = list_options().bullet_cycle(Some(vec!['-', '*']));
+        assert_eq!(convert_test(html, &options), "\n\n- G\n  * A\n\n");
+    }
+
+    #[test]
+    fn test_datalist_values() {
+        let html = r#"<datalist id="browsers">
+            <option value="Firefox"></option>
+            <option value="Chrome"></option>
+        </datalist>"#;
+        assert_eq!(
+            convert_test(html, &list_options()),
+            "\n\n- Firefox\n- Chrome\n\n"
+        );
+    }
+
+    #[test]
+    fn test_only_placeholders() {
+        let html = r#"<select><option value="" selected>Pick one</option></select>"#;
+        assert_eq!(convert_test(html, &list_options()), "");
+    }
+}
@@ -2,9 +2,9 @@
 
 use scraper::ElementRef;
 
-use crate::options::Options;
+use crate::options::{Options, StrikethroughStyle};
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 pub struct StrikethroughRule;
 
@@ -18,6 +18,7 @@ impl Rule for StrikethroughRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let content = convert_children(element, metadata, options);
@@ -27,7 +28,25 @@ impl Rule for StrikethroughRule {
             return String::new();
         }
 
-        format!("~~{}~~", content)
+        if !options.allow_raw_html {
+            let content = content.replace('~', r"\~");
+            return match options.strikethrough_style {
+                StrikethroughStyle::SingleTilde => format!("~{}~", content),
+                _ => format!("~~{}~~", content),
+            };
+        }
+
+        // A literal ~ could close or extend tilde markers
+        let style = if content.contains('~') {
+            StrikethroughStyle::Html
+        } else {
+            options.strikethrough_style
+        };
+        match style {
+            StrikethroughStyle::DoubleTilde => format!("~~{}~~", content),
+            StrikethroughStyle::SingleTilde => format!("~{}~", content),
+            StrikethroughStyle::Html => format!("<del>{}</del>", content),
+        }
     }
 }
 
@@ -37,14 +56,23 @@ mod tests {
     use scraper::Html;
 
     fn convert_test(html: &str) -> String {
+        convert_with(html, StrikethroughStyle::DoubleTilde)
+    }
+
+    fn convert_with(html: &str, style: StrikethroughStyle) -> String {
         let dom = Html::parse_fragment(html);
         let element = dom.root_element().first_child().unwrap();
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
+        let options = Options::new().strikethrough_style(style);
 
-        StrikethroughRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        StrikethroughRule.convert(
+            element,
+            &metadata,
+            &options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -62,6 +90,47 @@ mod tests {
         assert_eq!(convert_test("<strike>old</strike>"), "~~old~~");
     }
 
+    #[test]
+    fn test_single_tilde() {
+        assert_eq!(
+            convert_with("<del>old</del>", StrikethroughStyle::SingleTilde),
+            "~old~"
+        );
+    }
+
+    #[test]
+    fn test_html_style() {
+        assert_eq!(
+            convert_with("<s>old</s>", StrikethroughStyle::Html),
+            "<del>old</del>"
+        );
+    }
+
+    #[test]
+    fn test_literal_tilde_falls_back_to_html() {
+        assert_eq!(
+            convert_test("<del>~/old/path</del>"),
+            "<del>~/old/path</del>"
+        );
+        assert_eq!(
+            convert_with("<del>approx ~5</del>", StrikethroughStyle::SingleTilde),
+            "<del>approx ~5</del>"
+        );
+    }
+
+    #[test]
+    fn test_without_raw_html() {
+        let options = Options::new()
+            .strikethrough_style(StrikethroughStyle::Html)
+            .allow_raw_html(false);
+        let convert = |html| crate::convert_with_options(html, &options);
+        assert_eq!(convert("<p><del>old</del></p>"), "~~old~~\n");
+        assert_eq!(
+            convert("<p><del>~/old/path</del></p>"),
+            "~~\\~/old/path~~\n"
+        );
+    }
+
     #[test]
     fn test_empty() {
         assert_eq!(convert_test("<del></del>"), "");
@@ -4,7 +4,7 @@ use scraper::ElementRef;
 
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 pub struct SubscriptRule;
 
@@ -18,6 +18,7 @@ impl Rule for SubscriptRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let content = convert_children(element, metadata, options);
@@ -27,6 +28,12 @@ impl Rule for SubscriptRule {
             return String::new();
         }
 
+        // Plain text without HTML: `H2O` reads fine, and a `_` marker
+        // would clash with emphasis
+        if !options.allow_raw_html {
+            return content.to_string();
+        }
+
         // Use HTML tag for compatibility
         format!("<sub>{}</sub>", content)
     }
@@ -43,9 +50,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        SubscriptRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        SubscriptRule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -53,6 +64,15 @@ mod tests {
         assert_eq!(convert_test("<sub>2</sub>"), "<sub>2</sub>");
     }
 
+    #[test]
+    fn test_plain_without_raw_html() {
+        let options = Options::new().allow_raw_html(false);
+        assert_eq!(
+            crate::convert_with_options("<p>H<sub>2</sub>O</p>", &options),
+            "H2O\n"
+        );
+    }
+
     #[test]
     fn test_empty() {
         assert_eq!(convert_test("<sub></sub>"), "");
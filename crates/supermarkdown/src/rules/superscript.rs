@@ -4,7 +4,7 @@ use scraper::ElementRef;
 
 use crate::options::Options;
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 pub struct SuperscriptRule;
 
@@ -18,6 +18,7 @@ impl Rule for SuperscriptRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        _context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let content = convert_children(element, metadata, options);
@@ -27,6 +28,15 @@ impl Rule for SuperscriptRule {
             return String::new();
         }
 
+        if !options.allow_raw_html {
+            // Parenthesized so `x^(n + 1)` reads as one exponent
+            return if content.contains(char::is_whitespace) {
+                format!("^({})", content)
+            } else {
+                format!("^{}", content)
+            };
+        }
+
         // Use HTML tag for compatibility (not all markdown parsers support ^)
         format!("<sup>{}</sup>", content)
     }
@@ -43,9 +53,13 @@ mod tests {
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        SuperscriptRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        SuperscriptRule.convert(
+            element,
+            &metadata,
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
     }
 
     #[test]
@@ -53,6 +67,14 @@ mod tests {
         assert_eq!(convert_test("<sup>2</sup>"), "<sup>2</sup>");
     }
 
+    #[test]
+    fn test_caret_without_raw_html() {
+        let options = Options::new().allow_raw_html(false);
+        let convert = |html| crate::convert_with_options(html, &options);
+        assert_eq!(convert("<p>E = mc<sup>2</sup></p>"), "E = mc^2\n");
+        assert_eq!(convert("<p>2<sup>n + 1</sup></p>"), "2^(n + 1)\n");
+    }
+
     #[test]
     fn test_empty() {
         assert_eq!(convert_test("<sup></sup>"), "");
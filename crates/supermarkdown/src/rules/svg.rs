@@ -0,0 +1,212 @@
+//! Inline SVG rule.
+//!
+//! SVG icons carry `<title>`/`<text>` children that would otherwise leak
+//! into the surrounding paragraph as stray words. Larger graphics like
+//! charts often carry the only description of what they show, so by
+//! default only icons are dropped.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::ElementRef;
+
+use crate::options::{Options, SvgHandling};
+use crate::precompute::MetadataMap;
+use crate::rules::{ConversionContext, Rule};
+
+/// Regex for normalizing whitespace in titles.
+static WS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
+
+/// Largest width and height, in pixels, of an svg treated as an icon.
+const ICON_MAX_SIZE: f64 = 48.0;
+
+pub struct SvgRule;
+
+impl Rule for SvgRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["svg"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        _metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        _convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        match options.svg_handling {
+            SvgHandling::Skip => String::new(),
+            SvgHandling::Auto => {
+                if is_decorative(&element) || is_icon_sized(&element) {
+                    return String::new();
+                }
+                normalized(element.value().attr("aria-label"))
+                    .or_else(|| svg_title(&element))
+                    .unwrap_or_default()
+            }
+            SvgHandling::Html if options.allow_raw_html => element.html(),
+            SvgHandling::TitleText | SvgHandling::Html => {
+                if is_decorative(&element) {
+                    return String::new();
+                }
+                svg_title(&element).unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Whether the svg explicitly opts out of the accessibility tree.
+fn is_decorative(svg: &ElementRef) -> bool {
+    let el = svg.value();
+    el.attr("aria-hidden") == Some("true")
+        || matches!(el.attr("role"), Some("presentation" | "none"))
+}
+
+/// Whether the svg is no larger than an icon, or gives no size to tell.
+fn is_icon_sized(svg: &ElementRef) -> bool {
+    let el = svg.value();
+    let view_box: Vec<f64> = el
+        .attr("viewBox")
+        .map(|v| {
+            v.split(|c: char| c.is_whitespace() || c == ',')
+                .filter_map(|n| n.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let (box_width, box_height) = match view_box[..] {
+        [_, _, width, height] => (Some(width), Some(height)),
+        _ => (None, None),
+    };
+
+    let width = el.attr("width").and_then(pixels).or(box_width);
+    let height = el.attr("height").and_then(pixels).or(box_height);
+    match (width, height) {
+        (None, None) => true,
+        (width, height) => {
+            width.unwrap_or(0.0) <= ICON_MAX_SIZE && height.unwrap_or(0.0) <= ICON_MAX_SIZE
+        }
+    }
+}
+
+/// A length in pixels, like `24` or `24px`; other units aren't known.
+fn pixels(value: &str) -> Option<f64> {
+    let value = value.trim();
+    value
+        .strip_suffix("px")
+        .unwrap_or(value)
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Whitespace-normalized text, if any is left.
+fn normalized(text: Option<&str>) -> Option<String> {
+    text.map(|text| WS_RE.replace_all(text.trim(), " ").to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Text of the svg's direct `<title>` child, whitespace-normalized.
+fn svg_title(svg: &ElementRef) -> Option<String> {
+    svg.children()
+        .filter_map(ElementRef::wrap)
+        .find(|el| el.value().name() == "title")
+        .and_then(|title| normalized(Some(&title.text().collect::<String>())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    fn convert_test(html: &str, options: &Options) -> String {
+        let dom = Html::parse_fragment(html);
+        let element = dom.root_element().first_child().unwrap();
+        let element = ElementRef::wrap(element).unwrap();
+        let metadata = MetadataMap::default();
+
+        SvgRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
+    }
+
+    const ICON: &str =
+        r#"<svg width="16" height="16"><title>Close Menu</title><path d="M0 0"/></svg>"#;
+
+    const CHART: &str = r#"<svg viewBox="0 0 640 320" role="img" aria-label="Signups by month">
+        <title>Signups chart</title><text x="10" y="20">Jan</text></svg>"#;
+
+    #[test]
+    fn test_icon_skipped_by_default() {
+        assert_eq!(convert_test(ICON, &Options::default()), "");
+        assert_eq!(
+            convert_test(r#"<svg><title>Unsized</title></svg>"#, &Options::default()),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_large_svg_label_by_default() {
+        assert_eq!(convert_test(CHART, &Options::default()), "Signups by month");
+        let titled = r#"<svg width="640px" height="320"><title>Signups
+            chart</title></svg>"#;
+        assert_eq!(convert_test(titled, &Options::default()), "Signups chart");
+    }
+
+    #[test]
+    fn test_large_decorative_svg_skipped_by_default() {
+        for attr in [r#"aria-hidden="true""#, r#"role="presentation""#] {
+            let html = format!(r#"<svg width="800" height="200" {attr}><title>Wave</title></svg>"#);
+            assert_eq!(convert_test(&html, &Options::default()), "");
+        }
+    }
+
+    #[test]
+    fn test_skip() {
+        let options = Options::new().svg_handling(SvgHandling::Skip);
+        assert_eq!(convert_test(CHART, &options), "");
+    }
+
+    #[test]
+    fn test_title_text() {
+        let options = Options::new().svg_handling(SvgHandling::TitleText);
+        assert_eq!(convert_test(ICON, &options), "Close Menu");
+    }
+
+    #[test]
+    fn test_title_text_aria_hidden() {
+        let options = Options::new().svg_handling(SvgHandling::TitleText);
+        let result = convert_test(
+            r#"<svg aria-hidden="true"><title>Search</title></svg>"#,
+            &options,
+        );
+        assert_eq!(result, "");
+    }
+
+    #[test]
+    fn test_title_text_without_title() {
+        let options = Options::new().svg_handling(SvgHandling::TitleText);
+        let result = convert_test(r#"<svg><text>Label</text></svg>"#, &options);
+        assert_eq!(result, "");
+    }
+
+    #[test]
+    fn test_html_passthrough() {
+        let options = Options::new().svg_handling(SvgHandling::Html);
+        let result = convert_test(ICON, &options);
+        assert!(result.starts_with("<svg"));
+        assert!(result.contains("<title>Close Menu</title>"));
+        assert!(result.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_html_falls_back_to_title_without_raw_html() {
+        let options = Options::new()
+            .svg_handling(SvgHandling::Html)
+            .allow_raw_html(false);
+        assert_eq!(convert_test(ICON, &options), "Close Menu");
+    }
+}
@@ -4,9 +4,12 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use scraper::ElementRef;
 
-use crate::options::Options;
+use crate::escape::escape_table_cell;
+use crate::options::{
+    Options, RowHeaderStyle, SingleRowTables, TableCaption, TableImageCells, TableStyle, TextAlign,
+};
 use crate::precompute::MetadataMap;
-use crate::rules::Rule;
+use crate::rules::{ConversionContext, Rule};
 
 /// Regex for normalizing whitespace in cells.
 static WS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
@@ -21,10 +24,69 @@ enum Alignment {
     Right,
 }
 
+/// Every alignment, in discriminant order.
+const ALIGNMENTS: [Alignment; 4] = [
+    Alignment::None,
+    Alignment::Left,
+    Alignment::Center,
+    Alignment::Right,
+];
+
+impl From<TextAlign> for Alignment {
+    fn from(align: TextAlign) -> Self {
+        match align {
+            TextAlign::Left => Alignment::Left,
+            TextAlign::Center => Alignment::Center,
+            TextAlign::Right => Alignment::Right,
+        }
+    }
+}
+
+/// Symbols for icon cells, with the words in alt texts, titles, classes and
+/// file names that select them.
+const ICON_SYMBOLS: &[(&str, &[&str])] = &[
+    (
+        "✓",
+        &[
+            "check",
+            "checkmark",
+            "checked",
+            "tick",
+            "yes",
+            "true",
+            "included",
+            "available",
+        ],
+    ),
+    (
+        "✗",
+        &[
+            "cross",
+            "times",
+            "x",
+            "no",
+            "false",
+            "close",
+            "excluded",
+            "unavailable",
+        ],
+    ),
+    ("—", &["dash", "minus", "none", "na"]),
+];
+
+/// Largest `span` the HTML spec allows on `<col>` and `<colgroup>`.
+const MAX_COL_SPAN: usize = 1000;
+
+/// Most dashes a delimiter row sized by width hints gets to give its
+/// narrowest column three, so a 1% column doesn't make a 300-dash row.
+const MAX_HINTED_WIDTH: f64 = 72.0;
+
 /// Cell data with content and alignment.
 struct CellData {
     content: String,
     alignment: Alignment,
+    /// Whether the cell is a `<th>`.
+    header: bool,
 }
 
 pub struct TableRule;
@@ -39,134 +101,414 @@ impl Rule for TableRule {
         element: ElementRef,
         metadata: &MetadataMap,
         options: &Options,
+        context: &ConversionContext,
         convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     ) -> String {
         let mut rows: Vec<Vec<CellData>> = Vec::new();
         let mut caption: Option<String> = None;
+        let mut has_thead = false;
 
-        // Extract rows from thead, tbody, or direct tr children
-        for child in element.children() {
-            if let Some(el) = ElementRef::wrap(child) {
-                match el.value().name() {
-                    "caption" => {
-                        let text = convert_children(el, metadata, options);
-                        let text = WS_RE.replace_all(text.trim(), " ").to_string();
-                        if !text.is_empty() {
-                            caption = Some(text);
-                        }
-                    }
-                    "thead" => {
-                        extract_rows(&el, metadata, options, convert_children, &mut rows);
-                    }
-                    "tbody" | "tfoot" => {
-                        extract_rows(&el, metadata, options, convert_children, &mut rows);
-                    }
-                    "tr" => {
-                        if let Some(row) = extract_row(&el, metadata, options, convert_children) {
-                            rows.push(row);
-                        }
-                    }
-                    _ => {}
+        for child in element.children().filter_map(ElementRef::wrap) {
+            if child.value().name() == "caption" {
+                let text = convert_children(child, metadata, options);
+                let text = WS_RE.replace_all(text.trim(), " ").to_string();
+                if !text.is_empty() {
+                    caption = Some(text);
                 }
             }
         }
+        extract_rows(
+            &element,
+            metadata,
+            options,
+            convert_children,
+            &mut rows,
+            &mut has_thead,
+        );
 
         if rows.is_empty() {
             return String::new();
         }
 
-        // Calculate column widths and alignments
-        let col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
-        let mut col_widths: Vec<usize> = vec![3; col_count]; // minimum width of 3
-        let mut col_alignments: Vec<Alignment> = vec![Alignment::None; col_count];
-
-        for row in &rows {
-            for (i, cell) in row.iter().enumerate() {
-                if i < col_widths.len() {
-                    col_widths[i] = col_widths[i].max(cell.content.chars().count());
-                    // Use alignment from first row (header) if specified
-                    if col_alignments[i] == Alignment::None && cell.alignment != Alignment::None {
-                        col_alignments[i] = cell.alignment;
-                    }
-                }
+        if options.table_caption == TableCaption::Drop {
+            caption = None;
+        }
+
+        match options.table_style {
+            // A header row alone has no records to list
+            TableStyle::KeyValueList if rows.len() > 1 => render_key_value(&rows, caption),
+            TableStyle::Html if options.allow_raw_html => format!("\n\n{}\n\n", element.html()),
+            // A table in a cell can't have rows of its own
+            _ if context.in_table_cell => render_inline(&rows, caption, options.allow_raw_html),
+            _ if rows.len() == 1 && options.single_row_tables == SingleRowTables::Text => {
+                render_single_row(&rows[0], caption, options.table_caption)
             }
+            _ => {
+                let rows = if !has_thead && has_row_headers(&rows) {
+                    add_empty_header(rows, options.row_header_style)
+                } else {
+                    rows
+                };
+                let columns = column_alignments(&element, options);
+                let col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+                let shares = if options.table_width_hints {
+                    width_shares(&element, col_count)
+                } else {
+                    Vec::new()
+                };
+                render_pipes(
+                    &rows,
+                    &columns,
+                    &shares,
+                    caption,
+                    options.table_caption,
+                    options.compact,
+                )
+            }
+        }
+    }
+}
+
+/// Rule for a `<tr>` converted outside its table's rule, as when
+/// include_selectors keeps a row of an excluded table: its cells go on one
+/// line, separated by ` | `.
+pub struct TableRowRule;
+
+impl Rule for TableRowRule {
+    fn tags(&self) -> &'static [&'static str] {
+        &["tr"]
+    }
+
+    fn convert(
+        &self,
+        element: ElementRef,
+        metadata: &MetadataMap,
+        options: &Options,
+        _context: &ConversionContext,
+        convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+    ) -> String {
+        let cells: Vec<String> = extract_row(&element, metadata, options, convert_children)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|cell| cell.content)
+            .filter(|content| !content.is_empty())
+            .collect();
+        if cells.is_empty() {
+            return String::new();
         }
+        format!("\n\n{}\n\n", cells.join(" | "))
+    }
+}
 
-        // Build markdown table
-        let mut result = String::from("\n\n");
+/// Render rows as a GFM pipe table, the first row being the header.
+///
+/// See [`column_alignment`] for how each column's alignment is chosen.
+/// Compact tables aren't padded to line their columns up. With `shares`
+/// from width hints, one per column, the delimiter row gives each column
+/// its share of the width instead of its content's.
+fn render_pipes(
+    rows: &[Vec<CellData>],
+    columns: &[Alignment],
+    shares: &[f64],
+    caption: Option<String>,
+    placement: TableCaption,
+    compact: bool,
+) -> String {
+    // Escape pipes in cell content, including inside code spans
+    let rows: Vec<Vec<CellData>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| CellData {
+                    content: escape_table_cell(&cell.content),
+                    alignment: cell.alignment,
+                    header: cell.header,
+                })
+                .collect()
+        })
+        .collect();
+
+    // Calculate column widths and alignments
+    let col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut col_widths: Vec<usize> = vec![3; col_count]; // minimum width of 3
 
-        for (row_idx, row) in rows.iter().enumerate() {
+    // Compact separators keep the minimum width
+    for row in rows.iter().filter(|_| !compact) {
+        for (i, cell) in row.iter().enumerate() {
+            if i < col_widths.len() {
+                col_widths[i] = col_widths[i].max(cell.content.chars().count());
+            }
+        }
+    }
+    if !shares.is_empty() && shares.len() == col_count {
+        col_widths = hinted_widths(&col_widths, shares);
+    }
+    let col_alignments: Vec<Alignment> = (0..col_count)
+        .map(|i| column_alignment(&rows, i, columns.get(i).copied().unwrap_or(Alignment::None)))
+        .collect();
+
+    // Build markdown table
+    let mut result = String::from("\n\n");
+    if let (Some(cap), TableCaption::Before) = (&caption, placement) {
+        result.push_str(&format!("**{}**\n\n", cap));
+    }
+
+    for (row_idx, row) in rows.iter().enumerate() {
+        result.push('|');
+        for (col_idx, cell) in row.iter().enumerate() {
+            let width = if compact {
+                0
+            } else {
+                col_widths.get(col_idx).copied().unwrap_or(3)
+            };
+            let alignment = col_alignments
+                .get(col_idx)
+                .copied()
+                .unwrap_or(Alignment::None);
+
+            // Format cell content with alignment
+            let formatted = match alignment {
+                Alignment::Right => format!(" {:>width$} |", cell.content, width = width),
+                Alignment::Center => format!(" {:^width$} |", cell.content, width = width),
+                _ => format!(" {:width$} |", cell.content, width = width),
+            };
+            result.push_str(&formatted);
+        }
+        // Pad missing columns
+        for col_idx in row.len()..col_count {
+            let width = if compact {
+                0
+            } else {
+                col_widths.get(col_idx).copied().unwrap_or(3)
+            };
+            result.push_str(&format!(" {:width$} |", "", width = width));
+        }
+        result.push('\n');
+
+        // Add separator after header row (first row)
+        if row_idx == 0 {
             result.push('|');
-            for (col_idx, cell) in row.iter().enumerate() {
-                let width = col_widths.get(col_idx).copied().unwrap_or(3);
+            for (col_idx, width) in col_widths.iter().enumerate() {
                 let alignment = col_alignments
                     .get(col_idx)
                     .copied()
                     .unwrap_or(Alignment::None);
-
-                // Format cell content with alignment
-                let formatted = match alignment {
-                    Alignment::Right => format!(" {:>width$} |", cell.content, width = width),
-                    Alignment::Center => format!(" {:^width$} |", cell.content, width = width),
-                    _ => format!(" {:width$} |", cell.content, width = width),
+                let separator = match alignment {
+                    Alignment::Left => format!(" :{} |", "-".repeat(*width - 1)),
+                    Alignment::Center => {
+                        format!(" :{}: |", "-".repeat(width.saturating_sub(2)))
+                    }
+                    Alignment::Right => format!(" {}: |", "-".repeat(*width - 1)),
+                    Alignment::None => format!(" {} |", "-".repeat(*width)),
                 };
-                result.push_str(&formatted);
-            }
-            // Pad missing columns
-            for col_idx in row.len()..col_count {
-                let width = col_widths.get(col_idx).copied().unwrap_or(3);
-                result.push_str(&format!(" {:width$} |", "", width = width));
+                result.push_str(&separator);
             }
             result.push('\n');
+        }
+    }
 
-            // Add separator after header row (first row)
-            if row_idx == 0 {
-                result.push('|');
-                for (col_idx, width) in col_widths.iter().enumerate() {
-                    let alignment = col_alignments
-                        .get(col_idx)
-                        .copied()
-                        .unwrap_or(Alignment::None);
-                    let separator = match alignment {
-                        Alignment::Left => format!(" :{} |", "-".repeat(*width - 1)),
-                        Alignment::Center => {
-                            format!(" :{}: |", "-".repeat(width.saturating_sub(2)))
-                        }
-                        Alignment::Right => format!(" {}: |", "-".repeat(*width - 1)),
-                        Alignment::None => format!(" {} |", "-".repeat(*width)),
-                    };
-                    result.push_str(&separator);
-                }
-                result.push('\n');
+    // A caption after the table needs a blank line, or GFM reads it as a row
+    match (caption, placement) {
+        (Some(cap), TableCaption::After) => result.push_str(&format!("\n*{}*", cap)),
+        (Some(cap), TableCaption::Pandoc) => result.push_str(&format!("\n: {}", cap)),
+        _ => {}
+    }
+
+    result.push('\n');
+    result
+}
+
+/// Render the only row of a table as one line, `**A** · **B** · C`, with
+/// header cells in bold. A caption goes on a line of its own.
+fn render_single_row(row: &[CellData], caption: Option<String>, placement: TableCaption) -> String {
+    let line = row_text(row);
+    if line.is_empty() {
+        return String::new();
+    }
+    match (caption, placement) {
+        (Some(cap), TableCaption::Before) => format!("\n\n**{}**\n\n{}\n\n", cap, line),
+        (Some(cap), _) => format!("\n\n{}\n\n*{}*\n\n", line, cap),
+        (None, _) => format!("\n\n{}\n\n", line),
+    }
+}
+
+/// Render a table nested in a cell on one line: the caption and each row
+/// as text, separated by `<br>`, or by `; ` without `allow_raw_html`.
+fn render_inline(rows: &[Vec<CellData>], caption: Option<String>, raw_html: bool) -> String {
+    caption
+        .map(|cap| format!("*{}*", cap))
+        .into_iter()
+        .chain(rows.iter().map(|row| row_text(row)))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(if raw_html { "<br>" } else { "; " })
+}
+
+/// The non-empty cells of a row separated by ` · `, header cells in bold.
+fn row_text(row: &[CellData]) -> String {
+    row.iter()
+        .filter(|cell| !cell.content.is_empty())
+        .map(|cell| {
+            // Already bold headers would otherwise end up as ****A****
+            if cell.header && !cell.content.starts_with("**") {
+                format!("**{}**", cell.content)
+            } else {
+                cell.content.clone()
             }
-        }
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Whether every row starts with a `<th>` while the first row isn't all
+/// `<th>`, so the table is labelled by row headers rather than a header row.
+/// Alignment of column `index`. Each cell counts with its own alignment,
+/// or else the column's `<col>` alignment. The header cell's wins; without
+/// one, the alignment most data cells share, with ties going to none.
+/// A single aligned data cell thus doesn't realign the whole column.
+fn column_alignment(rows: &[Vec<CellData>], index: usize, column: Alignment) -> Alignment {
+    let effective = |cell: &CellData| match cell.alignment {
+        Alignment::None => column,
+        alignment => alignment,
+    };
+
+    let header = rows.first().and_then(|row| row.get(index)).map(effective);
+    if let Some(alignment) = header.filter(|&alignment| alignment != Alignment::None) {
+        return alignment;
+    }
+
+    let mut counts = [0usize; 4];
+    for cell in rows.iter().skip(1).filter_map(|row| row.get(index)) {
+        counts[effective(cell) as usize] += 1;
+    }
+    let most = counts.iter().copied().max().unwrap_or(0);
+    let mut leaders = ALIGNMENTS
+        .iter()
+        .zip(counts)
+        .filter(|&(_, count)| count == most);
+    match (leaders.next(), leaders.next()) {
+        (Some((&alignment, _)), None) => alignment,
+        _ => Alignment::None,
+    }
+}
+
+fn has_row_headers(rows: &[Vec<CellData>]) -> bool {
+    rows.iter().all(|row| row[0].header) && !rows[0].iter().all(|cell| cell.header)
+}
+
+/// Put an empty header row above a row-headed table so that no data row is
+/// promoted, bolding the row headers with `RowHeaderStyle::Bold`.
+fn add_empty_header(rows: Vec<Vec<CellData>>, style: RowHeaderStyle) -> Vec<Vec<CellData>> {
+    if style == RowHeaderStyle::FirstRow {
+        return rows;
+    }
 
-        // Add caption if present
-        if let Some(cap) = caption {
-            result.push_str(&format!("\n*{}*", cap));
+    let col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let header = (0..col_count)
+        .map(|_| CellData {
+            content: String::new(),
+            alignment: Alignment::None,
+            header: true,
+        })
+        .collect();
+
+    let mut table = Vec::with_capacity(rows.len() + 1);
+    table.push(header);
+    for mut row in rows {
+        let cell = &mut row[0];
+        // Already bold headers would otherwise end up as ****Key****
+        if style == RowHeaderStyle::Bold
+            && !cell.content.is_empty()
+            && !cell.content.starts_with("**")
+        {
+            cell.content = format!("**{}**", cell.content);
         }
+        table.push(row);
+    }
+    table
+}
+
+/// Render each data row as a bullet item of `**Header**: value` pairs,
+/// keyed by the first row. Empty cells are left out.
+fn render_key_value(rows: &[Vec<CellData>], caption: Option<String>) -> String {
+    let (header, records) = rows.split_first().expect("at least two rows");
+    let mut result = String::from("\n\n");
 
+    if let Some(cap) = caption {
+        result.push_str(&format!("**{}**\n\n", cap));
+    }
+
+    for row in records {
+        let pairs: Vec<String> = row
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| !cell.content.is_empty())
+            .map(
+                |(i, cell)| match header.get(i).map(|h| h.content.as_str()) {
+                    Some(key) if !key.is_empty() => format!("**{}**: {}", key, cell.content),
+                    // Cells past the header or under an empty header have no key
+                    _ => cell.content.clone(),
+                },
+            )
+            .collect();
+        if pairs.is_empty() {
+            continue;
+        }
+        result.push_str("- ");
+        result.push_str(&pairs.join("\n  "));
         result.push('\n');
-        result
     }
+
+    result.push('\n');
+    result
 }
 
+/// Collect the rows below `container` in document order.
+///
+/// Parsers normally put every `tr` in a row group and every cell in a `tr`,
+/// but trees built from fragments or by other tools don't always follow
+/// that. So rows are found at any depth short of a nested table, whatever
+/// wraps them, and cells outside any `tr` are gathered into rows of their
+/// own, one per run of sibling cells.
 fn extract_rows(
     container: &ElementRef,
     metadata: &MetadataMap,
     options: &Options,
     convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
     rows: &mut Vec<Vec<CellData>>,
+    has_thead: &mut bool,
 ) {
-    for child in container.children() {
-        if let Some(el) = ElementRef::wrap(child) {
-            if el.value().name() == "tr" {
+    let mut stray_cells = Vec::new();
+
+    for el in container.children().filter_map(ElementRef::wrap) {
+        let tag = el.value().name();
+        if tag == "td" || tag == "th" {
+            stray_cells.push(extract_cell(&el, metadata, options, convert_children));
+            continue;
+        }
+        if !stray_cells.is_empty() {
+            rows.push(std::mem::take(&mut stray_cells));
+        }
+
+        match tag {
+            "tr" => {
                 if let Some(row) = extract_row(&el, metadata, options, convert_children) {
                     rows.push(row);
                 }
             }
+            // Nested tables are their own cells' content, and the caption
+            // is rendered apart from the rows
+            "table" | "caption" | "colgroup" | "col" => {}
+            _ => {
+                *has_thead |= tag == "thead";
+                extract_rows(&el, metadata, options, convert_children, rows, has_thead);
+            }
         }
     }
+
+    if !stray_cells.is_empty() {
+        rows.push(stray_cells);
+    }
 }
 
 fn extract_row(
@@ -181,15 +523,7 @@ fn extract_row(
         if let Some(el) = ElementRef::wrap(child) {
             let tag = el.value().name();
             if tag == "th" || tag == "td" {
-                let content = convert_children(el, metadata, options);
-                let content = WS_RE.replace_all(content.trim(), " ");
-                // Escape pipes in cell content
-                let content = content.replace('|', "\\|");
-
-                // Extract alignment from align attribute or style
-                let alignment = extract_alignment(&el);
-
-                cells.push(CellData { content, alignment });
+                cells.push(extract_cell(&el, metadata, options, convert_children));
             }
         }
     }
@@ -201,8 +535,328 @@ fn extract_row(
     }
 }
 
-/// Extract alignment from element's align attribute or style.
-fn extract_alignment(element: &ElementRef) -> Alignment {
+fn extract_cell(
+    cell: &ElementRef,
+    metadata: &MetadataMap,
+    options: &Options,
+    convert_children: &dyn Fn(ElementRef, &MetadataMap, &Options) -> String,
+) -> CellData {
+    let content = match options.table_image_cells {
+        TableImageCells::Markdown => None,
+        style => icon_cell(cell, metadata, style),
+    }
+    .unwrap_or_else(|| convert_children(*cell, metadata, options));
+    let content = WS_RE.replace_all(content.trim(), " ").into_owned();
+
+    // Extract alignment from align attribute or style
+    let alignment = extract_alignment(cell, options);
+
+    CellData {
+        content,
+        alignment,
+        header: cell.value().name() == "th",
+    }
+}
+
+/// Text for a cell holding only images or icon elements, or None when the
+/// cell has text of its own or an icon reads as nothing.
+fn icon_cell(cell: &ElementRef, metadata: &MetadataMap, style: TableImageCells) -> Option<String> {
+    let mut icons = Vec::new();
+    if !collect_icons(*cell, metadata, &mut icons) || icons.is_empty() {
+        return None;
+    }
+
+    let texts: Vec<String> = icons
+        .iter()
+        .map(|icon| {
+            let alt = icon_alt(icon);
+            let symbol = || icon_symbol(icon);
+            match style {
+                TableImageCells::AltText => alt.or_else(symbol),
+                _ => symbol().or(alt),
+            }
+        })
+        .collect::<Option<_>>()?;
+    Some(texts.join(" "))
+}
+
+/// Gather the images, svgs and icon-font elements (`<i class="fa fa-check">`)
+/// under `element`, returning false if it also holds text. Excluded
+/// elements are passed over.
+fn collect_icons<'a>(
+    element: ElementRef<'a>,
+    metadata: &MetadataMap,
+    icons: &mut Vec<ElementRef<'a>>,
+) -> bool {
+    for child in element.children() {
+        if let Some(text) = child.value().as_text() {
+            if !text.trim().is_empty() {
+                return false;
+            }
+            continue;
+        }
+        let Some(el) = ElementRef::wrap(child) else {
+            continue;
+        };
+        if metadata.get(&el.id()).is_some_and(|meta| meta.skip) {
+            continue;
+        }
+        let childless = el.children().all(|c| c.value().as_text().is_some());
+        match el.value().name() {
+            // An svg's <title> labels it rather than being cell text
+            "img" | "svg" => icons.push(el),
+            _ if childless && el.value().attr("class").is_some() => {
+                if el.text().any(|t| !t.trim().is_empty()) {
+                    return false;
+                }
+                icons.push(el);
+            }
+            _ => {
+                if !collect_icons(el, metadata, icons) {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// The label an icon gives readers: alt text, `aria-label`, `title`, or an
+/// svg's `<title>`.
+fn icon_alt(icon: &ElementRef) -> Option<String> {
+    let svg_title = icon
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|el| icon.value().name() == "svg" && el.value().name() == "title")
+        .map(|title| title.text().collect::<String>());
+
+    ["alt", "aria-label", "title"]
+        .iter()
+        .filter_map(|name| icon.value().attr(name).map(str::to_string))
+        .chain(svg_title)
+        .map(|text| WS_RE.replace_all(text.trim(), " ").into_owned())
+        .find(|text| !text.is_empty())
+}
+
+/// The symbol the words of an icon's labels, classes and file name select.
+fn icon_symbol(icon: &ElementRef) -> Option<String> {
+    let file = icon
+        .value()
+        .attr("src")
+        .map(|src| src.split(['?', '#']).next().unwrap_or(src))
+        .and_then(|path| path.rsplit('/').next());
+    let sources = ["alt", "aria-label", "title", "class"]
+        .iter()
+        .filter_map(|name| icon.value().attr(name))
+        .chain(file);
+
+    for source in sources {
+        let lower = source.to_lowercase();
+        for word in lower.split(|c: char| !c.is_alphanumeric()) {
+            if let Some((symbol, _)) = ICON_SYMBOLS.iter().find(|(_, words)| words.contains(&word))
+            {
+                return Some(symbol.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Alignment of each column from the table's `<colgroup>` and `<col>`
+/// elements, indexed by column.
+fn column_alignments(table: &ElementRef, options: &Options) -> Vec<Alignment> {
+    let mut columns = Vec::new();
+
+    for child in table.children().filter_map(ElementRef::wrap) {
+        match child.value().name() {
+            "colgroup" => {
+                let group = extract_alignment(&child, options);
+                let mut cols = child
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|el| el.value().name() == "col")
+                    .peekable();
+
+                // A colgroup without cols spans columns itself
+                if cols.peek().is_none() {
+                    columns.extend(std::iter::repeat_n(group, col_span(&child)));
+                }
+                for col in cols {
+                    let alignment = match extract_alignment(&col, options) {
+                        Alignment::None => group,
+                        alignment => alignment,
+                    };
+                    columns.extend(std::iter::repeat_n(alignment, col_span(&col)));
+                }
+            }
+            "col" => {
+                let alignment = extract_alignment(&child, options);
+                columns.extend(std::iter::repeat_n(alignment, col_span(&child)));
+            }
+            _ => {}
+        }
+    }
+
+    columns
+}
+
+/// Each column's share of the table width, from the `width` of its `<col>`
+/// or else of its cell in the first row. Columns without a hint split what
+/// percentages leave over, or take the average hint. Empty when no column
+/// has a hint or percentages and pixels are mixed.
+fn width_shares(table: &ElementRef, col_count: usize) -> Vec<f64> {
+    let mut widths: Vec<Option<Width>> = Vec::new();
+    for child in table.children().filter_map(ElementRef::wrap) {
+        match child.value().name() {
+            "colgroup" => {
+                let group = extract_width(&child);
+                let mut cols = child
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|el| el.value().name() == "col")
+                    .peekable();
+                if cols.peek().is_none() {
+                    widths.extend(std::iter::repeat_n(group, col_span(&child)));
+                }
+                for col in cols {
+                    let width = extract_width(&col).or(group);
+                    widths.extend(std::iter::repeat_n(width, col_span(&col)));
+                }
+            }
+            "col" => widths.extend(std::iter::repeat_n(extract_width(&child), col_span(&child))),
+            _ => {}
+        }
+    }
+    widths.resize(col_count, None);
+    for (width, cell) in widths.iter_mut().zip(first_row_cells(table)) {
+        if width.is_none() {
+            *width = extract_width(&cell);
+        }
+    }
+
+    let hints: Vec<Width> = widths.iter().flatten().copied().collect();
+    let Some(first) = hints.first() else {
+        return Vec::new();
+    };
+    let percent = matches!(first, Width::Percent(_));
+    if hints
+        .iter()
+        .any(|hint| matches!(hint, Width::Percent(_)) != percent)
+    {
+        return Vec::new();
+    }
+
+    let sum: f64 = hints.iter().map(Width::value).sum();
+    let missing = col_count - hints.len();
+    let fill = if percent && sum < 100.0 {
+        (100.0 - sum) / missing.max(1) as f64
+    } else {
+        sum / hints.len() as f64
+    };
+    let values: Vec<f64> = widths
+        .iter()
+        .map(|width| width.as_ref().map_or(fill, Width::value))
+        .collect();
+    let total: f64 = values.iter().sum();
+    values.iter().map(|value| value / total).collect()
+}
+
+/// Delimiter widths in proportion to `shares`, at least three dashes each,
+/// widened until no column is narrower than `content` says it must be.
+fn hinted_widths(content: &[usize], shares: &[f64]) -> Vec<usize> {
+    let smallest = shares.iter().copied().fold(f64::INFINITY, f64::min);
+    let total = content
+        .iter()
+        .zip(shares)
+        .map(|(&width, share)| width as f64 / share)
+        .fold((3.0 / smallest).min(MAX_HINTED_WIDTH), f64::max);
+    content
+        .iter()
+        .zip(shares)
+        .map(|(&width, share)| ((share * total).round() as usize).max(width))
+        .collect()
+}
+
+/// The cells of a table's first row, found the way rows are extracted.
+fn first_row_cells<'a>(container: &ElementRef<'a>) -> Vec<ElementRef<'a>> {
+    let mut stray_cells = Vec::new();
+    for el in container.children().filter_map(ElementRef::wrap) {
+        match el.value().name() {
+            "td" | "th" => stray_cells.push(el),
+            _ if !stray_cells.is_empty() => break,
+            "tr" => {
+                let cells: Vec<ElementRef> = el
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                    .collect();
+                if !cells.is_empty() {
+                    return cells;
+                }
+            }
+            "table" | "caption" | "colgroup" | "col" => {}
+            _ => {
+                let cells = first_row_cells(&el);
+                if !cells.is_empty() {
+                    return cells;
+                }
+            }
+        }
+    }
+    stray_cells
+}
+
+/// A column width from HTML.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Width {
+    Percent(f64),
+    Pixels(f64),
+}
+
+impl Width {
+    fn value(&self) -> f64 {
+        match *self {
+            Width::Percent(value) | Width::Pixels(value) => value,
+        }
+    }
+}
+
+/// Width from an element's `width` style, or else its `width` attribute,
+/// in percent or pixels. Other units can't be compared and are ignored.
+fn extract_width(element: &ElementRef) -> Option<Width> {
+    let el = element.value();
+    let style = el.attr("style").and_then(|style| {
+        style.split(';').find_map(|declaration| {
+            let (name, value) = declaration.split_once(':')?;
+            name.trim().eq_ignore_ascii_case("width").then_some(value)
+        })
+    });
+    let value = style
+        .or_else(|| el.attr("width"))?
+        .trim()
+        .to_ascii_lowercase();
+    let value = value.trim_end_matches("!important").trim_end();
+
+    let width = if let Some(percent) = value.strip_suffix('%') {
+        Width::Percent(percent.trim().parse().ok()?)
+    } else {
+        let pixels = value.strip_suffix("px").unwrap_or(value);
+        Width::Pixels(pixels.trim().parse().ok()?)
+    };
+    (width.value() > 0.0 && width.value().is_finite()).then_some(width)
+}
+
+/// The number of columns a `<col>` or `<colgroup>` covers.
+fn col_span(element: &ElementRef) -> usize {
+    element
+        .value()
+        .attr("span")
+        .and_then(|span| span.trim().parse::<usize>().ok())
+        .map_or(1, |span| span.clamp(1, MAX_COL_SPAN))
+}
+
+/// Extract alignment from element's align attribute, style or classes.
+fn extract_alignment(element: &ElementRef, options: &Options) -> Alignment {
     // Check align attribute first
     if let Some(align) = element.value().attr("align") {
         return match align.to_lowercase().as_str() {
@@ -227,6 +881,17 @@ fn extract_alignment(element: &ElementRef) -> Alignment {
         }
     }
 
+    // Framework classes like text-right or has-text-centered
+    for class in element.value().classes() {
+        if let Some((_, align)) = options
+            .alignment_classes
+            .iter()
+            .find(|(name, _)| name == class)
+        {
+            return (*align).into();
+        }
+    }
+
     Alignment::None
 }
 
@@ -236,14 +901,26 @@ mod tests {
     use scraper::Html;
 
     fn convert_test(html: &str) -> String {
+        convert_with(html, &Options::default())
+    }
+
+    fn convert_with(html: &str, options: &Options) -> String {
         let dom = Html::parse_fragment(html);
         let element = dom.root_element().first_child().unwrap();
         let element = ElementRef::wrap(element).unwrap();
         let metadata = MetadataMap::default();
 
-        TableRule.convert(element, &metadata, &Options::default(), &|e, _, _| {
-            e.text().collect::<Vec<_>>().join("")
-        })
+        TableRule.convert(
+            element,
+            &metadata,
+            options,
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
+    }
+
+    fn key_value() -> Options {
+        Options::new().table_style(TableStyle::KeyValueList)
     }
 
     #[test]
@@ -312,6 +989,55 @@ mod tests {
         assert!(result.contains("----:"));
     }
 
+    #[test]
+    fn test_single_aligned_body_cell_keeps_column_unaligned() {
+        let result = convert_test(
+            r#"<table>
+                <tr><th>Item</th><th>Price</th></tr>
+                <tr><td>Tea</td><td>3</td></tr>
+                <tr><td>Coffee</td><td align="right">4</td></tr>
+                <tr><td>Water</td><td>1</td></tr>
+            </table>"#,
+        );
+        assert!(result.contains("| ------ | ----- |"), "{result}");
+    }
+
+    #[test]
+    fn test_agreeing_body_cells_align_column() {
+        let result = convert_test(
+            r#"<table>
+                <tr><th>Item</th><th>Price</th></tr>
+                <tr><td>Tea</td><td align="right">3</td></tr>
+                <tr><td>Coffee</td><td align="right">4</td></tr>
+            </table>"#,
+        );
+        assert!(result.contains("| ------ | ----: |"), "{result}");
+    }
+
+    #[test]
+    fn test_header_alignment_beats_body_majority() {
+        let result = convert_test(
+            r#"<table>
+                <tr><th>Item</th><th align="center">Price</th></tr>
+                <tr><td>Tea</td><td align="right">3</td></tr>
+                <tr><td>Coffee</td><td align="right">4</td></tr>
+            </table>"#,
+        );
+        assert!(result.contains("| ------ | :---: |"), "{result}");
+    }
+
+    #[test]
+    fn test_tied_body_alignments_leave_column_unaligned() {
+        let result = convert_test(
+            r#"<table>
+                <tr><th>Item</th><th>Price</th></tr>
+                <tr><td>Tea</td><td align="right">3</td></tr>
+                <tr><td>Coffee</td><td align="left">4</td></tr>
+            </table>"#,
+        );
+        assert!(result.contains("| ------ | ----- |"), "{result}");
+    }
+
     #[test]
     fn test_table_with_caption() {
         let result = convert_test(
@@ -340,6 +1066,161 @@ mod tests {
         assert!(result.contains("---:"));
     }
 
+    #[test]
+    fn test_colgroup_alignment() {
+        let result = convert_test(
+            r#"<table>
+                <colgroup>
+                    <col>
+                    <col span="2" align="right">
+                </colgroup>
+                <tr><th>Item</th><th>Qty</th><th>Price</th></tr>
+                <tr><td>Tea</td><td>2</td><td>$4</td></tr>
+            </table>"#,
+        );
+        assert!(result.contains("| ---- | --: | ----: |"));
+    }
+
+    fn width_hints() -> Options {
+        Options::new().table_width_hints(true)
+    }
+
+    /// Dash counts of the delimiter row, colons included.
+    fn delimiter_widths(table: &str) -> Vec<usize> {
+        let line = table.lines().find(|line| line.starts_with("| -")).unwrap();
+        line.split('|')
+            .map(str::trim)
+            .filter(|cell| !cell.is_empty())
+            .map(str::len)
+            .collect()
+    }
+
+    #[test]
+    fn test_width_hints_from_cols() {
+        let html = r#"<table>
+            <colgroup><col width="70%"><col style="width: 30%"></colgroup>
+            <tr><th>Name</th><th>Age</th></tr>
+            <tr><td>Ada</td><td>36</td></tr>
+        </table>"#;
+        assert_eq!(
+            convert_with(html, &width_hints()),
+            "\n\n| Name    | Age |\n| ------- | --- |\n| Ada     | 36  |\n\n"
+        );
+        assert_eq!(delimiter_widths(&convert_test(html)), vec![4, 3]);
+    }
+
+    #[test]
+    fn test_width_hints_widen_to_fit_content() {
+        let html = r#"<table>
+            <tr><th style="width:70%">Description</th><th width="30%">Price per unit</th></tr>
+            <tr><td>Tea</td><td>3</td></tr>
+        </table>"#;
+        let widths = delimiter_widths(&convert_with(html, &width_hints()));
+        assert_eq!(widths, vec![33, 14]);
+        let compact = width_hints().compact(true);
+        assert_eq!(delimiter_widths(&convert_with(html, &compact)), vec![7, 3]);
+    }
+
+    #[test]
+    fn test_width_hints_pixels_and_missing_columns() {
+        let html = r#"<table>
+            <tr><th width="300">A</th><th style="width: 100px">B</th><th>C</th></tr>
+            <tr><td>1</td><td>2</td><td>3</td></tr>
+        </table>"#;
+        // C takes the average hint, 200px
+        assert_eq!(
+            delimiter_widths(&convert_with(html, &width_hints())),
+            vec![9, 3, 6]
+        );
+
+        let html = r#"<table><col width="50%"><col width="100px">
+            <tr><th>A</th><th>B</th></tr></table>"#;
+        assert_eq!(
+            delimiter_widths(&convert_with(html, &width_hints())),
+            vec![3, 3]
+        );
+    }
+
+    #[test]
+    fn test_single_row_table() {
+        let html = r#"<table><tr><th>Version 1.2</th><th>MIT</th><th>Linux</th></tr></table>"#;
+        assert_eq!(
+            convert_test(html),
+            "\n\n| Version 1.2 | MIT | Linux |\n| ----------- | --- | ----- |\n\n"
+        );
+        let text = Options::new().single_row_tables(SingleRowTables::Text);
+        assert_eq!(
+            convert_with(html, &text),
+            "\n\n**Version 1.2** · **MIT** · **Linux**\n\n"
+        );
+
+        // Data cells stay plain and empty cells are left out
+        let html = r#"<table><tr><th>Version 1.2</th><td>MIT</td><td></td></tr></table>"#;
+        assert_eq!(convert_with(html, &text), "\n\n**Version 1.2** · MIT\n\n");
+    }
+
+    #[test]
+    fn test_single_row_text_caption_and_longer_tables() {
+        let text = Options::new().single_row_tables(SingleRowTables::Text);
+        let html =
+            r#"<table><caption>Status</caption><tr><th>Build</th><th>Docs</th></tr></table>"#;
+        assert_eq!(
+            convert_with(html, &text),
+            "\n\n**Build** · **Docs**\n\n*Status*\n\n"
+        );
+
+        let html = r#"<table><tr><th>A</th></tr><tr><td>1</td></tr></table>"#;
+        assert_eq!(convert_with(html, &text), convert_test(html));
+    }
+
+    #[test]
+    fn test_nested_table_inline() {
+        let html = r#"<table><tr><th>Item</th><th>Sizes</th></tr><tr><td>Shirt</td><td>
+            <table><caption>In cm</caption><tr><th>S</th><th>M</th></tr><tr><td>90</td><td>96</td></tr></table>
+            </td></tr></table>"#;
+        assert!(
+            crate::convert(html).ends_with("\n| Shirt | *In cm*<br>**S** · **M**<br>90 · 96 |\n")
+        );
+        let options = Options::new().allow_raw_html(false);
+        assert!(crate::convert_with_options(html, &options)
+            .ends_with("\n| Shirt | *In cm*; **S** · **M**; 90 · 96 |\n"));
+    }
+
+    #[test]
+    fn test_colgroup_style_and_cell_override() {
+        let result = convert_test(
+            r#"<table>
+                <colgroup style="text-align: center" span="2"></colgroup>
+                <tr><th>Name</th><th align="left">Notes</th></tr>
+                <tr><td>A</td><td>B</td></tr>
+            </table>"#,
+        );
+        assert!(result.contains("| :--: | :---- |"));
+    }
+
+    #[test]
+    fn test_class_alignment() {
+        let result = convert_test(
+            r#"<table>
+                <tr><th class="text-right">Bootstrap</th><th class="has-text-centered">Bulma</th></tr>
+                <tr><td>1</td><td>2</td></tr>
+            </table>"#,
+        );
+        assert!(result.contains("| --------: | :---: |"));
+    }
+
+    #[test]
+    fn test_custom_alignment_classes() {
+        let html = r#"<table>
+            <tr><th class="num">Total</th></tr>
+            <tr><td>9</td></tr>
+        </table>"#;
+        assert!(convert_test(html).contains("| ----- |"));
+
+        let options = Options::new().alignment_classes(vec![("num".to_string(), TextAlign::Right)]);
+        assert!(convert_with(html, &options).contains("| ----: |"));
+    }
+
     #[test]
     fn test_table_with_missing_cells() {
         // Rows with fewer cells than header should be padded
@@ -375,6 +1256,98 @@ mod tests {
         assert!(result.contains("| Total"));
     }
 
+    /// Parse `html`, then reshape the tree the way non-browser tree
+    /// builders can: `unwrap` elements are replaced by their children and
+    /// each `(child, parent)` pair moves the first match of `child` into the
+    /// first match of `parent`.
+    fn convert_reshaped(html: &str, unwrap: &[&str], moves: &[(&str, &str)]) -> String {
+        let mut dom = Html::parse_fragment(html);
+        let find = |dom: &Html, selector: &str| {
+            let selector = scraper::Selector::parse(selector).unwrap();
+            dom.select(&selector).next().unwrap().id()
+        };
+
+        for &(child, parent) in moves {
+            let (child, parent) = (find(&dom, child), find(&dom, parent));
+            dom.tree.get_mut(parent).unwrap().append_id(child);
+        }
+        for selector in unwrap {
+            let id = find(&dom, selector);
+            let children: Vec<_> = dom
+                .tree
+                .get(id)
+                .unwrap()
+                .children()
+                .map(|c| c.id())
+                .collect();
+            for child in children {
+                dom.tree.get_mut(id).unwrap().insert_id_before(child);
+            }
+            dom.tree.get_mut(id).unwrap().detach();
+        }
+
+        let table = dom
+            .select(&scraper::Selector::parse("table").unwrap())
+            .next()
+            .unwrap();
+        TableRule.convert(
+            table,
+            &MetadataMap::default(),
+            &Options::default(),
+            &ConversionContext::default(),
+            &|e, _, _| e.text().collect::<Vec<_>>().join(""),
+        )
+    }
+
+    const RELEASES: &str = "<table><thead><tr><th>Version</th><th>Date</th></tr></thead>\
+        <tbody><tr class=\"a\"><td>1.0</td><td>March</td></tr>\
+        <tr class=\"b\"><td>1.1</td><td>June</td></tr></tbody>\
+        <tbody class=\"late\"><tr class=\"c\"><td>2.0</td><td>December</td></tr></tbody></table>\
+        <div class=\"wrapper\"></div>";
+
+    const RELEASE_TABLE: &str = "\n\n| Version | Date     |\n| ------- | -------- |\n\
+        | 1.0     | March    |\n| 1.1     | June     |\n| 2.0     | December |\n\n";
+
+    #[test]
+    fn test_bare_rows_mixed_with_row_groups() {
+        let result = convert_reshaped(RELEASES, &["tbody.late"], &[]);
+        assert_eq!(result, RELEASE_TABLE);
+    }
+
+    #[test]
+    fn test_stray_cells_form_a_row() {
+        // The 1.1 cells sit directly in the tbody, between two rows
+        assert_eq!(convert_reshaped(RELEASES, &["tr.b"], &[]), RELEASE_TABLE);
+        // and directly in the table, after every row group
+        let result = convert_reshaped(RELEASES, &["tbody.late", "tr.c"], &[]);
+        assert_eq!(result, RELEASE_TABLE);
+    }
+
+    #[test]
+    fn test_rows_inside_wrappers() {
+        let result = convert_reshaped(
+            RELEASES,
+            &[],
+            &[
+                ("div.wrapper", "tbody"),
+                ("tr.a", "div.wrapper"),
+                ("tr.b", "div.wrapper"),
+            ],
+        );
+        assert_eq!(result, RELEASE_TABLE);
+    }
+
+    #[test]
+    fn test_nested_table_rows_not_collected() {
+        let html =
+            "<table><tr><td>Outer</td><td><table><tr><td>Inner</td></tr></table></td></tr></table>";
+        // The inner table is cell content, not a second row
+        assert_eq!(
+            convert_test(html),
+            "\n\n| Outer | Inner |\n| ----- | ----- |\n\n"
+        );
+    }
+
     #[test]
     fn test_table_header_only() {
         // Table with only a header row
@@ -386,4 +1359,171 @@ mod tests {
         assert!(result.contains("| Col A"));
         assert!(result.contains("---"));
     }
+
+    const SPEC_TABLE: &str = r#"<table>
+        <tr><th scope="row">Type</th><td>String</td></tr>
+        <tr><th scope="row">Default</th><td>None</td></tr>
+        <tr><th scope="row">Required</th><td>No</td></tr>
+    </table>"#;
+
+    #[test]
+    fn test_row_headers_get_empty_header() {
+        assert_eq!(
+            convert_test(SPEC_TABLE),
+            "\n\n|          |        |\n| -------- | ------ |\n| Type     | String |\n| Default  | None   |\n| Required | No     |\n\n"
+        );
+    }
+
+    #[test]
+    fn test_row_headers_bold() {
+        let options = Options::new().row_header_style(RowHeaderStyle::Bold);
+        // Column widths count the bold markers
+        assert_eq!(
+            convert_with(SPEC_TABLE, &options),
+            "\n\n|              |        |\n| ------------ | ------ |\n| **Type**     | String |\n| **Default**  | None   |\n| **Required** | No     |\n\n"
+        );
+    }
+
+    #[test]
+    fn test_row_headers_bold_with_alignment() {
+        let html = r#"<table>
+            <tr><th>Size</th><td align="right">10</td></tr>
+            <tr><th>Weight</th><td align="right">2200</td></tr>
+        </table>"#;
+        let options = Options::new().row_header_style(RowHeaderStyle::Bold);
+        assert_eq!(
+            convert_with(html, &options),
+            "\n\n|            |      |\n| ---------- | ---: |\n| **Size**   |   10 |\n| **Weight** | 2200 |\n\n"
+        );
+    }
+
+    #[test]
+    fn test_row_headers_first_row() {
+        let options = Options::new().row_header_style(RowHeaderStyle::FirstRow);
+        let result = convert_with(SPEC_TABLE, &options);
+        assert!(result.starts_with("\n\n| Type     | String |\n| -------- | ------ |\n"));
+    }
+
+    #[test]
+    fn test_header_row_with_row_headers_unchanged() {
+        // A header row (all th) or a thead labels the columns as usual
+        let html = r#"<table>
+            <tr><th>Key</th><th>Value</th></tr>
+            <tr><th>Type</th><td>String</td></tr>
+        </table>"#;
+        assert!(convert_test(html).starts_with("\n\n| Key  | Value  |\n"));
+
+        let html = r#"<table>
+            <thead><tr><th>Key</th><td>Value</td></tr></thead>
+            <tr><th>Type</th><td>String</td></tr>
+        </table>"#;
+        assert!(convert_test(html).starts_with("\n\n| Key  | Value  |\n"));
+    }
+
+    #[test]
+    fn test_icon_cells() {
+        let html = r#"<table>
+            <tr><th>Plan</th><th>SSO</th></tr>
+            <tr><td>Free</td><td><img src="/i/no.png" alt=""></td></tr>
+            <tr><td>Pro</td><td><i class="fa fa-check"></i></td></tr>
+        </table>"#;
+        let symbol = Options::new().table_image_cells(TableImageCells::Symbol);
+        let result = convert_with(html, &symbol);
+        assert!(result.contains("| Free | ✗   |"));
+        assert!(result.contains("| Pro  | ✓   |"));
+    }
+
+    #[test]
+    fn test_icon_cells_need_only_icons() {
+        let html = r#"<table>
+            <tr><th>Plan</th><th>SSO</th></tr>
+            <tr><td>Free</td><td><img src="/i/status.png" alt=""></td></tr>
+            <tr><td>Pro</td><td><img src="/i/check.png" alt="Yes"> Included</td></tr>
+        </table>"#;
+        let options = Options::new().table_image_cells(TableImageCells::AltText);
+        // An unlabelled icon and a cell with text of its own convert as usual
+        let result = convert_with(html, &options);
+        assert!(result.contains("| Free | "));
+        assert!(!result.contains("| Free | Yes"));
+        assert!(result.contains("| Pro  | Included |"));
+    }
+
+    #[test]
+    fn test_key_value_list() {
+        let result = convert_with(
+            r#"<table>
+                <caption>People</caption>
+                <thead><tr><th>Name</th><th align="right">Age</th></tr></thead>
+                <tbody>
+                    <tr><td>Alice</td><td>30</td></tr>
+                    <tr><td>Bob</td><td>a | b</td></tr>
+                </tbody>
+            </table>"#,
+            &key_value(),
+        );
+        assert_eq!(
+            result,
+            "\n\n**People**\n\n- **Name**: Alice\n  **Age**: 30\n- **Name**: Bob\n  **Age**: a | b\n\n"
+        );
+    }
+
+    #[test]
+    fn test_key_value_list_missing_cells() {
+        let result = convert_with(
+            r#"<table>
+                <tr><th>A</th><th>B</th><th></th></tr>
+                <tr><td>1</td><td></td><td>extra</td></tr>
+                <tr><td></td><td>2</td></tr>
+                <tr><td> </td></tr>
+            </table>"#,
+            &key_value(),
+        );
+        assert_eq!(result, "\n\n- **A**: 1\n  extra\n- **B**: 2\n\n");
+    }
+
+    #[test]
+    fn test_key_value_list_header_only_uses_pipes() {
+        let html = "<table><tr><th>Col A</th><th>Col B</th></tr></table>";
+        assert_eq!(convert_with(html, &key_value()), convert_test(html));
+    }
+
+    #[test]
+    fn test_html_table_style() {
+        let options = Options::new().table_style(TableStyle::Html);
+        let result = convert_with("<table><tr><td>x</td></tr></table>", &options);
+        assert_eq!(
+            result,
+            "\n\n<table><tbody><tr><td>x</td></tr></tbody></table>\n\n"
+        );
+    }
+
+    #[test]
+    fn test_html_table_style_without_raw_html() {
+        let html = "<table><tr><th>A</th></tr><tr><td>x</td></tr></table>";
+        let options = Options::new()
+            .table_style(TableStyle::Html)
+            .allow_raw_html(false);
+        assert_eq!(convert_with(html, &options), convert_test(html));
+    }
+
+    #[test]
+    fn test_code_span_pipes_in_table() {
+        let html = "<table><tr><th>Op</th></tr><tr><td><code>a | b</code></td></tr></table>";
+        let result = crate::convert(html);
+        assert!(result.contains(r"| `a \| b` |"));
+    }
+
+    #[test]
+    fn test_code_span_pipes_outside_table() {
+        let result = crate::convert("<p>Use <code>a | b</code> to pipe.</p>");
+        assert_eq!(result, "Use `a | b` to pipe.\n");
+    }
+
+    #[test]
+    fn test_multiline_code_in_table() {
+        let html =
+            "<table><tr><th>Call</th></tr><tr><td><code>run(x,\n  y)</code></td></tr></table>";
+        let result = crate::convert(html);
+        assert!(result.contains("| `run(x, y)` |"));
+    }
 }
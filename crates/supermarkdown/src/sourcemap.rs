@@ -0,0 +1,475 @@
+//! Mapping of markdown output ranges back to the HTML elements they came from.
+//!
+//! While converting with a source map, the output of each block-level rule
+//! is wrapped in invisible marker characters carrying an index into the list
+//! of recorded elements. Markers sit just inside the block's surrounding
+//! whitespace, so they travel with the block through parent rules (quote
+//! prefixes, list indentation) and postprocessing. Stripping them from the
+//! final markdown yields each block's byte range.
+//!
+//! The document's own characters in the marker range are escaped before
+//! converting and given back when the markers are stripped.
+
+use std::borrow::Cow;
+use std::cmp::Reverse;
+
+use scraper::{ElementRef, Html, Node};
+
+/// Start of a block; followed by its index digits.
+const BLOCK_START: char = '\u{E000}';
+/// End of a block; followed by its index digits.
+const BLOCK_END: char = '\u{E001}';
+/// Index digits are encoded as `DIGIT_BASE + 0..16`.
+const DIGIT_BASE: u32 = 0xE010;
+/// Escapes a document character that would read as a marker; followed by
+/// the character's offset from `BLOCK_START` as `LITERAL_BASE + 0..32`.
+const LITERAL: char = '\u{E002}';
+const LITERAL_BASE: u32 = 0xE040;
+
+/// Tags whose output is recorded in the source map.
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "ul",
+    "ol",
+    "li",
+    "pre",
+    "table",
+    "hr",
+    "dl",
+    "dt",
+    "dd",
+    "details",
+    "figure",
+];
+
+/// A block-level element's contribution to the markdown output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SourceMapEntry {
+    /// Byte offset where the block starts in the markdown.
+    pub md_start: usize,
+    /// Byte offset just past the block's last character.
+    pub md_end: usize,
+    /// Tag name of the source element.
+    pub html_tag: String,
+    /// `id` attribute of the source element.
+    pub html_id: Option<String>,
+    /// `class` attribute of the source element.
+    pub html_class: Option<String>,
+}
+
+/// Source element of a marked block.
+#[derive(Debug)]
+pub(crate) struct BlockSource {
+    tag: String,
+    id: Option<String>,
+    class: Option<String>,
+}
+
+/// Whether an element's output should be marked.
+///
+/// Blocks inside table cells are left out: table rules measure cell text,
+/// and the cell's row is already covered by the table's entry.
+pub(crate) fn is_mapped_block(element: ElementRef) -> bool {
+    BLOCK_TAGS.contains(&element.value().name())
+        && !element.ancestors().any(|node| {
+            node.value()
+                .as_element()
+                .is_some_and(|el| matches!(el.name(), "td" | "th"))
+        })
+}
+
+/// Whether `c` is part of a block marker.
+pub(crate) fn is_marker(c: char) -> bool {
+    c == BLOCK_START || c == BLOCK_END || is_digit(c)
+}
+
+/// Whether `c` has to be escaped in the document to be kept as text.
+fn is_reserved(c: char) -> bool {
+    is_marker(c) || c == LITERAL
+}
+
+/// Escape characters in the document's text and attributes that would
+/// read as markers.
+pub(crate) fn escape_reserved(dom: &mut Html) {
+    for node in dom.tree.values_mut() {
+        match node {
+            Node::Text(text) => {
+                if let Cow::Owned(escaped) = escape_text(&text.text) {
+                    text.text = escaped.as_str().into();
+                }
+            }
+            Node::Element(element) => {
+                for value in element.attrs.values_mut() {
+                    if let Cow::Owned(escaped) = escape_text(value) {
+                        *value = escaped.as_str().into();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn escape_text(text: &str) -> Cow<'_, str> {
+    if !text.contains(is_reserved) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        let literal = is_reserved(c)
+            .then(|| char::from_u32(LITERAL_BASE + (c as u32 - BLOCK_START as u32)))
+            .flatten();
+        match literal {
+            Some(literal) => {
+                escaped.push(LITERAL);
+                escaped.push(literal);
+            }
+            None => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// The document character an escape stands for.
+fn unescape_literal(literal: char) -> Option<char> {
+    (literal as u32)
+        .checked_sub(LITERAL_BASE)
+        .filter(|&offset| offset < 0x20)
+        .and_then(|offset| char::from_u32(BLOCK_START as u32 + offset))
+}
+
+/// Split `line` into its leading markers and the rest.
+pub(crate) fn split_leading_markers(line: &str) -> (&str, &str) {
+    let rest = line.trim_start_matches(is_marker);
+    (&line[..line.len() - rest.len()], rest)
+}
+
+/// Split `line` into the part before its trailing markers and the markers.
+pub(crate) fn split_trailing_markers(line: &str) -> (&str, &str) {
+    let rest = line.trim_end_matches(is_marker);
+    (rest, &line[rest.len()..])
+}
+
+/// `text` without any markers.
+pub(crate) fn strip_markers(text: &str) -> Cow<'_, str> {
+    if text.contains(is_marker) {
+        Cow::Owned(text.chars().filter(|&c| !is_marker(c)).collect())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Record `element` and wrap its converted output in markers.
+pub(crate) fn mark_block(
+    output: String,
+    element: ElementRef,
+    sources: &mut Vec<BlockSource>,
+) -> String {
+    let start = output.len() - output.trim_start().len();
+    let end = output.trim_end().len();
+    if start >= end {
+        return output;
+    }
+
+    let index = sources.len();
+    sources.push(BlockSource {
+        tag: element.value().name().to_string(),
+        id: element.value().attr("id").map(str::to_string),
+        class: element.value().attr("class").map(str::to_string),
+    });
+
+    let mut marked = String::with_capacity(output.len() + 16);
+    marked.push_str(&output[..start]);
+    push_marker(&mut marked, BLOCK_START, index);
+    marked.push_str(&output[start..end]);
+    push_marker(&mut marked, BLOCK_END, index);
+    marked.push_str(&output[end..]);
+    marked
+}
+
+fn push_marker(out: &mut String, marker: char, index: usize) {
+    out.push(marker);
+    out.push_str(&encode_index(index));
+}
+
+/// Hex digits of `index` as marker digit characters.
+fn encode_index(index: usize) -> String {
+    format!("{:x}", index)
+        .chars()
+        .filter_map(|d| d.to_digit(16))
+        .filter_map(|d| char::from_u32(DIGIT_BASE + d))
+        .collect()
+}
+
+/// Markers in `line` whose partner isn't on the same line, as
+/// (start markers, end markers).
+///
+/// When postprocessing drops a line, blocks entirely on it go with it, but
+/// the markers of enclosing blocks must move to the neighbouring lines.
+pub(crate) fn unmatched_markers(line: &str) -> (String, String) {
+    let markers = scan_markers(line);
+    let unmatched = |start: bool| -> String {
+        markers
+            .iter()
+            .filter(|m| m.0 == start && !markers.iter().any(|o| o.0 != start && o.1 == m.1))
+            .map(|m| m.2)
+            .collect()
+    };
+    (unmatched(true), unmatched(false))
+}
+
+/// Each marker in `text` as (is start, index, marker text).
+fn scan_markers(text: &str) -> Vec<(bool, usize, &str)> {
+    let mut markers = Vec::new();
+    for (i, c) in text.char_indices() {
+        if c != BLOCK_START && c != BLOCK_END {
+            continue;
+        }
+        let digits: usize = text[i + c.len_utf8()..]
+            .chars()
+            .take_while(|&d| is_digit(d))
+            .map(char::len_utf8)
+            .sum();
+        let marker = &text[i..i + c.len_utf8() + digits];
+        if let Some(index) = decode_index(&marker[c.len_utf8()..]) {
+            markers.push((c == BLOCK_START, index, marker));
+        }
+    }
+    markers
+}
+
+fn is_digit(c: char) -> bool {
+    (DIGIT_BASE..DIGIT_BASE + 16).contains(&(c as u32))
+}
+
+/// Index from marker digit characters; `None` when there are none or
+/// there are too many for an index.
+fn decode_index(digits: &str) -> Option<usize> {
+    if digits.is_empty() {
+        return None;
+    }
+    digits.chars().try_fold(0usize, |acc, d| {
+        acc.checked_mul(16)?
+            .checked_add((d as u32 - DIGIT_BASE) as usize)
+    })
+}
+
+/// Strip markers from the final markdown, returning it with the entries
+/// ordered by start offset (enclosing blocks before the blocks inside them).
+///
+/// Blocks whose markers didn't both survive postprocessing are left out.
+pub(crate) fn resolve(marked: &str, sources: Vec<BlockSource>) -> (String, Vec<SourceMapEntry>) {
+    let mut markdown = String::with_capacity(marked.len());
+    let mut ranges: Vec<(Option<usize>, Option<usize>)> = vec![(None, None); sources.len()];
+    let mut chars = marked.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == LITERAL {
+            if let Some(original) = chars.peek().and_then(|&l| unescape_literal(l)) {
+                markdown.push(original);
+                chars.next();
+                continue;
+            }
+        }
+        if c != BLOCK_START && c != BLOCK_END {
+            markdown.push(c);
+            continue;
+        }
+
+        let mut digits = String::new();
+        while let Some(&d) = chars.peek().filter(|&&d| is_digit(d)) {
+            digits.push(d);
+            chars.next();
+        }
+        let index = decode_index(&digits);
+
+        match index.and_then(|i| ranges.get_mut(i)) {
+            Some(range) if c == BLOCK_START => range.0 = Some(markdown.len()),
+            Some(range) => range.1 = Some(markdown.len()),
+            // Not one of ours; keep the character
+            None => markdown.push(c),
+        }
+    }
+
+    let mut entries: Vec<(usize, SourceMapEntry)> = sources
+        .into_iter()
+        .zip(ranges)
+        .enumerate()
+        .filter_map(|(index, (source, range))| match range {
+            (Some(md_start), Some(md_end)) if md_start <= md_end => Some((
+                index,
+                SourceMapEntry {
+                    md_start,
+                    md_end,
+                    html_tag: source.tag,
+                    html_id: source.id,
+                    html_class: source.class,
+                },
+            )),
+            _ => None,
+        })
+        .collect();
+    // Blocks are recorded as they finish, so with equal ranges the later
+    // index is the enclosing block
+    entries.sort_by_key(|(index, e)| (e.md_start, Reverse(e.md_end), Reverse(*index)));
+    let entries = entries.into_iter().map(|(_, e)| e).collect();
+
+    (markdown, entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Options;
+
+    #[test]
+    fn test_index_round_trip() {
+        let mut marked = String::from("x");
+        push_marker(&mut marked, BLOCK_START, 0x1a2);
+        marked.push_str("body");
+        push_marker(&mut marked, BLOCK_END, 0x1a2);
+
+        let sources = (0..=0x1a2)
+            .map(|_| BlockSource {
+                tag: "p".to_string(),
+                id: None,
+                class: None,
+            })
+            .collect();
+        let (markdown, entries) = resolve(&marked, sources);
+        assert_eq!(markdown, "xbody");
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].md_start, entries[0].md_end), (1, 5));
+    }
+
+    #[test]
+    fn test_overlong_index_not_a_marker() {
+        assert_eq!(decode_index(&"\u{E01F}".repeat(20)), None);
+        assert_eq!(decode_index("\u{E011}\u{E010}"), Some(16));
+    }
+
+    #[test]
+    fn test_stray_marker_kept() {
+        let (markdown, entries) = resolve("a\u{E000}b", Vec::new());
+        assert_eq!(markdown, "a\u{E000}b");
+        assert!(entries.is_empty());
+    }
+
+    fn slices<'a>(markdown: &'a str, entries: &'a [SourceMapEntry]) -> Vec<(&'a str, &'a str)> {
+        entries
+            .iter()
+            .map(|e| (e.html_tag.as_str(), &markdown[e.md_start..e.md_end]))
+            .collect()
+    }
+
+    #[test]
+    fn test_heading_and_paragraph_ranges() {
+        let html = "<h2 id='intro'>Intro</h2><p class='a b'>First <em>para</em>.</p><p>Second</p>";
+        let (markdown, entries) = crate::convert_with_sourcemap(html, &Options::default());
+        assert_eq!(markdown, "## Intro\n\nFirst *para*.\n\nSecond\n");
+        assert_eq!(
+            slices(&markdown, &entries),
+            vec![("h2", "## Intro"), ("p", "First *para*."), ("p", "Second")]
+        );
+        assert_eq!(entries[0].html_id.as_deref(), Some("intro"));
+        assert_eq!(entries[1].html_class.as_deref(), Some("a b"));
+        assert_eq!(entries[2].html_id, None);
+    }
+
+    #[test]
+    fn test_nested_block_ranges() {
+        let html = "<blockquote><p>Quoted</p></blockquote><ul><li>One</li><li>Two</li></ul>";
+        let (markdown, entries) = crate::convert_with_sourcemap(html, &Options::default());
+        assert_eq!(
+            slices(&markdown, &entries),
+            vec![
+                ("blockquote", "> Quoted"),
+                ("p", "Quoted"),
+                ("ul", "- One\n- Two"),
+                ("li", "- One"),
+                ("li", "- Two"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_block_range() {
+        let html = "<p>Run:</p><pre><code class='language-sh'>make</code></pre>";
+        let (markdown, entries) = crate::convert_with_sourcemap(html, &Options::default());
+        assert_eq!(markdown, crate::convert(html));
+        assert_eq!(
+            slices(&markdown, &entries),
+            vec![("p", "Run:"), ("pre", "```bash\nmake\n```")]
+        );
+    }
+
+    #[test]
+    fn test_table_cells_not_mapped() {
+        let html = "<table><tr><th><p>A</p></th></tr><tr><td><p>1</p></td></tr></table>";
+        let (markdown, entries) = crate::convert_with_sourcemap(html, &Options::default());
+        assert_eq!(markdown, crate::convert(html));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].html_tag, "table");
+        assert_eq!(
+            &markdown[entries[0].md_start..entries[0].md_end],
+            markdown.trim_end()
+        );
+    }
+
+    #[test]
+    fn test_dropped_list_item_keeps_list_range() {
+        let html =
+            r#"<ul><li><a href="/x"><svg></svg></a></li><li><a href="/c">Contact</a></li></ul>"#;
+        let (markdown, entries) = crate::convert_with_sourcemap(html, &Options::default());
+        assert_eq!(markdown, "- [Contact](/c)\n");
+        assert_eq!(
+            slices(&markdown, &entries),
+            vec![("ul", "- [Contact](/c)"), ("li", "- [Contact](/c)")]
+        );
+    }
+
+    #[test]
+    fn test_marker_like_text_kept() {
+        let html = "<p>icon \u{E000}\u{E010} here</p><p>\u{E002}\u{E041}</p>";
+        let (markdown, entries) = crate::convert_with_sourcemap(html, &Options::default());
+        assert_eq!(markdown, crate::convert(html));
+        assert_eq!(markdown, "icon \u{E000}\u{E010} here\n\n\u{E002}\u{E041}\n");
+        assert_eq!(
+            slices(&markdown, &entries),
+            vec![
+                ("p", "icon \u{E000}\u{E010} here"),
+                ("p", "\u{E002}\u{E041}")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_long_marker_like_run() {
+        let run = format!("\u{E000}{}", "\u{E01F}".repeat(20));
+        let html = format!("<p title='{run}'>{run}</p><p>{run}</p>");
+        let (markdown, entries) = crate::convert_with_sourcemap(&html, &Options::default());
+        assert_eq!(markdown, crate::convert(&html));
+        assert_eq!(markdown, format!("{run}\n\n{run}\n"));
+        assert_eq!(
+            slices(&markdown, &entries),
+            vec![("p", &run[..]), ("p", &run[..])]
+        );
+    }
+
+    #[test]
+    fn test_is_marker() {
+        assert!(is_marker(BLOCK_START));
+        assert!(is_marker(BLOCK_END));
+        assert!(is_marker('\u{E01F}'));
+        assert!(!is_marker('\u{E020}'));
+        assert!(!is_marker('a'));
+    }
+}
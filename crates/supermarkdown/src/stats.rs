@@ -0,0 +1,278 @@
+//! Conversion statistics and metadata returned alongside the markdown.
+//!
+//! Counters are only collected when requested through
+//! [`convert_with_metadata`](crate::convert_with_metadata), so plain
+//! conversions pay nothing for them.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+#[cfg(feature = "profile")]
+use std::time::Duration;
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+use crate::diagnostics::Warning;
+use crate::limits::Truncation;
+use crate::options::Options;
+use crate::rules::{extract_image, extract_link};
+
+/// Counts describing how much of a document made it into the output.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConversionStats {
+    /// Elements visited in the parsed document.
+    pub elements_visited: usize,
+
+    /// Elements matched by an exclude selector (subtree roots only).
+    pub elements_skipped: usize,
+
+    /// Skipped subtree roots per exclude selector, keyed by the selector
+    /// string as given in options. An element is attributed to the first
+    /// selector it matches, or else to the first `exclude_text_patterns`
+    /// entry, keyed by the pattern. Asides and dialogs dropped by
+    /// `aside_handling` or `dialog_handling` are keyed by the option name.
+    pub skipped_by_selector: BTreeMap<String, usize>,
+
+    /// Bytes of text inside skipped elements.
+    pub text_bytes_dropped: usize,
+
+    /// Images inside skipped elements or that produced no output.
+    pub images_dropped: usize,
+
+    /// Links converted to markdown.
+    pub links_converted: usize,
+
+    /// Tables converted to markdown.
+    pub tables_converted: usize,
+
+    /// Elements no rule handles, per tag name, whose children were
+    /// converted in their place. Tags that only group content, like `div`
+    /// and `span`, are not counted.
+    pub unhandled_tags: BTreeMap<String, usize>,
+
+    /// JSON-LD blocks left out of the metadata as invalid JSON, with
+    /// `extract_json_ld`.
+    pub json_ld_invalid: usize,
+
+    /// Estimated LLM tokens in the markdown, from
+    /// [`estimate_tokens`](crate::estimate_tokens).
+    #[cfg(feature = "token-estimate")]
+    pub estimated_tokens: usize,
+}
+
+/// Tags left to the default unwrap path on purpose.
+const INERT_TAGS: &[&str] = &["div", "span", "section", "article", "body", "html", "head"];
+
+impl ConversionStats {
+    /// Record an element no rule handles.
+    pub(crate) fn record_unhandled(&mut self, tag: &str) {
+        if !INERT_TAGS.contains(&tag) {
+            *self.unhandled_tags.entry(tag.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Record a subtree that was dropped from the output.
+    pub(crate) fn record_dropped(&mut self, node: NodeRef<Node>) {
+        for descendant in node.descendants() {
+            match descendant.value() {
+                Node::Text(text) => self.text_bytes_dropped += text.len(),
+                Node::Element(el) if el.name() == "img" => self.images_dropped += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Attribute values recorded from an element matching a
+/// `capture_attributes` selector.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CapturedAttributes {
+    /// The selector the element matched, as given in options.
+    pub selector: String,
+
+    /// Tag name of the element.
+    pub tag: String,
+
+    /// Position of the element among all elements of the document, in
+    /// document (and so output) order.
+    pub index: usize,
+
+    /// Values of the requested attributes the element has.
+    pub attrs: BTreeMap<String, String>,
+}
+
+/// A link found in the document.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExtractedLink {
+    /// The `href` attribute as written.
+    pub href: String,
+
+    /// The `href` resolved against `base_url`, before `url_rewriter`.
+    pub url: String,
+
+    /// Text of the link with whitespace collapsed.
+    pub text: String,
+
+    /// Whether the link stays on the site: a relative URL, or an absolute
+    /// one on the host of `base_url` (ignoring a leading `www.`).
+    pub internal: bool,
+
+    /// Set when the link is not in the output as a link: it is inside an
+    /// excluded element, has no target, or produced no output.
+    pub dropped: bool,
+}
+
+/// An image found in the document.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExtractedImage {
+    /// Image URL from `src`, `data-src` or `srcset`, resolved against
+    /// `base_url`; empty when the image has none.
+    pub src: String,
+
+    /// The `alt` attribute.
+    pub alt: String,
+
+    /// Set when the image is not in the output: it is inside an excluded
+    /// element, has no source, or was removed by `data_uri_images`.
+    pub dropped: bool,
+}
+
+/// Time spent in each phase of a conversion, measured with the `profile`
+/// feature.
+#[cfg(feature = "profile")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PhaseTimings {
+    /// Parsing the HTML into a document with html5ever.
+    pub parse: Duration,
+
+    /// Matching selectors and computing element metadata, including
+    /// breadcrumb and JSON-LD extraction.
+    pub precompute: Duration,
+
+    /// Running the rules over the document.
+    pub convert: Duration,
+
+    /// Post-processing the markdown.
+    pub postprocess: Duration,
+}
+
+#[cfg(feature = "profile")]
+impl PhaseTimings {
+    /// Time spent in all phases.
+    pub fn total(&self) -> Duration {
+        self.parse + self.precompute + self.convert + self.postprocess
+    }
+}
+
+/// An entry added to a [`MetadataSink`] before its element is converted.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Extracted {
+    Link(usize),
+    Image(usize),
+}
+
+/// Everything `convert_with_metadata` collects while converting.
+#[derive(Debug, Default)]
+pub(crate) struct MetadataSink {
+    pub stats: RefCell<ConversionStats>,
+    pub links: RefCell<Vec<ExtractedLink>>,
+    pub images: RefCell<Vec<ExtractedImage>>,
+    #[cfg(feature = "serde")]
+    pub json_ld: RefCell<Vec<serde_json::Value>>,
+    pub breadcrumbs: RefCell<Vec<String>>,
+    #[cfg(feature = "validate")]
+    pub expected: RefCell<crate::validate::Expectations>,
+}
+
+impl MetadataSink {
+    /// Record a link or image before it is converted, so entries stay in
+    /// document order when links hold images or other links.
+    pub fn begin(&self, element: ElementRef, options: &Options) -> Option<Extracted> {
+        match element.value().name() {
+            "a" => {
+                let mut links = self.links.borrow_mut();
+                links.push(extract_link(element, options));
+                Some(Extracted::Link(links.len() - 1))
+            }
+            "img" => {
+                let mut images = self.images.borrow_mut();
+                images.push(extract_image(element, options));
+                Some(Extracted::Image(images.len() - 1))
+            }
+            _ => None,
+        }
+    }
+
+    /// Mark an entry from [`begin`](Self::begin) as dropped unless its
+    /// element became a link or image in the output.
+    pub fn end(&self, extracted: Extracted, kept: bool) {
+        if kept {
+            return;
+        }
+        match extracted {
+            Extracted::Link(i) => self.links.borrow_mut()[i].dropped = true,
+            Extracted::Image(i) => self.images.borrow_mut()[i].dropped = true,
+        }
+    }
+
+    /// Record a skipped subtree, listing its links and images as dropped.
+    pub fn record_dropped(&self, element: ElementRef, options: &Options) {
+        self.stats.borrow_mut().record_dropped(*element);
+        for descendant in element.descendants().filter_map(ElementRef::wrap) {
+            if let Some(extracted) = self.begin(descendant, options) {
+                self.end(extracted, false);
+            }
+        }
+    }
+}
+
+/// Information collected during a conversion, returned next to the markdown.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConversionMetadata {
+    /// Content statistics.
+    pub stats: ConversionStats,
+
+    /// Set when a limit from the options stopped the conversion early; the
+    /// markdown then holds what was converted up to that point.
+    pub truncated: Option<Truncation>,
+
+    /// Invalid options that were ignored, limits that cut the output short
+    /// and, with `validate_output`, problems found in the markdown, in that
+    /// order.
+    pub warnings: Vec<Warning>,
+
+    /// Text of the document's `<title>`, if it has one.
+    pub title: Option<String>,
+
+    /// Attributes captured with `Options::capture_attributes`, in document
+    /// order. Elements inside excluded subtrees are left out.
+    pub captured_attributes: Vec<CapturedAttributes>,
+
+    /// Every `<a>` in the document, in document order, including links
+    /// that were dropped from the output.
+    pub links: Vec<ExtractedLink>,
+
+    /// Every `<img>` in the document, in document order, including images
+    /// that were dropped from the output.
+    pub images: Vec<ExtractedImage>,
+
+    /// Parsed `<script type="application/ld+json">` blocks, in document
+    /// order, with `extract_json_ld`.
+    #[cfg(feature = "serde")]
+    pub json_ld: Vec<serde_json::Value>,
+
+    /// Text of each step of the document's first breadcrumb trail, whether
+    /// or not `breadcrumbs` keeps it in the output.
+    pub breadcrumbs: Vec<String>,
+
+    /// Time spent in each phase of the conversion. Collecting this metadata
+    /// is timed too, so plain conversions run a little faster.
+    #[cfg(feature = "profile")]
+    pub timings: PhaseTimings,
+}
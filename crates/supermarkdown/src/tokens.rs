@@ -0,0 +1,151 @@
+//! Token count estimate for LLM budgeting (`token-estimate` feature).
+//!
+//! Text is split roughly the way the cl100k_base tokenizer splits it before
+//! merging: words with their leading space, runs of up to three digits,
+//! punctuation runs and line breaks. Common English words are a single
+//! token there, and longer or rarer ones a few; the costs below follow
+//! those ratios, so the estimate lands within about 15% on English prose
+//! and markdown without shipping a vocabulary.
+
+/// Letters of an ASCII word that still make up a single token, on average.
+const WORD_CHARS: usize = 11;
+
+/// Characters per token of a word beyond [`WORD_CHARS`].
+const EXTRA_CHARS_PER_TOKEN: usize = 8;
+
+/// Bytes per token of words in other scripts, like Cyrillic or Greek.
+const NON_ASCII_BYTES_PER_TOKEN: usize = 4;
+
+/// Characters per token of a punctuation run like `**`, `](` or `://`.
+const PUNCTUATION_CHARS_PER_TOKEN: usize = 3;
+
+/// Estimate how many cl100k_base tokens `markdown` takes.
+///
+/// ```
+/// assert_eq!(supermarkdown::estimate_tokens("Hello, world!"), 4);
+/// ```
+pub fn estimate_tokens(markdown: &str) -> usize {
+    let mut tokens = 0;
+    let mut chars = markdown.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_ideograph(c) {
+            tokens += 1;
+        } else if c.is_alphabetic() {
+            let mut len = c.len_utf8();
+            let mut ascii = c.is_ascii();
+            while let Some(&next) = chars.peek() {
+                if !next.is_alphabetic() || is_ideograph(next) {
+                    break;
+                }
+                len += next.len_utf8();
+                ascii &= next.is_ascii();
+                chars.next();
+            }
+            tokens += if ascii {
+                1 + len
+                    .saturating_sub(WORD_CHARS)
+                    .div_ceil(EXTRA_CHARS_PER_TOKEN)
+            } else {
+                len.div_ceil(NON_ASCII_BYTES_PER_TOKEN)
+            };
+        } else if c.is_numeric() {
+            let digits = 1 + count_while(&mut chars, char::is_numeric);
+            tokens += digits.div_ceil(3);
+        } else if c == '\n' || c == '\r' {
+            // A run of line breaks, with the indentation after it, is one token
+            count_while(&mut chars, char::is_whitespace);
+            tokens += 1;
+        } else if c.is_whitespace() {
+            // A single space joins the word after it, but not a number
+            let run = 1 + count_while(&mut chars, |c| c.is_whitespace() && c != '\n' && c != '\r');
+            if run > 1 || chars.peek().is_some_and(|c| c.is_numeric()) {
+                tokens += 1;
+            }
+        } else {
+            let run = 1 + count_while(&mut chars, |c| {
+                !c.is_alphanumeric() && !c.is_whitespace() && !is_ideograph(c)
+            });
+            tokens += run.div_ceil(PUNCTUATION_CHARS_PER_TOKEN);
+        }
+    }
+    tokens
+}
+
+/// Consume characters matching `pred`, returning how many there were.
+fn count_while(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    pred: impl Fn(char) -> bool,
+) -> usize {
+    let mut count = 0;
+    while chars.next_if(|&c| pred(c)).is_some() {
+        count += 1;
+    }
+    count
+}
+
+/// CJK characters, which cl100k_base mostly takes one or more tokens each
+/// for.
+fn is_ideograph(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assert the estimate is within 15% of a cl100k_base count.
+    fn assert_close(text: &str, actual: usize) {
+        let estimate = estimate_tokens(text);
+        let error = estimate.abs_diff(actual) as f64 / actual as f64;
+        assert!(
+            error <= 0.15,
+            "estimated {} tokens for {} actual in {:?}",
+            estimate,
+            actual,
+            text
+        );
+    }
+
+    #[test]
+    fn test_prose() {
+        assert_close("The quick brown fox jumps over the lazy dog.", 10);
+        assert_close(
+            "It was the best of times, it was the worst of times, it was the age of \
+             wisdom, it was the age of foolishness.",
+            28,
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        assert_close(
+            "# Getting Started\n\nInstall the package with **npm** and read the \
+             [guide](https://example.com/docs).\n\n- First item\n- Second item\n",
+            31,
+        );
+    }
+
+    #[test]
+    fn test_numbers_and_code() {
+        assert_close("Released in 2024 with 1500000 downloads.", 12);
+        assert_close("```rust\nfn main() {\n    let x = 42;\n}\n```\n", 17);
+    }
+
+    #[test]
+    fn test_empty_and_whitespace() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("\n\n"), 1);
+    }
+
+    #[test]
+    fn test_other_scripts() {
+        // One per ideograph errs high, as budgets should
+        assert_eq!(estimate_tokens("你好世界"), 4);
+        assert!(estimate_tokens("Привет мир") >= 3);
+    }
+}
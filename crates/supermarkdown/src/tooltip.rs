@@ -0,0 +1,185 @@
+//! Inline tooltips (`Options::inline_tooltips`).
+//!
+//! Glossary-style pages hide definitions in `title` attributes
+//! (`<span title="Hypertext Transfer Protocol">HTTP</span>`) or in an element
+//! named by `aria-describedby`. With the option on, that text follows the
+//! element in parentheses: `HTTP (Hypertext Transfer Protocol)`.
+
+use scraper::ElementRef;
+
+use crate::escape::escape_tag_start;
+use crate::ids::IdIndex;
+use crate::options::Options;
+use crate::whitespace::normalize_block_whitespace;
+
+/// Longest tooltip kept, in characters; longer ones are cut with an ellipsis.
+const MAX_TOOLTIP_CHARS: usize = 200;
+
+/// Inline elements whose tooltips are inlined.
+///
+/// Links and images render their titles themselves, and `<abbr>` is passed
+/// through as HTML with its title.
+const TOOLTIP_TAGS: &[&str] = &[
+    "span", "dfn", "em", "i", "strong", "b", "u", "mark", "small", "cite", "q", "time", "data",
+    "var", "ins",
+];
+
+/// Elements whose content is literal, so nothing is added inside them.
+const LITERAL_TAGS: &[&str] = &["code", "pre", "kbd", "samp"];
+
+/// Tooltip text for `element`, escaped and ready to append, or None when it
+/// has none worth adding.
+///
+/// The `title` comes first, then the text of each `aria-describedby`
+/// target; a target without text passes on to the targets it names in turn.
+/// Text that repeats the element's visible text is dropped.
+pub(crate) fn tooltip<'a>(
+    ids: &IdIndex<'a>,
+    element: ElementRef<'a>,
+    options: &Options,
+) -> Option<String> {
+    if !TOOLTIP_TAGS.contains(&element.value().name()) || in_literal(&element) {
+        return None;
+    }
+
+    let visible = clean_text(&element.text().collect::<String>());
+    let mut parts: Vec<String> = Vec::new();
+    let mut push = |text: String| {
+        if !text.is_empty() && !text.eq_ignore_ascii_case(&visible) && !parts.contains(&text) {
+            parts.push(text);
+        }
+    };
+
+    if let Some(title) = element.value().attr("title") {
+        push(clean_text(title));
+    }
+    for target in ids.follow(element, described_by, |target| text_of(target).is_empty()) {
+        push(text_of(target));
+    }
+
+    if parts.is_empty() {
+        return None;
+    }
+    let text = truncate(parts.join("; "));
+    Some(escape_tag_start(&text, options.text_escape_mode()).into_owned())
+}
+
+/// Append a tooltip to converted element content, before any trailing
+/// whitespace so the space separating it from the next word stays outside.
+pub(crate) fn append_tooltip(mut converted: String, tooltip: &str) -> String {
+    let end = converted.trim_end().len();
+    converted.insert_str(end, &format!(" ({})", tooltip));
+    converted
+}
+
+fn in_literal(element: &ElementRef) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|el| LITERAL_TAGS.contains(&el.value().name()))
+}
+
+fn described_by(element: ElementRef<'_>) -> Option<&str> {
+    element.value().attr("aria-describedby")
+}
+
+fn text_of(element: ElementRef) -> String {
+    clean_text(&element.text().collect::<String>())
+}
+
+fn clean_text(text: &str) -> String {
+    normalize_block_whitespace(text).trim().to_string()
+}
+
+fn truncate(text: String) -> String {
+    if text.chars().count() <= MAX_TOOLTIP_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_TOOLTIP_CHARS).collect();
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{convert_with_options, Options};
+
+    fn convert(html: &str) -> String {
+        convert_with_options(html, &Options::new().inline_tooltips(true))
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let html = r#"<p><span title="Hypertext Transfer Protocol">HTTP</span> is old.</p>"#;
+        assert_eq!(
+            convert_with_options(html, &Options::default()),
+            "HTTP is old.\n"
+        );
+    }
+
+    #[test]
+    fn test_span_title() {
+        let html = r#"<p><span title="Hypertext Transfer Protocol">HTTP</span> is old.</p>"#;
+        assert_eq!(
+            convert(html),
+            "HTTP (Hypertext Transfer Protocol) is old.\n"
+        );
+    }
+
+    #[test]
+    fn test_aria_describedby() {
+        let html = r#"<p>Set the <dfn aria-describedby="ttl-def">TTL</dfn> low.</p>
+            <div id="ttl-def">Time to live, in seconds</div>"#;
+        assert_eq!(
+            convert(html),
+            "Set the TTL (Time to live, in seconds) low.\n\nTime to live, in seconds\n"
+        );
+    }
+
+    #[test]
+    fn test_aria_describedby_self_and_cycle() {
+        // Points at itself, and through an empty target back at itself
+        let html = r#"<p><dfn id="ttl" aria-describedby="ttl hop">TTL</dfn> low.</p>
+            <span id="hop" aria-describedby="ttl def"></span><div id="def">Time to live</div>"#;
+        assert_eq!(convert(html), "TTL (Time to live) low.\n\nTime to live\n");
+    }
+
+    #[test]
+    fn test_title_and_description_combined() {
+        let html = r#"<p><span title="Domain Name System" aria-describedby="a b missing">DNS</span></p>
+            <span id="a">Resolves names</span><span id="b">Domain Name System</span>"#;
+        assert!(convert(html).starts_with("DNS (Domain Name System; Resolves names)\n"));
+    }
+
+    #[test]
+    fn test_duplicate_of_visible_text_skipped() {
+        let html = r#"<p><span title="read more">Read more</span></p>"#;
+        assert_eq!(convert(html), "Read more\n");
+    }
+
+    #[test]
+    fn test_links_abbr_and_code_untouched() {
+        let html = r#"<p><a href="/x" title="Go">X</a> <abbr title="World Wide Web">WWW</abbr> <code><span title="type">u8</span></code></p>"#;
+        assert_eq!(
+            convert(html),
+            "[X](/x \"Go\") <abbr title=\"World Wide Web\">WWW</abbr> `u8`\n"
+        );
+    }
+
+    #[test]
+    fn test_long_tooltip_truncated() {
+        let html = format!(
+            r#"<p><span title="{}">Term</span></p>"#,
+            "word ".repeat(100)
+        );
+        let output = convert(&html);
+        assert!(output.starts_with("Term (word word"));
+        assert!(output.ends_with("word…)\n"));
+        assert!(output.chars().count() < 220);
+    }
+
+    #[test]
+    fn test_tooltip_after_emphasis() {
+        let html = r#"<p><em title="Cascading Style Sheets">CSS</em></p>"#;
+        assert_eq!(convert(html), "*CSS* (Cascading Style Sheets)\n");
+    }
+}
@@ -0,0 +1,326 @@
+//! URL attribute helpers shared by rules.
+//!
+//! Rules read URLs from several attributes (`src`, `data-src`, `srcset`,
+//! `poster`, ...); these helpers pick the first usable one and resolve it
+//! against `base_url` so every rule treats relative URLs the same way.
+
+use std::borrow::Cow;
+
+use scraper::ElementRef;
+
+use crate::escape::resolve_url;
+use crate::options::{Options, UrlKind};
+
+/// Read the first non-empty attribute out of `attrs`, resolved against
+/// `base_url` when set.
+pub fn attr_url(element: &ElementRef, attrs: &[&str], options: &Options) -> Option<String> {
+    attrs.iter().find_map(|attr| {
+        let value = element.value().attr(attr)?.trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        Some(match &options.base_url {
+            Some(base) => resolve_url(base, value),
+            None => value.to_string(),
+        })
+    })
+}
+
+/// Lowercased host of an absolute URL without a leading `www.`, or an
+/// empty string for relative URLs.
+pub fn url_domain(url: &str) -> String {
+    let Some((_, rest)) = url.split_once("://") else {
+        return String::new();
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host).to_ascii_lowercase();
+    match host.strip_prefix("www.") {
+        Some(host) => host.to_string(),
+        None => host,
+    }
+}
+
+/// Whether `url` stays on the site of `base_url`: a relative URL, or an
+/// absolute one on the same host. Other schemes (`mailto:`, `tel:`, ...)
+/// are never internal.
+pub fn is_internal(url: &str, base_url: Option<&str>) -> bool {
+    let url = url.trim();
+    let scheme_end = url.find(':').filter(|&i| {
+        url[..i]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            && !url[..i].contains('/')
+    });
+    let absolute = match scheme_end {
+        Some(i) => {
+            let scheme = url[..i].to_ascii_lowercase();
+            if scheme != "http" && scheme != "https" {
+                return false;
+            }
+            url.to_string()
+        }
+        // Protocol-relative URLs name a host too
+        None if url.starts_with("//") => format!("https:{}", url),
+        None => return true,
+    };
+    base_url.is_some_and(|base| url_domain(&absolute) == url_domain(base))
+}
+
+/// Pass a resolved URL through `Options::url_rewriter`, if one is set.
+pub fn rewrite_url<'a>(kind: UrlKind, url: &'a str, options: &Options) -> Cow<'a, str> {
+    match options
+        .url_rewriter
+        .as_ref()
+        .and_then(|rewriter| (rewriter.0)(kind, url))
+    {
+        Some(rewritten) => Cow::Owned(rewritten),
+        None => Cow::Borrowed(url),
+    }
+}
+
+/// Decode the `%XX` escapes of a URL part. Malformed escapes are kept, and
+/// text that wouldn't decode to UTF-8 is returned as it is.
+pub fn percent_decode(text: &str) -> Cow<'_, str> {
+    if !text.contains('%') {
+        return Cow::Borrowed(text);
+    }
+
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_or(Cow::Borrowed(text), Cow::Owned)
+}
+
+/// URL of the first candidate in a `srcset`, resolved against `base_url`.
+pub fn srcset_url(element: &ElementRef, options: &Options) -> Option<String> {
+    let srcset = element.value().attr("srcset")?;
+    let (url, _) = srcset_candidates(srcset).into_iter().next()?;
+
+    Some(match &options.base_url {
+        Some(base) => resolve_url(base, url),
+        None => url.to_string(),
+    })
+}
+
+/// Split a `srcset` value into (url, descriptor) pairs.
+///
+/// Follows the HTML parsing rules closely enough for real pages: a URL is
+/// a run of non-whitespace (so commas inside URLs survive), and a trailing
+/// comma on the URL ends the candidate.
+fn srcset_candidates(srcset: &str) -> Vec<(&str, &str)> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        let url_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let url = &rest[..url_end];
+        rest = &rest[url_end..];
+
+        if let Some(url) = url.strip_suffix(',') {
+            candidates.push((url.trim_end_matches(','), ""));
+            continue;
+        }
+
+        let descriptor_end = descriptor_end(rest);
+        candidates.push((url, rest[..descriptor_end].trim()));
+        rest = &rest[descriptor_end..];
+    }
+
+    candidates
+}
+
+/// Byte length of the descriptor at the start of `s`, up to the next comma
+/// outside parentheses.
+fn descriptor_end(s: &str) -> usize {
+    let mut depth = 0usize;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => return i,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    fn with_element<T>(html: &str, f: impl FnOnce(&ElementRef) -> T) -> T {
+        let dom = Html::parse_fragment(html);
+        let element = ElementRef::wrap(dom.root_element().first_child().unwrap()).unwrap();
+        f(&element)
+    }
+
+    fn base(url: &str) -> Options {
+        Options::new().base_url(Some(url.to_string()))
+    }
+
+    #[test]
+    fn test_url_domain() {
+        assert_eq!(
+            url_domain("https://www.Example.com:8080/a?b#c"),
+            "example.com"
+        );
+        assert_eq!(url_domain("http://user@host.org"), "host.org");
+        assert_eq!(url_domain("/relative/path"), "");
+        assert_eq!(url_domain("mailto:me@x.com"), "");
+    }
+
+    #[test]
+    fn test_is_internal() {
+        let base = Some("https://example.com/blog/");
+        assert!(is_internal("/about", base));
+        assert!(is_internal("#section", base));
+        assert!(is_internal("post.html", None));
+        assert!(is_internal("https://www.EXAMPLE.com/a", base));
+        assert!(is_internal("//example.com/a", base));
+        assert!(!is_internal("https://example.org/", base));
+        assert!(!is_internal("https://example.com/", None));
+        assert!(!is_internal("mailto:me@example.com", base));
+        assert!(!is_internal("javascript:void(0)", base));
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("Page%20Name"), "Page Name");
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%+1%zz"), "%+1%zz");
+        // Not UTF-8 once decoded
+        assert_eq!(percent_decode("%FF"), "%FF");
+    }
+
+    #[test]
+    fn test_srcset_candidates() {
+        assert_eq!(
+            srcset_candidates("a.png 1x, b.png 2x"),
+            vec![("a.png", "1x"), ("b.png", "2x")]
+        );
+        assert_eq!(
+            srcset_candidates("small.jpg 480w,large.jpg 1080w"),
+            vec![("small.jpg", "480w"), ("large.jpg", "1080w")]
+        );
+        assert_eq!(srcset_candidates("only.png"), vec![("only.png", "")]);
+        assert_eq!(
+            srcset_candidates("a.png, b.png 2x"),
+            vec![("a.png", ""), ("b.png", "2x")]
+        );
+        assert!(srcset_candidates("  ,  ").is_empty());
+    }
+
+    #[test]
+    fn test_srcset_candidates_comma_in_url() {
+        assert_eq!(
+            srcset_candidates("img.php?size=1,2 1x, other.png 2x"),
+            vec![("img.php?size=1,2", "1x"), ("other.png", "2x")]
+        );
+    }
+
+    #[test]
+    fn test_attr_url_fallback_order() {
+        let url = with_element(r#"<img data-src="lazy.png">"#, |el| {
+            attr_url(el, &["src", "data-src"], &Options::default())
+        });
+        assert_eq!(url.as_deref(), Some("lazy.png"));
+
+        let url = with_element(r#"<img src=" " data-src="lazy.png">"#, |el| {
+            attr_url(el, &["src", "data-src"], &Options::default())
+        });
+        assert_eq!(url.as_deref(), Some("lazy.png"));
+
+        let url = with_element("<img>", |el| {
+            attr_url(el, &["src", "data-src"], &Options::default())
+        });
+        assert_eq!(url, None);
+    }
+
+    #[test]
+    fn test_attr_url_resolves_against_base() {
+        let options = base("https://example.com/docs/guide/");
+        let url = with_element(r#"<video poster="../thumb.jpg"></video>"#, |el| {
+            attr_url(el, &["poster"], &options)
+        });
+        assert_eq!(
+            url.as_deref(),
+            Some("https://example.com/docs/guide/../thumb.jpg")
+        );
+    }
+
+    #[test]
+    fn test_srcset_url() {
+        let options = base("https://example.com/a/page");
+        let url = with_element(r#"<img srcset="small.png 480w, big.png 960w">"#, |el| {
+            srcset_url(el, &options)
+        });
+        assert_eq!(url.as_deref(), Some("https://example.com/a/small.png"));
+    }
+    /// Moves images to a CDN and leaves every other URL alone.
+    fn cdn_images() -> Options {
+        base("https://example.com/blog/").url_rewriter(Some(std::sync::Arc::new(|kind, url| {
+            (kind == UrlKind::Image)
+                .then(|| url.strip_prefix("https://example.com/"))
+                .flatten()
+                .map(|path| format!("https://cdn.example.net/{}", path))
+        })))
+    }
+
+    #[test]
+    fn test_rewrite_url() {
+        let options = cdn_images();
+        assert_eq!(
+            rewrite_url(UrlKind::Image, "https://example.com/a.png", &options),
+            "https://cdn.example.net/a.png"
+        );
+        assert_eq!(
+            rewrite_url(UrlKind::Link, "https://example.com/a.png", &options),
+            "https://example.com/a.png"
+        );
+        assert_eq!(
+            rewrite_url(UrlKind::Image, "a.png", &Options::default()),
+            "a.png"
+        );
+    }
+
+    #[test]
+    fn test_rewriter_sees_resolved_urls() {
+        let html = r#"<p><a href="post.html"><img src="hero.png" alt="Hero"></a></p>
+            <figure><picture><source srcset="wide.webp 2x"><img alt="Wide"></picture></figure>
+            <object data="report.pdf"></object>
+            <p><img src="data:image/png;base64,AAAA" alt="Inline"></p>"#;
+        assert_eq!(
+            crate::convert_with_options(html, &cdn_images()),
+            "[![Hero](https://cdn.example.net/blog/hero.png)](https://example.com/blog/post.html)\n\n\
+             ![Wide](https://cdn.example.net/blog/wide.webp)\n\n\
+             [report.pdf](https://example.com/blog/report.pdf)\n\n\
+             ![Inline](data:image/png;base64,AAAA)\n"
+        );
+    }
+}
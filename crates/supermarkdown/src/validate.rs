@@ -0,0 +1,246 @@
+//! Output self-check for `validate_output` (`validate` feature).
+//!
+//! While converting, the headings and table rows the rules write are
+//! counted. The finished markdown is then parsed with pulldown-cmark, and
+//! any difference becomes a warning: a heading glued to the line before
+//! it, a pipe in a cell that splits a row, or a fence that swallows the
+//! rest of the document all change what a renderer sees.
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use scraper::ElementRef;
+
+use crate::diagnostics::{Warning, WarningCode};
+use crate::options::{AsideHandling, Options};
+
+/// What the markdown should hold, counted from the rules' output.
+#[derive(Debug, Default)]
+pub(crate) struct Expectations {
+    headings: usize,
+    /// Rows of each pipe table, header included, in output order.
+    table_rows: Vec<usize>,
+}
+
+impl Expectations {
+    /// Count the output of a rule for `element`.
+    pub fn record(&mut self, element: ElementRef, converted: &str, options: &Options) {
+        if converted.trim().is_empty() || is_flattened(element) {
+            return;
+        }
+        match element.value().name() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if !is_aside_label(element, options) => {
+                self.headings += 1
+            }
+            "table" => {
+                let rows = converted
+                    .lines()
+                    .filter(|line| line.trim_start().starts_with('|'))
+                    .count();
+                // Less than a header and delimiter row is not a pipe table
+                if rows >= 2 {
+                    self.table_rows.push(rows - 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Parse `markdown` and warn about each expectation it breaks.
+    pub fn check(&self, markdown: &str) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        let mut headings = 0;
+        let mut table_rows = Vec::new();
+
+        let parser = Parser::new_ext(markdown, pulldown_cmark::Options::ENABLE_TABLES);
+        for (event, range) in parser.into_offset_iter() {
+            match event {
+                Event::Start(Tag::Heading { .. }) => headings += 1,
+                Event::Start(Tag::Table(_)) => table_rows.push(0),
+                Event::Start(Tag::TableHead | Tag::TableRow) => {
+                    if let Some(rows) = table_rows.last_mut() {
+                        *rows += 1;
+                    }
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(_))) => {
+                    let block = &markdown[range];
+                    if !is_closed_fence(block) {
+                        warnings.push(invalid(
+                            "code fence is never closed".to_string(),
+                            block.lines().next().map(str::to_string),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if headings != self.headings {
+            warnings.push(invalid(
+                format!(
+                    "markdown has {} headings, {} were written",
+                    headings, self.headings
+                ),
+                None,
+            ));
+        }
+        if table_rows.len() != self.table_rows.len() {
+            warnings.push(invalid(
+                format!(
+                    "markdown has {} tables, {} were written",
+                    table_rows.len(),
+                    self.table_rows.len()
+                ),
+                None,
+            ));
+        } else if let Some((i, (found, written))) = table_rows
+            .iter()
+            .zip(&self.table_rows)
+            .enumerate()
+            .find(|(_, (found, written))| found != written)
+        {
+            warnings.push(invalid(
+                format!(
+                    "table {} has {} rows in the markdown, {} were written",
+                    i + 1,
+                    found,
+                    written
+                ),
+                None,
+            ));
+        }
+        warnings
+    }
+}
+
+fn invalid(message: String, detail: Option<String>) -> Warning {
+    Warning {
+        code: WarningCode::InvalidOutput,
+        message,
+        detail,
+    }
+}
+
+/// Whether a converted element sits in a table cell or heading, where block
+/// output is flattened into one line.
+fn is_flattened(element: ElementRef) -> bool {
+    element.ancestors().filter_map(ElementRef::wrap).any(|el| {
+        matches!(
+            el.value().name(),
+            "td" | "th" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+        )
+    })
+}
+
+/// Whether a heading becomes the bold label of a `Labeled` aside or dialog
+/// instead of a heading: it is the first child of one without an
+/// `aria-label`.
+fn is_aside_label(heading: ElementRef, options: &Options) -> bool {
+    let Some(parent) = heading.parent().and_then(ElementRef::wrap) else {
+        return false;
+    };
+    let handling = match parent.value().name() {
+        "aside" => options.aside_handling,
+        "dialog" => options.dialog_handling,
+        _ => return false,
+    };
+    handling == AsideHandling::Labeled
+        && parent.value().attr("aria-label").is_none()
+        && parent.children().find_map(ElementRef::wrap) == Some(heading)
+}
+
+/// Whether a fenced code block, as written in the markdown, ends with a
+/// closing fence. Without one the block runs on to the end of its
+/// container.
+fn is_closed_fence(block: &str) -> bool {
+    let mut lines = block.trim_end().lines();
+    let Some(open) = lines.next().map(strip) else {
+        return false;
+    };
+    let Some(fence_char) = open.chars().next() else {
+        return false;
+    };
+    let open_len = open.chars().take_while(|&c| c == fence_char).count();
+    lines.next_back().map(strip).is_some_and(|close| {
+        let close = close.trim_end();
+        close.len() >= open_len && close.chars().all(|c| c == fence_char)
+    })
+}
+
+/// A line without the quote markers and indentation before its content.
+fn strip(line: &str) -> &str {
+    line.trim_start_matches(|c: char| c == '>' || c.is_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert_with_metadata, HeadingStyle};
+
+    fn warnings(html: &str, options: Options) -> Vec<Warning> {
+        convert_with_metadata(html, &options.validate_output(true))
+            .1
+            .warnings
+    }
+
+    #[test]
+    fn test_valid_output_has_no_warnings() {
+        let html = r#"<h1>Title</h1><p>Text with a | pipe.</p>
+            <blockquote><p>Quoted</p><pre><code>let x = 1;</code></pre></blockquote>
+            <table><tr><th>A</th><th>B</th></tr><tr><td>1 | 2</td><td><h2>In cell</h2></td></tr>
+            <tr><td>3</td><td>4</td></tr></table>
+            <ul><li><h3>Item heading</h3><pre>code</pre></li></ul>"#;
+        assert_eq!(warnings(html, Options::new()), vec![]);
+        let setext = Options::new().heading_style(HeadingStyle::Setext);
+        assert_eq!(warnings(html, setext), vec![]);
+
+        let html = "<aside><h3>Related</h3><p>Links</p></aside><h2>Next</h2>";
+        let labeled = Options::new().aside_handling(AsideHandling::Labeled);
+        assert_eq!(warnings(html, labeled), vec![]);
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let (_, metadata) = convert_with_metadata("<h1>Title</h1>", &Options::new());
+        assert!(metadata.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_heading_count_mismatch() {
+        let expected = Expectations {
+            headings: 2,
+            table_rows: vec![],
+        };
+        let warnings = expected.check("# One\n\nTwo\n");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, WarningCode::InvalidOutput);
+        assert_eq!(
+            warnings[0].message,
+            "markdown has 1 headings, 2 were written"
+        );
+    }
+
+    #[test]
+    fn test_table_row_mismatch() {
+        let expected = Expectations {
+            headings: 0,
+            table_rows: vec![3],
+        };
+        let warnings = expected.check("| A |\n| --- |\n| 1 |\n| 2 |\nbroken row\n");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].message,
+            "table 1 has 4 rows in the markdown, 3 were written"
+        );
+    }
+
+    #[test]
+    fn test_unclosed_fence() {
+        let expected = Expectations::default();
+        let warnings = expected.check("```rust\nfn main() {}\n\nMore text\n");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "code fence is never closed");
+        assert_eq!(warnings[0].detail.as_deref(), Some("```rust"));
+
+        assert!(expected.check("> ~~~\n> code\n> ~~~~\n").is_empty());
+        assert!(expected.check("````\n```\n````\n").is_empty());
+    }
+}
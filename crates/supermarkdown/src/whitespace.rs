@@ -2,6 +2,8 @@
 
 #![allow(dead_code)] // Utility functions available for extensibility
 
+use std::borrow::Cow;
+
 use once_cell::sync::Lazy;
 use regex::Regex;
 
@@ -20,9 +22,29 @@ pub fn normalize_inline_whitespace(text: &str) -> String {
 
 /// Normalize whitespace for block elements.
 ///
-/// Collapses all whitespace including newlines to single space.
-pub fn normalize_block_whitespace(text: &str) -> String {
-    ALL_WS_RE.replace_all(text, " ").into_owned()
+/// Collapses all whitespace including newlines to single space. Text that is
+/// already normalized is returned borrowed, so large text nodes aren't copied.
+pub fn normalize_block_whitespace(text: &str) -> Cow<'_, str> {
+    if is_block_normalized(text) {
+        return Cow::Borrowed(text);
+    }
+    ALL_WS_RE.replace_all(text, " ")
+}
+
+/// Whether all whitespace in `text` is single spaces.
+fn is_block_normalized(text: &str) -> bool {
+    let mut prev_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if c != ' ' || prev_space {
+                return false;
+            }
+            prev_space = true;
+        } else {
+            prev_space = false;
+        }
+    }
+    true
 }
 
 /// Trim leading/trailing whitespace and return the trimmed content with
@@ -62,6 +84,23 @@ mod tests {
         assert_eq!(normalize_inline_whitespace("line1\nline2"), "line1\nline2");
     }
 
+    #[test]
+    fn test_normalize_block_whitespace_borrows_normalized_text() {
+        assert!(matches!(
+            normalize_block_whitespace("already normal text"),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            normalize_block_whitespace("two  spaces"),
+            Cow::Owned(_)
+        ));
+        assert!(matches!(
+            normalize_block_whitespace("tab\there"),
+            Cow::Owned(_)
+        ));
+        assert_eq!(normalize_block_whitespace("a\u{00A0}b"), "a b");
+    }
+
     #[test]
     fn test_normalize_block_whitespace() {
         assert_eq!(normalize_block_whitespace("hello   world"), "hello world");
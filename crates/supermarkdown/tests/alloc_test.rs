@@ -0,0 +1,101 @@
+//! Allocation tests for `convert_into`.
+//!
+//! This runs in its own test binary because it installs a counting global
+//! allocator. Counts are kept per thread, so other tests running in
+//! parallel don't disturb them.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fs;
+use std::path::PathBuf;
+
+use supermarkdown::{convert_into, convert_with_options, Options};
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static ALLOCATED_BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+fn record(bytes: usize) {
+    // Ignore allocations made while the thread is being torn down
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+    let _ = ALLOCATED_BYTES.try_with(|total| total.set(total.get() + bytes));
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size.saturating_sub(layout.size()));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Allocations and bytes allocated on this thread while running `f`.
+fn measure(f: impl FnOnce()) -> (usize, usize) {
+    let (count, bytes) = (ALLOCATIONS.with(Cell::get), ALLOCATED_BYTES.with(Cell::get));
+    f();
+    (
+        ALLOCATIONS.with(Cell::get) - count,
+        ALLOCATED_BYTES.with(Cell::get) - bytes,
+    )
+}
+
+fn load_fixtures() -> Vec<(String, String)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures");
+    let mut fixtures: Vec<(String, String)> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+#[test]
+fn test_convert_into_allocates_less() {
+    let options = Options::default();
+    for (name, html) in load_fixtures() {
+        // Warm up lazily compiled regexes and size the buffer
+        let mut out = String::new();
+        convert_into(&html, &options, &mut out);
+        let _ = convert_with_options(&html, &options);
+
+        let (fresh_count, fresh_bytes) = measure(|| {
+            let _ = convert_with_options(&html, &options);
+        });
+        let (reused_count, reused_bytes) = measure(|| convert_into(&html, &options, &mut out));
+
+        assert!(
+            reused_count < fresh_count,
+            "{}: {} allocations reusing the buffer, {} without",
+            name,
+            reused_count,
+            fresh_count
+        );
+        assert!(
+            reused_bytes + out.len() <= fresh_bytes,
+            "{}: {} bytes reusing the buffer, {} without",
+            name,
+            reused_bytes,
+            fresh_bytes
+        );
+    }
+}
@@ -3,10 +3,16 @@
 //! These tests verify that supermarkdown handles various real-world HTML
 //! documents correctly, including edge cases and malformed input.
 
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::PathBuf;
 
-use supermarkdown::{convert, convert_with_options, Options};
+use supermarkdown::{
+    convert, convert_into, convert_outline, convert_with_metadata, convert_with_options,
+    convert_with_sourcemap, AsideHandling, BlockLinkStyle, HeadingDedupe, HeadingStyle, LinkStyle,
+    Options, SelectHandling, SingleRowTables, SvgHandling, TableCaption, TableImageCells,
+    TableStyle,
+};
 
 fn fixtures_dir() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -38,6 +44,19 @@ fn test_blog_post_headings() {
     assert!(markdown.contains("## Conclusion"));
 }
 
+#[test]
+fn test_blog_post_title_not_in_output() {
+    let html = load_fixture("blog_post.html");
+    let (markdown, metadata) = convert_with_metadata(&html, &Options::default());
+
+    // The h1 repeats the title; the head's copy must not appear above it
+    assert_eq!(markdown.matches("A Beginner's Guide").count(), 1);
+    assert_eq!(
+        metadata.title.as_deref(),
+        Some("Getting Started with Rust - A Beginner's Guide")
+    );
+}
+
 #[test]
 fn test_blog_post_code_blocks() {
     let html = load_fixture("blog_post.html");
@@ -95,6 +114,28 @@ fn test_documentation_tables() {
     assert!(markdown.contains("Required"));
 }
 
+#[test]
+fn test_documentation_links_metadata() {
+    let html = load_fixture("documentation.html");
+    let options = Options::new()
+        .base_url(Some("https://docs.example.com/guide/".to_string()))
+        .exclude_selectors(vec![".docs-nav".to_string()]);
+    let (markdown, metadata) = convert_with_metadata(&html, &options);
+
+    // The nav links are excluded from the markdown but still listed
+    let texts: Vec<&str> = metadata.links.iter().map(|l| l.text.as_str()).collect();
+    assert_eq!(texts, ["Home", "Docs", "API"]);
+    assert!(metadata.links.iter().all(|l| l.dropped && l.internal));
+    assert_eq!(metadata.links[1].url, "https://docs.example.com/docs");
+    assert!(!markdown.contains("[Docs]"));
+    assert_eq!(metadata.stats.links_converted, 0);
+
+    let (_, metadata) = convert_with_metadata(&html, &Options::default());
+    assert_eq!(metadata.links.len(), 3);
+    assert!(metadata.links.iter().all(|l| !l.dropped));
+    assert!(metadata.images.is_empty());
+}
+
 #[test]
 fn test_documentation_code_in_text() {
     let html = load_fixture("documentation.html");
@@ -167,6 +208,170 @@ fn test_tables_separator() {
     assert!(markdown.contains("|---") || markdown.contains("| ---"));
 }
 
+#[test]
+fn test_tables_status_codes_pipes() {
+    let html = load_fixture("tables.html");
+    let markdown = convert(&html);
+
+    assert!(markdown.contains("| Code  | Status       | Description             |"));
+    assert!(markdown.contains("| `200` | OK           | Request succeeded       |"));
+}
+
+#[test]
+fn test_tables_status_codes_key_value() {
+    let html = load_fixture("tables.html");
+    let options = Options::new().table_style(TableStyle::KeyValueList);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains(
+        "## HTTP Status Codes\n\n- **Code**: `200`\n  **Status**: OK\n  **Description**: Request succeeded\n- **Code**: `400`"
+    ));
+    assert!(markdown.contains("  **Description**: Internal server error\n\n## Nested Content"));
+    assert!(!markdown.contains("| --"));
+}
+
+#[test]
+fn test_single_row_tables_as_tables() {
+    let html = load_fixture("badges.html");
+    let markdown = convert(&html);
+
+    assert!(markdown.contains(
+        "| Version 0.4.2 | Released 2024-03-01 | MSRV 1.74 |\n| ------------- | ------------------- | --------- |\n\n## Commands"
+    ));
+    assert!(markdown.contains("| ![license MIT](/badges/license.svg) |\n| ---"));
+}
+
+#[test]
+fn test_single_row_tables_as_text() {
+    let html = load_fixture("badges.html");
+    let options = Options::new().single_row_tables(SingleRowTables::Text);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.starts_with(
+        "# fastq-tools\n\n[![build passing](/badges/build.svg)](https://ci.example.com/fastq-tools) · \
+         [![crates.io v0.4.2](/badges/version.svg)](https://crates.io/crates/fastq-tools) · \
+         ![license MIT](/badges/license.svg)\n\nFast tools for sequencing reads.\n\n"
+    ));
+    assert!(markdown
+        .contains("**Version 0.4.2** · **Released 2024-03-01** · **MSRV 1.74**\n\n## Commands"));
+    // Tables with more rows are unchanged
+    assert!(markdown.contains("| Command | Does           |\n| ------- | -------------- |\n"));
+}
+
+const MONTHLY_SALES: &str = "| Month    | Revenue |\n| -------- | ------: |\n| January  | $12,400 |\n| February |  $9,850 |\n";
+
+fn sales_table(caption: TableCaption) -> String {
+    let html = load_fixture("tables.html");
+    let markdown = convert_with_options(&html, &Options::new().table_caption(caption));
+    let start = markdown.find("## Sales").expect("sales heading");
+    markdown[start..].to_string()
+}
+
+#[test]
+fn test_table_caption_after() {
+    assert_eq!(
+        sales_table(TableCaption::After),
+        format!("## Sales\n\n{}\n*Monthly Sales*\n", MONTHLY_SALES)
+    );
+}
+
+#[test]
+fn test_table_caption_before() {
+    assert_eq!(
+        sales_table(TableCaption::Before),
+        format!("## Sales\n\n**Monthly Sales**\n\n{}", MONTHLY_SALES)
+    );
+}
+
+#[test]
+fn test_table_caption_pandoc() {
+    assert_eq!(
+        sales_table(TableCaption::Pandoc),
+        format!("## Sales\n\n{}\n: Monthly Sales\n", MONTHLY_SALES)
+    );
+}
+
+#[test]
+fn test_table_caption_drop() {
+    assert_eq!(
+        sales_table(TableCaption::Drop),
+        format!("## Sales\n\n{}", MONTHLY_SALES)
+    );
+}
+
+#[test]
+fn test_pricing_icons_markdown_by_default() {
+    let html = load_fixture("pricing.html");
+    let markdown = convert(&html);
+
+    assert!(
+        markdown.contains("![Yes](https://cdn.example.com/assets/icons/check-mark-green-24px.svg)")
+    );
+}
+
+#[test]
+fn test_pricing_icons_alt_text() {
+    let html = load_fixture("pricing.html");
+    let options = Options::new().table_image_cells(TableImageCells::AltText);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains("| Custom domains | Yes            | Yes  | Yes "));
+    // Icons without alt text fall back to symbols
+    assert!(markdown.contains("| Single sign-on | No             | ✗    | ✓ "));
+    assert!(markdown.contains("| Audit log      | Not applicable | ✗    | ![Yes]("));
+}
+
+#[test]
+fn test_pricing_icons_symbols() {
+    let html = load_fixture("pricing.html");
+    let options = Options::new().table_image_cells(TableImageCells::Symbol);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains("| Custom domains | ✓       | ✓    | ✓ "));
+    assert!(markdown.contains("| Single sign-on | ✗       | ✗    | ✓ "));
+    // A cell with text besides its icon is converted as usual
+    assert!(markdown.contains("| Audit log      | —       | ✗    | ![Yes]("));
+    assert!(markdown.contains(") Included |"));
+}
+
+#[test]
+fn test_malformed_tables_keep_every_row() {
+    let html = load_fixture("malformed_tables.html");
+    let markdown = convert(&html);
+
+    for row in [
+        "| 1.0     | March    |",
+        "| 1.1     | June     |",
+        "| 2.0     | December |",
+        "| EU     | Live    |",
+        "| US     | Beta    |",
+        "| APAC   | Planned |",
+        "| LATAM  | Planned |",
+        "| Stable  | 6 weeks |",
+        "| Nightly | Daily   |",
+        "| core    | platform |",
+        "| cli     | tools    |",
+    ] {
+        assert!(
+            markdown.contains(row),
+            "missing {:?} in:\n{}",
+            row,
+            markdown
+        );
+    }
+}
+
+#[test]
+fn test_malformed_tables_keep_header_rows() {
+    let html = load_fixture("malformed_tables.html");
+    let markdown = convert(&html);
+
+    assert!(markdown.contains("| Version | Date     |\n| ------- | -------- |\n"));
+    assert!(markdown.contains("| Region | Status  |\n| ------ | ------- |\n"));
+    assert!(markdown.contains("| Channel | Cadence |\n| ------- | ------- |\n"));
+    assert!(markdown.contains("| Package | Owner    |\n| ------- | -------- |\n"));
+}
+
 // =============================================================================
 // Code Heavy Tests
 // =============================================================================
@@ -289,6 +494,321 @@ fn test_malformed_valid_list_at_end() {
     assert!(lower.contains("item three") || markdown.contains("Item three"));
 }
 
+// =============================================================================
+// Icon / Embedded Content Tests
+// =============================================================================
+
+#[test]
+fn test_icons_svg_text_skipped() {
+    let html = load_fixture("icons.html");
+    let markdown = convert(&html);
+
+    // SVG titles and text must not leak into the output
+    assert!(!markdown.contains("Close Menu"));
+    assert!(!markdown.contains("Search"));
+    assert!(!markdown.contains("gear"));
+
+    // Link text next to the icons survives
+    assert!(markdown.contains("[Start](/)"));
+    assert!(markdown.contains("[Find](/search)"));
+    assert!(markdown.contains("Press the settings button"));
+}
+
+#[test]
+fn test_icons_large_svg_label() {
+    let html = load_fixture("icons.html");
+    let markdown = convert(&html);
+
+    // A chart isn't an icon: its label stands in for it, without its own text
+    assert!(markdown.contains("\n\nPower draw peaks at 40 W around noon\n\n"));
+    assert!(!markdown.contains("Power draw chart"));
+    // Only in "40 W", not as the axis label
+    assert_eq!(markdown.matches("0 W").count(), 1);
+
+    let options = Options::new().svg_handling(SvgHandling::Skip);
+    let markdown = convert_with_options(&html, &options);
+    assert!(!markdown.contains("Power draw peaks"));
+}
+
+#[test]
+fn test_icons_svg_title_text() {
+    let html = load_fixture("icons.html");
+    let options = Options::new().svg_handling(SvgHandling::TitleText);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains("[Home Start](/)"));
+    // aria-hidden icons stay silent
+    assert!(markdown.contains("[Find](/search)"));
+}
+
+#[test]
+fn test_icons_object_and_embed_links() {
+    let html = load_fixture("icons.html");
+    let markdown = convert(&html);
+
+    assert!(markdown.contains("[PDF document](/files/manual.pdf)"));
+    assert!(markdown.contains("[Video](/media/setup.mp4)"));
+    // Object fallback content follows the link
+    assert!(
+        markdown.contains("[PDF document](/files/manual.pdf)\n\nYour browser cannot display PDFs.")
+    );
+}
+
+#[test]
+fn test_icons_empty_links_removed() {
+    let html = load_fixture("icons.html");
+    let markdown = convert(&html);
+
+    assert!(!markdown.contains("[]("));
+    assert!(!markdown.contains("twitter.com"));
+    assert!(!markdown.contains("/print"));
+    assert!(markdown.contains("- [Contact us](/contact)"));
+    assert!(markdown.contains("Print this page or share it."));
+    // No bullets left behind by the icon-only items
+    assert!(!markdown.lines().any(|line| line.trim() == "-"));
+}
+
+#[test]
+fn test_icons_empty_links_kept_when_disabled() {
+    let html = load_fixture("icons.html");
+    let options = Options::new().remove_empty_links(false);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains("[](https://twitter.com/example)"));
+    assert!(markdown.contains("[](https://github.com/example)"));
+}
+
+// =============================================================================
+// Product Page Tests
+// =============================================================================
+
+#[test]
+fn test_product_select_text_by_default() {
+    let html = load_fixture("product_page.html");
+    let markdown = convert(&html);
+
+    assert!(markdown.contains("# Trail Runner 2"));
+    assert!(!markdown.contains("- EU 41"));
+}
+
+#[test]
+fn test_product_select_options_as_list() {
+    let html = load_fixture("product_page.html");
+    let options = Options::new().select_handling(SelectHandling::List);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains("- EU 40\n- **EU 41**\n- EU 43"));
+    assert!(!markdown.contains("Select a size"));
+    assert!(!markdown.contains("sold out"));
+    assert!(markdown.contains("- Classic\n  - Black\n  - Grey\n- Limited edition\n  - Lime"));
+    assert!(markdown.contains("## Details"));
+}
+
+#[test]
+fn test_product_card_attributes_captured() {
+    let html = load_fixture("product_page.html");
+    let options = Options::new().capture_attributes(vec![
+        (
+            "a.product-card".to_string(),
+            vec!["data-product-id".to_string(), "data-price".to_string()],
+        ),
+        (
+            "img".to_string(),
+            vec!["loading".to_string(), "decoding".to_string()],
+        ),
+    ]);
+    let (markdown, metadata) = convert_with_metadata(&html, &options);
+
+    // Capturing leaves the markdown alone
+    assert_eq!(markdown, convert(&html));
+    assert!(markdown.contains("[Trail Runner 3](/p/trail-runner-3)"));
+
+    let captured = &metadata.captured_attributes;
+    assert_eq!(captured.len(), 3);
+
+    assert_eq!(captured[0].selector, "a.product-card");
+    assert_eq!(captured[0].attrs["data-product-id"], "TR3");
+    assert_eq!(captured[0].attrs["data-price"], "149.00");
+    assert_eq!(captured[1].attrs["data-product-id"], "RG1");
+    assert!(!captured[1].attrs.contains_key("data-price"));
+
+    assert_eq!(captured[2].tag, "img");
+    assert_eq!(captured[2].attrs["loading"], "lazy");
+    assert_eq!(captured[2].attrs["decoding"], "async");
+    assert!(captured[0].index < captured[1].index && captured[1].index < captured[2].index);
+}
+
+// =============================================================================
+// Card Grid Tests
+// =============================================================================
+
+fn merged_cards() -> String {
+    let html = load_fixture("cards.html");
+    convert_with_options(&html, &Options::new().merge_adjacent_links(true))
+}
+
+#[test]
+fn test_cards_links_kept_apart_by_default() {
+    let markdown = convert(&load_fixture("cards.html"));
+    assert!(markdown.contains(
+        "[![](/img/rust-async.jpg)](/articles/rust-async) [Understanding Async Rust](/articles/rust-async)"
+    ));
+}
+
+#[test]
+fn test_cards_image_and_title_links_merged() {
+    let markdown = merged_cards();
+    assert!(markdown
+        .contains("[![](/img/rust-async.jpg) Understanding Async Rust](/articles/rust-async)\n"));
+    // The title of either link is kept
+    assert!(markdown.contains(
+        "[![Bytes](/img/wasm.jpg) Strings Across the Wasm Boundary](/articles/wasm-strings \"Read the article\")\n"
+    ));
+}
+
+#[test]
+fn test_cards_duplicate_text_links_dropped() {
+    let markdown = merged_cards();
+    assert!(markdown.contains("Vectorizing a tokenizer. [Read more](/articles/simd)\n"));
+}
+
+#[test]
+fn test_cards_fragment_links_not_merged() {
+    let markdown = merged_cards();
+    assert!(markdown.contains("[setup](/articles/simd#setup) [results](/articles/simd#results)"));
+}
+
+#[test]
+fn test_cards_links_in_separate_blocks_not_merged() {
+    let markdown = merged_cards();
+    assert!(markdown.contains(
+        "[![Flame graph](/img/profiling.jpg)](/articles/profiling)\n\n### [Profiling Without Guesswork](/articles/profiling)"
+    ));
+}
+
+// =============================================================================
+// Tabbed Docs Tests
+// =============================================================================
+
+#[test]
+fn test_tabbed_docs_headings_kept_by_default() {
+    let html = load_fixture("tabbed_docs.html");
+    let markdown = convert(&html);
+
+    assert_eq!(markdown.matches("### Example\n").count(), 3);
+    assert_eq!(markdown.matches("### Getting Help\n").count(), 2);
+}
+
+#[test]
+fn test_tabbed_docs_dedupe_suffix() {
+    let html = load_fixture("tabbed_docs.html");
+    let options = Options::new().dedupe_headings(true);
+    let markdown = convert_with_options(&html, &options);
+
+    assert_eq!(markdown.matches("### Example\n").count(), 1);
+    assert!(markdown.contains("### Example (2)"));
+    assert!(markdown.contains("### Example (3)"));
+    assert!(markdown.contains("### Getting Help (2)"));
+    // Comment lines inside code blocks are not headings
+    assert_eq!(markdown.matches("# install the package").count(), 3);
+}
+
+#[test]
+fn test_tabbed_docs_dedupe_merge() {
+    let html = load_fixture("tabbed_docs.html");
+    let options = Options::new()
+        .dedupe_headings(true)
+        .heading_dedupe(HeadingDedupe::Merge);
+    let markdown = convert_with_options(&html, &options);
+
+    // Tabs with different code keep their content under one heading
+    assert_eq!(markdown.matches("### Example").count(), 1);
+    assert!(markdown.contains("pip install widgets"));
+    assert!(markdown.contains("npm install widgets"));
+    assert!(markdown.contains("go get example.com/widgets"));
+
+    // Identical tabs collapse to one
+    assert_eq!(markdown.matches("### Getting Help").count(), 1);
+    assert_eq!(markdown.matches("Open an issue on the tracker.").count(), 1);
+}
+
+// =============================================================================
+// Image Map Tests
+// =============================================================================
+
+#[test]
+fn test_image_map_areas_listed_after_image() {
+    let html = load_fixture("image_map.html");
+    let markdown = convert(&html);
+
+    assert!(markdown.contains(
+        "![Campus map](/images/campus.gif)\n\n\
+         - [Lecture Hall](/buildings/lecture-hall.html)\n\
+         - [labs/optics.html](labs/optics.html)\n\n\
+         The lecture halls"
+    ));
+    // The nohref area has nowhere to link
+    assert!(!markdown.contains("[Campus]"));
+}
+
+#[test]
+fn test_image_map_areas_resolved_against_base_url() {
+    let html = load_fixture("image_map.html");
+    let options = Options::new().base_url(Some("https://physics.example.edu/visit/".to_string()));
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown
+        .contains("- [Lecture Hall](https://physics.example.edu/buildings/lecture-hall.html)"));
+    assert!(markdown.contains(
+        "- [https://physics.example.edu/visit/labs/optics.html]\
+         (https://physics.example.edu/visit/labs/optics.html)"
+    ));
+}
+
+// =============================================================================
+// Heading Placement Tests
+// =============================================================================
+
+#[test]
+fn test_heading_inside_list_item_leaves_marker() {
+    let html = "<ul><li><h2>Title</h2><p>Body</p></li><li>Two</li></ul>";
+    assert_eq!(convert(html), "## Title\n\n- Body\n\n- Two\n");
+
+    // The item with only a heading goes, so the list starts at its second item
+    let html = "<ol><li><h3>Step</h3></li><li>Next</li></ol>";
+    assert_eq!(convert(html), "### Step\n\n2. Next\n");
+}
+
+#[test]
+fn test_heading_after_table_is_apart() {
+    let html = "<table><tr><th>A</th></tr><tr><td>1</td></tr></table><h2>After</h2><p>Text</p>";
+    assert_eq!(
+        convert(html),
+        "| A   |\n| --- |\n| 1   |\n\n## After\n\nText\n"
+    );
+
+    let html = "<ul><li><table><tr><td>1</td></tr></table><h2>After</h2></li></ul>";
+    let markdown = convert(html);
+    assert!(markdown.contains("|\n\n"), "{:?}", markdown);
+    assert!(!markdown.contains("- ##"), "{:?}", markdown);
+}
+
+#[test]
+fn test_heading_in_blockquote_stays_quoted() {
+    let html = "<blockquote><h2>Quoted</h2><p>Text</p></blockquote>";
+    assert_eq!(convert(html), "> ## Quoted\n>\n> Text\n");
+}
+
+#[test]
+fn test_sourcemap_hoisted_heading() {
+    let html = "<ul><li><h2>Title</h2><p>Body</p></li></ul>";
+    let (markdown, map) = convert_with_sourcemap(html, &Options::default());
+
+    assert_eq!(markdown, convert(html));
+    let heading = map.iter().find(|e| e.html_tag == "h2").unwrap();
+    assert_eq!(&markdown[heading.md_start..heading.md_end], "## Title");
+}
+
 // =============================================================================
 // Options Tests
 // =============================================================================
@@ -313,6 +833,574 @@ fn test_exclude_footer() {
     assert!(!markdown.contains("supermarkdown documentation"));
 }
 
+#[test]
+fn test_inject_og_image_after_title() {
+    let html = load_fixture("og_article.html");
+    let options = Options::new().inject_og_image(true);
+    let markdown = convert_with_options(&html, &options);
+
+    // Resolved against og:url, placed right after the title
+    assert!(markdown.starts_with(
+        "# Night Trains Return to the Alps\n\n\
+         ![](https://travel.example.com/media/night-train-hero.jpg)\n\n\
+         After a decade away"
+    ));
+    assert!(!convert(&html).contains("night-train-hero"));
+}
+
+#[test]
+fn test_inject_og_image_skips_duplicate() {
+    let html = load_fixture("og_article.html").replace("couchette.jpg", "night-train-hero.jpg");
+    let options = Options::new()
+        .inject_og_image(true)
+        .base_url(Some("https://travel.example.com/".to_string()));
+    let markdown = convert_with_options(&html, &options);
+
+    assert_eq!(markdown.matches("night-train-hero.jpg").count(), 1);
+    assert!(markdown.contains(
+        "![A six-berth couchette](https://travel.example.com/media/night-train-hero.jpg)"
+    ));
+}
+
+fn news_boilerplate_options() -> Options {
+    Options::new().exclude_text_patterns(vec![
+        "Share this article".to_string(),
+        "advertisement".to_string(),
+        "we use cookies".to_string(),
+    ])
+}
+
+#[test]
+fn test_exclude_text_patterns_drop_boilerplate() {
+    let html = load_fixture("news_article.html");
+    let markdown = convert_with_options(&html, &news_boilerplate_options());
+
+    // Share clusters go with their buttons, ad slots and the cookie banner
+    // with their contents
+    assert!(!markdown.contains("Share this"));
+    assert!(!markdown.contains("Twitter"));
+    assert!(!markdown.contains("Copy link"));
+    assert!(!markdown.contains("banner.png"));
+    assert!(!markdown.contains("Cookie settings"));
+    assert!(markdown.starts_with("# City Council Approves New Bike Lanes"));
+    assert!(markdown.contains("Construction is expected to begin in the spring."));
+}
+
+#[test]
+fn test_exclude_text_patterns_keep_long_paragraphs() {
+    let html = load_fixture("news_article.html");
+    let markdown = convert_with_options(&html, &news_boilerplate_options());
+
+    // Mentioning "advertisement" doesn't drop a paragraph of the article
+    assert!(markdown.contains("spent heavily on advertisement campaigns"));
+
+    let markdown = convert_with_options(&html, &news_boilerplate_options().exclude_text_max_len(0));
+    assert!(markdown.contains("Share this article"));
+    assert!(markdown.contains("Advertisement"));
+}
+
+#[test]
+fn test_include_selectors_rescue_text_matches() {
+    let html = load_fixture("news_article.html");
+    let options = news_boilerplate_options().include_selectors(vec![".share-bar".to_string()]);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains("Copy link"));
+    assert!(!markdown.contains("Twitter"));
+}
+
+// =============================================================================
+// Outline Tests
+// =============================================================================
+
+fn include_override_options() -> Options {
+    Options::new()
+        .exclude_selectors(vec!["nav".to_string(), ".sidebar".to_string()])
+        .include_selectors(vec![".sidebar .keep".to_string()])
+}
+
+#[test]
+fn test_outline_include_overrides_exclude() {
+    let html = load_fixture("include_override.html");
+    let outline = convert_outline(&html, &include_override_options());
+
+    assert!(outline.contains("    nav.site-nav [skipped by \"nav\"] \"Home Releases\"\n"));
+    assert!(outline.contains(
+        "      aside.sidebar -> AsideRule [skipped by \".sidebar\"] \"Related Blog Roadmap Upgrading from 1.x?…\"\n"
+    ));
+    assert!(outline
+        .contains("        h2 -> HeadingRule [skipped: inside excluded element] \"Related\"\n"));
+    assert!(outline.contains(
+        "        div.callout.keep [kept by \".sidebar .keep\"] \"Upgrading from 1.x? Read the migration g…\"\n"
+    ));
+}
+
+#[test]
+fn test_outline_kept_subtree_not_skipped() {
+    let html = load_fixture("include_override.html");
+    let outline = convert_outline(&html, &include_override_options());
+
+    assert!(outline
+        .contains("          p -> ParagraphRule \"Upgrading from 1.x? Read the migration g…\"\n"));
+}
+
+#[test]
+fn test_include_override_converts_kept_subtree() {
+    let html = load_fixture("include_override.html");
+    let markdown = convert_with_options(&html, &include_override_options());
+
+    assert!(markdown.contains(
+        "Version 2.0 brings a new parser and faster output.\n\nUpgrading from 1.x? Read the migration guide first.\n\n## Breaking Changes"
+    ));
+    assert!(!markdown.contains("Related"));
+    assert!(!markdown.contains("Roadmap"));
+    assert!(!markdown.contains("Releases"));
+}
+
+#[test]
+fn test_outline_unaffected_content() {
+    let html = load_fixture("include_override.html");
+    let outline = convert_outline(&html, &include_override_options());
+
+    assert!(outline.contains("    main#content \"Release Notes Version 2.0"));
+    assert!(outline.contains("      h1 -> HeadingRule \"Release Notes\"\n"));
+    assert!(outline.contains("        code -> CodeRule \"legacy\"\n"));
+    assert!(!outline.contains("main#content ["));
+}
+
+// =============================================================================
+// Short Block Tests
+// =============================================================================
+
+#[test]
+fn test_pager_short_paragraphs_dropped() {
+    let html = load_fixture("pager.html");
+    let options = Options::new().min_block_text_len(Some(12));
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(!markdown.contains("Read more"));
+    assert!(!markdown.contains("Next"));
+    assert!(!markdown.contains("Prev"));
+    // Exactly at the threshold
+    assert!(markdown.contains("\n\nUpgrade now.\n\n"));
+    // Headings, list items, table cells, images and code stay
+    assert!(markdown.contains("## Fixes"));
+    assert!(markdown.contains("- Typos"));
+    assert!(markdown.contains("| Free | $0    |"));
+    assert!(markdown.contains("![Chart](/img/chart.png)"));
+    assert!(markdown.contains("Run `up`"));
+}
+
+#[test]
+fn test_pager_short_list_items_dropped() {
+    let html = load_fixture("pager.html");
+    let options = Options::new()
+        .min_block_text_len(Some(12))
+        .min_block_text_len_lists(true);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains("- Crash on exit\n"));
+    assert!(!markdown.contains("Typos"));
+}
+
+#[test]
+fn test_pager_unchanged_by_default() {
+    let html = load_fixture("pager.html");
+    let markdown = convert(&html);
+
+    assert!(markdown.contains("[Read more](/notes/2.0)"));
+    assert!(markdown.contains("[Next →](/notes?page=3)"));
+}
+
+// =============================================================================
+// Compact Output Tests
+// =============================================================================
+
+fn release_notes_options() -> Options {
+    Options::new().exclude_selectors(vec![
+        "nav".to_string(),
+        ".promo".to_string(),
+        "footer".to_string(),
+    ])
+}
+
+/// Words of `markdown` outside link titles, which compact output drops.
+fn content_words(markdown: &str) -> BTreeSet<&str> {
+    let mut words = BTreeSet::new();
+    for (i, part) in markdown.split(" \"").enumerate() {
+        // Every part but the first starts with a title
+        let text = match part.split_once("\")") {
+            Some((_, after)) if i > 0 => after,
+            _ => part,
+        };
+        words.extend(
+            text.split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty()),
+        );
+    }
+    words
+}
+
+#[test]
+fn test_compact_output_is_smaller() {
+    // Only the titled link and the padded table shrink here: 1359 bytes by
+    // default, 1296 compact
+    let html = load_fixture("complex.html");
+    let markdown = convert(&html);
+    let compact = convert_with_options(&html, &Options::new().compact(true));
+
+    assert!(
+        compact.len() * 100 <= markdown.len() * 96,
+        "compact output is {} bytes, default {}",
+        compact.len(),
+        markdown.len()
+    );
+    let missing: Vec<&str> = content_words(&markdown)
+        .difference(&content_words(&compact))
+        .copied()
+        .collect();
+    assert!(missing.is_empty(), "compact output lost {:?}", missing);
+}
+
+#[test]
+fn test_compact_output_never_larger() {
+    for entry in fs::read_dir(fixtures_dir()).unwrap() {
+        let html = fs::read_to_string(entry.unwrap().path()).unwrap();
+        let markdown = convert(&html);
+        let compact = convert_with_options(&html, &Options::new().compact(true));
+        assert!(compact.len() <= markdown.len());
+        let missing: Vec<&str> = content_words(&markdown)
+            .difference(&content_words(&compact))
+            .copied()
+            .collect();
+        assert!(missing.is_empty(), "compact output lost {:?}", missing);
+    }
+}
+
+#[test]
+fn test_compact_output_details() {
+    let html = load_fixture("release_notes.html");
+    let compact = convert_with_options(&html, &release_notes_options().compact(true));
+
+    // Rules around the excluded promo and footer collapse to one
+    assert!(compact.starts_with("# Acme Storage 4.2 Release Notes\n"));
+    assert_eq!(compact.matches("---\n").count(), 1);
+    assert!(compact.contains("| Small object write | 12.4 | 8.1 | 35% faster |\n"));
+    assert!(compact.contains("| --- | --: | --: | --- |\n"));
+    // Only the repeated URL becomes a reference
+    assert!(compact.contains("[upgrade guide][1] covers"));
+    assert!(compact.contains("[forum](https://acme.example/forum);"));
+    assert!(compact.ends_with("\n\n[1]: https://acme.example/docs/upgrade\n"));
+    assert!(compact.contains("Downloads are at <https://acme.example/downloads> and"));
+}
+
+// =============================================================================
+// Aside Tests
+// =============================================================================
+
+#[test]
+fn test_magazine_asides_labeled() {
+    let html = load_fixture("magazine_article.html");
+    let options = Options::new().aside_handling(AsideHandling::Labeled);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains(
+        "**Pull quote**\n\n\"You go to sleep in one country and wake up in another.\"\n\n"
+    ));
+    assert!(markdown
+        .contains("**Related articles**\n\n- [Are rail passes still worth it?](/rail-passes)\n"));
+    assert!(!markdown.contains("### Related articles"));
+    // The newsletter dialog is skipped by default
+    assert!(!markdown.contains("newsletter"));
+}
+
+#[test]
+fn test_magazine_asides_blockquote() {
+    let html = load_fixture("magazine_article.html");
+    let options = Options::new().aside_handling(AsideHandling::Blockquote);
+    let markdown = convert_with_options(&html, &options);
+
+    assert!(markdown.contains(
+        "> ### Related articles\n>\n> - [Are rail passes still worth it?](/rail-passes)\n"
+    ));
+    assert!(markdown.contains("\n\nPrices start at"));
+}
+
+#[test]
+fn test_magazine_asides_skipped() {
+    let html = load_fixture("magazine_article.html");
+    let options = Options::new().aside_handling(AsideHandling::Skip);
+    let (markdown, metadata) = convert_with_metadata(&html, &options);
+
+    assert!(!markdown.contains("You go to sleep"));
+    assert!(!markdown.contains("rail-passes"));
+    assert!(markdown.contains("Operators report"));
+    assert_eq!(metadata.stats.skipped_by_selector["aside_handling"], 2);
+    assert_eq!(metadata.stats.skipped_by_selector["dialog_handling"], 1);
+
+    let kept = options.include_selectors(vec![".related".to_string()]);
+    let markdown = convert_with_options(&html, &kept);
+    assert!(!markdown.contains("You go to sleep"));
+    assert!(markdown.contains("[Packing for a sleeper cabin](/packing-for-sleepers)"));
+
+    let outline = convert_outline(&html, &Options::new().aside_handling(AsideHandling::Skip));
+    assert!(outline.contains("aside.pull-quote -> AsideRule [skipped by aside_handling]"));
+}
+
+// =============================================================================
+// JSON-LD Tests
+// =============================================================================
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_recipe_json_ld_extracted() {
+    let html = load_fixture("recipe.html");
+    let options = Options::new()
+        .extract_json_ld(true)
+        .exclude_selectors(vec!["nav".to_string(), "script".to_string()]);
+    let (markdown, metadata) = convert_with_metadata(&html, &options);
+
+    assert!(markdown.starts_with("# Lemon Drizzle Cake\n"));
+    assert!(!markdown.contains("schema.org"));
+
+    let json_ld = &metadata.json_ld;
+    assert_eq!(json_ld.len(), 2);
+    let recipe = &json_ld[0];
+    assert_eq!(recipe["@type"], "Recipe");
+    assert_eq!(recipe["author"]["name"], "Ada Baker");
+    assert_eq!(recipe["recipeIngredient"].as_array().unwrap().len(), 4);
+    assert_eq!(
+        recipe["recipeInstructions"][1]["text"],
+        "Beat the butter and sugar, then add the eggs."
+    );
+    assert_eq!(json_ld[1]["@type"], "Organization");
+    assert_eq!(metadata.stats.json_ld_invalid, 1);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_ld_kept_out_of_markdown_only_when_extracted() {
+    let html = load_fixture("recipe.html");
+    let (markdown, metadata) = convert_with_metadata(&html, &Options::default());
+    assert!(markdown.contains("Weekday Bakes"));
+    assert!(metadata.json_ld.is_empty());
+
+    let options = Options::new().extract_json_ld(true);
+    let markdown = convert_with_options(&html, &options);
+    assert!(!markdown.contains("Weekday Bakes"));
+    assert!(markdown.contains("A sharp, sticky loaf cake"));
+}
+
+// =============================================================================
+// Source Map Tests
+// =============================================================================
+
+#[test]
+fn test_sourcemap_markdown_matches_convert() {
+    let option_sets = [
+        Options::default(),
+        Options::new()
+            .heading_style(HeadingStyle::Setext)
+            .link_style(LinkStyle::Referenced),
+        Options::new()
+            .dedupe_headings(true)
+            .block_link_style(BlockLinkStyle::HeadingLink),
+        Options::new()
+            .dedupe_headings(true)
+            .heading_dedupe(HeadingDedupe::Merge),
+        Options::new().dedupe_repeated_blocks(Some(1)),
+    ];
+
+    for entry in fs::read_dir(fixtures_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        let html = fs::read_to_string(&path).unwrap();
+
+        for options in &option_sets {
+            let (markdown, map) = convert_with_sourcemap(&html, options);
+
+            assert_eq!(
+                markdown,
+                convert_with_options(&html, options),
+                "{}",
+                path.display()
+            );
+            assert!(!map.is_empty(), "{}", path.display());
+            for entry in &map {
+                assert!(entry.md_start <= entry.md_end && entry.md_end <= markdown.len());
+                assert!(markdown.is_char_boundary(entry.md_start));
+                assert!(markdown.is_char_boundary(entry.md_end));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sourcemap_blog_headings() {
+    let html = load_fixture("blog_post.html");
+    let (markdown, map) = convert_with_sourcemap(&html, &Options::default());
+
+    let headings: Vec<&str> = map
+        .iter()
+        .filter(|e| e.html_tag == "h2")
+        .map(|e| &markdown[e.md_start..e.md_end])
+        .collect();
+    assert!(headings.contains(&"## Why Rust?"));
+    assert!(headings.contains(&"## Installation"));
+}
+
+#[cfg(feature = "validate")]
+#[test]
+fn test_fixtures_pass_output_validation() {
+    let option_sets = [
+        Options::default(),
+        Options::new().heading_style(HeadingStyle::Setext),
+        Options::new().link_style(LinkStyle::Referenced),
+        Options::new().aside_handling(AsideHandling::Labeled),
+    ];
+
+    for entry in fs::read_dir(fixtures_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        let html = fs::read_to_string(&path).unwrap();
+
+        for options in &option_sets {
+            let options = options.clone().validate_output(true);
+            let (_, metadata) = convert_with_metadata(&html, &options);
+            assert_eq!(metadata.warnings, vec![], "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn test_convert_into_matches_convert_with_options() {
+    let option_sets = [
+        Options::default(),
+        Options::new().link_style(LinkStyle::Referenced),
+        Options::new().trim_document(false).trailing_newline(false),
+    ];
+
+    let mut out = String::from("left over from before");
+    for entry in fs::read_dir(fixtures_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        let html = fs::read_to_string(&path).unwrap();
+
+        for options in &option_sets {
+            convert_into(&html, options, &mut out);
+            assert_eq!(
+                out,
+                convert_with_options(&html, options),
+                "{}",
+                path.display()
+            );
+        }
+    }
+
+    convert_into("", &Options::default(), &mut out);
+    assert_eq!(out, "");
+}
+
+// =============================================================================
+// Email Tests
+// =============================================================================
+
+#[test]
+fn test_email_reply_history_removed() {
+    let html = load_fixture("email_reply.html");
+    let markdown = convert_with_options(&html, &Options::new().email_mode(true));
+
+    assert!(markdown.starts_with(
+        "Hi Sam,\n\nThanks for the review notes. I've fixed the two typos and moved the\n\
+         pricing table to the appendix, as you suggested.\n\nOpen questions:\n\n"
+    ));
+    assert!(markdown.ends_with("I'll send the final draft by **Friday**.\n\nBest,\nAlex\n"));
+    assert!(!markdown.contains("wrote:"));
+    assert!(!markdown.contains("first draft of the report"));
+    // Outlook-only content
+    assert!(!markdown.contains("Sent from Outlook"));
+}
+
+#[test]
+fn test_email_reply_history_kept() {
+    let html = load_fixture("email_reply.html");
+    let markdown = convert(&html);
+    assert!(markdown.contains("wrote:"));
+    assert!(markdown.contains("Sent from Outlook"));
+
+    // include_selectors overrides the built-in quote selectors
+    let options = Options::new()
+        .email_mode(true)
+        .include_selectors(vec![".gmail_quote".to_string()]);
+    let markdown = convert_with_options(&html, &options);
+    assert!(markdown.contains("> Hi Alex,\n>\n> I read through the draft."));
+    assert!(!markdown.contains("Sent from Outlook"));
+
+    let options = Options::new()
+        .email_mode(true)
+        .email_quote_selectors(vec![]);
+    assert!(convert_with_options(&html, &options).contains("wrote:"));
+}
+
+// =============================================================================
+// Repeated Block Tests
+// =============================================================================
+
+const CTA: &str = "**Enjoying this guide?** [Get new recipes every Friday.](/newsletter)";
+
+#[test]
+fn test_repeated_cta_first_kept() {
+    let html = load_fixture("repeated_cta.html");
+    assert_eq!(convert(&html).matches(CTA).count(), 4);
+
+    let options = Options::new().dedupe_repeated_blocks(Some(1));
+    let markdown = convert_with_options(&html, &options);
+    assert_eq!(markdown.matches(CTA).count(), 1);
+    let cta = markdown.find(CTA).unwrap();
+    assert!(cta > markdown.find("how much feeding it needs.").unwrap());
+    assert!(cta < markdown.find("## On the counter").unwrap());
+    // Only whitespace is left where the later copies were
+    let between = |after: &str, before: &str| {
+        let start = markdown.find(after).unwrap() + after.len();
+        markdown[start..markdown.find(before).unwrap()]
+            .trim()
+            .to_string()
+    };
+    assert_eq!(between("once or twice a day.", "## In the fridge"), "");
+    assert_eq!(between("```\n\n", "## Dried"), "");
+    assert!(markdown.ends_with("- Feed it the day before\n- Feed it the day before\n"));
+    // Code is never deduplicated
+    assert!(markdown.contains("```\nFeed 1:1:1\nFeed 1:1:1\n```"));
+}
+
+#[test]
+fn test_repeated_cta_limits() {
+    let html = load_fixture("repeated_cta.html");
+    let options = Options::new().dedupe_repeated_blocks(Some(2));
+    assert_eq!(
+        convert_with_options(&html, &options).matches(CTA).count(),
+        2
+    );
+
+    // Blocks longer than the threshold are all kept
+    let options = Options::new()
+        .dedupe_repeated_blocks(Some(1))
+        .dedupe_block_max_len(20);
+    assert_eq!(
+        convert_with_options(&html, &options).matches(CTA).count(),
+        4
+    );
+}
+
+#[test]
+fn test_repeated_block_with_marker_like_text() {
+    // Private use characters that look like source map markers are text
+    let text = format!("\u{E000}{}", "\u{E01F}".repeat(20));
+    let html = format!("<p>{text}</p><p>{text}</p><p>other {text}</p>");
+    let options = Options::new().dedupe_repeated_blocks(Some(1));
+    assert_eq!(
+        convert_with_options(&html, &options),
+        format!("{text}\n\nother {text}\n")
+    );
+}
+
 // =============================================================================
 // Edge Cases
 // =============================================================================
@@ -0,0 +1,84 @@
+//! Tests for debug logging behind the `logging` feature.
+
+#![cfg(feature = "logging")]
+
+use std::sync::{Mutex, Once};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use supermarkdown::{convert_with_options, Options};
+
+/// Logger that keeps every message in memory.
+struct CaptureLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug
+    }
+
+    fn log(&self, record: &Record) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger {
+    messages: Mutex::new(Vec::new()),
+};
+
+/// Install the capture logger once and return all messages logged so far.
+///
+/// Tests share one process-wide logger, so assert on presence only.
+fn captured() -> Vec<String> {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Debug);
+    });
+    LOGGER.messages.lock().unwrap().clone()
+}
+
+#[test]
+fn test_logs_excluded_nav() {
+    captured();
+    let options = Options::new().exclude_selectors(vec![".ad".to_string(), "nav".to_string()]);
+    let markdown = convert_with_options("<nav><a href='/'>Home</a></nav><p>Body</p>", &options);
+    assert_eq!(markdown, "Body\n");
+
+    let messages = captured();
+    assert!(messages
+        .iter()
+        .any(|m| m == "Skipping <nav>: matched exclude selector 'nav'"));
+}
+
+#[test]
+fn test_logs_phase_summary() {
+    captured();
+    convert_with_options("<h1>Timing</h1>", &Options::default());
+
+    let messages = captured();
+    assert!(messages.iter().any(|m| m.starts_with("Converted ")
+        && m.contains("parse ")
+        && m.contains("precompute ")
+        && m.contains("convert ")
+        && m.contains("postprocess ")));
+}
+
+#[test]
+fn test_logs_missing_pre_language() {
+    captured();
+    convert_with_options(
+        "<pre class='snippet'><code>plain text</code></pre>",
+        &Options::default(),
+    );
+
+    let messages = captured();
+    assert!(messages
+        .iter()
+        .any(|m| m.contains("No language detected for <pre>") && m.contains("snippet")));
+}
@@ -0,0 +1,147 @@
+//! Golden tests: every fixture converted in full and compared with a
+//! checked-in snapshot, catching the spacing and structure changes that
+//! the substring checks in `fixtures_test.rs` miss.
+//!
+//! `tests/fixtures/NAME.html` converts with default options to
+//! `tests/snapshots/NAME.md`. Each `tests/snapshots/NAME.VARIANT.options.json`
+//! holds options as JSON, read with the `serde` feature, and adds the
+//! snapshot `NAME.VARIANT.md` converted with them.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to write the snapshots instead of
+//! comparing them, then review the diff.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pretty_assertions::StrComparison;
+use supermarkdown::{convert_with_options, Options};
+
+const OPTIONS_SUFFIX: &str = ".options.json";
+
+fn tests_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+/// One snapshot: a fixture converted with a set of options.
+struct Case {
+    /// `NAME` or `NAME.VARIANT`, the snapshot's file stem.
+    name: String,
+    fixture: PathBuf,
+    options: Options,
+}
+
+/// Every fixture with default options, then every options file.
+fn cases() -> Vec<Case> {
+    let mut cases: Vec<Case> = sorted_files(&tests_dir().join("fixtures"))
+        .into_iter()
+        .filter_map(|path| {
+            let name = path
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".html")?
+                .to_string();
+            Some(Case {
+                name,
+                fixture: path,
+                options: Options::default(),
+            })
+        })
+        .collect();
+    cases.extend(variant_cases());
+    cases
+}
+
+#[cfg(feature = "serde")]
+fn variant_cases() -> Vec<Case> {
+    sorted_files(&tests_dir().join("snapshots"))
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.strip_suffix(OPTIONS_SUFFIX)?;
+            let (fixture, _) = name.split_once('.')?;
+            let json = fs::read_to_string(&path).unwrap();
+            let options = serde_json::from_str(&json)
+                .unwrap_or_else(|e| panic!("invalid options in {}: {}", path.display(), e));
+            Some(Case {
+                name: name.to_string(),
+                fixture: tests_dir()
+                    .join("fixtures")
+                    .join(format!("{}.html", fixture)),
+                options,
+            })
+        })
+        .collect()
+}
+
+/// Options files are read with serde only; their snapshots go unchecked
+/// without it.
+#[cfg(not(feature = "serde"))]
+fn variant_cases() -> Vec<Case> {
+    Vec::new()
+}
+
+fn sorted_files(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_snapshots() {
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some_and(|v| v == "1");
+    let snapshots = tests_dir().join("snapshots");
+    let mut failures = Vec::new();
+
+    for case in cases() {
+        let html = fs::read_to_string(&case.fixture)
+            .unwrap_or_else(|e| panic!("Failed to load {}: {}", case.fixture.display(), e));
+        let markdown = convert_with_options(&html, &case.options);
+        let path = snapshots.join(format!("{}.md", case.name));
+
+        if update {
+            fs::write(&path, &markdown).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(expected) if expected == markdown => {}
+            Ok(expected) => {
+                eprintln!(
+                    "{} differs from the snapshot:\n{}",
+                    case.name,
+                    StrComparison::new(&expected, &markdown)
+                );
+                failures.push(case.name);
+            }
+            Err(_) => {
+                eprintln!("{} has no snapshot", case.name);
+                failures.push(case.name);
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "snapshots failed: {}; run with UPDATE_SNAPSHOTS=1 to accept the output",
+        failures.join(", ")
+    );
+}
+
+#[test]
+fn test_no_orphaned_snapshots() {
+    let fixtures = tests_dir().join("fixtures");
+    let snapshots = tests_dir().join("snapshots");
+    for path in sorted_files(&snapshots) {
+        let file = path.file_name().unwrap().to_str().unwrap();
+        let Some(name) = file.strip_suffix(".md") else {
+            continue;
+        };
+        let source = match name.split_once('.') {
+            Some(_) => snapshots.join(format!("{}{}", name, OPTIONS_SUFFIX)),
+            None => fixtures.join(format!("{}.html", name)),
+        };
+        assert!(source.exists(), "{} has no {}", file, source.display());
+    }
+}
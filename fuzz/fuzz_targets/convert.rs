@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use supermarkdown::{convert, convert_with_options, LinkStyle, Options};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(html) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let markdown = convert(html);
+    assert!(!markdown.contains('\0'), "NUL in output");
+
+    // Relative URL resolution and reference links take other code paths
+    let options = Options::new()
+        .base_url(Some("https://example.com/dir/page".to_string()))
+        .link_style(LinkStyle::Referenced);
+    let markdown = convert_with_options(html, &options);
+    assert!(!markdown.contains('\0'), "NUL in output");
+});